@@ -0,0 +1,13 @@
+#![no_main]
+
+use baihu::util::{hex_decode, hex_encode};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        if let Ok(bytes) = hex_decode(text) {
+            assert_eq!(hex_encode(&bytes), text.to_ascii_lowercase());
+        }
+    }
+    assert_eq!(hex_decode(&hex_encode(data)).unwrap(), data);
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use baihu::providers::http_client::is_private_ip;
+use libfuzzer_sys::fuzz_target;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+fuzz_target!(|data: &[u8]| {
+    let ip = match data.len() {
+        4 => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(data).unwrap())),
+        16 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(data).unwrap())),
+        _ => return,
+    };
+    let private = is_private_ip(ip);
+    // An IPv4-mapped address must classify like the address it maps.
+    if let IpAddr::V4(v4) = ip {
+        assert_eq!(is_private_ip(IpAddr::V6(v4.to_ipv6_mapped())), private);
+    }
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use baihu::memory::compression::{maybe_decompress_with_limit, preview};
+use libfuzzer_sys::fuzz_target;
+
+/// Small enough that a hostile size header can't exhaust the fuzzer's memory.
+const LIMIT: usize = 1 << 20;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    // Most interesting inputs are compressed entries, so reach that path directly too.
+    let _ = maybe_decompress_with_limit(text, LIMIT);
+    let stored = format!("lz4:{text}");
+    let _ = maybe_decompress_with_limit(&stored, LIMIT);
+    let _ = preview(&stored, 64);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use baihu::providers::http_client::validate_url_not_private;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(url) = std::str::from_utf8(data) {
+        let _ = validate_url_not_private(url);
+    }
+});
@@ -0,0 +1,193 @@
+//! What this build supports: version, cargo features, platform sandboxes and
+//! the backends compiled in. Backs `baihu features` and `GET /about`.
+
+use serde::Serialize;
+
+/// Provider names accepted by `providers::create_provider` (aliases omitted;
+/// `custom:<url>` takes any OpenAI-compatible endpoint).
+pub const PROVIDERS: &[&str] = &[
+    "openrouter",
+    "anthropic",
+    "openai",
+    "ollama",
+    "venice",
+    "vercel",
+    "cloudflare",
+    "moonshot",
+    "synthetic",
+    "opencode",
+    "zai",
+    "glm",
+    "minimax",
+    "bedrock",
+    "qianfan",
+    "groq",
+    "mistral",
+    "xai",
+    "deepseek",
+    "together",
+    "fireworks",
+    "perplexity",
+    "cohere",
+    "custom",
+];
+
+pub const CHANNELS: &[&str] = &[
+    "cli", "telegram", "discord", "slack", "imessage", "matrix", "whatsapp",
+];
+
+pub const TUNNELS: &[&str] = &["none", "cloudflare", "tailscale", "ngrok", "custom"];
+
+pub const MEMORY_BACKENDS: &[&str] = &["sqlite", "markdown"];
+
+pub const RUNTIMES: &[&str] = &["native"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub os: &'static str,
+    pub arch: &'static str,
+    /// Optional cargo features this binary was built with
+    pub features: Vec<&'static str>,
+    pub sandboxes: Vec<SandboxInfo>,
+    pub providers: &'static [&'static str],
+    pub channels: &'static [&'static str],
+    pub tunnels: &'static [&'static str],
+    pub memory_backends: &'static [&'static str],
+    pub runtimes: &'static [&'static str],
+}
+
+/// One OS-level sandbox the shell tool knows about.
+#[derive(Debug, Clone, Serialize)]
+pub struct SandboxInfo {
+    pub name: &'static str,
+    /// Built for this target
+    pub compiled: bool,
+    /// The running OS offers it
+    pub available: bool,
+    /// Applied to spawned commands today
+    pub enforced: bool,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        let mut features = Vec::new();
+        if cfg!(feature = "blocking") {
+            features.push("blocking");
+        }
+        if cfg!(feature = "testing") {
+            features.push("testing");
+        }
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            features,
+            sandboxes: sandboxes(),
+            providers: PROVIDERS,
+            channels: CHANNELS,
+            tunnels: TUNNELS,
+            memory_backends: MEMORY_BACKENDS,
+            runtimes: RUNTIMES,
+        }
+    }
+}
+
+// Both sandboxes are compiled for their targets but not yet attached to
+// spawned commands (see `tools::shell`), so neither reports as enforced.
+fn sandboxes() -> Vec<SandboxInfo> {
+    vec![
+        SandboxInfo {
+            name: "landlock",
+            compiled: cfg!(target_os = "linux"),
+            available: cfg!(target_os = "linux") && landlock_available(),
+            enforced: false,
+        },
+        SandboxInfo {
+            name: "job_objects",
+            compiled: cfg!(windows),
+            available: cfg!(windows),
+            enforced: false,
+        },
+    ]
+}
+
+/// Whether the kernel has the Landlock LSM enabled (5.13+, listed in the
+/// active LSMs).
+fn landlock_available() -> bool {
+    std::fs::read_to_string("/sys/kernel/security/lsm")
+        .is_ok_and(|lsms| lsms.trim().split(',').any(|lsm| lsm == "landlock"))
+}
+
+/// Human-readable report for `baihu features`.
+pub fn print() {
+    let info = BuildInfo::current();
+    println!("🦀 Baihu {} ({}/{})", info.version, info.os, info.arch);
+    println!();
+    println!(
+        "Features:   {}",
+        if info.features.is_empty() {
+            "(none)".to_string()
+        } else {
+            info.features.join(", ")
+        }
+    );
+    println!("Sandboxes:");
+    for sandbox in &info.sandboxes {
+        let state = match (sandbox.compiled, sandbox.available, sandbox.enforced) {
+            (false, _, _) => "not built for this target",
+            (true, false, _) => "built, not offered by this OS",
+            (true, true, false) => "built and available, not yet enforced",
+            (true, true, true) => "enforced",
+        };
+        println!("  {:<12} {state}", sandbox.name);
+    }
+    println!("Providers:  {}", info.providers.join(", "));
+    println!("Channels:   {}", info.channels.join(", "));
+    println!("Tunnels:    {}", info.tunnels.join(", "));
+    println!("Memory:     {}", info.memory_backends.join(", "));
+    println!("Runtimes:   {}", info.runtimes.join(", "));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listed_providers_are_accepted_by_the_factory() {
+        for name in PROVIDERS {
+            let name = if *name == "custom" {
+                "custom:https://llm.example.com"
+            } else {
+                name
+            };
+            assert!(
+                crate::providers::create_provider(name, Some("k")).is_ok(),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn listed_runtimes_are_accepted_by_the_factory() {
+        for kind in RUNTIMES {
+            let config = crate::config::RuntimeConfig {
+                kind: (*kind).to_string(),
+            };
+            assert!(crate::runtime::create_runtime(&config).is_ok(), "{kind}");
+        }
+    }
+
+    #[test]
+    fn sandboxes_match_target() {
+        let info = BuildInfo::current();
+        let landlock = info
+            .sandboxes
+            .iter()
+            .find(|s| s.name == "landlock")
+            .unwrap();
+        assert_eq!(landlock.compiled, cfg!(target_os = "linux"));
+        assert!(info.sandboxes.iter().all(|s| s.compiled || !s.available));
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+    }
+}
@@ -0,0 +1,221 @@
+use dashmap::DashMap;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// Cooperative cancellation signal shared between a running turn and whoever
+/// may want to abort it. Cloning yields a handle to the same signal.
+///
+/// A token may carry a deadline, after which it counts as cancelled. Everything
+/// downstream of the caller that created it (agent turn, provider chain, tool
+/// calls) races against the same token, so one deadline bounds the whole chain.
+#[derive(Debug, Clone)]
+pub struct CancelToken {
+    tx: Arc<watch::Sender<bool>>,
+    deadline: Option<Instant>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self {
+            tx: Arc::new(watch::channel(false).0),
+            deadline: None,
+        }
+    }
+
+    /// Whether both handles share one underlying signal.
+    fn same_signal(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.tx, &other.tx)
+    }
+
+    /// A token that also fires on its own at `deadline`.
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..Self::new()
+        }
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    pub fn cancel(&self) {
+        self.tx.send_replace(true);
+    }
+
+    /// True once `cancel` was called or the deadline has passed.
+    pub fn is_cancelled(&self) -> bool {
+        *self.tx.borrow() || self.is_expired()
+    }
+
+    /// True if the deadline has passed (regardless of explicit cancellation).
+    pub fn is_expired(&self) -> bool {
+        self.deadline.is_some_and(|at| Instant::now() >= at)
+    }
+
+    /// Resolves once `cancel` has been called or the deadline passes
+    /// (immediately if either already happened).
+    pub async fn cancelled(&self) {
+        let mut rx = self.tx.subscribe();
+        match self.deadline {
+            Some(at) => {
+                tokio::select! {
+                    _ = rx.wait_for(|cancelled| *cancelled) => {}
+                    () = tokio::time::sleep_until(at) => {}
+                }
+            }
+            None => {
+                let _ = rx.wait_for(|cancelled| *cancelled).await;
+            }
+        }
+    }
+}
+
+/// In-flight turns addressable by request id, so an external caller can abort them.
+#[derive(Debug, Clone, Default)]
+pub struct CancelRegistry {
+    inflight: Arc<DashMap<String, CancelToken>>,
+}
+
+impl CancelRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track a new turn. The entry is removed when the returned guard drops.
+    pub fn register(&self, request_id: &str) -> (CancelToken, CancelRegistration) {
+        self.register_token(request_id, CancelToken::new())
+    }
+
+    /// Like [`CancelRegistry::register`], with a token that expires at `deadline`.
+    pub fn register_with_deadline(
+        &self,
+        request_id: &str,
+        deadline: Instant,
+    ) -> (CancelToken, CancelRegistration) {
+        self.register_token(request_id, CancelToken::with_deadline(deadline))
+    }
+
+    fn register_token(
+        &self,
+        request_id: &str,
+        token: CancelToken,
+    ) -> (CancelToken, CancelRegistration) {
+        self.inflight.insert(request_id.to_string(), token.clone());
+        let guard = CancelRegistration {
+            inflight: Arc::clone(&self.inflight),
+            request_id: request_id.to_string(),
+            token: token.clone(),
+        };
+        (token, guard)
+    }
+
+    /// Cancel the turn with this id. Returns false if nothing is running under it.
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self.inflight.get(request_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Deregisters a request id from its [`CancelRegistry`] on drop.
+///
+/// Only its own entry is removed: if a later turn re-registered the same id,
+/// that newer registration stays addressable.
+pub struct CancelRegistration {
+    inflight: Arc<DashMap<String, CancelToken>>,
+    request_id: String,
+    token: CancelToken,
+}
+
+impl Drop for CancelRegistration {
+    fn drop(&mut self) {
+        self.inflight
+            .remove_if(&self.request_id, |_, token| token.same_signal(&self.token));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn cancelled_resolves_after_cancel() {
+        let token = CancelToken::new();
+        assert!(!token.is_cancelled());
+
+        let waiter = token.clone();
+        let handle = tokio::spawn(async move { waiter.cancelled().await });
+        token.cancel();
+
+        tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .expect("cancelled() should resolve")
+            .unwrap();
+        assert!(token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn cancelled_resolves_immediately_when_already_cancelled() {
+        let token = CancelToken::new();
+        token.cancel();
+        tokio::time::timeout(Duration::from_millis(100), token.cancelled())
+            .await
+            .expect("already-cancelled token should resolve");
+    }
+
+    #[tokio::test]
+    async fn deadline_fires_without_explicit_cancel() {
+        let token = CancelToken::with_deadline(Instant::now() + Duration::from_millis(20));
+        assert!(!token.is_cancelled());
+
+        tokio::time::timeout(Duration::from_secs(1), token.cancelled())
+            .await
+            .expect("deadline should fire");
+        assert!(token.is_cancelled());
+        assert!(token.is_expired());
+    }
+
+    #[tokio::test]
+    async fn explicit_cancel_beats_deadline() {
+        let token = CancelToken::with_deadline(Instant::now() + Duration::from_secs(60));
+        token.cancel();
+        tokio::time::timeout(Duration::from_millis(100), token.cancelled())
+            .await
+            .expect("cancel should resolve before the deadline");
+        assert!(!token.is_expired());
+    }
+
+    #[test]
+    fn registry_cancels_registered_request() {
+        let registry = CancelRegistry::new();
+        let (token, _guard) = registry.register("req-1");
+        assert!(registry.cancel("req-1"));
+        assert!(token.is_cancelled());
+        assert!(!registry.cancel("req-unknown"));
+    }
+
+    #[test]
+    fn registry_entry_removed_when_guard_drops() {
+        let registry = CancelRegistry::new();
+        let (_token, guard) = registry.register("req-2");
+        drop(guard);
+        assert!(!registry.cancel("req-2"));
+    }
+
+    #[test]
+    fn stale_guard_does_not_deregister_a_reused_id() {
+        let registry = CancelRegistry::new();
+        let (_first, first_guard) = registry.register("req-3");
+        let (second, _second_guard) = registry.register("req-3");
+        drop(first_guard);
+        assert!(registry.cancel("req-3"));
+        assert!(second.is_cancelled());
+    }
+}
@@ -1,217 +1,482 @@
+use super::cancel::CancelToken;
+use crate::channels::OutputFormat;
 use crate::config::Config;
 use crate::memory::{self, Memory, MemoryCategory};
 use crate::observability::{self, Observer, ObserverEvent};
-use crate::providers::{self, Provider};
+use crate::providers::{self, ChatClient, Provider};
 use crate::runtime;
 use crate::security::SecurityPolicy;
 use crate::tools;
 use anyhow::Result;
-use std::fmt::Write;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+/// Tokens held back for the reply when fitting a prompt into the context window.
+const RESPONSE_RESERVE_TOKENS: usize = 4_096;
+
+/// Search memory for entries relevant to this message, most relevant first.
+async fn recall_context(mem: &dyn Memory, user_msg: &str) -> Vec<String> {
+    mem.recall(user_msg, 5)
+        .await
+        .map(|entries| {
+            entries
+                .iter()
+                .map(|entry| format!("- {}: {}\n", entry.key, entry.content))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Prepend as many memory lines as fit in `window` (after the system prompt,
+/// the message and a reply reserve), dropping the least relevant first.
+/// Fails early when the bare prompt alone would not fit.
+fn fit_context(
+    system_prompt: &str,
+    memories: &[String],
+    user_msg: &str,
+    window: Option<usize>,
+) -> Result<String> {
+    let mut budget = usize::MAX;
+    if let Some(window) = window {
+        let reserve = RESPONSE_RESERVE_TOKENS.min(window / 4);
+        let bare = providers::estimate_tokens(system_prompt)
+            + providers::estimate_tokens(user_msg)
+            + reserve;
+        if bare > window {
+            anyhow::bail!(
+                "Prompt needs ~{bare} tokens (system prompt, message and {reserve} reserved for \
+                 the reply) but the model's context window is {window}. Shorten the message or \
+                 the workspace files that feed the system prompt."
+            );
+        }
+        budget = window - bare;
+    }
 
-/// Build context preamble by searching memory for relevant entries
-async fn build_context(mem: &dyn Memory, user_msg: &str) -> String {
     let mut context = String::new();
+    let mut used = providers::estimate_tokens("[Memory context]\n\n");
+    let mut dropped = 0;
+    for line in memories {
+        let cost = providers::estimate_tokens(line);
+        if used + cost > budget {
+            dropped += 1;
+            continue;
+        }
+        used += cost;
+        context.push_str(line);
+    }
+    if dropped > 0 {
+        tracing::debug!(dropped, "Trimmed memory context to fit the context window");
+    }
+
+    if context.is_empty() {
+        Ok(user_msg.to_string())
+    } else {
+        Ok(format!("[Memory context]\n{context}\n{user_msg}"))
+    }
+}
+
+/// Result of a single non-interactive agent turn.
+#[derive(Debug, Clone, Default)]
+pub struct AgentOutcome {
+    /// Final assistant text.
+    pub text: String,
+    /// Tools invoked while producing the answer, in call order.
+    pub tool_calls: Vec<ToolCallRecord>,
+    /// Tokens consumed, when the provider reports usage.
+    pub tokens_used: Option<u64>,
+}
+
+/// A tool invocation made during an agent turn.
+#[derive(Debug, Clone)]
+pub struct ToolCallRecord {
+    pub name: String,
+    pub success: bool,
+    pub duration: Duration,
+}
+
+/// Everything a turn needs, wired once per `run`/`run_capture` call.
+struct AgentContext {
+    observer: Arc<dyn Observer>,
+    mem: Arc<dyn Memory>,
+    /// Provider with the resolved model and turn temperature.
+    chat: ChatClient,
+    provider_name: String,
+    system_prompt: String,
+    auto_save: bool,
+    /// Shape replies are converted into before they are returned.
+    output_format: OutputFormat,
+}
+
+impl AgentContext {
+    fn new(
+        config: &Config,
+        provider_override: Option<&str>,
+        model_override: Option<&str>,
+        temperature: f64,
+    ) -> Result<Self> {
+        // ── Wire up agnostic subsystems ──────────────────────────────
+        let observer: Arc<dyn Observer> =
+            Arc::from(observability::create_observer(&config.observability));
+        let _runtime = runtime::create_runtime(&config.runtime)?;
+        let security = Arc::new(SecurityPolicy::from_config(
+            &config.autonomy,
+            &config.workspace_dir,
+        ));
+        security.validate_workspace()?;
+
+        // ── Memory (the brain) ────────────────────────────────────────
+        let mem: Arc<dyn Memory> = Arc::from(memory::create_memory(
+            &config.memory,
+            &config.workspace_dir,
+            config.api_key.as_deref(),
+        )?);
+        tracing::info!(backend = mem.name(), "Memory initialized");
+
+        // ── Tools (including memory tools) ────────────────────────────
+        let tools = tools::ToolRegistry::from_config(config, &security, mem.clone());
+
+        // ── Resolve provider ─────────────────────────────────────────
+        let provider_name = provider_override
+            .or(config.default_provider.as_deref())
+            .unwrap_or("openrouter")
+            .to_string();
+
+        let model_name = model_override.unwrap_or_else(|| providers::client::default_model(config));
+
+        let provider: Arc<dyn Provider> = Arc::from(providers::with_configured_tap(
+            providers::create_resilient_provider_with_state(
+                &provider_name,
+                config.api_key.as_deref(),
+                &config.reliability,
+                &config.request_overrides,
+                Some(&providers::state_file_path(
+                    config,
+                    &format!("agent-{provider_name}"),
+                )),
+                Some(observer.clone()),
+            )?,
+            config,
+        ));
+        let chat = ChatClient::new(provider, model_name, temperature);
+
+        // ── Build system prompt from workspace MD files ──
+        let skills = crate::skills::load_skills(&config.workspace_dir);
+        let tool_descs = tools.prompt_descriptions();
+        let system_prompt = crate::channels::build_system_prompt(
+            &config.workspace_dir,
+            model_name,
+            &tool_descs,
+            &skills,
+        );
 
-    // Pull relevant memories for this message
-    if let Ok(entries) = mem.recall(user_msg, 5).await {
-        if !entries.is_empty() {
-            context.push_str("[Memory context]\n");
-            for entry in &entries {
-                let _ = writeln!(context, "- {}: {}", entry.key, entry.content);
+        Ok(Self {
+            observer,
+            mem,
+            chat,
+            provider_name,
+            system_prompt,
+            auto_save: config.memory.auto_save,
+            output_format: OutputFormat::Markdown,
+        })
+    }
+
+    /// Ask the model for `format` and convert its replies to match.
+    fn with_output_format(mut self, format: OutputFormat) -> Self {
+        if format != OutputFormat::Markdown {
+            self.system_prompt.push_str(&format.prompt_section());
+        }
+        self.output_format = format;
+        self
+    }
+
+    fn record_start(&self) {
+        self.observer.record_event(&ObserverEvent::AgentStart {
+            provider: self.provider_name.clone(),
+            model: self.chat.model().to_string(),
+        });
+    }
+
+    fn record_end(&self, duration: Duration, tokens_used: Option<u64>) {
+        self.observer.record_event(&ObserverEvent::AgentEnd {
+            duration,
+            tokens_used,
+        });
+    }
+
+    /// One user message in, one assistant response out (with memory enrichment).
+    /// Aborts as soon as `cancel` fires, dropping the in-flight provider call.
+    /// Runs inside an `agent_turn` span so tool-call spans nest under it.
+    async fn turn(&self, msg: &str, cancel: &CancelToken) -> Result<AgentOutcome> {
+        let span = tracing::info_span!(
+            "agent_turn",
+            provider = %self.provider_name,
+            model = %self.chat.model(),
+        );
+        self.turn_inner(msg, cancel).instrument(span).await
+    }
+
+    async fn turn_inner(&self, msg: &str, cancel: &CancelToken) -> Result<AgentOutcome> {
+        // Auto-save user message to memory
+        if self.auto_save {
+            let _ = self
+                .mem
+                .store("user_msg", msg, MemoryCategory::Conversation)
+                .await;
+        }
+
+        // Inject memory context into user message, trimmed to the context window
+        let memories = recall_context(self.mem.as_ref(), msg).await;
+        let enriched = fit_context(
+            &self.system_prompt,
+            &memories,
+            msg,
+            self.chat.context_window(),
+        )?;
+
+        // A single call can fail over freely. Once a turn makes several
+        // (tool rounds), send them all through one `ProviderPin` so a
+        // fallback never picks up another provider's tool-call history.
+        let call = self
+            .chat
+            .ask_with_system(Some(&self.system_prompt), &enriched);
+        let response = tokio::select! {
+            result = call => result?,
+            () = cancel.cancelled() => {
+                if cancel.is_expired() {
+                    anyhow::bail!("Agent turn deadline exceeded");
+                }
+                anyhow::bail!("Agent turn cancelled")
             }
-            context.push('\n');
+        };
+
+        if self.auto_save {
+            self.save_reply(&response).await;
         }
+
+        Ok(AgentOutcome {
+            text: self.output_format.render(&response),
+            tool_calls: Vec::new(),
+            tokens_used: None,
+        })
     }
 
-    context
+    /// Auto-save the assistant response to the daily log.
+    async fn save_reply(&self, response: &str) {
+        let summary = if response.len() > 100 {
+            format!("{}...", &response[..100])
+        } else {
+            response.to_string()
+        };
+        // One entry per turn, so recall surfaces single replies rather
+        // than a whole day's log. The suffix keeps concurrent turns apart.
+        let key = format!(
+            "assistant_log_{}_{}",
+            chrono::Local::now().format("%Y-%m-%d_%H%M%S"),
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        );
+        let _ = self.mem.store(&key, &summary, MemoryCategory::Daily).await;
+    }
+}
+
+/// Run a single agent turn and return its outcome instead of printing it.
+pub async fn run_capture(
+    config: Config,
+    message: String,
+    provider_override: Option<String>,
+    model_override: Option<String>,
+    temperature: f64,
+) -> Result<AgentOutcome> {
+    run_capture_with_cancel(
+        config,
+        message,
+        provider_override,
+        model_override,
+        temperature,
+        CancelToken::new(),
+    )
+    .await
+}
+
+/// Like [`run_capture`], but aborts the turn when `cancel` fires.
+pub async fn run_capture_with_cancel(
+    config: Config,
+    message: String,
+    provider_override: Option<String>,
+    model_override: Option<String>,
+    temperature: f64,
+    cancel: CancelToken,
+) -> Result<AgentOutcome> {
+    let agent = AgentContext::new(
+        &config,
+        provider_override.as_deref(),
+        model_override.as_deref(),
+        temperature,
+    )?;
+    agent.record_start();
+
+    let start = Instant::now();
+    let outcome = agent.turn(&message, &cancel).await?;
+    agent.record_end(start.elapsed(), outcome.tokens_used);
+
+    Ok(outcome)
 }
 
-#[allow(clippy::too_many_lines)]
 pub async fn run(
     config: Config,
     message: Option<String>,
     provider_override: Option<String>,
     model_override: Option<String>,
     temperature: f64,
+    output_format: OutputFormat,
 ) -> Result<()> {
-    // ── Wire up agnostic subsystems ──────────────────────────────
-    let observer: Arc<dyn Observer> =
-        Arc::from(observability::create_observer(&config.observability));
-    let _runtime = runtime::create_runtime(&config.runtime)?;
-    let security = Arc::new(SecurityPolicy::from_config(
-        &config.autonomy,
-        &config.workspace_dir,
-    ));
-
-    // ── Memory (the brain) ────────────────────────────────────────
-    let mem: Arc<dyn Memory> = Arc::from(memory::create_memory(
-        &config.memory,
-        &config.workspace_dir,
-        config.api_key.as_deref(),
-    )?);
-    tracing::info!(backend = mem.name(), "Memory initialized");
-
-    // ── Tools (including memory tools) ────────────────────────────
-    let composio_key = if config.composio.enabled {
-        config.composio.api_key.as_deref()
-    } else {
-        None
-    };
-    let _tools = tools::all_tools(&security, mem.clone(), composio_key, &config.browser);
-
-    // ── Resolve provider ─────────────────────────────────────────
-    let provider_name = provider_override
-        .as_deref()
-        .or(config.default_provider.as_deref())
-        .unwrap_or("openrouter");
-
-    let model_name = model_override
-        .as_deref()
-        .or(config.default_model.as_deref())
-        .unwrap_or("anthropic/claude-sonnet-4-20250514");
-
-    let provider: Box<dyn Provider> = providers::create_resilient_provider(
-        provider_name,
-        config.api_key.as_deref(),
-        &config.reliability,
-    )?;
+    let mut agent = AgentContext::new(
+        &config,
+        provider_override.as_deref(),
+        model_override.as_deref(),
+        temperature,
+    )?
+    .with_output_format(output_format);
+    // Someone is typing at the terminal: always answer fresh.
+    agent.chat = agent.chat.uncached();
+    agent.record_start();
 
-    observer.record_event(&ObserverEvent::AgentStart {
-        provider: provider_name.to_string(),
-        model: model_name.to_string(),
-    });
-
-    // ── Build system prompt from workspace MD files ──
-    let skills = crate::skills::load_skills(&config.workspace_dir);
-    let mut tool_descs: Vec<(&str, &str)> = vec![
-        (
-            "shell",
-            "Execute terminal commands. Use when: running local checks, build/test commands, diagnostics. Don't use when: a safer dedicated tool exists, or command is destructive without approval.",
-        ),
-        (
-            "file_read",
-            "Read file contents. Use when: inspecting project files, configs, logs. Don't use when: a targeted search is enough.",
-        ),
-        (
-            "file_write",
-            "Write file contents. Use when: applying focused edits, scaffolding files, updating docs/code. Don't use when: side effects are unclear or file ownership is uncertain.",
-        ),
-        (
-            "memory_store",
-            "Save to memory. Use when: preserving durable preferences, decisions, key context. Don't use when: information is transient/noisy/sensitive without need.",
-        ),
-        (
-            "memory_recall",
-            "Search memory. Use when: retrieving prior decisions, user preferences, historical context. Don't use when: answer is already in current context.",
-        ),
-        (
-            "memory_forget",
-            "Delete a memory entry. Use when: memory is incorrect/stale or explicitly requested for removal. Don't use when: impact is uncertain.",
-        ),
-    ];
-    if config.browser.enabled {
-        tool_descs.push((
-            "browser_open",
-            "Open approved HTTPS URLs in Brave Browser (allowlist-only, no scraping)",
-        ));
+    if let Some(msg) = message {
+        let start = Instant::now();
+        let outcome = agent.turn(&msg, &CancelToken::new()).await?;
+        agent.record_end(start.elapsed(), outcome.tokens_used);
+        println!("{}", outcome.text);
+        return Ok(());
     }
-    let system_prompt = crate::channels::build_system_prompt(
-        &config.workspace_dir,
-        model_name,
-        &tool_descs,
-        &skills,
-    );
 
     // ── Execute ──────────────────────────────────────────────────
     let start = Instant::now();
 
-    if let Some(msg) = message {
-        // Auto-save user message to memory
-        if config.memory.auto_save {
-            let _ = mem
-                .store("user_msg", &msg, MemoryCategory::Conversation)
-                .await;
-        }
+    println!("🦀 Baihu Interactive Mode");
+    println!("Type /quit to exit.\n");
 
-        // Inject memory context into user message
-        let context = build_context(mem.as_ref(), &msg).await;
-        let enriched = if context.is_empty() {
-            msg.clone()
-        } else {
-            format!("{context}{msg}")
-        };
+    let (tx, mut rx) = tokio::sync::mpsc::channel(32);
+    let cli = crate::channels::CliChannel::new();
 
-        let response = provider
-            .chat_with_system(Some(&system_prompt), &enriched, model_name, temperature)
-            .await?;
-        println!("{response}");
-
-        // Auto-save assistant response to daily log
-        if config.memory.auto_save {
-            let summary = if response.len() > 100 {
-                format!("{}...", &response[..100])
-            } else {
-                response.clone()
-            };
-            let _ = mem
-                .store("assistant_resp", &summary, MemoryCategory::Daily)
-                .await;
-        }
-    } else {
-        println!("🦀 Baihu Interactive Mode");
-        println!("Type /quit to exit.\n");
+    // Spawn listener
+    let listen_handle = tokio::spawn(async move {
+        let _ = crate::channels::Channel::listen(&cli, tx).await;
+    });
 
-        let (tx, mut rx) = tokio::sync::mpsc::channel(32);
-        let cli = crate::channels::CliChannel::new();
+    let never_cancelled = CancelToken::new();
+    while let Some(msg) = rx.recv().await {
+        let outcome = agent.turn(&msg.content, &never_cancelled).await?;
+        println!("\n{}\n", outcome.text);
+    }
 
-        // Spawn listener
-        let listen_handle = tokio::spawn(async move {
-            let _ = crate::channels::Channel::listen(&cli, tx).await;
-        });
+    listen_handle.abort();
 
-        while let Some(msg) = rx.recv().await {
-            // Auto-save conversation turns
-            if config.memory.auto_save {
-                let _ = mem
-                    .store("user_msg", &msg.content, MemoryCategory::Conversation)
-                    .await;
-            }
+    agent.record_end(start.elapsed(), None);
 
-            // Inject memory context into user message
-            let context = build_context(mem.as_ref(), &msg.content).await;
-            let enriched = if context.is_empty() {
-                msg.content.clone()
-            } else {
-                format!("{context}{}", msg.content)
-            };
-
-            let response = provider
-                .chat_with_system(Some(&system_prompt), &enriched, model_name, temperature)
-                .await?;
-            println!("\n{response}\n");
-
-            if config.memory.auto_save {
-                let summary = if response.len() > 100 {
-                    format!("{}...", &response[..100])
-                } else {
-                    response.clone()
-                };
-                let _ = mem
-                    .store("assistant_resp", &summary, MemoryCategory::Daily)
-                    .await;
-            }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memories() -> Vec<String> {
+        vec![
+            "- lang: Rust\n".to_string(),
+            format!("- notes: {}\n", "x".repeat(4_000)),
+            "- editor: helix\n".to_string(),
+        ]
+    }
+
+    #[test]
+    fn fit_context_keeps_everything_without_window() {
+        let prompt = fit_context("sys", &memories(), "hi", None).unwrap();
+        assert!(prompt.starts_with("[Memory context]\n- lang: Rust\n"));
+        assert!(prompt.contains("- notes: "));
+        assert!(prompt.ends_with("- editor: helix\n\nhi"));
+    }
+
+    #[test]
+    fn fit_context_drops_entries_that_do_not_fit() {
+        let prompt = fit_context("sys", &memories(), "hi", Some(1_000)).unwrap();
+        assert!(prompt.contains("- lang: Rust"));
+        assert!(prompt.contains("- editor: helix"));
+        assert!(!prompt.contains("- notes: "));
+    }
+
+    #[test]
+    fn fit_context_without_memories_is_the_message() {
+        assert_eq!(fit_context("sys", &[], "hi", Some(1_000)).unwrap(), "hi");
+    }
+
+    #[test]
+    fn fit_context_errors_when_bare_prompt_is_too_big() {
+        let system = "s".repeat(40_000);
+        let err = fit_context(&system, &memories(), "hi", Some(8_192)).unwrap_err();
+        assert!(err.to_string().contains("context window is 8192"), "{err}");
+    }
+
+    /// Replies with each scripted answer in turn and records the prompts.
+    struct ScriptedProvider {
+        replies: parking_lot::Mutex<Vec<&'static str>>,
+        prompts: parking_lot::Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Provider for ScriptedProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> Result<String> {
+            self.prompts.lock().push(message.to_string());
+            Ok(self.replies.lock().remove(0).to_string())
         }
+    }
 
-        listen_handle.abort();
+    fn scripted_agent(
+        replies: Vec<&'static str>,
+        auto_save: bool,
+    ) -> (tempfile::TempDir, Arc<ScriptedProvider>, AgentContext) {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let provider = Arc::new(ScriptedProvider {
+            replies: parking_lot::Mutex::new(replies),
+            prompts: parking_lot::Mutex::default(),
+        });
+        let mem_cfg = crate::config::MemoryConfig {
+            backend: "sqlite".into(),
+            ..crate::config::MemoryConfig::default()
+        };
+        let agent = AgentContext {
+            observer: Arc::new(observability::NoopObserver),
+            mem: Arc::from(memory::create_memory(&mem_cfg, tmp.path(), None).unwrap()),
+            chat: ChatClient::new(provider.clone(), "test-model", 0.0),
+            provider_name: "scripted".into(),
+            system_prompt: String::new(),
+            auto_save,
+            output_format: OutputFormat::Markdown,
+        };
+        (tmp, provider, agent)
     }
 
-    let duration = start.elapsed();
-    observer.record_event(&ObserverEvent::AgentEnd {
-        duration,
-        tokens_used: None,
-    });
+    #[tokio::test]
+    async fn auto_save_keeps_one_log_entry_per_turn() {
+        let (_tmp, _provider, agent) = scripted_agent(vec!["first reply", "second reply"], true);
+        agent.turn("one", &CancelToken::new()).await.unwrap();
+        agent.turn("two", &CancelToken::new()).await.unwrap();
 
-    Ok(())
+        let logs: Vec<String> = agent
+            .mem
+            .list(Some(&MemoryCategory::Daily))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.content)
+            .collect();
+        assert_eq!(logs.len(), 2, "{logs:?}");
+        assert!(logs.contains(&"first reply".to_string()));
+        assert!(logs.contains(&"second reply".to_string()));
+    }
 }
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+/// Identical calls allowed in one turn before the guard steps in.
+pub const DEFAULT_REPEAT_LIMIT: usize = 3;
+
+/// What the tool loop should do with the call it is about to make.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepeatVerdict {
+    /// Run the tool as usual.
+    Proceed,
+    /// Skip the call and hand this error back to the model as the tool result,
+    /// so it gets one chance to change course.
+    Warn(String),
+    /// The model ignored the warning: end the turn with this message.
+    Stop(String),
+}
+
+/// Per-turn tracker for repeated tool calls.
+///
+/// A model that keeps calling the same tool with the same arguments is
+/// stuck; an iteration cap catches that only after burning every remaining
+/// round. The guard spots the repetition itself, warns the model once, and
+/// ends the turn if it carries on. Create a fresh guard for every turn.
+#[derive(Debug)]
+pub struct RepeatGuard {
+    limit: usize,
+    /// Times each `(tool, args)` pair was seen this turn.
+    seen: HashMap<(String, String), usize>,
+    warned: bool,
+}
+
+impl RepeatGuard {
+    /// `limit` is clamped to at least 2: a single call is never a loop.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit: limit.max(2),
+            seen: HashMap::new(),
+            warned: false,
+        }
+    }
+
+    /// Record a call and decide whether it may run. Arguments are compared
+    /// as canonical JSON, so key order does not matter.
+    pub fn check(&mut self, tool: &str, args: &serde_json::Value) -> RepeatVerdict {
+        let count = self
+            .seen
+            .entry((tool.to_string(), args.to_string()))
+            .or_insert(0);
+        *count += 1;
+        if *count < self.limit {
+            return RepeatVerdict::Proceed;
+        }
+
+        let times = *count;
+        if self.warned {
+            return RepeatVerdict::Stop(format!(
+                "Stopped: the model kept calling '{tool}' with the same arguments \
+                 ({times} times) after being warned"
+            ));
+        }
+        self.warned = true;
+        RepeatVerdict::Warn(format!(
+            "Detected repeated tool call: '{tool}' was called {times} times with identical \
+             arguments. The result will not change. Use what you have, try different \
+             arguments, or answer the user."
+        ))
+    }
+}
+
+impl Default for RepeatGuard {
+    fn default() -> Self {
+        Self::new(DEFAULT_REPEAT_LIMIT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn distinct_calls_proceed() {
+        let mut guard = RepeatGuard::default();
+        for i in 0..10 {
+            let verdict = guard.check("shell", &json!({ "command": format!("echo {i}") }));
+            assert_eq!(verdict, RepeatVerdict::Proceed);
+        }
+    }
+
+    #[test]
+    fn warns_once_then_stops() {
+        let mut guard = RepeatGuard::new(3);
+        let args = json!({ "command": "ls", "cwd": "src" });
+        assert_eq!(guard.check("shell", &args), RepeatVerdict::Proceed);
+        assert_eq!(guard.check("shell", &args), RepeatVerdict::Proceed);
+
+        let RepeatVerdict::Warn(msg) = guard.check("shell", &args) else {
+            panic!("expected a warning");
+        };
+        assert!(msg.contains("Detected repeated tool call"));
+
+        // Other calls are still fine after the warning.
+        assert_eq!(
+            guard.check("file_read", &json!({ "path": "a" })),
+            RepeatVerdict::Proceed
+        );
+        // Key order doesn't hide a repeat.
+        let reordered = json!({ "cwd": "src", "command": "ls" });
+        assert!(matches!(
+            guard.check("shell", &reordered),
+            RepeatVerdict::Stop(_)
+        ));
+    }
+
+    #[test]
+    fn limit_is_at_least_two() {
+        let mut guard = RepeatGuard::new(0);
+        assert_eq!(guard.check("t", &json!({})), RepeatVerdict::Proceed);
+        assert!(matches!(
+            guard.check("t", &json!({})),
+            RepeatVerdict::Warn(_)
+        ));
+    }
+}
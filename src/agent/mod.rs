@@ -1,3 +1,8 @@
+pub mod cancel;
 pub mod loop_;
+pub mod loop_guard;
 
+pub use cancel::CancelRegistry;
+pub use cancel::CancelToken;
 pub use loop_::run;
+pub use loop_::run_capture;
@@ -0,0 +1,138 @@
+//! Blocking wrappers over the async API, for scripts and hosts without a
+//! tokio runtime (enable the `blocking` feature).
+//!
+//! Every call runs on one shared multi-threaded runtime started on first
+//! use. Calling in from inside a runtime fails instead of panicking: async
+//! code should use [`Provider`] and [`Memory`] directly.
+
+use crate::config::Config;
+use crate::memory::{self, Memory, MemoryCategory, MemoryEntry};
+use crate::providers::{self, Provider};
+use std::future::Future;
+use std::sync::OnceLock;
+use tokio::runtime::Runtime;
+
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .thread_name("baihu-blocking")
+            .enable_all()
+            .build()
+            .expect("failed to start the baihu blocking runtime")
+    })
+}
+
+fn block_on<T>(future: impl Future<Output = anyhow::Result<T>>) -> anyhow::Result<T> {
+    if tokio::runtime::Handle::try_current().is_ok() {
+        anyhow::bail!("baihu::blocking called from inside an async runtime; use the async API");
+    }
+    runtime().block_on(future)
+}
+
+/// A provider you can call without `.await`.
+pub struct BlockingProvider {
+    inner: Box<dyn Provider>,
+}
+
+impl BlockingProvider {
+    pub fn new(inner: Box<dyn Provider>) -> Self {
+        Self { inner }
+    }
+
+    /// The configured provider chain, with retries and fallbacks, as the
+    /// agent builds it.
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        providers::http_client::configure_tls(config)?;
+        let name = config.default_provider.as_deref().unwrap_or("openrouter");
+        let inner = providers::create_resilient_provider_with_state(
+            name,
+            config.api_key.as_deref(),
+            &config.reliability,
+            &config.request_overrides,
+            Some(&providers::state_file_path(config, "blocking")),
+            None,
+        )?;
+        Ok(Self::new(inner))
+    }
+
+    pub fn chat(&self, message: &str, model: &str, temperature: f64) -> anyhow::Result<String> {
+        block_on(self.inner.chat(message, model, temperature))
+    }
+
+    pub fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        block_on(
+            self.inner
+                .chat_with_system(system_prompt, message, model, temperature),
+        )
+    }
+}
+
+/// A memory backend you can call without `.await`.
+pub struct BlockingMemory {
+    inner: Box<dyn Memory>,
+}
+
+impl BlockingMemory {
+    pub fn new(inner: Box<dyn Memory>) -> Self {
+        Self { inner }
+    }
+
+    /// The configured backend for the configured workspace.
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let inner = memory::create_memory(
+            &config.memory,
+            &config.workspace_dir,
+            config.api_key.as_deref(),
+        )?;
+        Ok(Self::new(inner))
+    }
+
+    pub fn recall(&self, query: &str, limit: usize) -> anyhow::Result<Vec<MemoryEntry>> {
+        block_on(self.inner.recall(query, limit))
+    }
+
+    pub fn store(&self, key: &str, content: &str, category: MemoryCategory) -> anyhow::Result<()> {
+        block_on(self.inner.store(key, content, category))
+    }
+
+    pub fn get(&self, key: &str) -> anyhow::Result<Option<MemoryEntry>> {
+        block_on(self.inner.get(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MarkdownMemory;
+    use crate::providers::testing::ScriptedProvider;
+
+    #[test]
+    fn provider_chat_blocks_until_reply() {
+        let provider = BlockingProvider::new(Box::new(ScriptedProvider::new("hi there")));
+        assert_eq!(provider.chat("hello", "m", 0.0).unwrap(), "hi there");
+    }
+
+    #[test]
+    fn memory_round_trip() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mem = BlockingMemory::new(Box::new(MarkdownMemory::new(tmp.path())));
+        mem.store("lang", "prefers Rust", MemoryCategory::Core)
+            .unwrap();
+        let hits = mem.recall("Rust", 5).unwrap();
+        assert!(hits.iter().any(|e| e.content.contains("prefers Rust")));
+    }
+
+    #[tokio::test]
+    async fn refuses_to_nest_inside_a_runtime() {
+        let provider = BlockingProvider::new(Box::new(ScriptedProvider::new("hi")));
+        let err = provider.chat("hello", "m", 0.0).unwrap_err();
+        assert!(err.to_string().contains("inside an async runtime"));
+    }
+}
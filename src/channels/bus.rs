@@ -0,0 +1,136 @@
+//! Inbound message bus shared by every channel listener.
+//!
+//! Listeners push into one `mpsc` queue, whose single consumer (the agent
+//! loop) applies backpressure as before. Each message it takes is also copied
+//! to any number of taps — audit logs, metrics — over a `broadcast` channel. A
+//! tap that falls behind loses the oldest messages instead of stalling the
+//! listeners or the agent.
+
+use super::traits::ChannelMessage;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+/// Messages a tap may fall behind by before it starts losing them.
+const TAP_CAPACITY: usize = 256;
+
+pub struct MessageBus {
+    rx: mpsc::Receiver<ChannelMessage>,
+    taps: broadcast::Sender<ChannelMessage>,
+}
+
+impl MessageBus {
+    /// Like `mpsc::channel`: hand the sender to listeners and drop the last
+    /// copy to close the bus.
+    pub fn new(capacity: usize) -> (mpsc::Sender<ChannelMessage>, Self) {
+        let (tx, rx) = mpsc::channel(capacity);
+        let taps = broadcast::channel(TAP_CAPACITY).0;
+        (tx, Self { rx, taps })
+    }
+
+    /// Attach a tap. It sees messages taken by [`MessageBus::recv`] after
+    /// this call.
+    pub fn subscribe(&self) -> broadcast::Receiver<ChannelMessage> {
+        self.taps.subscribe()
+    }
+
+    /// Next inbound message, copied to every tap. `None` once all senders
+    /// are gone and the queue is drained.
+    pub async fn recv(&mut self) -> Option<ChannelMessage> {
+        let msg = self.rx.recv().await?;
+        // No taps attached is fine.
+        let _ = self.taps.send(msg.clone());
+        Some(msg)
+    }
+}
+
+/// Run `on_message` for every message a tap receives, until the bus closes.
+pub fn spawn_tap<F>(
+    name: &'static str,
+    mut rx: broadcast::Receiver<ChannelMessage>,
+    mut on_message: F,
+) -> JoinHandle<()>
+where
+    F: FnMut(&ChannelMessage) + Send + 'static,
+{
+    tokio::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(msg) => on_message(&msg),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(tap = name, skipped, "Inbound message tap fell behind");
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::traits::MessageKind;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn message(id: usize) -> ChannelMessage {
+        ChannelMessage {
+            id: id.to_string(),
+            sender: "alice".into(),
+            content: format!("hello {id}"),
+            channel: "test".into(),
+            timestamp: 0,
+            kind: MessageKind::New,
+            refers_to: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn taps_see_every_message_the_consumer_takes() {
+        let (tx, mut bus) = MessageBus::new(8);
+        let mut audit = bus.subscribe();
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&seen);
+        let metrics = spawn_tap("metrics", bus.subscribe(), move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        for id in 0..3 {
+            tx.send(message(id)).await.unwrap();
+        }
+        drop(tx);
+
+        let mut consumed = Vec::new();
+        while let Some(msg) = bus.recv().await {
+            consumed.push(msg.id);
+        }
+        assert_eq!(consumed, ["0", "1", "2"]);
+        assert_eq!(audit.recv().await.unwrap().content, "hello 0");
+
+        drop(bus);
+        metrics.await.unwrap();
+        assert_eq!(seen.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn slow_tap_drops_instead_of_stalling() {
+        let (tx, mut bus) = MessageBus::new(TAP_CAPACITY * 2);
+        let mut idle = bus.subscribe();
+
+        let total = TAP_CAPACITY + 10;
+        for id in 0..total {
+            tx.send(message(id)).await.unwrap();
+        }
+        drop(tx);
+        let mut consumed = 0;
+        while bus.recv().await.is_some() {
+            consumed += 1;
+        }
+        assert_eq!(consumed, total);
+
+        assert!(matches!(
+            idle.recv().await,
+            Err(broadcast::error::RecvError::Lagged(10))
+        ));
+        assert_eq!(idle.recv().await.unwrap().id, "10");
+    }
+}
@@ -1,4 +1,4 @@
-use super::traits::{Channel, ChannelMessage};
+use super::traits::{Channel, ChannelMessage, MessageKind};
 use async_trait::async_trait;
 use tokio::io::{self, AsyncBufReadExt, BufReader};
 use uuid::Uuid;
@@ -46,6 +46,8 @@ impl Channel for CliChannel {
                     .duration_since(std::time::UNIX_EPOCH)
                     .unwrap_or_default()
                     .as_secs(),
+                kind: MessageKind::New,
+                refers_to: None,
             };
 
             if tx.send(msg).await.is_err() {
@@ -93,6 +95,8 @@ mod tests {
             content: "hello".into(),
             channel: "cli".into(),
             timestamp: 1_234_567_890,
+            kind: MessageKind::New,
+            refers_to: None,
         };
         assert_eq!(msg.id, "test-id");
         assert_eq!(msg.sender, "user");
@@ -109,6 +113,8 @@ mod tests {
             content: "c".into(),
             channel: "ch".into(),
             timestamp: 0,
+            kind: MessageKind::New,
+            refers_to: None,
         };
         let cloned = msg.clone();
         assert_eq!(cloned.id, msg.id);
@@ -1,4 +1,5 @@
-use super::traits::{Channel, ChannelMessage};
+use super::traits::{Channel, ChannelMessage, MessageKind};
+use crate::agent::CancelToken;
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
 use serde_json::json;
@@ -83,6 +84,11 @@ impl Channel for DiscordChannel {
         "discord"
     }
 
+    fn max_inbound_chars(&self) -> usize {
+        // Nitro accounts may send up to 4000
+        4000
+    }
+
     async fn send(&self, message: &str, channel_id: &str) -> anyhow::Result<()> {
         let url = format!("https://discord.com/api/v10/channels/{channel_id}/messages");
         let body = json!({ "content": message });
@@ -97,8 +103,17 @@ impl Channel for DiscordChannel {
         Ok(())
     }
 
-    #[allow(clippy::too_many_lines)]
     async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
+        self.listen_until(tx, &CancelToken::new()).await
+    }
+
+    /// Sends a websocket Close frame on cancel instead of dropping mid-frame.
+    #[allow(clippy::too_many_lines)]
+    async fn listen_until(
+        &self,
+        tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+        cancel: &CancelToken,
+    ) -> anyhow::Result<()> {
         let bot_user_id = Self::bot_user_id_from_token(&self.bot_token).unwrap_or_default();
 
         // Get Gateway URL
@@ -165,6 +180,12 @@ impl Channel for DiscordChannel {
 
         loop {
             tokio::select! {
+                () = cancel.cancelled() => {
+                    tracing::info!("Discord: shutdown requested, closing gateway connection");
+                    let _ = write.send(Message::Close(None)).await;
+                    let _ = write.close().await;
+                    break;
+                }
                 _ = hb_rx.recv() => {
                     let hb = json!({"op": 1, "d": null});
                     if write.send(Message::Text(hb.to_string())).await.is_err() {
@@ -183,15 +204,43 @@ impl Channel for DiscordChannel {
                         Err(_) => continue,
                     };
 
-                    // Only handle MESSAGE_CREATE (opcode 0, type "MESSAGE_CREATE")
-                    let event_type = event.get("t").and_then(|t| t.as_str()).unwrap_or("");
-                    if event_type != "MESSAGE_CREATE" {
-                        continue;
-                    }
+                    // Dispatch events (opcode 0) for new, edited and deleted messages
+                    let kind = match event.get("t").and_then(|t| t.as_str()).unwrap_or("") {
+                        "MESSAGE_CREATE" => MessageKind::New,
+                        "MESSAGE_UPDATE" => MessageKind::Edited,
+                        "MESSAGE_DELETE" => MessageKind::Deleted,
+                        _ => continue,
+                    };
 
                     let Some(d) = event.get("d") else {
                         continue;
                     };
+                    let message_id = d.get("id").and_then(serde_json::Value::as_str).map(ToString::to_string);
+
+                    // Deletions carry only ids, so there is no author to filter on.
+                    if kind == MessageKind::Deleted {
+                        let channel_msg = ChannelMessage {
+                            id: Uuid::new_v4().to_string(),
+                            sender: d.get("channel_id").and_then(|c| c.as_str()).unwrap_or("").to_string(),
+                            content: String::new(),
+                            channel: "discord".to_string(),
+                            timestamp: std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_secs(),
+                            kind,
+                            refers_to: message_id,
+                        };
+                        if tx.send(channel_msg).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+
+                    // Embed unfurls also fire MESSAGE_UPDATE, without content or author.
+                    if kind == MessageKind::Edited && d.get("content").is_none() {
+                        continue;
+                    }
 
                     // Skip messages from the bot itself
                     let author_id = d.get("author").and_then(|a| a.get("id")).and_then(|i| i.as_str()).unwrap_or("");
@@ -234,6 +283,8 @@ impl Channel for DiscordChannel {
                             .duration_since(std::time::UNIX_EPOCH)
                             .unwrap_or_default()
                             .as_secs(),
+                        kind,
+                        refers_to: (kind == MessageKind::Edited).then_some(message_id).flatten(),
                     };
 
                     if tx.send(channel_msg).await.is_err() {
@@ -252,8 +303,7 @@ impl Channel for DiscordChannel {
             .header("Authorization", format!("Bot {}", self.bot_token))
             .send()
             .await
-            .map(|r| r.status().is_success())
-            .unwrap_or(false)
+            .is_ok_and(|r| r.status().is_success())
     }
 }
 
@@ -0,0 +1,432 @@
+//! Reply formats channels prefer, and conversion from the Markdown models
+//! naturally write into each of them.
+
+use serde::{Deserialize, Serialize};
+
+/// How an agent reply should be shaped for its destination.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    /// `CommonMark`, passed through untouched
+    #[default]
+    Markdown,
+    /// No markup at all
+    Plain,
+    /// A single JSON value
+    Json,
+    /// Slack `mrkdwn`
+    SlackMrkdwn,
+    /// Telegram `MarkdownV2`, with every reserved character escaped
+    TelegramMarkdownV2,
+}
+
+impl OutputFormat {
+    /// Instruction appended to the system prompt so the model aims for this format.
+    pub fn prompt_hint(self) -> &'static str {
+        match self {
+            Self::Markdown => "Format replies in Markdown.",
+            Self::Plain => {
+                "Reply in plain text only: no Markdown emphasis, headings, tables, or code fences."
+            }
+            Self::Json => {
+                "Reply with a single valid JSON value and nothing else: no prose, no code fences."
+            }
+            Self::SlackMrkdwn | Self::TelegramMarkdownV2 => {
+                "Keep formatting light (bold, italics, inline code, links, simple lists); \
+                 avoid tables and nested structure. It is converted for the chat client."
+            }
+        }
+    }
+
+    /// `## Output Format` section for the system prompt.
+    pub fn prompt_section(self) -> String {
+        format!("## Output Format\n\n{}\n\n", self.prompt_hint())
+    }
+
+    /// Convert a model reply (assumed Markdown) into this format.
+    pub fn render(self, reply: &str) -> String {
+        match self {
+            Self::Markdown => reply.to_string(),
+            Self::Json => normalize_json(reply),
+            Self::Plain | Self::SlackMrkdwn | Self::TelegramMarkdownV2 => convert(reply, self),
+        }
+    }
+}
+
+/// Strip a code fence around a JSON reply, or wrap non-JSON text as `{"text": ...}`.
+fn normalize_json(reply: &str) -> String {
+    let trimmed = reply.trim();
+    let inner = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map_or(trimmed, str::trim);
+
+    if serde_json::from_str::<serde_json::Value>(inner).is_ok() {
+        inner.to_string()
+    } else {
+        serde_json::json!({ "text": reply }).to_string()
+    }
+}
+
+/// Inline Markdown, parsed just far enough to re-emit in another dialect.
+#[derive(Debug, PartialEq)]
+enum Inline<'a> {
+    Text(&'a str),
+    Code(&'a str),
+    Bold(Vec<Inline<'a>>),
+    Italic(Vec<Inline<'a>>),
+    Link { text: Vec<Inline<'a>>, url: &'a str },
+}
+
+fn convert(reply: &str, format: OutputFormat) -> String {
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+
+    for line in reply.lines() {
+        let trimmed = line.trim_start();
+
+        if trimmed.starts_with("```") {
+            in_code_block = !in_code_block;
+            if format != OutputFormat::Plain {
+                lines.push("```".to_string());
+            }
+            continue;
+        }
+
+        if in_code_block {
+            lines.push(match format {
+                OutputFormat::SlackMrkdwn => escape_slack(line),
+                OutputFormat::TelegramMarkdownV2 => escape_telegram_code(line),
+                _ => line.to_string(),
+            });
+            continue;
+        }
+
+        let heading = trimmed.trim_start_matches('#');
+        if heading.len() < trimmed.len() && heading.starts_with(' ') {
+            let body = render_inline(&parse_inline(heading.trim()), format);
+            lines.push(match format {
+                OutputFormat::Plain => body,
+                _ => format!("*{body}*"),
+            });
+            continue;
+        }
+
+        let bullet = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|marker| trimmed.strip_prefix(marker));
+        if let Some(item) = bullet {
+            let indent = &line[..line.len() - trimmed.len()];
+            let marker = if format == OutputFormat::Plain {
+                "- "
+            } else {
+                "• "
+            };
+            lines.push(format!(
+                "{indent}{marker}{}",
+                render_inline(&parse_inline(item), format)
+            ));
+            continue;
+        }
+
+        lines.push(render_inline(&parse_inline(line), format));
+    }
+
+    // A reply cut off inside a code block would leave the fence open, and
+    // Telegram rejects the whole message over it.
+    if in_code_block && format != OutputFormat::Plain {
+        lines.push("```".to_string());
+    }
+
+    lines.join("\n")
+}
+
+fn parse_inline(s: &str) -> Vec<Inline<'_>> {
+    let mut out = Vec::new();
+    let mut text_start = 0;
+    let mut i = 0;
+
+    while i < s.len() {
+        let rest = &s[i..];
+        let parsed = if let Some(after) = rest.strip_prefix('`') {
+            after
+                .find('`')
+                .map(|end| (Inline::Code(&after[..end]), end + 2))
+        } else if rest.starts_with("**") || rest.starts_with("__") {
+            let marker = &rest[..2];
+            rest[2..]
+                .find(marker)
+                .filter(|&end| end > 0)
+                .map(|end| (Inline::Bold(parse_inline(&rest[2..2 + end])), end + 4))
+        } else if (rest.starts_with('*') || rest.starts_with('_')) && opens_emphasis(s, i) {
+            let marker = &rest[..1];
+            rest[1..]
+                .find(marker)
+                .filter(|&end| end > 0 && !rest[1..=end].ends_with(' '))
+                .map(|end| (Inline::Italic(parse_inline(&rest[1..=end])), end + 2))
+        } else if rest.starts_with('[') {
+            parse_link(rest)
+        } else {
+            None
+        };
+
+        if let Some((node, len)) = parsed {
+            if text_start < i {
+                out.push(Inline::Text(&s[text_start..i]));
+            }
+            out.push(node);
+            i += len;
+            text_start = i;
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    if text_start < s.len() {
+        out.push(Inline::Text(&s[text_start..]));
+    }
+    out
+}
+
+/// A lone `*` or `_` opens emphasis only at a word start, so `snake_case`
+/// and `2*3*4` stay literal.
+fn opens_emphasis(s: &str, at: usize) -> bool {
+    let next_is_word = s[at + 1..]
+        .chars()
+        .next()
+        .is_some_and(|c| !c.is_whitespace());
+    let prev_is_word = s[..at]
+        .chars()
+        .next_back()
+        .is_some_and(char::is_alphanumeric);
+    next_is_word && !prev_is_word
+}
+
+fn parse_link(rest: &str) -> Option<(Inline<'_>, usize)> {
+    let text_end = rest.find("](")?;
+    let text = &rest[1..text_end];
+    let url_start = text_end + 2;
+    let url_len = rest[url_start..].find(')')?;
+    let url = &rest[url_start..url_start + url_len];
+    if url.is_empty() || url.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((
+        Inline::Link {
+            text: parse_inline(text),
+            url,
+        },
+        url_start + url_len + 1,
+    ))
+}
+
+fn render_inline(nodes: &[Inline<'_>], format: OutputFormat) -> String {
+    let mut out = String::new();
+    for node in nodes {
+        match (node, format) {
+            (Inline::Text(text), OutputFormat::SlackMrkdwn) => out.push_str(&escape_slack(text)),
+            (Inline::Text(text), OutputFormat::TelegramMarkdownV2) => {
+                out.push_str(&escape_telegram(text));
+            }
+            (Inline::Text(text), _) => out.push_str(text),
+
+            (Inline::Code(code), OutputFormat::SlackMrkdwn) => {
+                out.push('`');
+                out.push_str(&escape_slack(code));
+                out.push('`');
+            }
+            (Inline::Code(code), OutputFormat::TelegramMarkdownV2) => {
+                out.push('`');
+                out.push_str(&escape_telegram_code(code));
+                out.push('`');
+            }
+            (Inline::Code(code), _) => out.push_str(code),
+
+            (Inline::Bold(inner) | Inline::Italic(inner), OutputFormat::Plain) => {
+                out.push_str(&render_inline(inner, format));
+            }
+            (Inline::Bold(inner), _) => {
+                out.push('*');
+                out.push_str(&render_inline(inner, format));
+                out.push('*');
+            }
+            (Inline::Italic(inner), _) => {
+                out.push('_');
+                out.push_str(&render_inline(inner, format));
+                out.push('_');
+            }
+
+            (Inline::Link { text, url }, OutputFormat::SlackMrkdwn) => {
+                out.push('<');
+                out.push_str(url);
+                out.push('|');
+                out.push_str(&render_inline(text, format));
+                out.push('>');
+            }
+            (Inline::Link { text, url }, OutputFormat::TelegramMarkdownV2) => {
+                out.push('[');
+                out.push_str(&render_inline(text, format));
+                out.push_str("](");
+                out.push_str(&url.replace('\\', "\\\\").replace(')', "\\)"));
+                out.push(')');
+            }
+            (Inline::Link { text, url }, _) => {
+                let label = render_inline(text, format);
+                out.push_str(&label);
+                if label != *url {
+                    out.push_str(" (");
+                    out.push_str(url);
+                    out.push(')');
+                }
+            }
+        }
+    }
+    out
+}
+
+fn escape_slack(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Characters Telegram `MarkdownV2` reserves outside code.
+const TELEGRAM_RESERVED: &str = "_*[]()~`>#+-=|{}.!\\";
+
+fn escape_telegram(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if TELEGRAM_RESERVED.contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn escape_telegram_code(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('`', "\\`")
+}
+
+/// Undo `MarkdownV2` escaping, for resending a reply Telegram refused to
+/// parse as plain text.
+pub fn unescape_telegram(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(&next) if c == '\\' && TELEGRAM_RESERVED.contains(next) => {
+                out.push(next);
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_passes_through() {
+        let reply = "**bold** and `code`";
+        assert_eq!(OutputFormat::Markdown.render(reply), reply);
+    }
+
+    #[test]
+    fn plain_strips_markup() {
+        let reply = "# Title\n**Bold** and *soft* with `code`.\n- item\nSee [docs](https://x.dev).";
+        assert_eq!(
+            OutputFormat::Plain.render(reply),
+            "Title\nBold and soft with code.\n- item\nSee docs (https://x.dev)."
+        );
+    }
+
+    #[test]
+    fn plain_keeps_identifiers_literal() {
+        let reply = "Set max_retries to 2*3*4 in config_file";
+        assert_eq!(OutputFormat::Plain.render(reply), reply);
+    }
+
+    #[test]
+    fn plain_drops_code_fences() {
+        let reply = "Run:\n```sh\ncargo test\n```";
+        assert_eq!(OutputFormat::Plain.render(reply), "Run:\ncargo test");
+    }
+
+    #[test]
+    fn slack_converts_bold_and_links() {
+        let reply = "**Done**: see [PR](https://x.dev/1) & <notes>";
+        assert_eq!(
+            OutputFormat::SlackMrkdwn.render(reply),
+            "*Done*: see <https://x.dev/1|PR> &amp; &lt;notes&gt;"
+        );
+    }
+
+    #[test]
+    fn telegram_escapes_reserved_characters() {
+        let reply = "**Done!** Version 1.2 (final) - see [site](https://x.dev/a_b)";
+        assert_eq!(
+            OutputFormat::TelegramMarkdownV2.render(reply),
+            "*Done\\!* Version 1\\.2 \\(final\\) \\- see [site](https://x.dev/a_b)"
+        );
+    }
+
+    #[test]
+    fn telegram_headings_and_bullets() {
+        let reply = "## Steps\n- first_step\n- `a.b`";
+        assert_eq!(
+            OutputFormat::TelegramMarkdownV2.render(reply),
+            "*Steps*\n• first\\_step\n• `a.b`"
+        );
+    }
+
+    #[test]
+    fn unclosed_code_block_is_closed() {
+        let reply = "Here:\n```rust\nlet x = 1;";
+        assert_eq!(
+            OutputFormat::TelegramMarkdownV2.render(reply),
+            "Here:\n```\nlet x = 1;\n```"
+        );
+        assert_eq!(OutputFormat::Plain.render(reply), "Here:\nlet x = 1;");
+    }
+
+    #[test]
+    fn telegram_unescape_reverses_escaping() {
+        let reply = "Version 1.2 (final) - a_b! C:\\path";
+        let escaped = OutputFormat::TelegramMarkdownV2.render(reply);
+        assert_ne!(escaped, reply);
+        assert_eq!(unescape_telegram(&escaped), reply);
+    }
+
+    #[test]
+    fn json_unwraps_fenced_json() {
+        let reply = "```json\n{\"ok\": true}\n```";
+        assert_eq!(OutputFormat::Json.render(reply), "{\"ok\": true}");
+    }
+
+    #[test]
+    fn json_wraps_prose() {
+        let rendered = OutputFormat::Json.render("just text");
+        let value: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(value["text"], "just text");
+    }
+
+    #[test]
+    fn unclosed_markers_stay_literal() {
+        assert_eq!(
+            OutputFormat::Plain.render("a ** b and [x](y"),
+            "a ** b and [x](y"
+        );
+    }
+
+    #[test]
+    fn prompt_section_has_heading() {
+        assert!(OutputFormat::Json
+            .prompt_section()
+            .starts_with("## Output Format\n\n"));
+    }
+}
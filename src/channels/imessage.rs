@@ -1,4 +1,5 @@
-use crate::channels::traits::{Channel, ChannelMessage};
+use crate::channels::format::OutputFormat;
+use crate::channels::traits::{Channel, ChannelMessage, MessageKind};
 use async_trait::async_trait;
 use directories::UserDirs;
 use tokio::sync::mpsc;
@@ -89,6 +90,10 @@ impl Channel for IMessageChannel {
         "imessage"
     }
 
+    fn output_format(&self) -> OutputFormat {
+        OutputFormat::Plain
+    }
+
     async fn send(&self, message: &str, target: &str) -> anyhow::Result<()> {
         // Defense-in-depth: validate target format before any interpolation
         if !is_valid_imessage_target(target) {
@@ -172,6 +177,8 @@ end tell"#
                                 .duration_since(std::time::UNIX_EPOCH)
                                 .unwrap_or_default()
                                 .as_secs(),
+                            kind: MessageKind::New,
+                            refers_to: None,
                         };
 
                         if tx.send(msg).await.is_err() {
@@ -501,7 +508,7 @@ mod tests {
     fn invalid_target_applescript_injection() {
         // Various injection attempts
         assert!(!is_valid_imessage_target(r#"test" & quit"#));
-        assert!(!is_valid_imessage_target(r#"test\ndo shell script"#));
+        assert!(!is_valid_imessage_target(r"test\ndo shell script"));
         assert!(!is_valid_imessage_target("test\"; malicious code; \""));
     }
 
@@ -1,4 +1,4 @@
-use crate::channels::traits::{Channel, ChannelMessage};
+use crate::channels::traits::{Channel, ChannelMessage, MessageKind};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::Deserialize;
@@ -47,6 +47,9 @@ struct TimelineEvent {
     sender: String,
     #[serde(default)]
     content: EventContent,
+    /// Target of an `m.room.redaction` (room versions before 11)
+    #[serde(default)]
+    redacts: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -55,6 +58,61 @@ struct EventContent {
     body: Option<String>,
     #[serde(default)]
     msgtype: Option<String>,
+    #[serde(default, rename = "m.relates_to")]
+    relates_to: Option<Relation>,
+    /// Replacement content of an edit; `body` holds a `* `-prefixed fallback
+    #[serde(default, rename = "m.new_content")]
+    new_content: Option<Box<EventContent>>,
+    /// Target of an `m.room.redaction` (room version 11+)
+    #[serde(default)]
+    redacts: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Relation {
+    #[serde(default)]
+    rel_type: Option<String>,
+    #[serde(default)]
+    event_id: Option<String>,
+}
+
+impl TimelineEvent {
+    /// Kind, text and referenced event of a message or redaction; `None`
+    /// for anything else.
+    fn classify(&self) -> Option<(MessageKind, String, Option<String>)> {
+        match self.event_type.as_str() {
+            "m.room.redaction" => {
+                let target = self
+                    .redacts
+                    .clone()
+                    .or_else(|| self.content.redacts.clone());
+                Some((MessageKind::Deleted, String::new(), target))
+            }
+            "m.room.message" => {
+                if self.content.msgtype.as_deref() != Some("m.text") {
+                    return None;
+                }
+                let edit_of = self
+                    .content
+                    .relates_to
+                    .as_ref()
+                    .filter(|r| r.rel_type.as_deref() == Some("m.replace"))
+                    .and_then(|r| r.event_id.clone());
+                match edit_of {
+                    Some(original) => {
+                        let body = self
+                            .content
+                            .new_content
+                            .as_ref()
+                            .and_then(|c| c.body.clone())?;
+                        Some((MessageKind::Edited, body, Some(original)))
+                    }
+                    None => Some((MessageKind::New, self.content.body.clone()?, None)),
+                }
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -210,16 +268,8 @@ impl Channel for MatrixChannel {
                         continue;
                     }
 
-                    // Only process text messages
-                    if event.event_type != "m.room.message" {
-                        continue;
-                    }
-
-                    if event.content.msgtype.as_deref() != Some("m.text") {
-                        continue;
-                    }
-
-                    let Some(ref body) = event.content.body else {
+                    // Only process text messages, their edits and redactions
+                    let Some((kind, body, refers_to)) = event.classify() else {
                         continue;
                     };
 
@@ -230,12 +280,14 @@ impl Channel for MatrixChannel {
                     let msg = ChannelMessage {
                         id: format!("mx_{}", chrono::Utc::now().timestamp_millis()),
                         sender: event.sender.clone(),
-                        content: body.clone(),
+                        content: body,
                         channel: "matrix".to_string(),
                         timestamp: std::time::SystemTime::now()
                             .duration_since(std::time::UNIX_EPOCH)
                             .unwrap_or_default()
                             .as_secs(),
+                        kind,
+                        refers_to,
                     };
 
                     if tx.send(msg).await.is_err() {
@@ -464,4 +516,38 @@ mod tests {
         let resp: SyncResponse = serde_json::from_str(json).unwrap();
         assert!(resp.rooms.join.is_empty());
     }
+
+    #[test]
+    fn classifies_new_edited_and_redacted_events() {
+        let parse = |json: &str| {
+            serde_json::from_str::<TimelineEvent>(json)
+                .unwrap()
+                .classify()
+        };
+
+        let new = r#"{"type":"m.room.message","sender":"@u:m","content":{"msgtype":"m.text","body":"hi"}}"#;
+        assert_eq!(parse(new), Some((MessageKind::New, "hi".into(), None)));
+
+        let edit = r#"{"type":"m.room.message","sender":"@u:m","content":{
+            "msgtype":"m.text","body":"* hello",
+            "m.new_content":{"msgtype":"m.text","body":"hello"},
+            "m.relates_to":{"rel_type":"m.replace","event_id":"$orig"}}}"#;
+        assert_eq!(
+            parse(edit),
+            Some((MessageKind::Edited, "hello".into(), Some("$orig".into())))
+        );
+
+        let old_redaction =
+            r#"{"type":"m.room.redaction","sender":"@u:m","redacts":"$a","content":{}}"#;
+        let new_redaction =
+            r#"{"type":"m.room.redaction","sender":"@u:m","content":{"redacts":"$b"}}"#;
+        assert_eq!(
+            parse(old_redaction),
+            Some((MessageKind::Deleted, String::new(), Some("$a".into())))
+        );
+        assert_eq!(parse(new_redaction).unwrap().2.as_deref(), Some("$b"));
+
+        let notice = r#"{"type":"m.room.message","sender":"@u:m","content":{"msgtype":"m.notice","body":"x"}}"#;
+        assert_eq!(parse(notice), None);
+    }
 }
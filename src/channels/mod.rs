@@ -1,25 +1,39 @@
+pub mod bus;
 pub mod cli;
 pub mod discord;
+pub mod format;
 pub mod imessage;
 pub mod matrix;
+pub mod signature;
 pub mod slack;
 pub mod telegram;
 pub mod traits;
 pub mod whatsapp;
 
+pub use bus::MessageBus;
 pub use cli::CliChannel;
 pub use discord::DiscordChannel;
+pub use format::OutputFormat;
 pub use imessage::IMessageChannel;
 pub use matrix::MatrixChannel;
+pub use signature::{verify_signature, SignatureScheme};
 pub use slack::SlackChannel;
 pub use telegram::TelegramChannel;
 pub use traits::Channel;
+use traits::MessageKind;
 pub use whatsapp::WhatsAppChannel;
 
+use crate::agent::CancelToken;
+use crate::config::schema::OversizedInbound;
 use crate::config::Config;
 use crate::memory::{self, Memory};
-use crate::providers::{self, Provider};
+use crate::observability::{Observer, ObserverEvent};
+use crate::providers::{self, ChatClient, Provider};
+use crate::security::{AutonomyLevel, SecurityPolicy};
+use crate::tools::ToolRegistry;
 use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -29,11 +43,71 @@ const BOOTSTRAP_MAX_CHARS: usize = 20_000;
 const DEFAULT_CHANNEL_INITIAL_BACKOFF_SECS: u64 = 2;
 const DEFAULT_CHANNEL_MAX_BACKOFF_SECS: u64 = 60;
 
+/// Rolls per-channel listener state up into one aggregate component: ok while
+/// every listener is up, degraded while some fail and the rest still serve,
+/// error once none are left.
+#[derive(Clone)]
+struct ChannelsHealth {
+    component: &'static str,
+    healthy: Arc<parking_lot::Mutex<BTreeMap<String, bool>>>,
+}
+
+impl ChannelsHealth {
+    fn new<'a>(component: &'static str, names: impl IntoIterator<Item = &'a str>) -> Self {
+        let healthy = names.into_iter().map(|n| (n.to_string(), true)).collect();
+        let health = Self {
+            component,
+            healthy: Arc::new(parking_lot::Mutex::new(healthy)),
+        };
+        health.publish(&health.healthy.lock());
+        health
+    }
+
+    fn set(&self, channel: &str, healthy: bool) {
+        let mut map = self.healthy.lock();
+        if map.insert(channel.to_string(), healthy) != Some(healthy) {
+            self.publish(&map);
+        }
+    }
+
+    fn publish(&self, map: &BTreeMap<String, bool>) {
+        let failing: Vec<&str> = map
+            .iter()
+            .filter(|(_, ok)| !**ok)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if failing.is_empty() {
+            crate::health::mark_component_ok(self.component);
+        } else if failing.len() == map.len() {
+            crate::health::mark_component_error(
+                self.component,
+                format!("all channels failing: {}", failing.join(", ")),
+            );
+        } else {
+            crate::health::mark_component_degraded(
+                self.component,
+                format!(
+                    "{} of {} channels failing: {}",
+                    failing.len(),
+                    map.len(),
+                    failing.join(", ")
+                ),
+            );
+        }
+    }
+}
+
+/// Run `ch.listen_until` in a restart loop until `shutdown` fires or the
+/// message bus closes. Cancellation lets the channel close its connection
+/// cleanly instead of being aborted mid-frame. Each listener backs off on its
+/// own, so one bad channel never restarts the others.
 fn spawn_supervised_listener(
     ch: Arc<dyn Channel>,
     tx: tokio::sync::mpsc::Sender<traits::ChannelMessage>,
     initial_backoff_secs: u64,
     max_backoff_secs: u64,
+    shutdown: CancelToken,
+    health: ChannelsHealth,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         let component = format!("channel:{}", ch.name());
@@ -42,8 +116,13 @@ fn spawn_supervised_listener(
 
         loop {
             crate::health::mark_component_ok(&component);
-            let result = ch.listen(tx.clone()).await;
+            health.set(ch.name(), true);
+            let result = ch.listen_until(tx.clone(), &shutdown).await;
 
+            if shutdown.is_cancelled() {
+                tracing::info!("Channel {} stopped", ch.name());
+                break;
+            }
             if tx.is_closed() {
                 break;
             }
@@ -59,13 +138,40 @@ fn spawn_supervised_listener(
                 }
             }
 
+            health.set(ch.name(), false);
             crate::health::bump_component_restart(&component);
-            tokio::time::sleep(Duration::from_secs(backoff)).await;
+            tokio::select! {
+                () = tokio::time::sleep(Duration::from_secs(backoff)) => {}
+                () = shutdown.cancelled() => break,
+            }
             backoff = backoff.saturating_mul(2).min(max_backoff);
         }
     })
 }
 
+/// Listener tasks that are aborted if dropped before being joined, so a
+/// caller that gives up on a graceful stop never leaves pollers running.
+struct Listeners(Vec<tokio::task::JoinHandle<()>>);
+
+impl Listeners {
+    async fn join(mut self) {
+        // Each handle stays in `self` until it finishes, so dropping this
+        // future midway still aborts the rest.
+        while let Some(handle) = self.0.last_mut() {
+            let _ = handle.await;
+            self.0.pop();
+        }
+    }
+}
+
+impl Drop for Listeners {
+    fn drop(&mut self) {
+        for handle in &self.0 {
+            handle.abort();
+        }
+    }
+}
+
 /// Load workspace identity files and build a system prompt.
 ///
 /// Follows the workspace framework structure:
@@ -189,9 +295,6 @@ pub fn build_system_prompt(
 }
 
 fn inject_identity(prompt: &mut String, workspace_dir: &std::path::Path) {
-    #[allow(unused_imports)]
-    use std::fmt::Write;
-
     prompt.push_str("## Project Context\n\n");
     prompt
         .push_str("The following workspace files define your identity, behavior, and context.\n\n");
@@ -249,6 +352,170 @@ fn inject_workspace_file(prompt: &mut String, workspace_dir: &std::path::Path, f
     }
 }
 
+/// Effective settings for agent turns triggered from one channel
+#[derive(Debug, Clone)]
+pub(crate) struct ChannelPersona {
+    pub(crate) system_prompt: String,
+    pub(crate) model: String,
+    pub(crate) temperature: f64,
+    pub(crate) autonomy: AutonomyLevel,
+    pub(crate) format: OutputFormat,
+    /// Inbound size limit in characters; 0 means unlimited
+    pub(crate) max_inbound_chars: usize,
+    /// Named workspace this channel works in; `None` is the default. Its
+    /// memory is swapped in per message, and `system_prompt` and `autonomy`
+    /// were already resolved from its directory and config
+    pub(crate) workspace: Option<String>,
+}
+
+impl ChannelPersona {
+    /// Client that sends this persona's prompt, model and temperature.
+    /// Channel messages come from people, so replies are never cached.
+    pub(crate) fn chat_client(&self, provider: &Arc<dyn Provider>) -> ChatClient {
+        ChatClient::new(Arc::clone(provider), &self.model, self.temperature)
+            .with_system_prompt(&self.system_prompt)
+            .uncached()
+    }
+}
+
+fn autonomy_guidance(level: AutonomyLevel) -> &'static str {
+    match level {
+        AutonomyLevel::ReadOnly => {
+            "Read-only: answer and observe, but do not modify files, run commands, or act externally."
+        }
+        AutonomyLevel::Supervised => {
+            "Supervised: ask for confirmation before running commands or changing anything."
+        }
+        AutonomyLevel::Full => {
+            "Full: you may act on your own within the workspace, still following the safety rules."
+        }
+    }
+}
+
+/// Apply `channels_config.personas[channel]` on top of the global defaults.
+/// `build_prompt` renders the base workspace prompt for the chosen model,
+/// describing the tools the persona's own policy allows; `format` and
+/// `max_inbound_chars` are what the channel declares.
+pub(crate) fn resolve_persona(
+    config: &Config,
+    channel: &str,
+    format: OutputFormat,
+    max_inbound_chars: usize,
+    default_model: &str,
+    build_prompt: &dyn Fn(&Path, &Arc<SecurityPolicy>, &str) -> String,
+) -> ChannelPersona {
+    use std::fmt::Write;
+
+    let overrides = config
+        .channels_config
+        .personas
+        .get(channel)
+        .cloned()
+        .unwrap_or_default();
+
+    let model = overrides.model.unwrap_or_else(|| default_model.to_string());
+    let autonomy = overrides.autonomy.unwrap_or(config.autonomy.level);
+    let security = Arc::new(SecurityPolicy::from_config(
+        &crate::config::AutonomyConfig {
+            level: autonomy,
+            ..config.autonomy.clone()
+        },
+        &config.workspace_dir,
+    ));
+
+    let mut system_prompt = build_prompt(&config.workspace_dir, &security, &model);
+    if format != OutputFormat::Markdown {
+        system_prompt.push_str(&format.prompt_section());
+    }
+    if overrides.autonomy.is_some() {
+        let _ = writeln!(
+            system_prompt,
+            "## Autonomy\n\n{}\n",
+            autonomy_guidance(autonomy)
+        );
+    }
+    if let Some(extra) = overrides.system_prompt.filter(|p| !p.trim().is_empty()) {
+        let _ = writeln!(system_prompt, "## Channel Persona\n\n{}\n", extra.trim());
+    }
+
+    ChannelPersona {
+        system_prompt,
+        model,
+        temperature: overrides.temperature.unwrap_or(config.default_temperature),
+        autonomy,
+        format,
+        max_inbound_chars: overrides.max_inbound_chars.unwrap_or(max_inbound_chars),
+        workspace: overrides.workspace,
+    }
+}
+
+/// A persona's base prompt for `dir`: the tools `security` allows and the
+/// skills installed there.
+pub(crate) fn persona_prompt(
+    config: &Config,
+    dir: &Path,
+    security: &Arc<SecurityPolicy>,
+    mem: Arc<dyn Memory>,
+    model: &str,
+) -> String {
+    let tools = ToolRegistry::from_config(config, security, mem);
+    build_system_prompt(
+        dir,
+        model,
+        &tools.prompt_descriptions(),
+        &crate::skills::load_skills(dir),
+    )
+}
+
+/// How an inbound message compares to its channel's size limit
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum InboundSize {
+    Fits,
+    /// Cut down to the limit; `chars` is the original length
+    Truncated {
+        chars: usize,
+    },
+    /// Too long and dropped; `chars` is its length
+    Rejected {
+        chars: usize,
+    },
+}
+
+/// Reply sent when an inbound message is rejected for its size.
+pub(crate) fn oversized_notice(chars: usize, max_chars: usize) -> String {
+    format!("⚠️ Message too long ({chars} characters, limit {max_chars}). Please send something shorter.")
+}
+
+/// Hold `content` to `max_chars` characters (0 = no limit), cutting or
+/// refusing it per `action`. Truncated text ends with a marker so the model
+/// knows it isn't seeing the whole message.
+pub(crate) fn limit_inbound(
+    content: &mut String,
+    max_chars: usize,
+    action: OversizedInbound,
+) -> InboundSize {
+    use std::fmt::Write;
+
+    if max_chars == 0 {
+        return InboundSize::Fits;
+    }
+    let Some((cut, _)) = content.char_indices().nth(max_chars) else {
+        return InboundSize::Fits;
+    };
+    let chars = max_chars + content[cut..].chars().count();
+    match action {
+        OversizedInbound::Reject => InboundSize::Rejected { chars },
+        OversizedInbound::Truncate => {
+            content.truncate(cut);
+            let _ = write!(
+                content,
+                "\n\n[Message truncated: {chars} characters, only the first {max_chars} kept]"
+            );
+            InboundSize::Truncated { chars }
+        }
+    }
+}
+
 pub fn handle_command(command: super::ChannelCommands, config: &Config) -> Result<()> {
     match command {
         super::ChannelCommands::Start => {
@@ -418,18 +685,55 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
 }
 
 /// Start all configured channels and route messages to the agent
-#[allow(clippy::too_many_lines)]
 pub async fn start_channels(config: Config) -> Result<()> {
-    let provider: Arc<dyn Provider> = Arc::from(providers::create_resilient_provider(
-        config.default_provider.as_deref().unwrap_or("openrouter"),
-        config.api_key.as_deref(),
-        &config.reliability,
-    )?);
-    let model = config
-        .default_model
-        .clone()
-        .unwrap_or_else(|| "anthropic/claude-sonnet-4-20250514".into());
-    let temperature = config.default_temperature;
+    let observer: Arc<dyn Observer> =
+        Arc::from(crate::observability::create_observer(&config.observability));
+    let provider: Arc<dyn Provider> = Arc::from(providers::with_configured_tap(
+        providers::create_resilient_provider_with_state(
+            config.default_provider.as_deref().unwrap_or("openrouter"),
+            config.api_key.as_deref(),
+            &config.reliability,
+            &config.request_overrides,
+            Some(&providers::state_file_path(&config, "channels")),
+            Some(Arc::clone(&observer)),
+        )?,
+        &config,
+    ));
+
+    // Ctrl+C asks every listener to disconnect; the bus then drains and closes.
+    let shutdown = CancelToken::new();
+    {
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                shutdown.cancel();
+            }
+        });
+    }
+    serve_channels(config, provider, observer, &shutdown).await
+}
+
+/// [`start_channels`] answering through `provider` until `shutdown` fires,
+/// for callers that share one chain across components and own shutdown.
+/// Every listener is asked to disconnect and joined before this returns.
+pub async fn start_channels_until(
+    config: Config,
+    provider: Arc<dyn Provider>,
+    shutdown: &CancelToken,
+) -> Result<()> {
+    let observer: Arc<dyn Observer> =
+        Arc::from(crate::observability::create_observer(&config.observability));
+    serve_channels(config, provider, observer, shutdown).await
+}
+
+#[allow(clippy::too_many_lines)]
+async fn serve_channels(
+    config: Config,
+    provider: Arc<dyn Provider>,
+    observer: Arc<dyn Observer>,
+    shutdown: &CancelToken,
+) -> Result<()> {
+    let model = providers::client::default_model(&config).to_string();
     let mem: Arc<dyn Memory> = Arc::from(memory::create_memory(
         &config.memory,
         &config.workspace_dir,
@@ -440,42 +744,34 @@ pub async fn start_channels(config: Config) -> Result<()> {
     let workspace = config.workspace_dir.clone();
     let skills = crate::skills::load_skills(&workspace);
 
-    // Collect tool descriptions for the prompt
-    let mut tool_descs: Vec<(&str, &str)> = vec![
-        (
-            "shell",
-            "Execute terminal commands. Use when: running local checks, build/test commands, diagnostics. Don't use when: a safer dedicated tool exists, or command is destructive without approval.",
-        ),
-        (
-            "file_read",
-            "Read file contents. Use when: inspecting project files, configs, logs. Don't use when: a targeted search is enough.",
-        ),
-        (
-            "file_write",
-            "Write file contents. Use when: applying focused edits, scaffolding files, updating docs/code. Don't use when: side effects are unclear or file ownership is uncertain.",
-        ),
-        (
-            "memory_store",
-            "Save to memory. Use when: preserving durable preferences, decisions, key context. Don't use when: information is transient/noisy/sensitive without need.",
-        ),
-        (
-            "memory_recall",
-            "Search memory. Use when: retrieving prior decisions, user preferences, historical context. Don't use when: answer is already in current context.",
-        ),
-        (
-            "memory_forget",
-            "Delete a memory entry. Use when: memory is incorrect/stale or explicitly requested for removal. Don't use when: impact is uncertain.",
-        ),
-    ];
-
-    if config.browser.enabled {
-        tool_descs.push((
-            "browser_open",
-            "Open approved HTTPS URLs in Brave Browser (allowlist-only, no scraping)",
-        ));
+    // Named workspaces picked by channel personas, each with its own memory
+    let mut workspaces: HashMap<String, (Config, Arc<dyn Memory>)> = HashMap::new();
+    for name in config
+        .channels_config
+        .personas
+        .values()
+        .filter_map(|persona| persona.workspace.as_ref())
+    {
+        if workspaces.contains_key(name) {
+            continue;
+        }
+        let scoped = crate::doctor::preflight::prepare_named_workspace(&config, name)?;
+        let scoped_mem: Arc<dyn Memory> = Arc::from(memory::create_memory(
+            &scoped.memory,
+            &scoped.workspace_dir,
+            scoped.api_key.as_deref(),
+        )?);
+        workspaces.insert(name.clone(), (scoped, scoped_mem));
     }
 
-    let system_prompt = build_system_prompt(&workspace, &model, &tool_descs, &skills);
+    // Describe only the tools each persona's own policy allows
+    let build_prompt = |dir: &Path, security: &Arc<SecurityPolicy>, model: &str| {
+        let dir_mem = workspaces
+            .values()
+            .find(|(scoped, _)| scoped.workspace_dir == dir)
+            .map_or(&mem, |(_, scoped_mem)| scoped_mem);
+        persona_prompt(&config, dir, security, Arc::clone(dir_mem), model)
+    };
 
     if !skills.is_empty() {
         println!(
@@ -541,6 +837,37 @@ pub async fn start_channels(config: Config) -> Result<()> {
         return Ok(());
     }
 
+    let default_persona = resolve_persona(
+        &config,
+        "",
+        OutputFormat::Markdown,
+        traits::DEFAULT_MAX_INBOUND_CHARS,
+        &model,
+        &build_prompt,
+    );
+    let personas: HashMap<String, ChannelPersona> = channels
+        .iter()
+        .map(|ch| {
+            (
+                ch.name().to_string(),
+                resolve_persona(
+                    config
+                        .channels_config
+                        .personas
+                        .get(ch.name())
+                        .and_then(|persona| persona.workspace.as_ref())
+                        .and_then(|name| workspaces.get(name))
+                        .map_or(&config, |(scoped, _)| scoped),
+                    ch.name(),
+                    ch.output_format(),
+                    ch.max_inbound_chars(),
+                    &model,
+                    &build_prompt,
+                ),
+            )
+        })
+        .collect();
+
     println!("🦀 Baihu Channel Server");
     println!("  🤖 Model:    {model}");
     println!(
@@ -556,11 +883,26 @@ pub async fn start_channels(config: Config) -> Result<()> {
             .collect::<Vec<_>>()
             .join(", ")
     );
+    for (name, persona) in personas
+        .iter()
+        .filter(|(name, _)| config.channels_config.personas.contains_key(*name))
+    {
+        println!(
+            "  🎭 Persona:  {name} → {} (temp {}, autonomy {:?}{})",
+            persona.model,
+            persona.temperature,
+            persona.autonomy,
+            persona
+                .workspace
+                .as_ref()
+                .map_or_else(String::new, |ws| format!(", workspace {ws}"))
+        );
+    }
     println!();
     println!("  Listening for messages... (Ctrl+C to stop)");
     println!();
 
-    crate::health::mark_component_ok("channels");
+    let health = ChannelsHealth::new("channels", channels.iter().map(|ch| ch.name()));
 
     let initial_backoff_secs = config
         .reliability
@@ -572,22 +914,76 @@ pub async fn start_channels(config: Config) -> Result<()> {
         .max(DEFAULT_CHANNEL_MAX_BACKOFF_SECS);
 
     // Single message bus — all channels send messages here
-    let (tx, mut rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(100);
+    let (tx, mut bus) = MessageBus::new(100);
+    let _metrics = bus::spawn_tap("metrics", bus.subscribe(), move |msg| {
+        observer.record_event(&ObserverEvent::ChannelMessage {
+            channel: msg.channel.clone(),
+            direction: "inbound".into(),
+        });
+    });
 
-    // Spawn a listener for each channel
-    let mut handles = Vec::new();
+    // Spawn a listener for each channel. Once `shutdown` fires they all
+    // disconnect, and the bus then drains and closes.
+    let mut listeners = Listeners(Vec::new());
     for ch in &channels {
-        handles.push(spawn_supervised_listener(
+        listeners.0.push(spawn_supervised_listener(
             ch.clone(),
             tx.clone(),
             initial_backoff_secs,
             max_backoff_secs,
+            shutdown.clone(),
+            health.clone(),
         ));
     }
     drop(tx); // Drop our copy so rx closes when all channels stop
 
     // Process incoming messages — call the LLM and reply
-    while let Some(msg) = rx.recv().await {
+    while let Some(mut msg) = bus.recv().await {
+        // Edits and deletions still reach the taps, but the original was
+        // already answered — replying again would double up.
+        if msg.kind != MessageKind::New {
+            tracing::info!(
+                channel = %msg.channel,
+                sender = %msg.sender,
+                kind = ?msg.kind,
+                refers_to = ?msg.refers_to,
+                "Ignoring non-new channel message"
+            );
+            continue;
+        }
+
+        let persona = personas.get(&msg.channel).unwrap_or(&default_persona);
+        match limit_inbound(
+            &mut msg.content,
+            persona.max_inbound_chars,
+            config.channels_config.oversized_inbound,
+        ) {
+            InboundSize::Fits => {}
+            InboundSize::Truncated { chars } => tracing::warn!(
+                channel = %msg.channel,
+                sender = %msg.sender,
+                chars,
+                limit = persona.max_inbound_chars,
+                "Truncated oversized inbound message"
+            ),
+            InboundSize::Rejected { chars } => {
+                tracing::warn!(
+                    channel = %msg.channel,
+                    sender = %msg.sender,
+                    chars,
+                    limit = persona.max_inbound_chars,
+                    "Rejected oversized inbound message"
+                );
+                if let Some(ch) = channels.iter().find(|ch| ch.name() == msg.channel) {
+                    let reply = persona
+                        .format
+                        .render(&oversized_notice(chars, persona.max_inbound_chars));
+                    let _ = ch.send(&reply, &msg.sender).await;
+                }
+                continue;
+            }
+        }
+
         println!(
             "  💬 [{}] from {}: {}",
             msg.channel,
@@ -601,6 +997,11 @@ pub async fn start_channels(config: Config) -> Result<()> {
 
         // Auto-save to memory
         if config.memory.auto_save {
+            let mem = persona
+                .workspace
+                .as_ref()
+                .and_then(|name| workspaces.get(name))
+                .map_or(&mem, |(_, scoped_mem)| scoped_mem);
             let _ = mem
                 .store(
                     &format!("{}_{}", msg.channel, msg.sender),
@@ -610,11 +1011,13 @@ pub async fn start_channels(config: Config) -> Result<()> {
                 .await;
         }
 
-        // Call the LLM with system prompt (identity + soul + tools)
-        match provider
-            .chat_with_system(Some(&system_prompt), &msg.content, &model, temperature)
-            .await
-        {
+        // Call the LLM with the channel's persona (identity + soul + tools + overrides)
+        let chat = persona.chat_client(&provider);
+        let reply = providers::origin::scope(
+            providers::origin::RequestOrigin::channel(&msg.channel),
+            chat.ask(&msg.content),
+        );
+        match reply.await {
             Ok(response) => {
                 println!(
                     "  🤖 Reply: {}",
@@ -627,7 +1030,8 @@ pub async fn start_channels(config: Config) -> Result<()> {
                 // Find the channel that sent this message and reply
                 for ch in &channels {
                     if ch.name() == msg.channel {
-                        if let Err(e) = ch.send(&response, &msg.sender).await {
+                        let reply = persona.format.render(&response);
+                        if let Err(e) = ch.send(&reply, &msg.sender).await {
                             eprintln!("  ❌ Failed to reply on {}: {e}", ch.name());
                         }
                         break;
@@ -638,7 +1042,8 @@ pub async fn start_channels(config: Config) -> Result<()> {
                 eprintln!("  ❌ LLM error: {e}");
                 for ch in &channels {
                     if ch.name() == msg.channel {
-                        let _ = ch.send(&format!("⚠️ Error: {e}"), &msg.sender).await;
+                        let reply = persona.format.render(&format!("⚠️ Error: {e}"));
+                        let _ = ch.send(&reply, &msg.sender).await;
                         break;
                     }
                 }
@@ -647,9 +1052,7 @@ pub async fn start_channels(config: Config) -> Result<()> {
     }
 
     // Wait for all channel tasks
-    for h in handles {
-        let _ = h.await;
-    }
+    listeners.join().await;
 
     Ok(())
 }
@@ -878,6 +1281,205 @@ mod tests {
         assert!(prompt.contains(&format!("Working directory: `{}`", ws.path().display())));
     }
 
+    fn stub_prompt(workspace: &Path, _security: &Arc<SecurityPolicy>, model: &str) -> String {
+        format!("base prompt for {model} in {}\n", workspace.display())
+    }
+
+    #[test]
+    fn persona_defaults_without_override() {
+        let config = Config::default();
+        let persona = resolve_persona(
+            &config,
+            "telegram",
+            OutputFormat::Markdown,
+            traits::DEFAULT_MAX_INBOUND_CHARS,
+            "default-model",
+            &stub_prompt,
+        );
+
+        assert_eq!(persona.model, "default-model");
+        assert!((persona.temperature - config.default_temperature).abs() < f64::EPSILON);
+        assert_eq!(persona.autonomy, config.autonomy.level);
+        assert_eq!(
+            persona.system_prompt,
+            format!(
+                "base prompt for default-model in {}\n",
+                config.workspace_dir.display()
+            )
+        );
+        assert_eq!(persona.workspace, None);
+    }
+
+    #[test]
+    fn persona_builds_prompt_in_its_workspace() {
+        let mut config = Config::default();
+        config.channels_config.personas.insert(
+            "slack".into(),
+            crate::config::schema::ChannelPersonaConfig {
+                workspace: Some("work".into()),
+                ..Default::default()
+            },
+        );
+        // start_channels hands resolve_persona the workspace-scoped config
+        let mut scoped = config.clone();
+        scoped.workspace_dir = std::path::PathBuf::from("/srv/work");
+
+        let slack = resolve_persona(
+            &scoped,
+            "slack",
+            OutputFormat::Markdown,
+            traits::DEFAULT_MAX_INBOUND_CHARS,
+            "default-model",
+            &stub_prompt,
+        );
+        assert_eq!(slack.workspace.as_deref(), Some("work"));
+        assert!(slack
+            .system_prompt
+            .starts_with("base prompt for default-model in /srv/work"));
+    }
+
+    #[test]
+    fn persona_applies_channel_overrides() {
+        let mut config = Config::default();
+        config.channels_config.personas.insert(
+            "slack".into(),
+            crate::config::schema::ChannelPersonaConfig {
+                system_prompt: Some("Keep answers work-appropriate.".into()),
+                model: Some("work-model".into()),
+                temperature: Some(0.1),
+                autonomy: Some(AutonomyLevel::ReadOnly),
+                max_inbound_chars: Some(500),
+                workspace: None,
+            },
+        );
+
+        let slack = resolve_persona(
+            &config,
+            "slack",
+            OutputFormat::Markdown,
+            traits::DEFAULT_MAX_INBOUND_CHARS,
+            "default-model",
+            &stub_prompt,
+        );
+        assert_eq!(slack.model, "work-model");
+        assert!((slack.temperature - 0.1).abs() < f64::EPSILON);
+        assert_eq!(slack.autonomy, AutonomyLevel::ReadOnly);
+        assert_eq!(slack.max_inbound_chars, 500);
+        assert!(slack
+            .system_prompt
+            .starts_with("base prompt for work-model"));
+        assert!(slack.system_prompt.contains("## Autonomy\n\nRead-only"));
+        assert!(slack
+            .system_prompt
+            .contains("## Channel Persona\n\nKeep answers work-appropriate."));
+
+        let telegram = resolve_persona(
+            &config,
+            "telegram",
+            OutputFormat::Markdown,
+            traits::DEFAULT_MAX_INBOUND_CHARS,
+            "default-model",
+            &stub_prompt,
+        );
+        assert_eq!(telegram.model, "default-model");
+        assert!(!telegram.system_prompt.contains("## Channel Persona"));
+    }
+
+    #[test]
+    fn persona_tools_follow_its_autonomy() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.memory.backend = "markdown".into();
+        config.workspace_dir = tmp.path().to_path_buf();
+        config.channels_config.personas.insert(
+            "slack".into(),
+            crate::config::schema::ChannelPersonaConfig {
+                autonomy: Some(AutonomyLevel::ReadOnly),
+                ..Default::default()
+            },
+        );
+        let mem: Arc<dyn Memory> =
+            Arc::from(memory::create_memory(&config.memory, tmp.path(), None).unwrap());
+        let tool_prompt = |_: &Path, security: &Arc<SecurityPolicy>, _: &str| {
+            ToolRegistry::from_config(&config, security, Arc::clone(&mem))
+                .prompt_descriptions()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>()
+                .join("\n")
+                + "\n"
+        };
+
+        let slack = resolve_persona(
+            &config,
+            "slack",
+            OutputFormat::Markdown,
+            traits::DEFAULT_MAX_INBOUND_CHARS,
+            "default-model",
+            &tool_prompt,
+        );
+        assert!(slack.system_prompt.contains("memory_inspect\n"));
+        assert!(!slack.system_prompt.contains("memory_store\n"));
+
+        let telegram = resolve_persona(
+            &config,
+            "telegram",
+            OutputFormat::Markdown,
+            traits::DEFAULT_MAX_INBOUND_CHARS,
+            "default-model",
+            &tool_prompt,
+        );
+        assert!(telegram.system_prompt.contains("memory_store\n"));
+    }
+
+    #[test]
+    fn persona_adds_channel_format_hint() {
+        let config = Config::default();
+        let persona = resolve_persona(
+            &config,
+            "telegram",
+            OutputFormat::TelegramMarkdownV2,
+            4096,
+            "default-model",
+            &stub_prompt,
+        );
+
+        assert_eq!(persona.format, OutputFormat::TelegramMarkdownV2);
+        assert!(persona.system_prompt.contains("## Output Format"));
+        assert_eq!(persona.format.render("v1.2"), "v1\\.2");
+        assert_eq!(persona.max_inbound_chars, 4096);
+    }
+
+    #[test]
+    fn inbound_within_limit_is_untouched() {
+        let mut content = "héllo".to_string();
+        let size = limit_inbound(&mut content, 5, OversizedInbound::Truncate);
+        assert_eq!(size, InboundSize::Fits);
+        assert_eq!(content, "héllo");
+
+        let mut huge = "x".repeat(100_000);
+        assert_eq!(
+            limit_inbound(&mut huge, 0, OversizedInbound::Reject),
+            InboundSize::Fits
+        );
+    }
+
+    #[test]
+    fn oversized_inbound_is_truncated_with_marker() {
+        let mut content = "ééééééééé".to_string();
+        let size = limit_inbound(&mut content, 4, OversizedInbound::Truncate);
+        assert_eq!(size, InboundSize::Truncated { chars: 9 });
+        assert!(content.starts_with("éééé\n\n[Message truncated: 9 characters"));
+    }
+
+    #[test]
+    fn oversized_inbound_can_be_rejected() {
+        let mut content = "x".repeat(20);
+        let size = limit_inbound(&mut content, 10, OversizedInbound::Reject);
+        assert_eq!(size, InboundSize::Rejected { chars: 20 });
+        assert_eq!(content.len(), 20);
+    }
+
     #[test]
     fn classify_health_ok_true() {
         let state = classify_health_result(&Ok(true));
@@ -933,8 +1535,9 @@ mod tests {
             calls: Arc::clone(&calls),
         });
 
-        let (_tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(1);
-        let handle = spawn_supervised_listener(channel, _tx, 1, 1);
+        let (tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(1);
+        let health = ChannelsHealth::new("test-supervised-fail-all", ["test-supervised-fail"]);
+        let handle = spawn_supervised_listener(channel, tx, 1, 1, CancelToken::new(), health);
 
         tokio::time::sleep(Duration::from_millis(80)).await;
         drop(rx);
@@ -951,4 +1554,99 @@ mod tests {
             .contains("listen boom"));
         assert!(calls.load(Ordering::SeqCst) >= 1);
     }
+
+    fn component_status(component: &str) -> serde_json::Value {
+        crate::health::snapshot_json()["components"][component]["status"].clone()
+    }
+
+    #[test]
+    fn channels_health_rolls_up_partial_failures() {
+        let health = ChannelsHealth::new("test-channels-rollup", ["discord", "slack", "telegram"]);
+        assert_eq!(component_status("test-channels-rollup"), "ok");
+
+        health.set("telegram", false);
+        assert_eq!(component_status("test-channels-rollup"), "degraded");
+        let snapshot = crate::health::snapshot_json();
+        assert_eq!(
+            snapshot["components"]["test-channels-rollup"]["last_error"],
+            "1 of 3 channels failing: telegram"
+        );
+
+        health.set("discord", false);
+        health.set("slack", false);
+        assert_eq!(component_status("test-channels-rollup"), "error");
+
+        health.set("discord", true);
+        health.set("slack", true);
+        health.set("telegram", true);
+        assert_eq!(component_status("test-channels-rollup"), "ok");
+    }
+
+    #[tokio::test]
+    async fn one_failing_listener_leaves_the_others_serving() {
+        let (tx, _rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(1);
+        let health = ChannelsHealth::new("test-partial-rollup", ["test-partial-bad", "cli"]);
+        let bad = spawn_supervised_listener(
+            Arc::new(AlwaysFailChannel {
+                name: "test-partial-bad",
+                calls: Arc::new(AtomicUsize::new(0)),
+            }),
+            tx,
+            60,
+            60,
+            CancelToken::new(),
+            health,
+        );
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(component_status("test-partial-rollup"), "degraded");
+        assert_eq!(component_status("channel:test-partial-bad"), "error");
+        bad.abort();
+    }
+
+    #[tokio::test]
+    async fn supervised_listener_exits_on_shutdown() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let channel: Arc<dyn Channel> = Arc::new(AlwaysFailChannel {
+            name: "test-supervised-shutdown",
+            calls: Arc::clone(&calls),
+        });
+
+        let (tx, _rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(1);
+        let shutdown = CancelToken::new();
+        // Long backoff: the listener is parked in its restart sleep when cancelled.
+        let health =
+            ChannelsHealth::new("test-supervised-shutdown-all", ["test-supervised-shutdown"]);
+        let handle = spawn_supervised_listener(channel, tx, 60, 60, shutdown.clone(), health);
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        shutdown.cancel();
+
+        tokio::time::timeout(Duration::from_secs(2), handle)
+            .await
+            .expect("supervisor should stop without being aborted")
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn dropped_listeners_are_aborted() {
+        let (held, released) = tokio::sync::oneshot::channel::<()>();
+        let listeners = Listeners(vec![tokio::spawn(async move {
+            let _held = held;
+            std::future::pending::<()>().await;
+        })]);
+
+        // Dropping the join midway, as a hard abort of the caller would.
+        let join = listeners.join();
+        assert!(tokio::time::timeout(Duration::from_millis(20), join)
+            .await
+            .is_err());
+
+        // The task was aborted, so its sender is gone.
+        tokio::time::timeout(Duration::from_secs(2), released)
+            .await
+            .expect("listener should be aborted")
+            .unwrap_err();
+    }
 }
@@ -0,0 +1,196 @@
+use crate::security::pairing::constant_time_eq;
+use crate::util::hex_encode;
+use base64::Engine;
+use ring::hmac;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How far a Slack request timestamp may be from now before it is treated
+/// as a replay, matching Slack's own recommendation.
+const SLACK_MAX_SKEW_SECS: u64 = 5 * 60;
+
+/// Signature formats used by inbound webhook providers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureScheme<'a> {
+    /// Lowercase hex HMAC-SHA256 of the body, optionally prefixed `sha256=`
+    /// (Meta / `WhatsApp` Cloud `X-Hub-Signature-256`, GitHub).
+    HmacSha256Hex,
+    /// Standard base64 HMAC-SHA256 of the body.
+    HmacSha256Base64,
+    /// Slack `X-Slack-Signature`: `v0=` + hex HMAC-SHA256 of `v0:{timestamp}:{body}`.
+    /// `timestamp` is `X-Slack-Request-Timestamp` and must be within five
+    /// minutes of now.
+    SlackV0 { timestamp: &'a str },
+}
+
+/// Verify `provided_sig` against the HMAC of `body` keyed by `secret`.
+///
+/// Comparison is constant-time; an empty secret or signature never verifies.
+pub fn verify_signature(
+    secret: &str,
+    body: &[u8],
+    provided_sig: &str,
+    scheme: SignatureScheme<'_>,
+) -> bool {
+    let provided_sig = provided_sig.trim();
+    if secret.is_empty() || provided_sig.is_empty() {
+        return false;
+    }
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+
+    match scheme {
+        SignatureScheme::HmacSha256Hex => {
+            let provided = provided_sig
+                .strip_prefix("sha256=")
+                .unwrap_or(provided_sig)
+                .to_ascii_lowercase();
+            let expected = hex_encode(hmac::sign(&key, body).as_ref());
+            constant_time_eq(&expected, &provided)
+        }
+        SignatureScheme::HmacSha256Base64 => {
+            let expected =
+                base64::engine::general_purpose::STANDARD.encode(hmac::sign(&key, body).as_ref());
+            constant_time_eq(&expected, provided_sig)
+        }
+        SignatureScheme::SlackV0 { timestamp } => {
+            let Some(provided) = provided_sig.strip_prefix("v0=") else {
+                return false;
+            };
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            if !slack_timestamp_is_fresh(timestamp, now) {
+                return false;
+            }
+            let mut ctx = hmac::Context::with_key(&key);
+            ctx.update(b"v0:");
+            ctx.update(timestamp.as_bytes());
+            ctx.update(b":");
+            ctx.update(body);
+            let expected = hex_encode(ctx.sign().as_ref());
+            constant_time_eq(&expected, &provided.to_ascii_lowercase())
+        }
+    }
+}
+
+/// Whether a Slack request timestamp (Unix seconds) is within
+/// `SLACK_MAX_SKEW_SECS` of `now`; unparseable timestamps are stale.
+fn slack_timestamp_is_fresh(timestamp: &str, now: u64) -> bool {
+    timestamp
+        .trim()
+        .parse::<u64>()
+        .is_ok_and(|sent| sent.abs_diff(now) <= SLACK_MAX_SKEW_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 4231 test case 2
+    const KEY: &str = "Jefe";
+    const BODY: &[u8] = b"what do ya want for nothing?";
+    const HEX: &str = "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843";
+
+    #[test]
+    fn hex_scheme_accepts_bare_and_prefixed() {
+        assert!(verify_signature(
+            KEY,
+            BODY,
+            HEX,
+            SignatureScheme::HmacSha256Hex
+        ));
+        assert!(verify_signature(
+            KEY,
+            BODY,
+            &format!("sha256={}", HEX.to_uppercase()),
+            SignatureScheme::HmacSha256Hex
+        ));
+    }
+
+    #[test]
+    fn base64_scheme_verifies() {
+        let sig = "W9zBRr9gdU5qBCQmCJV1x1oAPwidJzmDnexYuWTsOEM=";
+        assert!(verify_signature(
+            KEY,
+            BODY,
+            sig,
+            SignatureScheme::HmacSha256Base64
+        ));
+        assert!(!verify_signature(
+            KEY,
+            BODY,
+            HEX,
+            SignatureScheme::HmacSha256Base64
+        ));
+    }
+
+    fn slack_sig(timestamp: &str, body: &str) -> String {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, KEY.as_bytes());
+        let base = format!("v0:{timestamp}:{body}");
+        format!(
+            "v0={}",
+            hex_encode(hmac::sign(&key, base.as_bytes()).as_ref())
+        )
+    }
+
+    fn unix_now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[test]
+    fn slack_scheme_binds_timestamp() {
+        let now = unix_now().to_string();
+        let later = (unix_now() + 1).to_string();
+        let sig = slack_sig(&now, "payload");
+        let scheme = SignatureScheme::SlackV0 { timestamp: &now };
+        assert!(verify_signature(KEY, b"payload", &sig, scheme));
+        assert!(!verify_signature(
+            KEY,
+            b"payload",
+            &sig,
+            SignatureScheme::SlackV0 { timestamp: &later }
+        ));
+        assert!(!verify_signature(KEY, b"payload", &sig[3..], scheme));
+    }
+
+    #[test]
+    fn slack_scheme_rejects_stale_timestamps() {
+        let stale = (unix_now() - SLACK_MAX_SKEW_SECS - 1).to_string();
+        let sig = slack_sig(&stale, "payload");
+        assert!(!verify_signature(
+            KEY,
+            b"payload",
+            &sig,
+            SignatureScheme::SlackV0 { timestamp: &stale }
+        ));
+
+        assert!(slack_timestamp_is_fresh("1700000000", 1_700_000_300));
+        assert!(!slack_timestamp_is_fresh("1700000000", 1_700_000_301));
+        assert!(slack_timestamp_is_fresh("1700000300", 1_700_000_000));
+        assert!(!slack_timestamp_is_fresh("not-a-time", 1_700_000_000));
+    }
+
+    #[test]
+    fn rejects_tampered_body_and_empty_inputs() {
+        assert!(!verify_signature(
+            KEY,
+            b"tampered",
+            HEX,
+            SignatureScheme::HmacSha256Hex
+        ));
+        assert!(!verify_signature(
+            "",
+            BODY,
+            HEX,
+            SignatureScheme::HmacSha256Hex
+        ));
+        assert!(!verify_signature(
+            KEY,
+            BODY,
+            "",
+            SignatureScheme::HmacSha256Hex
+        ));
+    }
+}
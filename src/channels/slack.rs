@@ -1,4 +1,5 @@
-use super::traits::{Channel, ChannelMessage};
+use super::format::OutputFormat;
+use super::traits::{Channel, ChannelMessage, MessageKind};
 use async_trait::async_trait;
 use uuid::Uuid;
 
@@ -52,6 +53,10 @@ impl Channel for SlackChannel {
         "slack"
     }
 
+    fn output_format(&self) -> OutputFormat {
+        OutputFormat::SlackMrkdwn
+    }
+
     async fn send(&self, message: &str, channel: &str) -> anyhow::Result<()> {
         let body = serde_json::json!({
             "channel": channel,
@@ -147,6 +152,8 @@ impl Channel for SlackChannel {
                             .duration_since(std::time::UNIX_EPOCH)
                             .unwrap_or_default()
                             .as_secs(),
+                        kind: MessageKind::New,
+                        refers_to: None,
                     };
 
                     if tx.send(channel_msg).await.is_err() {
@@ -163,8 +170,7 @@ impl Channel for SlackChannel {
             .bearer_auth(&self.bot_token)
             .send()
             .await
-            .map(|r| r.status().is_success())
-            .unwrap_or(false)
+            .is_ok_and(|r| r.status().is_success())
     }
 }
 
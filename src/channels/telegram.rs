@@ -1,4 +1,5 @@
-use super::traits::{Channel, ChannelMessage};
+use super::format::{unescape_telegram, OutputFormat};
+use super::traits::{Channel, ChannelMessage, MessageKind};
 use async_trait::async_trait;
 use uuid::Uuid;
 
@@ -7,6 +8,7 @@ pub struct TelegramChannel {
     bot_token: String,
     allowed_users: Vec<String>,
     client: reqwest::Client,
+    api_base: String,
 }
 
 impl TelegramChannel {
@@ -15,11 +17,39 @@ impl TelegramChannel {
             bot_token,
             allowed_users,
             client: reqwest::Client::new(),
+            api_base: "https://api.telegram.org".into(),
         }
     }
 
+    #[cfg(test)]
+    fn with_api_base(mut self, api_base: &str) -> Self {
+        self.api_base = api_base.to_string();
+        self
+    }
+
     fn api_url(&self, method: &str) -> String {
-        format!("https://api.telegram.org/bot{}/{method}", self.bot_token)
+        format!("{}/bot{}/{method}", self.api_base, self.bot_token)
+    }
+
+    async fn send_message(
+        &self,
+        chat_id: &str,
+        text: &str,
+        parse_mode: Option<&str>,
+    ) -> anyhow::Result<reqwest::Response> {
+        let mut body = serde_json::json!({
+            "chat_id": chat_id,
+            "text": text,
+        });
+        if let Some(mode) = parse_mode {
+            body["parse_mode"] = mode.into();
+        }
+        Ok(self
+            .client
+            .post(self.api_url("sendMessage"))
+            .json(&body)
+            .send()
+            .await?)
     }
 
     fn is_user_allowed(&self, username: &str) -> bool {
@@ -40,19 +70,31 @@ impl Channel for TelegramChannel {
         "telegram"
     }
 
-    async fn send(&self, message: &str, chat_id: &str) -> anyhow::Result<()> {
-        let body = serde_json::json!({
-            "chat_id": chat_id,
-            "text": message,
-            "parse_mode": "Markdown"
-        });
+    fn output_format(&self) -> OutputFormat {
+        OutputFormat::TelegramMarkdownV2
+    }
 
-        self.client
-            .post(self.api_url("sendMessage"))
-            .json(&body)
-            .send()
-            .await?;
+    fn max_inbound_chars(&self) -> usize {
+        4096
+    }
 
+    /// Sends `MarkdownV2`; if Telegram can't parse it (400), resends the
+    /// text unescaped with no parse mode rather than dropping the reply.
+    async fn send(&self, message: &str, chat_id: &str) -> anyhow::Result<()> {
+        let mut resp = self
+            .send_message(chat_id, message, Some("MarkdownV2"))
+            .await?;
+        if resp.status() == reqwest::StatusCode::BAD_REQUEST {
+            let err = resp.text().await.unwrap_or_default();
+            tracing::warn!("Telegram rejected MarkdownV2, resending as plain text: {err}");
+            resp = self
+                .send_message(chat_id, &unescape_telegram(message), None)
+                .await?;
+        }
+        if !resp.status().is_success() {
+            let err = resp.text().await?;
+            anyhow::bail!("Telegram sendMessage failed: {err}");
+        }
         Ok(())
     }
 
@@ -66,7 +108,7 @@ impl Channel for TelegramChannel {
             let body = serde_json::json!({
                 "offset": offset,
                 "timeout": 30,
-                "allowed_updates": ["message"]
+                "allowed_updates": ["message", "edited_message"]
             });
 
             let resp = match self.client.post(&url).json(&body).send().await {
@@ -94,7 +136,13 @@ impl Channel for TelegramChannel {
                         offset = uid + 1;
                     }
 
-                    let Some(message) = update.get("message") else {
+                    // Edits arrive as their own update type; the bot API
+                    // does not report deletions.
+                    let (message, kind) = if let Some(m) = update.get("message") {
+                        (m, MessageKind::New)
+                    } else if let Some(m) = update.get("edited_message") {
+                        (m, MessageKind::Edited)
+                    } else {
                         continue;
                     };
 
@@ -144,6 +192,10 @@ Allowlist Telegram @username or numeric user ID, then run `baihu onboard --chann
                             .duration_since(std::time::UNIX_EPOCH)
                             .unwrap_or_default()
                             .as_secs(),
+                        kind,
+                        refers_to: (kind == MessageKind::Edited)
+                            .then(|| message.get("message_id").map(ToString::to_string))
+                            .flatten(),
                     };
 
                     if tx.send(msg).await.is_err() {
@@ -159,8 +211,7 @@ Allowlist Telegram @username or numeric user ID, then run `baihu onboard --chann
             .get(self.api_url("getMe"))
             .send()
             .await
-            .map(|r| r.status().is_success())
-            .unwrap_or(false)
+            .is_ok_and(|r| r.status().is_success())
     }
 }
 
@@ -183,6 +234,44 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn send_falls_back_to_plain_text_when_markdown_is_rejected() {
+        use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+        use parking_lot::Mutex;
+        use std::sync::Arc;
+
+        type Seen = Arc<Mutex<Vec<serde_json::Value>>>;
+        async fn send_message(
+            State(seen): State<Seen>,
+            Json(body): Json<serde_json::Value>,
+        ) -> StatusCode {
+            let markdown = body.get("parse_mode").is_some();
+            seen.lock().push(body);
+            if markdown {
+                StatusCode::BAD_REQUEST
+            } else {
+                StatusCode::OK
+            }
+        }
+
+        let seen = Seen::default();
+        let app = Router::new()
+            .route("/botT/sendMessage", post(send_message))
+            .with_state(Arc::clone(&seen));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let ch = TelegramChannel::new("T".into(), vec![]).with_api_base(&format!("http://{addr}"));
+        ch.send("v1\\.2 \\(final", "42").await.unwrap();
+
+        let seen = seen.lock();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0]["parse_mode"], "MarkdownV2");
+        assert_eq!(seen[1]["text"], "v1.2 (final");
+        assert!(seen[1].get("parse_mode").is_none());
+    }
+
     #[test]
     fn telegram_user_allowed_wildcard() {
         let ch = TelegramChannel::new("t".into(), vec!["*".into()]);
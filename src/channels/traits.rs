@@ -1,5 +1,22 @@
+use super::format::OutputFormat;
+use crate::agent::CancelToken;
 use async_trait::async_trait;
 
+/// Inbound size limit for platforms that don't declare their own.
+pub const DEFAULT_MAX_INBOUND_CHARS: usize = 16_000;
+
+/// What happened to the message a [`ChannelMessage`] describes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MessageKind {
+    /// A fresh message
+    #[default]
+    New,
+    /// An earlier message was edited; `content` is the new text
+    Edited,
+    /// An earlier message was deleted; `content` is empty
+    Deleted,
+}
+
 /// A message received from or sent to a channel
 #[derive(Debug, Clone)]
 pub struct ChannelMessage {
@@ -8,6 +25,9 @@ pub struct ChannelMessage {
     pub content: String,
     pub channel: String,
     pub timestamp: u64,
+    pub kind: MessageKind,
+    /// Platform id of the message an edit or deletion applies to
+    pub refers_to: Option<String>,
 }
 
 /// Three-tier lifecycle for channel connections.
@@ -35,6 +55,31 @@ pub trait Channel: Send + Sync {
     /// Start listening for incoming messages (long-running)
     async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()>;
 
+    /// Like [`Channel::listen`], but returns `Ok(())` once `cancel` fires.
+    /// The default simply drops the `listen` future; channels that hold a
+    /// live connection override this to close it cleanly before returning.
+    async fn listen_until(
+        &self,
+        tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+        cancel: &CancelToken,
+    ) -> anyhow::Result<()> {
+        tokio::select! {
+            result = self.listen(tx) => result,
+            () = cancel.cancelled() => Ok(()),
+        }
+    }
+
+    /// Reply format this platform renders; replies are converted before `send`.
+    fn output_format(&self) -> OutputFormat {
+        OutputFormat::Markdown
+    }
+
+    /// Longest inbound message, in characters, handed to the agent as-is.
+    /// `channels_config.personas[name].max_inbound_chars` overrides it.
+    fn max_inbound_chars(&self) -> usize {
+        DEFAULT_MAX_INBOUND_CHARS
+    }
+
     /// Check if channel is healthy
     async fn health_check(&self) -> bool {
         true
@@ -55,6 +100,45 @@ pub trait Channel: Send + Sync {
 mod tests {
     use super::*;
 
+    struct PendingChannel;
+
+    #[async_trait]
+    impl Channel for PendingChannel {
+        fn name(&self) -> &str {
+            "pending"
+        }
+
+        async fn send(&self, _message: &str, _recipient: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn listen(
+            &self,
+            _tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+        ) -> anyhow::Result<()> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn default_listen_until_returns_on_cancel() {
+        let (tx, _rx) = tokio::sync::mpsc::channel(1);
+        let cancel = CancelToken::new();
+        let stopper = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            stopper.cancel();
+        });
+
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(2),
+            PendingChannel.listen_until(tx, &cancel),
+        )
+        .await
+        .expect("listen_until should return once cancelled");
+        assert!(result.is_ok());
+    }
+
     #[test]
     fn channel_state_default_is_active() {
         assert_eq!(ChannelState::default(), ChannelState::Active);
@@ -62,8 +146,7 @@ mod tests {
 
     #[test]
     fn channel_state_transitions() {
-        let mut state = ChannelState::Active;
-        state = ChannelState::Suspended;
+        let mut state = ChannelState::Suspended;
         assert_eq!(state, ChannelState::Suspended);
         state = ChannelState::Active;
         assert_eq!(state, ChannelState::Active);
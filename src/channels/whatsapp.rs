@@ -1,4 +1,6 @@
-use super::traits::{Channel, ChannelMessage};
+use super::format::OutputFormat;
+use super::traits::{Channel, ChannelMessage, MessageKind};
+use super::{verify_signature, SignatureScheme};
 use async_trait::async_trait;
 use uuid::Uuid;
 
@@ -13,6 +15,7 @@ pub struct WhatsAppChannel {
     phone_number_id: String,
     verify_token: String,
     allowed_numbers: Vec<String>,
+    app_secret: Option<String>,
     client: reqwest::Client,
 }
 
@@ -28,10 +31,27 @@ impl WhatsAppChannel {
             phone_number_id,
             verify_token,
             allowed_numbers,
+            app_secret: None,
             client: reqwest::Client::new(),
         }
     }
 
+    /// Require webhook payloads to carry a valid `X-Hub-Signature-256` for this app secret.
+    pub fn with_app_secret(mut self, app_secret: Option<String>) -> Self {
+        self.app_secret = app_secret.filter(|s| !s.is_empty());
+        self
+    }
+
+    /// Check the `X-Hub-Signature-256` header of a webhook payload.
+    /// Always passes when no app secret is configured.
+    pub fn verify_payload_signature(&self, body: &[u8], signature: Option<&str>) -> bool {
+        let Some(ref secret) = self.app_secret else {
+            return true;
+        };
+        signature
+            .is_some_and(|sig| verify_signature(secret, body, sig, SignatureScheme::HmacSha256Hex))
+    }
+
     /// Check if a phone number is allowed (E.164 format: +1234567890)
     fn is_number_allowed(&self, phone: &str) -> bool {
         self.allowed_numbers.iter().any(|n| n == "*" || n == phone)
@@ -123,6 +143,8 @@ impl WhatsAppChannel {
                         content,
                         channel: "whatsapp".to_string(),
                         timestamp,
+                        kind: MessageKind::New,
+                        refers_to: None,
                     });
                 }
             }
@@ -138,6 +160,14 @@ impl Channel for WhatsAppChannel {
         "whatsapp"
     }
 
+    fn output_format(&self) -> OutputFormat {
+        OutputFormat::Plain
+    }
+
+    fn max_inbound_chars(&self) -> usize {
+        4096
+    }
+
     async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
         // WhatsApp Cloud API: POST to /v18.0/{phone_number_id}/messages
         let url = format!(
@@ -202,8 +232,7 @@ impl Channel for WhatsAppChannel {
             .header("Authorization", format!("Bearer {}", self.access_token))
             .send()
             .await
-            .map(|r| r.status().is_success())
-            .unwrap_or(false)
+            .is_ok_and(|r| r.status().is_success())
     }
 }
 
@@ -226,6 +255,22 @@ mod tests {
         assert_eq!(ch.name(), "whatsapp");
     }
 
+    #[test]
+    fn whatsapp_signature_optional_without_app_secret() {
+        let ch = make_channel();
+        assert!(ch.verify_payload_signature(b"{}", None));
+    }
+
+    #[test]
+    fn whatsapp_signature_required_with_app_secret() {
+        let ch = make_channel().with_app_secret(Some("Jefe".into()));
+        let body = b"what do ya want for nothing?";
+        let sig = "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843";
+        assert!(ch.verify_payload_signature(body, Some(sig)));
+        assert!(!ch.verify_payload_signature(body, None));
+        assert!(!ch.verify_payload_signature(b"tampered", Some(sig)));
+    }
+
     #[test]
     fn whatsapp_verify_token() {
         let ch = make_channel();
@@ -294,7 +339,7 @@ mod tests {
         assert_eq!(msgs[0].sender, "+1234567890");
         assert_eq!(msgs[0].content, "Hello Baihu!");
         assert_eq!(msgs[0].channel, "whatsapp");
-        assert_eq!(msgs[0].timestamp, 1699999999);
+        assert_eq!(msgs[0].timestamp, 1_699_999_999);
     }
 
     #[test]
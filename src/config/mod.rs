@@ -1,8 +1,8 @@
 pub mod schema;
 
 pub use schema::{
-    AutonomyConfig, BrowserConfig, ChannelsConfig, ComposioConfig, Config, DiscordConfig,
-    GatewayConfig, HeartbeatConfig, IMessageConfig, IdentityConfig, MatrixConfig, MemoryConfig,
-    ObservabilityConfig, ReliabilityConfig, RuntimeConfig, SecretsConfig, SlackConfig,
-    TelegramConfig, TunnelConfig, WebhookConfig,
+    AutonomyConfig, BrowserConfig, ChannelsConfig, ComposioConfig, Config, DaemonConfig,
+    DiscordConfig, GatewayConfig, HeartbeatConfig, IMessageConfig, IdentityConfig, MatrixConfig,
+    MemoryConfig, ObservabilityConfig, ProvidersConfig, ReliabilityConfig, RuntimeConfig,
+    SecretsConfig, SlackConfig, TelegramConfig, TunnelConfig, WebhookConfig,
 };
@@ -1,9 +1,15 @@
+use crate::memory::quota::MemoryFullStrategy;
+use crate::memory::secret_scan::SecretScanMode;
+use crate::providers::request_body::RequestBody;
+use crate::providers::SelectionStrategy;
+use crate::security::pairing::{CodeAlphabet, CodeFormat};
 use crate::security::AutonomyLevel;
 use anyhow::{Context, Result};
 use directories::UserDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 // ── Top-level config ──────────────────────────────────────────────
 
@@ -28,9 +34,15 @@ pub struct Config {
     #[serde(default)]
     pub reliability: ReliabilityConfig,
 
+    #[serde(default)]
+    pub providers: ProvidersConfig,
+
     #[serde(default)]
     pub heartbeat: HeartbeatConfig,
 
+    #[serde(default)]
+    pub daemon: DaemonConfig,
+
     #[serde(default)]
     pub channels_config: ChannelsConfig,
 
@@ -54,6 +66,27 @@ pub struct Config {
 
     #[serde(default)]
     pub identity: IdentityConfig,
+
+    /// Per-provider payload overrides, e.g. `[request_overrides.openrouter]`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub request_overrides: HashMap<String, RequestBody>,
+
+    /// Extra named workspaces served by the same daemon, e.g. `[workspaces.blog]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub workspaces: BTreeMap<String, WorkspaceConfig>,
+}
+
+// ── Workspaces ──────────────────────────────────────────────────
+
+/// A named workspace next to the default one. Requests and channels that
+/// select it get its directory, memory and security policy instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceConfig {
+    /// Relative paths are taken from the config directory
+    pub dir: PathBuf,
+    /// Overrides `[autonomy] level` inside this workspace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autonomy: Option<AutonomyLevel>,
 }
 
 // ── Identity (AIEOS / markdown format) ──────────────────────────
@@ -88,6 +121,7 @@ impl Default for IdentityConfig {
 // ── Gateway security ─────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct GatewayConfig {
     /// Require pairing before accepting requests (default: true)
     #[serde(default = "default_true")]
@@ -95,21 +129,65 @@ pub struct GatewayConfig {
     /// Allow binding to non-localhost without a tunnel (default: false)
     #[serde(default)]
     pub allow_public_bind: bool,
-    /// Paired bearer tokens (managed automatically, not user-edited)
+    /// Legacy paired bearer tokens; the gateway moves them into the token store
     #[serde(default)]
     pub paired_tokens: Vec<String>,
+    /// Where paired tokens are kept (default: `paired_tokens.json` next to config.toml)
+    #[serde(default)]
+    pub token_store_path: Option<PathBuf>,
+    /// Seconds an unused pairing code stays valid; 0 means until restart (default: 900)
+    #[serde(default = "default_pairing_code_ttl_secs")]
+    pub pairing_code_ttl_secs: u64,
+    /// Pairing code characters: "numeric" or "alphanumeric" (default: numeric)
+    #[serde(default)]
+    pub pairing_code_alphabet: CodeAlphabet,
+    /// Pairing code length, 6 to 32 (default: 6)
+    #[serde(default = "default_pairing_code_length")]
+    pub pairing_code_length: usize,
+    /// Pair by answering a nonce from `GET /pair/challenge` with
+    /// `HMAC-SHA256(code, nonce)` instead of sending the code itself, so a
+    /// captured request can't be replayed. Needs an alphanumeric code of at
+    /// least 12 characters (default: false)
+    #[serde(default)]
+    pub pairing_challenge_response: bool,
+    /// Extra consecutive ports to try when the configured one is taken (default: 0)
+    #[serde(default)]
+    pub port_search: u16,
+    /// Listen on this Unix domain socket (mode 0600) instead of host:port
+    #[serde(default)]
+    pub unix_socket: Option<PathBuf>,
+    /// Keep pairing on for socket connections; off by default since
+    /// filesystem permissions already gate access
+    #[serde(default)]
+    pub unix_socket_require_pairing: bool,
 }
 
 fn default_true() -> bool {
     true
 }
 
+fn default_pairing_code_ttl_secs() -> u64 {
+    900
+}
+
+fn default_pairing_code_length() -> usize {
+    CodeFormat::MIN_LENGTH
+}
+
 impl Default for GatewayConfig {
     fn default() -> Self {
         Self {
             require_pairing: true,
             allow_public_bind: false,
             paired_tokens: Vec::new(),
+            token_store_path: None,
+            pairing_code_ttl_secs: default_pairing_code_ttl_secs(),
+            pairing_code_alphabet: CodeAlphabet::default(),
+            pairing_code_length: default_pairing_code_length(),
+            port_search: 0,
+            unix_socket: None,
+            unix_socket_require_pairing: false,
+            pairing_challenge_response: false,
         }
     }
 }
@@ -214,6 +292,18 @@ pub struct MemoryConfig {
     /// Max tokens per chunk for document splitting
     #[serde(default = "default_chunk_size")]
     pub chunk_max_tokens: usize,
+    /// Reject compressed entries claiming a larger decompressed size (bytes)
+    #[serde(default = "default_max_decompressed_bytes")]
+    pub max_decompressed_bytes: usize,
+    /// Cap on stored key + content bytes; 0 means unlimited (default: 0)
+    #[serde(default)]
+    pub max_total_bytes: u64,
+    /// At the cap: "reject" new writes, or "evict" old conversation/daily/custom entries
+    #[serde(default)]
+    pub when_full: MemoryFullStrategy,
+    /// Scan content for credentials before storing: "off", "redact" or "reject" (default: off)
+    #[serde(default)]
+    pub secret_scan: SecretScanMode,
 }
 
 fn default_embedding_provider() -> String {
@@ -249,6 +339,9 @@ fn default_cache_size() -> usize {
 fn default_chunk_size() -> usize {
     512
 }
+fn default_max_decompressed_bytes() -> usize {
+    crate::memory::compression::DEFAULT_MAX_DECOMPRESSED_SIZE
+}
 
 impl Default for MemoryConfig {
     fn default() -> Self {
@@ -266,6 +359,10 @@ impl Default for MemoryConfig {
             keyword_weight: default_keyword_weight(),
             embedding_cache_size: default_cache_size(),
             chunk_max_tokens: default_chunk_size(),
+            max_decompressed_bytes: default_max_decompressed_bytes(),
+            max_total_bytes: 0,
+            when_full: MemoryFullStrategy::default(),
+            secret_scan: SecretScanMode::default(),
         }
     }
 }
@@ -276,12 +373,20 @@ impl Default for MemoryConfig {
 pub struct ObservabilityConfig {
     /// "none" | "log" | "prometheus" | "otel"
     pub backend: String,
+    /// Record every provider request and response, secrets redacted (default: false)
+    #[serde(default)]
+    pub provider_tap: bool,
+    /// JSON-lines file for the tap (default: `provider_tap.jsonl` next to config.toml)
+    #[serde(default)]
+    pub provider_tap_path: Option<PathBuf>,
 }
 
 impl Default for ObservabilityConfig {
     fn default() -> Self {
         Self {
             backend: "none".into(),
+            provider_tap: false,
+            provider_tap_path: None,
         }
     }
 }
@@ -293,6 +398,10 @@ pub struct AutonomyConfig {
     pub level: AutonomyLevel,
     pub workspace_only: bool,
     pub allowed_commands: Vec<String>,
+    /// Binaries commands must resolve to, e.g. "/usr/bin/git"; empty means
+    /// only `allowed_commands` name matching applies (default: empty)
+    #[serde(default)]
+    pub allowed_executables: Vec<PathBuf>,
     pub forbidden_paths: Vec<String>,
     pub max_actions_per_hour: u32,
     pub max_cost_per_day_cents: u32,
@@ -317,6 +426,7 @@ impl Default for AutonomyConfig {
                 "head".into(),
                 "tail".into(),
             ],
+            allowed_executables: Vec::new(),
             forbidden_paths: vec![
                 "/etc".into(),
                 "/root".into(),
@@ -374,18 +484,63 @@ pub struct ReliabilityConfig {
     /// Fallback provider chain (e.g. `["anthropic", "openai"]`).
     #[serde(default)]
     pub fallback_providers: Vec<String>,
+    /// Which provider gets the first attempt: `fallback` (always the primary),
+    /// `round_robin`, or `weighted`. Failures still fall through the rest of the chain.
+    #[serde(default)]
+    pub strategy: SelectionStrategy,
+    /// Relative weights for `weighted`, keyed by chain entry name. Unlisted
+    /// entries weigh 1; weight 0 keeps an entry fallback-only.
+    #[serde(default)]
+    pub provider_weights: HashMap<String, u32>,
+    /// Extra API keys for the primary provider. Each one becomes its own chain
+    /// entry named `<provider>#2`, `<provider>#3`, ... so load can be spread across keys.
+    #[serde(default)]
+    pub primary_api_keys: Vec<String>,
     /// Initial backoff for channel/daemon restarts.
     #[serde(default = "default_channel_backoff_secs")]
     pub channel_initial_backoff_secs: u64,
     /// Max backoff for channel/daemon restarts.
     #[serde(default = "default_channel_backoff_max_secs")]
     pub channel_max_backoff_secs: u64,
+    /// Give up on a daemon component after this many restarts in a row and
+    /// mark it `failed`. A run lasting over 3x the max backoff clears the
+    /// count. Unset restarts forever.
+    #[serde(default)]
+    pub max_restarts: Option<u64>,
+    /// How often the daemon writes its state snapshot, in seconds (min 1).
+    #[serde(default = "default_state_flush_secs")]
+    pub state_flush_secs: u64,
     /// Scheduler polling cadence in seconds.
     #[serde(default = "default_scheduler_poll_secs")]
     pub scheduler_poll_secs: u64,
     /// Max retries for cron job execution attempts.
     #[serde(default = "default_scheduler_retries")]
     pub scheduler_retries: u32,
+    /// Max in-flight provider calls across the process; extra calls queue.
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// Optional cap on in-flight calls to any single provider, across every
+    /// chain in the process.
+    #[serde(default)]
+    pub max_concurrent_per_provider: Option<usize>,
+    /// Per-provider model renames, e.g. `[reliability.model_map.openrouter]`
+    /// `"gpt-4o" = "openai/gpt-4o"`. Unmapped models pass through unchanged.
+    #[serde(default)]
+    pub model_map: HashMap<String, HashMap<String, String>>,
+    /// Optional cap on provider calls per request across the whole fallback chain.
+    #[serde(default)]
+    pub max_total_attempts: Option<u32>,
+    /// Optional wall-clock budget (ms) per request across the whole fallback chain.
+    #[serde(default)]
+    pub total_deadline_ms: Option<u64>,
+    /// A stream that fails after sending more reply characters than this is
+    /// returned truncated instead of being restarted on a retry or fallback.
+    #[serde(default = "default_stream_restart_max_chars")]
+    pub stream_restart_max_chars: usize,
+    /// When a request names a preferred provider, whether the rest of the
+    /// chain may still serve it if that provider fails. Requests can override.
+    #[serde(default = "default_true")]
+    pub preferred_provider_fallback: bool,
 }
 
 fn default_provider_retries() -> u32 {
@@ -404,6 +559,10 @@ fn default_channel_backoff_max_secs() -> u64 {
     60
 }
 
+fn default_state_flush_secs() -> u64 {
+    5
+}
+
 fn default_scheduler_poll_secs() -> u64 {
     15
 }
@@ -412,26 +571,75 @@ fn default_scheduler_retries() -> u32 {
     2
 }
 
+fn default_max_concurrent_requests() -> usize {
+    32
+}
+
+fn default_stream_restart_max_chars() -> usize {
+    crate::providers::reliable::DEFAULT_STREAM_RESTART_CHARS
+}
+
 impl Default for ReliabilityConfig {
     fn default() -> Self {
         Self {
             provider_retries: default_provider_retries(),
             provider_backoff_ms: default_provider_backoff_ms(),
             fallback_providers: Vec::new(),
+            strategy: SelectionStrategy::default(),
+            provider_weights: HashMap::new(),
+            primary_api_keys: Vec::new(),
             channel_initial_backoff_secs: default_channel_backoff_secs(),
             channel_max_backoff_secs: default_channel_backoff_max_secs(),
+            max_restarts: None,
+            state_flush_secs: default_state_flush_secs(),
             scheduler_poll_secs: default_scheduler_poll_secs(),
             scheduler_retries: default_scheduler_retries(),
+            max_concurrent_requests: default_max_concurrent_requests(),
+            max_concurrent_per_provider: None,
+            model_map: HashMap::new(),
+            max_total_attempts: None,
+            total_deadline_ms: None,
+            stream_restart_max_chars: default_stream_restart_max_chars(),
+            preferred_provider_fallback: true,
         }
     }
 }
 
+// ── Provider startup checks ──────────────────────────────────────
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProvidersConfig {
+    /// At daemon startup, make a cheap authenticated call to every provider
+    /// in the chain. Failures are logged and mark the provider degraded.
+    #[serde(default)]
+    pub preflight: bool,
+    /// Refuse to start when any provider fails its preflight. Implies `preflight`.
+    #[serde(default)]
+    pub strict_preflight: bool,
+    /// PEM bundle of extra CA certificates to trust for provider endpoints,
+    /// on top of the system roots (e.g. an on-prem gateway behind a corporate
+    /// PKI). Relative paths are taken from the config directory. SSRF rules
+    /// still apply to the endpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert_path: Option<PathBuf>,
+    /// Skip TLS certificate verification for providers entirely. For local
+    /// development against self-signed endpoints only: anyone on the network
+    /// path can read and rewrite prompts, replies and API keys.
+    #[serde(default)]
+    pub danger_accept_invalid_certs: bool,
+}
+
 // ── Heartbeat ────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HeartbeatConfig {
     pub enabled: bool,
+    /// Tick cadence; values under 5 are raised to 5.
     pub interval_minutes: u32,
+    /// Exact tick cadence in seconds, bypassing the 5-minute floor (testing,
+    /// low-latency monitoring). Takes precedence over `interval_minutes`.
+    #[serde(default)]
+    pub interval_seconds: Option<u64>,
 }
 
 impl Default for HeartbeatConfig {
@@ -439,6 +647,55 @@ impl Default for HeartbeatConfig {
         Self {
             enabled: false,
             interval_minutes: 30,
+            interval_seconds: None,
+        }
+    }
+}
+
+// ── Daemon ──────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonConfig {
+    /// Number of previous state snapshots to keep as `daemon_state.json.1`..`.N`
+    /// for post-mortem analysis. 0 keeps only the current file.
+    #[serde(default)]
+    pub state_history: usize,
+    /// Components the daemon should not start (`gateway`, `channels`,
+    /// `heartbeat`, `scheduler`, `state_writer`). They report `disabled` in health.
+    #[serde(default)]
+    pub disabled_components: Vec<String>,
+    /// Log the startup banner through `tracing` instead of printing it to stdout.
+    #[serde(default)]
+    pub quiet: bool,
+    /// Where to keep `daemon.lock`; relative paths are taken from the config
+    /// directory. Point it at local storage (e.g. a tmpfs) when the config
+    /// directory sits on a network filesystem with unreliable locking.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock_file: Option<PathBuf>,
+    /// Write the state snapshot LZ4-compressed as `daemon_state.json.lz4`
+    /// instead of pretty JSON. `baihu doctor` reads either form.
+    #[serde(default)]
+    pub compress_state: bool,
+    /// On shutdown, how long components get to finish in-flight work (the
+    /// gateway stops accepting and serves open requests) before they are
+    /// aborted (default: 10)
+    #[serde(default = "default_shutdown_grace_secs")]
+    pub shutdown_grace_secs: u64,
+}
+
+fn default_shutdown_grace_secs() -> u64 {
+    10
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            state_history: 0,
+            disabled_components: Vec::new(),
+            quiet: false,
+            lock_file: None,
+            compress_state: false,
+            shutdown_grace_secs: default_shutdown_grace_secs(),
         }
     }
 }
@@ -521,6 +778,23 @@ pub struct ChannelsConfig {
     pub imessage: Option<IMessageConfig>,
     pub matrix: Option<MatrixConfig>,
     pub whatsapp: Option<WhatsAppConfig>,
+    /// Per-channel overrides keyed by channel name (`telegram`, `slack`, ...)
+    #[serde(default)]
+    pub personas: HashMap<String, ChannelPersonaConfig>,
+    /// What to do with inbound messages over the channel's size limit
+    #[serde(default)]
+    pub oversized_inbound: OversizedInbound,
+}
+
+/// Handling for inbound messages longer than the channel's `max_inbound_chars`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OversizedInbound {
+    /// Keep the start of the message and mark the cut
+    #[default]
+    Truncate,
+    /// Drop the message and tell the sender
+    Reject,
 }
 
 impl Default for ChannelsConfig {
@@ -534,10 +808,33 @@ impl Default for ChannelsConfig {
             imessage: None,
             matrix: None,
             whatsapp: None,
+            personas: HashMap::new(),
+            oversized_inbound: OversizedInbound::default(),
         }
     }
 }
 
+/// Overrides applied when a message arrives on a specific channel.
+/// Unset fields fall back to the top-level defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChannelPersonaConfig {
+    /// Extra instructions appended to the workspace system prompt
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub autonomy: Option<AutonomyLevel>,
+    /// Inbound size limit in characters; 0 disables it
+    #[serde(default)]
+    pub max_inbound_chars: Option<usize>,
+    /// Named workspace (from `[workspaces]`) this channel works in
+    #[serde(default)]
+    pub workspace: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelegramConfig {
     pub bot_token: String,
@@ -591,6 +888,9 @@ pub struct WhatsAppConfig {
     /// Allowed phone numbers (E.164 format: +1234567890) or "*" for all
     #[serde(default)]
     pub allowed_numbers: Vec<String>,
+    /// Meta app secret; when set, POST /whatsapp requires a valid `X-Hub-Signature-256`
+    #[serde(default)]
+    pub app_secret: Option<String>,
 }
 
 // ── Config impl ──────────────────────────────────────────────────
@@ -612,7 +912,9 @@ impl Default for Config {
             autonomy: AutonomyConfig::default(),
             runtime: RuntimeConfig::default(),
             reliability: ReliabilityConfig::default(),
+            providers: ProvidersConfig::default(),
             heartbeat: HeartbeatConfig::default(),
+            daemon: DaemonConfig::default(),
             channels_config: ChannelsConfig::default(),
             memory: MemoryConfig::default(),
             tunnel: TunnelConfig::default(),
@@ -621,6 +923,8 @@ impl Default for Config {
             secrets: SecretsConfig::default(),
             browser: BrowserConfig::default(),
             identity: IdentityConfig::default(),
+            request_overrides: HashMap::new(),
+            workspaces: BTreeMap::new(),
         }
     }
 }
@@ -648,28 +952,32 @@ impl Config {
                 .context("Failed to create workspace directory")?;
         }
 
-        let mut config = if config_path.exists() {
-            // Check config file permissions (warn if too permissive)
-            Self::check_config_permissions(&config_path);
+        if config_path.exists() {
+            return Self::load_from(&config_path);
+        }
 
-            let contents =
-                fs::read_to_string(&config_path).context("Failed to read config file")?;
-            toml::from_str(&contents).context("Failed to parse config file")?
-        } else {
-            Config::default()
-        };
+        let mut config = Config::default();
+        config.apply_env_overrides();
+        config.decrypt_secrets()?;
+        // Save the default config (with env overrides) so there is one to edit
+        config.save()?;
+        Ok(config)
+    }
+
+    /// Read an existing config file, e.g. again on SIGHUP. Unlike
+    /// [`load_or_init`](Self::load_or_init), a missing file is an error.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        // Check config file permissions (warn if too permissive)
+        Self::check_config_permissions(path);
+
+        let contents = fs::read_to_string(path).context("Failed to read config file")?;
+        let mut config: Self = toml::from_str(&contents).context("Failed to parse config file")?;
 
         // Apply environment variable overrides (Docker/container support)
         config.apply_env_overrides();
 
         // Decrypt secrets (paired tokens, etc.) after loading
         config.decrypt_secrets()?;
-
-        // Save config if it didn't exist (creates default config with env overrides)
-        if !config_path.exists() {
-            config.save()?;
-        }
-
         Ok(config)
     }
 
@@ -718,6 +1026,30 @@ impl Config {
         }
     }
 
+    /// This config scoped to the named workspace: its directory and autonomy
+    /// replace the defaults, so memory, tools and the security policy built
+    /// from the result stay inside it.
+    pub fn for_workspace(&self, name: &str) -> Result<Self> {
+        let Some(workspace) = self.workspaces.get(name) else {
+            let known: Vec<&str> = self.workspaces.keys().map(String::as_str).collect();
+            anyhow::bail!(
+                "Unknown workspace '{name}'. Configured workspaces: {}",
+                if known.is_empty() {
+                    "(none)".to_string()
+                } else {
+                    known.join(", ")
+                }
+            );
+        };
+        let mut scoped = self.clone();
+        let config_dir = self.config_path.parent().unwrap_or(Path::new("."));
+        scoped.workspace_dir = config_dir.join(&workspace.dir);
+        if let Some(level) = workspace.autonomy {
+            scoped.autonomy.level = level;
+        }
+        Ok(scoped)
+    }
+
     pub fn save(&self) -> Result<()> {
         let mut config_to_save = self.clone();
 
@@ -756,6 +1088,25 @@ impl Config {
         Ok(())
     }
 
+    /// Remove legacy `gateway.paired_tokens` from the config file once they
+    /// have been imported into the token store, so startup imports them only
+    /// once. Edits the file on disk rather than saving `self`, which would
+    /// also write out environment overrides. Returns whether anything changed.
+    pub fn remove_legacy_paired_tokens(config_path: &Path) -> Result<bool> {
+        let contents = fs::read_to_string(config_path).context("Failed to read config file")?;
+        let mut doc: toml::Table = contents.parse().context("Failed to parse config file")?;
+        let removed = doc
+            .get_mut("gateway")
+            .and_then(toml::Value::as_table_mut)
+            .and_then(|gateway| gateway.remove("paired_tokens"))
+            .is_some();
+        if removed {
+            let toml_str = toml::to_string_pretty(&doc).context("Failed to serialize config")?;
+            crate::security::atomic_write::atomic_write_private(config_path, toml_str.as_bytes())?;
+        }
+        Ok(removed)
+    }
+
     /// Check config file permissions and warn if too permissive.
     /// On Unix, config.toml should be 0600 (owner read/write only).
     fn check_config_permissions(path: &std::path::Path) {
@@ -887,11 +1238,14 @@ mod tests {
             default_temperature: 0.5,
             observability: ObservabilityConfig {
                 backend: "log".into(),
+                provider_tap: true,
+                provider_tap_path: Some(PathBuf::from("/tmp/test/tap.jsonl")),
             },
             autonomy: AutonomyConfig {
                 level: AutonomyLevel::Full,
                 workspace_only: false,
                 allowed_commands: vec!["docker".into()],
+                allowed_executables: vec![PathBuf::from("/usr/bin/docker")],
                 forbidden_paths: vec!["/secret".into()],
                 max_actions_per_hour: 50,
                 max_cost_per_day_cents: 1000,
@@ -900,10 +1254,13 @@ mod tests {
                 kind: "docker".into(),
             },
             reliability: ReliabilityConfig::default(),
+            providers: ProvidersConfig::default(),
             heartbeat: HeartbeatConfig {
                 enabled: true,
                 interval_minutes: 15,
+                interval_seconds: None,
             },
+            daemon: DaemonConfig::default(),
             channels_config: ChannelsConfig {
                 cli: true,
                 telegram: Some(TelegramConfig {
@@ -916,6 +1273,8 @@ mod tests {
                 imessage: None,
                 matrix: None,
                 whatsapp: None,
+                personas: HashMap::new(),
+                oversized_inbound: OversizedInbound::default(),
             },
             memory: MemoryConfig::default(),
             tunnel: TunnelConfig::default(),
@@ -924,6 +1283,8 @@ mod tests {
             secrets: SecretsConfig::default(),
             browser: BrowserConfig::default(),
             identity: IdentityConfig::default(),
+            request_overrides: HashMap::new(),
+            workspaces: BTreeMap::new(),
         };
 
         let toml_str = toml::to_string_pretty(&config).unwrap();
@@ -985,7 +1346,9 @@ default_temperature = 0.7
             autonomy: AutonomyConfig::default(),
             runtime: RuntimeConfig::default(),
             reliability: ReliabilityConfig::default(),
+            providers: ProvidersConfig::default(),
             heartbeat: HeartbeatConfig::default(),
+            daemon: DaemonConfig::default(),
             channels_config: ChannelsConfig::default(),
             memory: MemoryConfig::default(),
             tunnel: TunnelConfig::default(),
@@ -994,6 +1357,8 @@ default_temperature = 0.7
             secrets: SecretsConfig::default(),
             browser: BrowserConfig::default(),
             identity: IdentityConfig::default(),
+            request_overrides: HashMap::new(),
+            workspaces: BTreeMap::new(),
         };
 
         config.save().unwrap();
@@ -1128,6 +1493,8 @@ default_temperature = 0.7
                 allowed_users: vec!["@u:m".into()],
             }),
             whatsapp: None,
+            personas: HashMap::new(),
+            oversized_inbound: OversizedInbound::default(),
         };
         let toml_str = toml::to_string_pretty(&c).unwrap();
         let parsed: ChannelsConfig = toml::from_str(&toml_str).unwrap();
@@ -1221,6 +1588,7 @@ channel_id = "C123"
             phone_number_id: "123456789".into(),
             verify_token: "my-verify-token".into(),
             allowed_numbers: vec!["+1234567890".into(), "+9876543210".into()],
+            app_secret: None,
         };
         let json = serde_json::to_string(&wc).unwrap();
         let parsed: WhatsAppConfig = serde_json::from_str(&json).unwrap();
@@ -1237,6 +1605,7 @@ channel_id = "C123"
             phone_number_id: "12345".into(),
             verify_token: "verify".into(),
             allowed_numbers: vec!["+1".into()],
+            app_secret: None,
         };
         let toml_str = toml::to_string(&wc).unwrap();
         let parsed: WhatsAppConfig = toml::from_str(&toml_str).unwrap();
@@ -1249,6 +1618,7 @@ channel_id = "C123"
         let json = r#"{"access_token":"tok","phone_number_id":"123","verify_token":"ver"}"#;
         let parsed: WhatsAppConfig = serde_json::from_str(json).unwrap();
         assert!(parsed.allowed_numbers.is_empty());
+        assert!(parsed.app_secret.is_none());
     }
 
     #[test]
@@ -1258,6 +1628,7 @@ channel_id = "C123"
             phone_number_id: "123".into(),
             verify_token: "ver".into(),
             allowed_numbers: vec!["*".into()],
+            app_secret: None,
         };
         let toml_str = toml::to_string(&wc).unwrap();
         let parsed: WhatsAppConfig = toml::from_str(&toml_str).unwrap();
@@ -1279,7 +1650,10 @@ channel_id = "C123"
                 phone_number_id: "123".into(),
                 verify_token: "ver".into(),
                 allowed_numbers: vec!["+1".into()],
+                app_secret: None,
             }),
+            personas: HashMap::new(),
+            oversized_inbound: OversizedInbound::default(),
         };
         let toml_str = toml::to_string_pretty(&c).unwrap();
         let parsed: ChannelsConfig = toml::from_str(&toml_str).unwrap();
@@ -1289,6 +1663,80 @@ channel_id = "C123"
         assert_eq!(wa.allowed_numbers, vec!["+1"]);
     }
 
+    #[test]
+    fn channels_config_parses_personas() {
+        let toml_str = r#"
+cli = true
+
+[personas.slack]
+system_prompt = "You are a terse work assistant."
+temperature = 0.2
+autonomy = "readonly"
+
+[personas.telegram]
+model = "openai/gpt-4o-mini"
+"#;
+        let parsed: ChannelsConfig = toml::from_str(toml_str).unwrap();
+        let slack = &parsed.personas["slack"];
+        assert_eq!(
+            slack.system_prompt.as_deref(),
+            Some("You are a terse work assistant.")
+        );
+        assert_eq!(slack.temperature, Some(0.2));
+        assert_eq!(slack.autonomy, Some(AutonomyLevel::ReadOnly));
+        assert!(slack.model.is_none());
+        assert_eq!(
+            parsed.personas["telegram"].model.as_deref(),
+            Some("openai/gpt-4o-mini")
+        );
+    }
+
+    #[test]
+    fn workspaces_resolve_against_config_dir() {
+        let toml_str = r#"
+workspace_dir = "/home/me/.baihu/workspace"
+config_path = "/home/me/.baihu/config.toml"
+default_temperature = 0.7
+
+[workspaces.blog]
+dir = "workspaces/blog"
+autonomy = "readonly"
+
+[workspaces.infra]
+dir = "/srv/infra"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+
+        let blog = config.for_workspace("blog").unwrap();
+        assert_eq!(
+            blog.workspace_dir,
+            PathBuf::from("/home/me/.baihu/workspaces/blog")
+        );
+        assert_eq!(blog.autonomy.level, AutonomyLevel::ReadOnly);
+
+        let infra = config.for_workspace("infra").unwrap();
+        assert_eq!(infra.workspace_dir, PathBuf::from("/srv/infra"));
+        assert_eq!(infra.autonomy.level, config.autonomy.level);
+    }
+
+    #[test]
+    fn unknown_workspace_lists_configured_ones() {
+        let mut config = Config::default();
+        let err = config.for_workspace("blog").unwrap_err().to_string();
+        assert!(err.contains("(none)"), "{err}");
+
+        config.workspaces.insert(
+            "infra".into(),
+            WorkspaceConfig {
+                dir: PathBuf::from("infra"),
+                autonomy: None,
+            },
+        );
+        let err = config.for_workspace("blog").unwrap_err().to_string();
+        assert!(err.contains("Unknown workspace 'blog'"), "{err}");
+        assert!(err.contains("infra"), "{err}");
+    }
+
     #[test]
     fn channels_config_default_has_no_whatsapp() {
         let c = ChannelsConfig::default();
@@ -1344,12 +1792,44 @@ channel_id = "C123"
             require_pairing: true,
             allow_public_bind: false,
             paired_tokens: vec!["bh_test_token".into()],
+            token_store_path: Some(PathBuf::from("/var/lib/baihu/paired_tokens.json")),
+            pairing_code_ttl_secs: 60,
+            pairing_code_alphabet: CodeAlphabet::Alphanumeric,
+            pairing_code_length: 10,
+            port_search: 3,
+            unix_socket: Some(PathBuf::from("/run/baihu/gateway.sock")),
+            unix_socket_require_pairing: false,
+            pairing_challenge_response: false,
         };
         let toml_str = toml::to_string(&g).unwrap();
         let parsed: GatewayConfig = toml::from_str(&toml_str).unwrap();
         assert!(parsed.require_pairing);
         assert!(!parsed.allow_public_bind);
         assert_eq!(parsed.paired_tokens, vec!["bh_test_token"]);
+        assert_eq!(parsed.pairing_code_alphabet, CodeAlphabet::Alphanumeric);
+        assert_eq!(parsed.pairing_code_length, 10);
+        assert_eq!(
+            parsed.unix_socket.as_deref(),
+            Some(std::path::Path::new("/run/baihu/gateway.sock"))
+        );
+    }
+
+    #[test]
+    fn legacy_paired_tokens_are_removed_from_the_file() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("config.toml");
+        fs::write(
+            &path,
+            "default_temperature = 0.5\n\n[gateway]\nrequire_pairing = true\npaired_tokens = [\"bh_old\"]\n",
+        )
+        .unwrap();
+
+        assert!(Config::remove_legacy_paired_tokens(&path).unwrap());
+        let doc: toml::Table = fs::read_to_string(&path).unwrap().parse().unwrap();
+        assert_eq!(doc["default_temperature"].as_float(), Some(0.5));
+        assert_eq!(doc["gateway"]["require_pairing"].as_bool(), Some(true));
+        assert!(doc["gateway"].get("paired_tokens").is_none());
+        assert!(!Config::remove_legacy_paired_tokens(&path).unwrap());
     }
 
     #[test]
@@ -1,154 +1,1238 @@
 use crate::config::Config;
+use crate::heartbeat::engine::TaskAction;
+use crate::observability::{NoopObserver, Observer, ObserverEvent};
+use crate::providers::{Provider, SwappableProvider};
 use anyhow::{Context, Result};
 use chrono::Utc;
 use fs2::FileExt;
 use std::future::Future;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::task::{JoinHandle, JoinSet};
 use tokio::time::Duration;
 
-const STATUS_FLUSH_SECONDS: u64 = 5;
+/// How often the daemon re-checks that it still owns `daemon.lock`.
+const LOCK_CHECK_SECONDS: u64 = 30;
+/// How often supervisors vouch for their running component in health.
+const COMPONENT_HEARTBEAT: Duration = Duration::from_secs(30);
 
-#[allow(clippy::too_many_lines)]
-pub async fn run(config: Config, host: String, port: u16) -> Result<()> {
-    // Acquire exclusive lock to prevent concurrent daemon instances
-    let lock_path = config
-        .config_path
-        .parent()
-        .map_or_else(|| PathBuf::from("."), PathBuf::from)
-        .join("daemon.lock");
-    if let Some(parent) = lock_path.parent() {
-        std::fs::create_dir_all(parent).ok();
-    }
-    let lock_file =
-        std::fs::File::create(&lock_path).context("Failed to create daemon lock file")?;
-    lock_file.try_lock_exclusive().map_err(|_| {
-        anyhow::anyhow!(
+/// Why the daemon stopped. Recorded in the `daemon` health component, the
+/// final state snapshot and the last log line, so post-mortems can tell an
+/// operator stop from a service manager stop from a lost lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownReason {
+    /// Ctrl+C / SIGINT
+    Interrupt,
+    /// SIGTERM, usually from the service manager
+    Terminate,
+    /// Another process took over `daemon.lock`
+    LockLost,
+    /// [`DaemonHandle::shutdown`], or every handle was dropped
+    Requested,
+}
+
+impl ShutdownReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Interrupt => "interrupt",
+            Self::Terminate => "terminate",
+            Self::LockLost => "lock_lost",
+            Self::Requested => "requested",
+        }
+    }
+
+    /// Stops nobody asked for are reported as errors.
+    pub fn is_requested(self) -> bool {
+        !matches!(self, Self::LockLost)
+    }
+}
+
+impl std::fmt::Display for ShutdownReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Components that can be switched off via `daemon.disabled_components`.
+pub const DAEMON_COMPONENTS: &[&str] = &[
+    "state_writer",
+    "gateway",
+    "channels",
+    "heartbeat",
+    "scheduler",
+];
+
+/// Ctrl+C and SIGTERM, caught from the first line of [`run`] so a signal
+/// during slow startup ends it at the next await point instead of killing the
+/// process halfway through writing the lock or heartbeat file.
+struct ShutdownSignals {
+    rx: tokio::sync::watch::Receiver<Option<ShutdownReason>>,
+    listener: Option<JoinHandle<()>>,
+}
+
+impl ShutdownSignals {
+    fn install() -> Result<Self> {
+        #[cfg(unix)]
+        let mut interrupt =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())?;
+        #[cfg(unix)]
+        let mut terminate =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+
+        let (tx, rx) = tokio::sync::watch::channel(None);
+        let listener = tokio::spawn(async move {
+            #[cfg(unix)]
+            let reason = tokio::select! {
+                _ = interrupt.recv() => ShutdownReason::Interrupt,
+                _ = terminate.recv() => ShutdownReason::Terminate,
+            };
+            #[cfg(not(unix))]
+            let reason = match tokio::signal::ctrl_c().await {
+                Ok(()) => ShutdownReason::Interrupt,
+                Err(e) => {
+                    tracing::error!("Failed to listen for Ctrl+C: {e}");
+                    return;
+                }
+            };
+            tx.send_replace(Some(reason));
+        });
+        Ok(Self {
+            rx,
+            listener: Some(listener),
+        })
+    }
+
+    /// Resolves with the first signal received, immediately if one already was.
+    async fn recv(&mut self) -> ShutdownReason {
+        if let Ok(reason) = self.rx.wait_for(Option::is_some).await {
+            if let Some(reason) = *reason {
+                return reason;
+            }
+        }
+        // The listener gave up without a signal; only lock loss can stop us now.
+        std::future::pending().await
+    }
+
+    /// Run a startup step unless a signal arrives first.
+    async fn interruptible<T>(
+        &mut self,
+        step: impl Future<Output = T>,
+    ) -> std::result::Result<T, ShutdownReason> {
+        tokio::select! {
+            biased;
+            reason = self.recv() => Err(reason),
+            value = step => Ok(value),
+        }
+    }
+}
+
+impl Drop for ShutdownSignals {
+    fn drop(&mut self) {
+        if let Some(listener) = self.listener.take() {
+            listener.abort();
+        }
+    }
+}
+
+/// SIGHUP, the conventional "re-read your config" signal. Registered with the
+/// shutdown signals so a hangup during startup doesn't kill the process; it
+/// is handled once the daemon is up. Never fires off unix.
+struct ReloadSignal {
+    #[cfg(unix)]
+    hangup: tokio::signal::unix::Signal,
+}
+
+impl ReloadSignal {
+    fn install() -> Result<Self> {
+        Ok(Self {
+            #[cfg(unix)]
+            hangup: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?,
+        })
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        if self.hangup.recv().await.is_some() {
+            return;
+        }
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Re-read `config.toml` and restart the components on it. A config that
+/// fails to load or apply is logged and the daemon keeps the one it has.
+async fn reload_from_disk(daemon: &DaemonHandle, config_path: &Path) {
+    let loaded = Config::load_from(config_path).and_then(|mut config| {
+        config.workspace_dir =
+            crate::security::SecurityPolicy::from_config(&config.autonomy, &config.workspace_dir)
+                .prepare_workspace()?;
+        Ok(config)
+    });
+    match daemon.apply_reload(loaded).await {
+        Ok(()) => tracing::info!(config = %config_path.display(), "Reloaded config on SIGHUP"),
+        Err(e) => tracing::error!(
+            config = %config_path.display(),
+            "Config reload failed, keeping the running config: {e:#}"
+        ),
+    }
+}
+
+/// Undo lock acquisition after startup was cancelled: release it and remove
+/// the file if this process created it, so nothing is left behind.
+fn abandon_startup_lock(lock_file: std::fs::File, lock_path: &Path, created: bool) {
+    // Unlink while still locked, so a daemon starting right now can't lock
+    // the file just before it disappears. Windows can't delete open files.
+    #[cfg(unix)]
+    if created {
+        let _ = std::fs::remove_file(lock_path);
+    }
+    drop(lock_file);
+    #[cfg(not(unix))]
+    if created {
+        let _ = std::fs::remove_file(lock_path);
+    }
+}
+
+/// Daemon lock taken by [`DaemonBuilder::start`]. Abandoned on drop unless
+/// startup got far enough to [`keep`](Self::keep) it, so a failed or
+/// cancelled start leaves nothing behind.
+struct StartupLock {
+    file: Option<std::fs::File>,
+    path: PathBuf,
+    created: bool,
+}
+
+impl StartupLock {
+    /// Lock `lock_path`, reclaiming it if the daemon recorded there is dead:
+    /// some filesystems keep an advisory lock after its holder was killed.
+    ///
+    /// Only a holder on this host, boot and PID namespace can be judged dead;
+    /// anyone else (a container, another NFS client) keeps the lock.
+    fn acquire(lock_path: &Path) -> Result<Self> {
+        if let Some(lock) = Self::try_acquire(lock_path)? {
+            return Ok(lock);
+        }
+        if reclaim_stale_lock(lock_path)? {
+            if let Some(lock) = Self::try_acquire(lock_path)? {
+                return Ok(lock);
+            }
+        }
+
+        let (holder, fix) = match read_lock_holder(lock_path) {
+            Some(holder) if holder.is_local() => (
+                format!("PID {}", holder.pid),
+                format!(
+                    "stop the existing daemon (PID {pid}) with Ctrl+C or `kill {pid}`",
+                    pid = holder.pid
+                ),
+            ),
+            Some(holder) => (
+                format!("PID {} on {}", holder.pid, holder.host),
+                format!("stop the daemon running on {}", holder.host),
+            ),
+            None => (
+                "another instance".to_string(),
+                "stop the existing daemon with Ctrl+C or remove the lock file".to_string(),
+            ),
+        };
+        anyhow::bail!(
             "{}",
             crate::health::structured_error(
                 "Failed to start daemon",
-                &format!("another instance holds the lock ({})", lock_path.display()),
-                "stop the existing daemon with Ctrl+C or remove the lock file"
+                &format!("{holder} holds the lock ({})", lock_path.display()),
+                &fix
             )
         )
-    })?;
-    // Lock held for lifetime of `lock_file` — released on drop at function exit
+    }
 
-    let initial_backoff = config.reliability.channel_initial_backoff_secs.max(1);
-    let max_backoff = config
-        .reliability
-        .channel_max_backoff_secs
-        .max(initial_backoff);
+    /// `None` if another handle holds the lock.
+    fn try_acquire(lock_path: &Path) -> Result<Option<Self>> {
+        if let Some(parent) = lock_path.parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+        let created = !lock_path.exists();
+        // Don't truncate before locking: that would wipe a running daemon's PID.
+        let lock_file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(lock_path)
+            .context("Failed to create daemon lock file")?;
+        if lock_file.try_lock_exclusive().is_err() {
+            return Ok(None);
+        }
+        let mut lock = Self {
+            file: Some(lock_file),
+            path: lock_path.to_path_buf(),
+            created,
+        };
+        if let Some(file) = lock.file.as_mut() {
+            write_lock_holder(file).context("Failed to record PID in daemon lock file")?;
+        }
+        Ok(Some(lock))
+    }
 
-    crate::health::mark_component_ok("daemon");
+    fn keep(mut self) -> std::fs::File {
+        self.file.take().expect("startup lock is kept only once")
+    }
+}
 
-    if config.heartbeat.enabled {
-        let _ =
-            crate::heartbeat::engine::HeartbeatEngine::ensure_heartbeat_file(&config.workspace_dir)
-                .await;
+impl Drop for StartupLock {
+    fn drop(&mut self) {
+        if let Some(file) = self.file.take() {
+            abandon_startup_lock(file, &self.path, self.created);
+        }
     }
+}
 
-    let mut tasks = JoinSet::new();
-    tasks.spawn(run_state_writer(config.clone()));
+/// The daemon as a library: the same supervision `baihu daemon` runs, driven
+/// through a [`DaemonHandle`] instead of signals.
+///
+/// ```ignore
+/// let daemon = Daemon::builder(config).port(0).start().await?;
+/// println!("{:?}", daemon.health().components.keys());
+/// daemon.reload(Config::load_or_init()?).await?;
+/// daemon.shutdown().await?;
+/// ```
+pub struct Daemon;
 
-    {
-        let gateway_cfg = config.clone();
-        let gateway_host = host.clone();
-        tasks.spawn(run_supervised_component(
-            "gateway",
-            initial_backoff,
-            max_backoff,
-            move || {
-                let cfg = gateway_cfg.clone();
-                let host = gateway_host.clone();
-                async move { crate::gateway::run_gateway(&host, port, cfg).await }
-            },
+impl Daemon {
+    pub fn builder(config: Config) -> DaemonBuilder {
+        DaemonBuilder {
+            config,
+            host: "127.0.0.1".into(),
+            port: 8080,
+        }
+    }
+}
+
+pub struct DaemonBuilder {
+    config: Config,
+    host: String,
+    port: u16,
+}
+
+impl DaemonBuilder {
+    /// Gateway bind address (default: 127.0.0.1)
+    #[must_use]
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Gateway port, 0 for a random one (default: 8080)
+    #[must_use]
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Take the daemon lock, run preflight, bind the gateway and spawn every
+    /// enabled component. Returns once the gateway is listening, so a port
+    /// that can't be bound fails here rather than in the component's restart
+    /// loop; dropping this future before then releases the lock again.
+    pub async fn start(self) -> Result<DaemonHandle> {
+        let Self { config, host, port } = self;
+        let lock = StartupLock::acquire(&lock_file_path(&config))?;
+        crate::health::mark_component_ok("lock");
+
+        crate::doctor::log_preflight(&config, &host);
+        warn_unknown_disabled_components(&config);
+        crate::doctor::run_provider_preflight(&config).await?;
+
+        let gateway = if component_enabled(&config, "gateway") {
+            Some(crate::gateway::bind_gateway(&host, port, &config).await?)
+        } else {
+            None
+        };
+        let gateway_addr = gateway
+            .as_ref()
+            .and_then(crate::gateway::GatewayListener::local_addr);
+        // Restarts and reloads rebind the port actually bound, even for port 0.
+        let port = gateway_addr.map_or(port, |addr| addr.port());
+        let gateway_display = match (&gateway, gateway_addr, &config.gateway.unix_socket) {
+            (None, _, _) => "disabled".to_string(),
+            (Some(_), Some(addr), _) => format!("http://{addr}"),
+            (Some(_), None, Some(path)) => format!("unix:{}", path.display()),
+            (Some(_), None, None) => format!("http://{host}:{port}"),
+        };
+
+        crate::health::mark_component_ok("daemon");
+
+        // Lifecycle events go to the observer so backends can count restarts and shutdowns
+        let observer = daemon_observer(&config);
+
+        let provider = Arc::new(SwappableProvider::new(provider_stack(&config, &observer)?));
+
+        if config.heartbeat.enabled {
+            crate::heartbeat::engine::HeartbeatEngine::ensure_heartbeat_file(&config.workspace_dir)
+                .await?;
+        }
+
+        let lock_path = lock.path.clone();
+        // Lock held for lifetime of the file — released when the supervisor stops
+        let lock_file = lock.keep();
+        let lock_lost = crate::agent::CancelToken::new();
+        // Windows locks are mandatory and can't be dropped silently, so only unix needs watching
+        #[cfg(unix)]
+        let lock_guard = tokio::spawn(run_lock_watchdog(
+            lock_path.clone(),
+            std::sync::Arc::new(lock_file),
+            lock_lost.clone(),
         ));
+        #[cfg(not(unix))]
+        let lock_guard = lock_file;
+
+        let components = Components::spawn(&config, &host, port, &observer, &provider, gateway);
+
+        let enabled = DAEMON_COMPONENTS
+            .iter()
+            .filter(|name| component_enabled(&config, name))
+            .map(|name| (*name).to_string())
+            .collect::<Vec<_>>();
+        let listing = enabled.join(", ");
+        observer.record_event(&ObserverEvent::DaemonStart {
+            components: enabled,
+        });
+        if config.daemon.quiet {
+            tracing::info!(
+                gateway = %gateway_display,
+                components = %listing,
+                "Baihu daemon started"
+            );
+        } else {
+            println!("🧠 Baihu daemon started");
+            println!("   Gateway:  {gateway_display}");
+            println!("   Components: {listing}");
+            println!("   Ctrl+C to stop");
+        }
+
+        let (control, requests) = tokio::sync::mpsc::channel(8);
+        let stopping = crate::agent::CancelToken::new();
+        let supervisor = Supervisor {
+            config,
+            host,
+            port,
+            observer: Arc::clone(&observer),
+            provider: Arc::clone(&provider),
+            lock_path,
+            lock_lost,
+            stopping: stopping.clone(),
+            lock_guard,
+            components,
+        };
+        let task = tokio::spawn(crate::health::in_current_scope(supervisor.run(requests)));
+        Ok(DaemonHandle {
+            control,
+            stopping,
+            gateway_addr,
+            observer,
+            provider,
+            task: Some(task),
+            health: crate::health::current(),
+        })
     }
+}
 
-    {
-        if has_supervised_channels(&config) {
-            let channels_cfg = config.clone();
+enum Control {
+    Stop(ShutdownReason),
+    Reload(Box<Config>, tokio::sync::oneshot::Sender<Result<()>>),
+}
+
+/// Control over a daemon started with [`Daemon::builder`]. Dropping every
+/// handle shuts the daemon down, so it never outlives the code embedding it.
+pub struct DaemonHandle {
+    control: tokio::sync::mpsc::Sender<Control>,
+    /// Fired before a stop is queued, so a reload in progress gives way to it
+    stopping: crate::agent::CancelToken,
+    gateway_addr: Option<std::net::SocketAddr>,
+    observer: Arc<dyn Observer>,
+    /// Shared with the supervisor, which swaps it on reload
+    provider: Arc<SwappableProvider>,
+    task: Option<JoinHandle<Result<()>>>,
+    health: Arc<crate::health::HealthRegistry>,
+}
+
+impl DaemonHandle {
+    /// Stop every component, write the final state snapshot and release the lock.
+    pub async fn shutdown(self) -> Result<()> {
+        self.stop(ShutdownReason::Requested).await
+    }
+
+    async fn stop(mut self, reason: ShutdownReason) -> Result<()> {
+        self.stopping.cancel();
+        // Fails only if the daemon already stopped; `wait` reports why.
+        let _ = self.control.send(Control::Stop(reason)).await;
+        self.wait().await
+    }
+
+    /// Swap in a provider stack built from `config`, then restart the
+    /// components on it. The lock, observer and gateway address stay as
+    /// started. A config that moves the lock, or whose provider stack can't
+    /// be built, is refused and the daemon keeps running on the old one.
+    pub async fn reload(&self, config: Config) -> Result<()> {
+        self.apply_reload(Ok(config)).await
+    }
+
+    /// Apply a reload, or record why the new config couldn't be loaded.
+    async fn apply_reload(&self, loaded: Result<Config>) -> Result<()> {
+        let result = match loaded {
+            Ok(config) => self.send_reload(config).await,
+            Err(e) => Err(e),
+        };
+        self.observer.record_event(&ObserverEvent::DaemonReload {
+            error: result.as_ref().err().map(|e| format!("{e:#}")),
+        });
+        result
+    }
+
+    async fn send_reload(&self, config: Config) -> Result<()> {
+        let (reply, result) = tokio::sync::oneshot::channel();
+        self.control
+            .send(Control::Reload(Box::new(config), reply))
+            .await
+            .map_err(|_| anyhow::anyhow!("Daemon is not running"))?;
+        result
+            .await
+            .map_err(|_| anyhow::anyhow!("Daemon stopped before the reload finished"))?
+    }
+
+    /// The provider stack the gateway and channels answer through. It is the
+    /// same handle across reloads; each call goes to the stack current then.
+    pub fn provider(&self) -> Arc<dyn Provider> {
+        Arc::clone(&self.provider) as Arc<dyn Provider>
+    }
+
+    /// TCP address the gateway is listening on; `None` when the gateway is
+    /// disabled or serves a Unix socket.
+    pub fn gateway_addr(&self) -> Option<std::net::SocketAddr> {
+        self.gateway_addr
+    }
+
+    /// Component health as `/health` reports it.
+    pub fn health(&self) -> crate::health::HealthSnapshot {
+        self.health.snapshot()
+    }
+
+    /// Resolves when the daemon stops without being asked (it lost its lock),
+    /// with the error that stopped it. Safe to cancel and call again.
+    pub async fn wait(&mut self) -> Result<()> {
+        let Some(task) = self.task.as_mut() else {
+            return Ok(());
+        };
+        let finished = task.await;
+        self.task = None;
+        finished.map_err(|e| anyhow::anyhow!("Daemon supervisor failed: {e}"))?
+    }
+}
+
+/// Everything that restarts on reload.
+struct Components {
+    tasks: JoinSet<()>,
+    /// Fired on stop so components drain and supervisors leave their backoff
+    shutdown: crate::agent::CancelToken,
+    /// How long `stop` waits for them before aborting the rest
+    grace: Duration,
+}
+
+impl Components {
+    /// `gateway` is a listener bound by [`DaemonBuilder::start`] for the
+    /// gateway's first run; later runs bind `host:port` themselves. The
+    /// gateway and channels answer through `provider`.
+    #[allow(clippy::too_many_lines)]
+    fn spawn(
+        config: &Config,
+        host: &str,
+        port: u16,
+        observer: &Arc<dyn Observer>,
+        provider: &Arc<SwappableProvider>,
+        gateway: Option<crate::gateway::GatewayListener>,
+    ) -> Self {
+        let initial_backoff = config.reliability.channel_initial_backoff_secs.max(1);
+        let max_backoff = config
+            .reliability
+            .channel_max_backoff_secs
+            .max(initial_backoff);
+        let max_restarts = config.reliability.max_restarts;
+        let shutdown = crate::agent::CancelToken::new();
+        let mut tasks = JoinSet::new();
+
+        if component_enabled(config, "state_writer") {
+            tasks.spawn(until_shutdown(
+                shutdown.clone(),
+                run_state_writer(config.clone()),
+            ));
+        } else {
+            crate::health::mark_component_disabled("state_writer");
+        }
+
+        if component_enabled(config, "gateway") {
+            let gateway_cfg = config.clone();
+            let gateway_host = host.to_string();
+            let gateway_provider: Arc<dyn Provider> = provider.clone();
+            let mut bound = gateway;
+            tasks.spawn(run_graceful_component(
+                "gateway",
+                Arc::clone(observer),
+                initial_backoff,
+                max_backoff,
+                max_restarts,
+                shutdown.clone(),
+                move |shutdown| {
+                    let cfg = gateway_cfg.clone();
+                    let host = gateway_host.clone();
+                    let provider = Arc::clone(&gateway_provider);
+                    let listener = bound.take();
+                    async move {
+                        let listener = match listener {
+                            Some(listener) => listener,
+                            None => crate::gateway::bind_gateway(&host, port, &cfg).await?,
+                        };
+                        crate::gateway::serve_gateway(listener, &host, cfg, provider, shutdown)
+                            .await
+                    }
+                },
+            ));
+        } else {
+            crate::health::mark_component_disabled("gateway");
+        }
+
+        if component_enabled(config, "channels") {
+            if has_supervised_channels(config) {
+                let channels_cfg = config.clone();
+                let channels_provider: Arc<dyn Provider> = provider.clone();
+                tasks.spawn(run_graceful_component(
+                    "channels",
+                    Arc::clone(observer),
+                    initial_backoff,
+                    max_backoff,
+                    max_restarts,
+                    shutdown.clone(),
+                    move |shutdown| {
+                        let cfg = channels_cfg.clone();
+                        let provider = Arc::clone(&channels_provider);
+                        async move {
+                            crate::channels::start_channels_until(cfg, provider, &shutdown).await
+                        }
+                    },
+                ));
+            } else {
+                crate::health::mark_component_ok("channels");
+                tracing::info!("No real-time channels configured; channel supervisor disabled");
+            }
+        } else {
+            crate::health::mark_component_disabled("channels");
+        }
+
+        if config.heartbeat.enabled && component_enabled(config, "heartbeat") {
+            let heartbeat_cfg = config.clone();
+            tasks.spawn(run_supervised_component(
+                "heartbeat",
+                Arc::clone(observer),
+                initial_backoff,
+                max_backoff,
+                max_restarts,
+                shutdown.clone(),
+                move || {
+                    let cfg = heartbeat_cfg.clone();
+                    async move { run_heartbeat_worker(cfg).await }
+                },
+            ));
+        } else if config.heartbeat.enabled {
+            crate::health::mark_component_disabled("heartbeat");
+        }
+
+        if component_enabled(config, "scheduler") {
+            let scheduler_cfg = config.clone();
             tasks.spawn(run_supervised_component(
-                "channels",
+                "scheduler",
+                Arc::clone(observer),
                 initial_backoff,
                 max_backoff,
+                max_restarts,
+                shutdown.clone(),
                 move || {
-                    let cfg = channels_cfg.clone();
-                    async move { crate::channels::start_channels(cfg).await }
+                    let cfg = scheduler_cfg.clone();
+                    async move { crate::cron::scheduler::run(cfg).await }
                 },
             ));
         } else {
-            crate::health::mark_component_ok("channels");
-            tracing::info!("No real-time channels configured; channel supervisor disabled");
+            crate::health::mark_component_disabled("scheduler");
+        }
+
+        // Periodic working set trimming on Windows (releases unused physical pages)
+        #[cfg(windows)]
+        tasks.spawn(until_shutdown(shutdown.clone(), async {
+            let mut interval = tokio::time::interval(Duration::from_secs(300)); // every 5 min
+            loop {
+                interval.tick().await;
+                // SetProcessWorkingSetSize with (SIZE_MAX, SIZE_MAX) trims the working set
+                unsafe {
+                    let process = windows_sys::Win32::System::Threading::GetCurrentProcess();
+                    windows_sys::Win32::System::Threading::SetProcessWorkingSetSize(
+                        process,
+                        usize::MAX,
+                        usize::MAX,
+                    );
+                }
+                tracing::debug!("Trimmed process working set");
+            }
+        }));
+
+        Self {
+            tasks,
+            shutdown,
+            grace: Duration::from_secs(config.daemon.shutdown_grace_secs),
         }
     }
 
-    if config.heartbeat.enabled {
-        let heartbeat_cfg = config.clone();
-        tasks.spawn(run_supervised_component(
-            "heartbeat",
-            initial_backoff,
-            max_backoff,
-            move || {
-                let cfg = heartbeat_cfg.clone();
-                async move { run_heartbeat_worker(cfg).await }
-            },
-        ));
+    async fn stop(&mut self) {
+        // Graceful components drain; the rest return as soon as they see this.
+        self.shutdown.cancel();
+        let tasks = &mut self.tasks;
+        let drained = tokio::time::timeout(self.grace, async {
+            while tasks.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            tracing::warn!(
+                remaining = self.tasks.len(),
+                grace_secs = self.grace.as_secs(),
+                "Daemon components still busy after the shutdown grace period; aborting them"
+            );
+            self.tasks.abort_all();
+            while self.tasks.join_next().await.is_some() {}
+        }
+    }
+}
+
+/// The configured observer, or a no-op one (with `observability` marked
+/// degraded) when the backend fails to start.
+fn daemon_observer(config: &Config) -> Arc<dyn Observer> {
+    match crate::observability::try_create_observer(&config.observability) {
+        Ok(observer) => {
+            crate::health::mark_component_ok("observability");
+            Arc::from(observer)
+        }
+        Err(e) => {
+            let msg = crate::health::structured_error(
+                "Observability backend failed to initialize",
+                &e.to_string(),
+                "fix [observability] backend in config.toml; telemetry is disabled until then",
+            );
+            tracing::error!("{msg}");
+            crate::health::mark_component_degraded("observability", msg);
+            Arc::new(NoopObserver)
+        }
+    }
+}
+
+/// The provider chain the daemon's gateway and channels share, built the
+/// way each would build its own.
+fn provider_stack(config: &Config, observer: &Arc<dyn Observer>) -> Result<Arc<dyn Provider>> {
+    Ok(Arc::from(crate::providers::with_configured_tap(
+        crate::providers::create_resilient_provider_with_state(
+            config.default_provider.as_deref().unwrap_or("openrouter"),
+            config.api_key.as_deref(),
+            &config.reliability,
+            &config.request_overrides,
+            Some(&crate::providers::state_file_path(config, "daemon")),
+            Some(Arc::clone(observer)),
+        )?,
+        config,
+    )))
+}
+
+/// Run `task` until `shutdown` fires, for work with nothing to finish.
+async fn until_shutdown(shutdown: crate::agent::CancelToken, task: impl Future<Output = ()>) {
+    tokio::select! {
+        () = task => {}
+        () = shutdown.cancelled() => {}
+    }
+}
+
+/// Owns the running daemon behind a [`DaemonHandle`].
+struct Supervisor {
+    config: Config,
+    host: String,
+    port: u16,
+    observer: Arc<dyn Observer>,
+    /// Swapped on reload; components hold the same handle
+    provider: Arc<SwappableProvider>,
+    lock_path: PathBuf,
+    lock_lost: crate::agent::CancelToken,
+    /// Fired by [`DaemonHandle::stop`] ahead of its request
+    stopping: crate::agent::CancelToken,
+    /// Keeps the lock: the watchdog holds the file on unix
+    #[cfg(unix)]
+    lock_guard: JoinHandle<()>,
+    #[cfg(not(unix))]
+    lock_guard: std::fs::File,
+    components: Components,
+}
+
+impl Supervisor {
+    async fn run(mut self, mut requests: tokio::sync::mpsc::Receiver<Control>) -> Result<()> {
+        let reason = loop {
+            tokio::select! {
+                () = self.lock_lost.cancelled() => break ShutdownReason::LockLost,
+                request = requests.recv() => match request {
+                    Some(Control::Stop(reason)) => break reason,
+                    Some(Control::Reload(config, reply)) => {
+                        // A stop or lost lock abandons the reload; the queued
+                        // stop request (or the lock check) is seen next.
+                        let lock_lost = self.lock_lost.clone();
+                        let stopping = self.stopping.clone();
+                        tokio::select! {
+                            biased;
+                            () = lock_lost.cancelled() => {}
+                            () = stopping.cancelled() => {}
+                            result = self.reload(*config) => {
+                                let _ = reply.send(result);
+                            }
+                        }
+                    }
+                    // Every handle was dropped; nothing can stop us later.
+                    None => break ShutdownReason::Requested,
+                },
+            }
+        };
+        self.stop(reason).await
+    }
+
+    async fn reload(&mut self, config: Config) -> Result<()> {
+        let lock_path = lock_file_path(&config);
+        if lock_path != self.lock_path {
+            anyhow::bail!(
+                "Reloaded config moves the daemon lock to {}; restart the daemon instead",
+                lock_path.display()
+            );
+        }
+        warn_unknown_disabled_components(&config);
+        // Built before anything is torn down, so a bad provider config
+        // leaves the running stack and components alone.
+        let stack = provider_stack(&config, &self.observer)?;
+        if config.heartbeat.enabled {
+            crate::heartbeat::engine::HeartbeatEngine::ensure_heartbeat_file(&config.workspace_dir)
+                .await?;
+        }
+
+        // Calls already in flight finish on the old stack.
+        self.provider.swap(stack);
+        // Stop first: the new gateway binds the same address.
+        self.components.stop().await;
+        self.components = Components::spawn(
+            &config,
+            &self.host,
+            self.port,
+            &self.observer,
+            &self.provider,
+            None,
+        );
+        self.config = config;
+        tracing::info!("Baihu daemon reloaded");
+        Ok(())
+    }
+
+    async fn stop(mut self, reason: ShutdownReason) -> Result<()> {
+        let status = format!("shutdown: {reason}");
+        if reason.is_requested() {
+            crate::health::mark_component_stopped("daemon", &status);
+        } else {
+            crate::health::mark_component_error("daemon", &status);
+        }
+        self.observer.record_event(&ObserverEvent::DaemonStop {
+            reason: reason.to_string(),
+        });
+        self.observer.flush();
+
+        self.components.stop().await;
+        #[cfg(unix)]
+        self.lock_guard.abort();
+        #[cfg(not(unix))]
+        drop(self.lock_guard);
+
+        // The periodic writer was just aborted; leave a snapshot that says why.
+        if component_enabled(&self.config, "state_writer") {
+            write_state_snapshot(
+                &state_file_path(&self.config),
+                self.config.daemon.state_history,
+            )
+            .await;
+        }
+        tracing::info!(reason = %reason, "Baihu daemon stopped");
+
+        if reason == ShutdownReason::LockLost {
+            anyhow::bail!(
+                "Daemon stopped because it no longer holds {}",
+                self.lock_path.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// `baihu daemon`: start the daemon, reload it on SIGHUP and stop it on
+/// Ctrl+C or SIGTERM.
+pub async fn run(config: Config, host: String, port: u16) -> Result<()> {
+    let mut signals = ShutdownSignals::install()?;
+    let mut hangups = ReloadSignal::install()?;
+    let config_path = config.config_path.clone();
+
+    let startup = Daemon::builder(config).host(host).port(port).start();
+    let mut daemon = match signals.interruptible(startup).await {
+        Ok(started) => started?,
+        Err(reason) => {
+            tracing::info!(reason = %reason, "Baihu daemon startup cancelled");
+            return Ok(());
+        }
+    };
+
+    let reason = loop {
+        tokio::select! {
+            reason = signals.recv() => break reason,
+            () = hangups.recv() => {
+                // Keep answering Ctrl+C / SIGTERM while components restart.
+                let reload = Box::pin(reload_from_disk(&daemon, &config_path));
+                if let Err(reason) = signals.interruptible(reload).await {
+                    break reason;
+                }
+            }
+            stopped = daemon.wait() => return stopped,
+        }
+    };
+    daemon.stop(reason).await
+}
+
+/// Who holds the daemon lock, as recorded in the lock file: `host:pid` on the
+/// first line, then on Linux the boot id and PID namespace the PID belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LockHolder {
+    host: String,
+    pid: u32,
+    scope: Option<String>,
+}
+
+impl LockHolder {
+    fn current() -> Self {
+        Self {
+            host: local_hostname(),
+            pid: std::process::id(),
+            scope: pid_scope(),
+        }
+    }
+
+    fn parse(contents: &str) -> Option<Self> {
+        let mut lines = contents.lines();
+        let (host, pid) = lines.next()?.trim().rsplit_once(':')?;
+        Some(Self {
+            host: host.to_string(),
+            pid: pid.parse().ok()?,
+            scope: lines
+                .next()
+                .map(str::trim)
+                .filter(|scope| !scope.is_empty())
+                .map(str::to_string),
+        })
     }
 
+    fn render(&self) -> String {
+        match &self.scope {
+            Some(scope) => format!("{}:{}\n{scope}\n", self.host, self.pid),
+            None => format!("{}:{}\n", self.host, self.pid),
+        }
+    }
+
+    fn is_local(&self) -> bool {
+        self.host == local_hostname()
+    }
+
+    /// Whether this PID is known to be gone. Needs a matching host, boot and
+    /// PID namespace: `/proc` says nothing about processes outside them.
+    fn is_dead(&self) -> bool {
+        self.is_local()
+            && self.scope.is_some()
+            && self.scope == pid_scope()
+            && process_alive(self.pid) == Some(false)
+    }
+}
+
+fn local_hostname() -> String {
+    hostname::get().map_or_else(|_| "unknown".into(), |h| h.to_string_lossy().to_string())
+}
+
+/// Boot id and PID namespace of this process, which together say whether
+/// another PID is visible to [`process_alive`]. `None` off Linux.
+fn pid_scope() -> Option<String> {
+    #[cfg(target_os = "linux")]
     {
-        let scheduler_cfg = config.clone();
-        tasks.spawn(run_supervised_component(
-            "scheduler",
-            initial_backoff,
-            max_backoff,
-            move || {
-                let cfg = scheduler_cfg.clone();
-                async move { crate::cron::scheduler::run(cfg).await }
-            },
-        ));
+        let boot = std::fs::read_to_string("/proc/sys/kernel/random/boot_id").ok()?;
+        let ns = std::fs::read_link("/proc/self/ns/pid").ok()?;
+        Some(format!("{} {}", boot.trim(), ns.display()))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// Replace the lock file contents with this process's [`LockHolder`].
+fn write_lock_holder(lock_file: &mut std::fs::File) -> std::io::Result<()> {
+    use std::io::{Seek, Write};
+    lock_file.set_len(0)?;
+    lock_file.rewind()?;
+    lock_file.write_all(LockHolder::current().render().as_bytes())?;
+    lock_file.sync_all()
+}
+
+/// Holder a daemon recorded in its lock file, if readable.
+fn read_lock_holder(lock_path: &Path) -> Option<LockHolder> {
+    LockHolder::parse(&std::fs::read_to_string(lock_path).ok()?)
+}
+
+/// Unlink a lock file whose recorded holder is dead. Returns whether it did.
+///
+/// The holder is read through an open handle and the file is only removed
+/// while the path still names that same file, so two daemons reclaiming at
+/// once can't delete the lock the first one just took.
+#[cfg(unix)]
+fn reclaim_stale_lock(lock_path: &Path) -> Result<bool> {
+    use std::io::Read;
+
+    let Ok(mut stale) = std::fs::File::open(lock_path) else {
+        return Ok(false);
+    };
+    let mut contents = String::new();
+    if stale.read_to_string(&mut contents).is_err() {
+        return Ok(false);
+    }
+    let Some(holder) = LockHolder::parse(&contents).filter(LockHolder::is_dead) else {
+        return Ok(false);
+    };
+    tracing::warn!(
+        pid = holder.pid,
+        path = %lock_path.display(),
+        "Reclaiming daemon lock left behind by a process that is gone"
+    );
+    unlink_if_unchanged(lock_path, &stale)
+}
+
+/// Remove `lock_path` only if it is still the file behind `opened`.
+#[cfg(unix)]
+fn unlink_if_unchanged(lock_path: &Path, opened: &std::fs::File) -> Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let opened = opened.metadata()?;
+    let Ok(on_disk) = std::fs::metadata(lock_path) else {
+        return Ok(false);
+    };
+    if (on_disk.dev(), on_disk.ino()) != (opened.dev(), opened.ino()) {
+        return Ok(false);
     }
+    std::fs::remove_file(lock_path).context("Failed to remove stale daemon lock file")?;
+    Ok(true)
+}
 
-    // Periodic working set trimming on Windows (releases unused physical pages)
+/// Windows releases a dead process's locks itself, so there is nothing to reclaim.
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)] // matches the unix signature
+fn reclaim_stale_lock(_lock_path: &Path) -> Result<bool> {
+    Ok(false)
+}
+
+/// Whether `pid` is a running process; `None` where the platform can't tell
+/// cheaply, which callers treat as alive.
+#[allow(clippy::unnecessary_wraps)] // only `None` off Linux and Windows
+fn process_alive(pid: u32) -> Option<bool> {
+    #[cfg(target_os = "linux")]
+    {
+        Some(Path::new("/proc").join(pid.to_string()).exists())
+    }
     #[cfg(windows)]
-    tasks.spawn(async {
-        let mut interval = tokio::time::interval(Duration::from_secs(300)); // every 5 min
-        loop {
-            interval.tick().await;
-            // SetProcessWorkingSetSize with (SIZE_MAX, SIZE_MAX) trims the working set
-            unsafe {
-                let process = windows_sys::Win32::System::Threading::GetCurrentProcess();
-                windows_sys::Win32::System::Threading::SetProcessWorkingSetSize(
-                    process,
-                    usize::MAX,
-                    usize::MAX,
-                );
+    {
+        use windows_sys::Win32::Foundation::{
+            CloseHandle, GetLastError, ERROR_ACCESS_DENIED, STILL_ACTIVE,
+        };
+        use windows_sys::Win32::System::Threading::{
+            GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+        };
+
+        unsafe {
+            let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if handle.is_null() {
+                // Denied means it exists but belongs to someone else.
+                return Some(GetLastError() == ERROR_ACCESS_DENIED);
             }
-            tracing::debug!("Trimmed process working set");
+            let mut code = 0_u32;
+            let queried = GetExitCodeProcess(handle, &mut code);
+            CloseHandle(handle);
+            #[allow(clippy::cast_sign_loss)]
+            let still_active = STILL_ACTIVE as u32;
+            Some(queried == 0 || code == still_active)
         }
-    });
+    }
+    #[cfg(not(any(target_os = "linux", windows)))]
+    {
+        let _ = pid;
+        None
+    }
+}
 
-    println!("🧠 Baihu daemon started");
-    println!("   Gateway:  http://{host}:{port}");
-    println!("   Components: gateway, channels, heartbeat, scheduler");
-    println!("   Ctrl+C to stop");
+/// Confirm `held` is still the locked file at `lock_path` and names this process.
+/// Advisory locks can be dropped silently on some network filesystems, and a
+/// deleted lock file lets a second daemon lock a fresh one.
+#[cfg(unix)]
+fn verify_lock(lock_path: &Path, held: &std::fs::File) -> std::result::Result<(), String> {
+    use std::os::unix::fs::MetadataExt;
 
-    tokio::signal::ctrl_c().await?;
-    crate::health::mark_component_error("daemon", "shutdown requested");
+    let contents =
+        std::fs::read_to_string(lock_path).map_err(|e| format!("lock file is unreadable ({e})"))?;
+    let ours = LockHolder::current();
+    if LockHolder::parse(&contents).as_ref() != Some(&ours) {
+        return Err(format!(
+            "lock file names {:?}, expected {:?}",
+            contents.trim(),
+            ours.render().trim()
+        ));
+    }
 
-    tasks.abort_all();
-    while tasks.join_next().await.is_some() {}
+    let on_disk = std::fs::metadata(lock_path).map_err(|e| e.to_string())?;
+    let held = held.metadata().map_err(|e| e.to_string())?;
+    if (on_disk.dev(), on_disk.ino()) != (held.dev(), held.ino()) {
+        return Err("lock file was replaced by another file".into());
+    }
 
+    // A separate handle must be refused while our lock is intact.
+    let probe = std::fs::File::open(lock_path).map_err(|e| e.to_string())?;
+    if probe.try_lock_exclusive().is_ok() {
+        let _ = FileExt::unlock(&probe);
+        return Err("advisory lock is no longer held".into());
+    }
     Ok(())
 }
 
+/// Periodically re-verify the daemon lock; on loss, report it and fire `lock_lost`
+/// so the daemon shuts down instead of racing another instance on shared state.
+#[cfg(unix)]
+async fn run_lock_watchdog(
+    lock_path: PathBuf,
+    lock_file: std::sync::Arc<std::fs::File>,
+    lock_lost: crate::agent::CancelToken,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(LOCK_CHECK_SECONDS));
+    loop {
+        interval.tick().await;
+        if let Err(why) = verify_lock(&lock_path, &lock_file) {
+            let msg = crate::health::structured_error(
+                "Daemon lock lost",
+                &why,
+                "stop any other daemon using this config directory, then restart; \
+                 avoid network filesystems for ~/.baihu",
+            );
+            tracing::error!(critical = true, "{msg}");
+            crate::health::mark_component_error("lock", msg);
+            lock_lost.cancel();
+            return;
+        }
+        crate::health::mark_component_ok("lock");
+    }
+}
+
+/// Entries of `daemon.disabled_components` that name no daemon component,
+/// most likely typos.
+fn unknown_disabled_components(config: &Config) -> Vec<&str> {
+    config
+        .daemon
+        .disabled_components
+        .iter()
+        .map(String::as_str)
+        .filter(|name| {
+            !DAEMON_COMPONENTS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(name))
+        })
+        .collect()
+}
+
+fn warn_unknown_disabled_components(config: &Config) {
+    let unknown = unknown_disabled_components(config);
+    if !unknown.is_empty() {
+        tracing::warn!(
+            unknown = %unknown.join(", "),
+            known = %DAEMON_COMPONENTS.join(", "),
+            "daemon.disabled_components names components that don't exist; ignoring them"
+        );
+    }
+}
+
+fn component_enabled(config: &Config, name: &str) -> bool {
+    !config
+        .daemon
+        .disabled_components
+        .iter()
+        .any(|disabled| disabled.eq_ignore_ascii_case(name))
+}
+
+/// `daemon.lock_file` if set, otherwise `daemon.lock` next to the config file.
+pub fn lock_file_path(config: &Config) -> PathBuf {
+    let config_dir = config
+        .config_path
+        .parent()
+        .map_or_else(|| PathBuf::from("."), PathBuf::from);
+    match &config.daemon.lock_file {
+        Some(path) => config_dir.join(path),
+        None => config_dir.join("daemon.lock"),
+    }
+}
+
+/// Whether a daemon currently holds the lock for this config.
+///
+/// Takes a shared lock, which only conflicts with the daemon's exclusive one,
+/// so status tools can probe concurrently. The shared lock is released before
+/// returning; a daemon starting in that instant may still see it as taken.
+pub fn is_running(config: &Config) -> bool {
+    // Never create the file: no lock file means no daemon.
+    let Ok(file) = std::fs::File::open(lock_file_path(config)) else {
+        return false;
+    };
+    if FileExt::try_lock_shared(&file).is_ok() {
+        let _ = FileExt::unlock(&file);
+        false
+    } else {
+        true
+    }
+}
+
+/// `daemon_state.json` next to the config file, or `daemon_state.json.lz4`
+/// with `daemon.compress_state`.
 pub fn state_file_path(config: &Config) -> PathBuf {
+    let name = if config.daemon.compress_state {
+        "daemon_state.json.lz4"
+    } else {
+        "daemon_state.json"
+    };
     config
         .config_path
         .parent()
         .map_or_else(|| PathBuf::from("."), PathBuf::from)
-        .join("daemon_state.json")
+        .join(name)
+}
+
+fn is_compressed_state(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "lz4")
+}
+
+/// Decode a state snapshot read from `path`, decompressing `.lz4` files.
+pub fn parse_state(path: &Path, raw: &[u8]) -> Result<serde_json::Value> {
+    if is_compressed_state(path) {
+        let json = crate::memory::compression::decompress_bytes(
+            raw,
+            crate::memory::compression::DEFAULT_MAX_DECOMPRESSED_SIZE,
+        )?;
+        return Ok(serde_json::from_slice(&json)?);
+    }
+    Ok(serde_json::from_slice(raw)?)
 }
 
 async fn run_state_writer(config: Config) {
@@ -156,187 +1240,818 @@ async fn run_state_writer(config: Config) {
     if let Some(parent) = path.parent() {
         let _ = tokio::fs::create_dir_all(parent).await;
     }
+    // A snapshot truncated by a crash would otherwise be rotated into history.
+    if let Ok(raw) = tokio::fs::read(&path).await {
+        if let Err(e) = parse_state(&path, &raw) {
+            crate::util::recover_corrupt(&path, "daemon state", &e.to_string());
+        }
+    }
 
-    let mut interval = tokio::time::interval(Duration::from_secs(STATUS_FLUSH_SECONDS));
+    let every = config.reliability.state_flush_secs.max(1);
+    let mut interval = tokio::time::interval(Duration::from_secs(every));
     loop {
         interval.tick().await;
-        let mut json = crate::health::snapshot_json();
-        if let Some(obj) = json.as_object_mut() {
-            obj.insert(
-                "written_at".into(),
-                serde_json::json!(Utc::now().to_rfc3339()),
-            );
+        write_state_snapshot(&path, config.daemon.state_history).await;
+    }
+}
+
+async fn write_state_snapshot(path: &Path, history: usize) {
+    let mut json = crate::health::snapshot_json();
+    if let Some(obj) = json.as_object_mut() {
+        obj.insert(
+            "written_at".into(),
+            serde_json::json!(Utc::now().to_rfc3339()),
+        );
+    }
+    let mut data = serde_json::to_vec_pretty(&json).unwrap_or_else(|_| b"{}".to_vec());
+    if is_compressed_state(path) {
+        data = crate::memory::compression::compress_bytes(&data);
+    }
+    rotate_state_history(path, history).await;
+    let _ = crate::security::atomic_write::atomic_write_async(path, data).await;
+}
+
+fn history_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{index}"));
+    PathBuf::from(name)
+}
+
+/// Shift `path` → `path.1` → … → `path.keep`, dropping the oldest snapshot.
+/// Renames are atomic and `path.1` is written like the current file, so a
+/// crash mid-rotation never leaves a torn snapshot. Best-effort: a missing
+/// file just leaves a gap in the history.
+async fn rotate_state_history(path: &Path, keep: usize) {
+    if keep == 0 {
+        return;
+    }
+    let Ok(current) = tokio::fs::read(path).await else {
+        return;
+    };
+    for index in (1..keep).rev() {
+        let from = history_path(path, index);
+        if from.exists() {
+            let _ = tokio::fs::rename(&from, history_path(path, index + 1)).await;
         }
-        let data = serde_json::to_vec_pretty(&json).unwrap_or_else(|_| b"{}".to_vec());
-        let _ = crate::security::atomic_write::atomic_write_async(&path, data).await;
     }
+    let _ =
+        crate::security::atomic_write::atomic_write_async(&history_path(path, 1), current).await;
 }
 
+/// How many quick restarts a component has left before its supervisor gives up.
+struct RestartBudget {
+    max: Option<u64>,
+    used: u64,
+    /// A run at least this long is healthy and clears `used`
+    reset_after: Duration,
+}
+
+impl RestartBudget {
+    fn new(max: Option<u64>, max_backoff_secs: u64) -> Self {
+        Self {
+            max,
+            used: 0,
+            reset_after: Duration::from_secs(max_backoff_secs.saturating_mul(3)),
+        }
+    }
+
+    /// Count a failed run that lasted `uptime`; `false` once the budget is spent.
+    fn allow_restart(&mut self, uptime: Duration) -> bool {
+        if uptime > self.reset_after {
+            self.used = 0;
+        }
+        if self.max.is_some_and(|max| self.used >= max) {
+            return false;
+        }
+        self.used += 1;
+        true
+    }
+}
+
+/// Supervise a component with nothing to drain: on shutdown its current run
+/// is dropped straight away.
 async fn run_supervised_component<F, Fut>(
     name: &'static str,
+    observer: Arc<dyn Observer>,
     initial_backoff_secs: u64,
     max_backoff_secs: u64,
+    max_restarts: Option<u64>,
+    shutdown: crate::agent::CancelToken,
     mut run_component: F,
 ) where
     F: FnMut() -> Fut + Send + 'static,
     Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    run_graceful_component(
+        name,
+        observer,
+        initial_backoff_secs,
+        max_backoff_secs,
+        max_restarts,
+        shutdown,
+        move |shutdown| {
+            let run = run_component();
+            async move {
+                tokio::select! {
+                    result = run => result,
+                    () = shutdown.cancelled() => Ok(()),
+                }
+            }
+        },
+    )
+    .await;
+}
+
+/// Supervise a component that watches the shutdown token itself: once it
+/// fires the component should stop taking new work, finish what it has and
+/// return. The daemon aborts it if that takes longer than
+/// `daemon.shutdown_grace_secs`.
+async fn run_graceful_component<F, Fut>(
+    name: &'static str,
+    observer: Arc<dyn Observer>,
+    initial_backoff_secs: u64,
+    max_backoff_secs: u64,
+    max_restarts: Option<u64>,
+    shutdown: crate::agent::CancelToken,
+    mut run_component: F,
+) where
+    F: FnMut(crate::agent::CancelToken) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
 {
     let mut backoff = initial_backoff_secs.max(1);
     let max_backoff = max_backoff_secs.max(backoff);
+    let mut budget = RestartBudget::new(max_restarts, max_backoff);
+    crate::health::expect_component_heartbeat(name, COMPONENT_HEARTBEAT);
+    let stopped = |reason: String, will_restart: bool| {
+        observer.record_event(&ObserverEvent::ComponentStop {
+            component: name.into(),
+            reason,
+            will_restart,
+        });
+    };
 
-    loop {
+    for restarts in 0_u64.. {
         crate::health::mark_component_ok(name);
-        match run_component().await {
+        observer.record_event(&ObserverEvent::ComponentStart {
+            component: name.into(),
+            restarts,
+        });
+        let started = std::time::Instant::now();
+        let run = run_component(shutdown.clone());
+        tokio::pin!(run);
+        let mut beat = tokio::time::interval(COMPONENT_HEARTBEAT);
+        let result = loop {
+            tokio::select! {
+                result = &mut run => break result,
+                _ = beat.tick() => crate::health::mark_component_heartbeat(name),
+            }
+        };
+        if shutdown.is_cancelled() {
+            let status = match &result {
+                Ok(()) => "shutdown".to_string(),
+                Err(e) => format!("shutdown: {e}"),
+            };
+            crate::health::mark_component_stopped(name, &status);
+            tracing::info!("Daemon component '{name}' stopped");
+            stopped(status, false);
+            return;
+        }
+        let reason = match result {
             Ok(()) => {
-                crate::health::mark_component_error(name, "component exited unexpectedly");
                 tracing::warn!("Daemon component '{name}' exited unexpectedly");
+                "component exited unexpectedly".to_string()
+            }
+            Err(e) if e.is::<crate::health::TerminalError>() => {
+                crate::health::mark_component_failed(name, e.to_string());
+                tracing::error!("Daemon component '{name}' cannot recover, not restarting: {e}");
+                stopped(e.to_string(), false);
+                return;
             }
             Err(e) => {
-                crate::health::mark_component_error(name, e.to_string());
                 tracing::error!("Daemon component '{name}' failed: {e}");
+                e.to_string()
+            }
+        };
+
+        if !budget.allow_restart(started.elapsed()) {
+            let msg = crate::health::structured_error(
+                &format!("Daemon component '{name}' gave up"),
+                &format!("{reason} (after {} restarts)", budget.used),
+                "fix the cause and restart the daemon, or raise reliability.max_restarts",
+            );
+            crate::health::mark_component_failed(name, &msg);
+            tracing::error!("{msg}");
+            stopped(reason, false);
+            return;
+        }
+        crate::health::mark_component_error(name, &reason);
+        stopped(reason, true);
+
+        crate::health::bump_component_restart(name);
+        // Jitter prevents a thundering herd on mass restart
+        let jittered = crate::util::jittered_backoff(backoff, crate::util::DEFAULT_JITTER_FRACTION);
+        tokio::select! {
+            () = tokio::time::sleep(Duration::from_secs(jittered)) => {}
+            () = shutdown.cancelled() => {
+                crate::health::mark_component_stopped(name, "shutdown during restart backoff");
+                tracing::info!("Daemon component '{name}' stopped during restart backoff");
+                return;
+            }
+        }
+        backoff = backoff.saturating_mul(2).min(max_backoff);
+    }
+}
+
+fn spawn_component_supervisor<F, Fut>(
+    name: &'static str,
+    observer: Arc<dyn Observer>,
+    initial_backoff_secs: u64,
+    max_backoff_secs: u64,
+    max_restarts: Option<u64>,
+    shutdown: crate::agent::CancelToken,
+    run_component: F,
+) -> JoinHandle<()>
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    tokio::spawn(crate::health::in_current_scope(run_supervised_component(
+        name,
+        observer,
+        initial_backoff_secs,
+        max_backoff_secs,
+        max_restarts,
+        shutdown,
+        run_component,
+    )))
+}
+
+async fn run_heartbeat_worker(config: Config) -> Result<()> {
+    let observer: std::sync::Arc<dyn crate::observability::Observer> =
+        std::sync::Arc::from(crate::observability::create_observer(&config.observability));
+    let engine = crate::heartbeat::engine::HeartbeatEngine::new(
+        config.heartbeat.clone(),
+        config.workspace_dir.clone(),
+        observer,
+    );
+
+    let mut interval = tokio::time::interval(crate::heartbeat::engine::HeartbeatEngine::interval(
+        &config.heartbeat,
+    ));
+
+    loop {
+        interval.tick().await;
+
+        let tasks = engine.collect_tasks().await?;
+        if tasks.is_empty() {
+            continue;
+        }
+
+        for task in tasks {
+            let prompt = match task.action {
+                TaskAction::Prompt(prompt) => format!("[Heartbeat Task] {prompt}"),
+                TaskAction::Tool { name, args } => {
+                    match run_heartbeat_tool(&config, &name, args).await {
+                        Ok(output) => {
+                            crate::health::mark_component_ok("heartbeat");
+                            tracing::info!(tool = %name, "Heartbeat tool task completed: {output}");
+                        }
+                        Err(e) => {
+                            crate::health::mark_component_error("heartbeat", e.to_string());
+                            tracing::warn!(tool = %name, "Heartbeat tool task failed: {e}");
+                        }
+                    }
+                    continue;
+                }
+            };
+            let temp = task.temperature.unwrap_or(config.default_temperature);
+            let run = Box::pin(crate::agent::run_capture(
+                config.clone(),
+                prompt,
+                None,
+                task.model,
+                temp,
+            ));
+            let run = crate::providers::origin::scope(
+                crate::providers::origin::RequestOrigin::new("heartbeat"),
+                run,
+            );
+            match run.await {
+                Ok(outcome) => {
+                    crate::health::mark_component_ok("heartbeat");
+                    tracing::info!(
+                        tool_calls = outcome.tool_calls.len(),
+                        "Heartbeat task completed: {}",
+                        outcome.text
+                    );
+                }
+                Err(e) => {
+                    crate::health::mark_component_error("heartbeat", e.to_string());
+                    tracing::warn!("Heartbeat task failed: {e}");
+                }
+            }
+        }
+    }
+}
+
+/// Run a heartbeat tool task without the model. The tool comes from the
+/// same registry the agent gets, so the autonomy level and command policy
+/// apply; a tool that policy leaves out is an error. Returns the output.
+async fn run_heartbeat_tool(
+    config: &Config,
+    name: &str,
+    args: serde_json::Value,
+) -> Result<String> {
+    let security = Arc::new(crate::security::SecurityPolicy::from_config(
+        &config.autonomy,
+        &config.workspace_dir,
+    ));
+    let mem: Arc<dyn crate::memory::Memory> = Arc::from(crate::memory::create_memory(
+        &config.memory,
+        &config.workspace_dir,
+        config.api_key.as_deref(),
+    )?);
+    let registry = crate::tools::ToolRegistry::from_config(config, &security, mem);
+    let Some(tool) = registry.get(name) else {
+        anyhow::bail!("tool '{name}' is unknown or not allowed under the current autonomy level");
+    };
+    let result = crate::tools::execute_with_timeout(tool, args).await?;
+    if result.success {
+        Ok(result.output)
+    } else {
+        anyhow::bail!(
+            "{}",
+            result
+                .error
+                .unwrap_or_else(|| "tool reported failure".into())
+        )
+    }
+}
+
+fn has_supervised_channels(config: &Config) -> bool {
+    config.channels_config.telegram.is_some()
+        || config.channels_config.discord.is_some()
+        || config.channels_config.slack.is_some()
+        || config.channels_config.imessage.is_some()
+        || config.channels_config.matrix.is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs2::FileExt;
+    use tempfile::TempDir;
+
+    fn noop() -> Arc<dyn Observer> {
+        Arc::new(NoopObserver)
+    }
+
+    #[derive(Default)]
+    struct RecordingObserver(parking_lot::Mutex<Vec<String>>);
+
+    impl Observer for RecordingObserver {
+        fn record_event(&self, event: &ObserverEvent) {
+            self.0.lock().push(format!("{event:?}"));
+        }
+
+        fn record_metric(&self, _metric: &crate::observability::traits::ObserverMetric) {}
+
+        fn name(&self) -> &str {
+            "recording"
+        }
+    }
+
+    fn test_config(tmp: &TempDir) -> Config {
+        let config = Config {
+            workspace_dir: tmp.path().join("workspace"),
+            config_path: tmp.path().join("config.toml"),
+            ..Config::default()
+        };
+        std::fs::create_dir_all(&config.workspace_dir).unwrap();
+        config
+    }
+
+    #[tokio::test]
+    async fn heartbeat_tool_task_runs_without_the_model() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = test_config(&tmp);
+        config.autonomy.allowed_commands = vec!["echo".into()];
+
+        let output = run_heartbeat_tool(
+            &config,
+            "shell",
+            serde_json::json!({"command": "echo maintenance"}),
+        )
+        .await
+        .unwrap();
+        assert!(output.contains("maintenance"));
+
+        let err = run_heartbeat_tool(&config, "shell", serde_json::json!({"command": "rm -rf /"}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not allowed"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn heartbeat_tool_task_respects_autonomy() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = test_config(&tmp);
+        config.autonomy.level = crate::security::AutonomyLevel::ReadOnly;
+
+        let err = run_heartbeat_tool(
+            &config,
+            "memory_store",
+            serde_json::json!({"key": "k", "content": "v"}),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("not allowed"));
+    }
+
+    #[test]
+    fn disabled_components_are_matched_case_insensitively() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = test_config(&tmp);
+        assert!(DAEMON_COMPONENTS
+            .iter()
+            .all(|name| component_enabled(&config, name)));
+
+        config.daemon.disabled_components = vec!["Scheduler".into(), "heartbeat".into()];
+        assert!(!component_enabled(&config, "scheduler"));
+        assert!(!component_enabled(&config, "heartbeat"));
+        assert!(component_enabled(&config, "gateway"));
+    }
+
+    #[tokio::test]
+    async fn terminal_error_stops_supervisor_without_restart() {
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = std::sync::Arc::clone(&calls);
+        let shutdown = crate::agent::CancelToken::new();
+        let run = run_supervised_component("gateway", noop(), 1, 1, None, shutdown, move || {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async { Err(crate::health::TerminalError("port taken".into()).into()) }
+        });
+
+        let health = crate::health::HealthRegistry::new();
+        let run = crate::health::scoped(Arc::clone(&health), run);
+        tokio::time::timeout(Duration::from_secs(5), run)
+            .await
+            .expect("supervisor should return on a terminal error");
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let snapshot = health.snapshot();
+        let component = &snapshot.components["gateway"];
+        assert_eq!(component.status, "failed");
+        assert_eq!(component.restart_count, 0);
+    }
+
+    #[tokio::test]
+    async fn supervisor_reports_lifecycle_to_observer() {
+        let observer = Arc::new(RecordingObserver::default());
+        let mut calls = 0;
+        let run = run_supervised_component(
+            "daemon-test-lifecycle",
+            Arc::clone(&observer) as Arc<dyn Observer>,
+            1,
+            1,
+            None,
+            crate::agent::CancelToken::new(),
+            move || {
+                calls += 1;
+                let first = calls == 1;
+                async move {
+                    if first {
+                        anyhow::bail!("flaky")
+                    }
+                    Err(crate::health::TerminalError("gone".into()).into())
+                }
+            },
+        );
+        tokio::time::timeout(Duration::from_secs(10), run)
+            .await
+            .unwrap();
+
+        let events = observer.0.lock();
+        assert_eq!(events.len(), 4, "{events:?}");
+        assert!(events[0].starts_with("ComponentStart") && events[0].contains("restarts: 0"));
+        assert!(events[1].contains("flaky") && events[1].contains("will_restart: true"));
+        assert!(events[2].contains("restarts: 1"));
+        assert!(events[3].contains("gone") && events[3].contains("will_restart: false"));
+    }
+
+    #[test]
+    fn state_file_path_uses_config_directory() {
+        let tmp = TempDir::new().unwrap();
+        let config = test_config(&tmp);
+
+        let path = state_file_path(&config);
+        assert_eq!(path, tmp.path().join("daemon_state.json"));
+    }
+
+    #[tokio::test]
+    async fn state_history_rotation_keeps_last_n() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("daemon_state.json");
+
+        for i in 0..5 {
+            rotate_state_history(&path, 2).await;
+            std::fs::write(&path, format!("{i}")).unwrap();
+        }
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "4");
+        assert_eq!(
+            std::fs::read_to_string(history_path(&path, 1)).unwrap(),
+            "3"
+        );
+        assert_eq!(
+            std::fs::read_to_string(history_path(&path, 2)).unwrap(),
+            "2"
+        );
+        assert!(!history_path(&path, 3).exists());
+    }
+
+    /// Yield until `done`, without sleeping: a sleep would let the paused
+    /// clock jump ahead on its own.
+    async fn settled(done: impl Fn() -> bool) -> bool {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while std::time::Instant::now() < deadline {
+            if done() {
+                return true;
             }
+            tokio::task::yield_now().await;
         }
+        done()
+    }
 
-        crate::health::bump_component_restart(name);
-        // +/-25% jitter to prevent thundering herd on mass restart
-        let jitter_bytes = uuid::Uuid::new_v4();
-        let raw = u32::from_le_bytes([
-            jitter_bytes.as_bytes()[0],
-            jitter_bytes.as_bytes()[1],
-            jitter_bytes.as_bytes()[2],
-            jitter_bytes.as_bytes()[3],
-        ]);
-        let factor = 0.75 + (f64::from(raw) / f64::from(u32::MAX)) * 0.5;
-        #[allow(
-            clippy::cast_possible_truncation,
-            clippy::cast_sign_loss,
-            clippy::cast_precision_loss
-        )]
-        let jittered = ((backoff as f64) * factor) as u64;
-        tokio::time::sleep(Duration::from_secs(jittered.max(1))).await;
-        backoff = backoff.saturating_mul(2).min(max_backoff);
+    #[tokio::test(start_paused = true)]
+    async fn state_writer_flushes_on_the_configured_interval() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = test_config(&tmp);
+        // 0 is raised to the 1s floor.
+        config.reliability.state_flush_secs = 0;
+        config.daemon.state_history = 10;
+        let path = state_file_path(&config);
+
+        let writer = tokio::spawn(run_state_writer(config));
+        assert!(settled(|| path.exists()).await, "first write is immediate");
+
+        tokio::time::advance(Duration::from_millis(500)).await;
+        for _ in 0..100 {
+            tokio::task::yield_now().await;
+        }
+        assert!(!history_path(&path, 1).exists(), "flushed before 1s");
+
+        tokio::time::advance(Duration::from_millis(500)).await;
+        assert!(settled(|| history_path(&path, 1).exists()).await);
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(settled(|| history_path(&path, 2).exists()).await);
+        writer.abort();
+        let _ = writer.await;
+
+        // Writes at 0s, 1s and 2s: the current file plus two older ones.
+        assert!(!history_path(&path, 3).exists());
     }
-}
 
-fn spawn_component_supervisor<F, Fut>(
-    name: &'static str,
-    initial_backoff_secs: u64,
-    max_backoff_secs: u64,
-    run_component: F,
-) -> JoinHandle<()>
-where
-    F: FnMut() -> Fut + Send + 'static,
-    Fut: Future<Output = Result<()>> + Send + 'static,
-{
-    tokio::spawn(run_supervised_component(
-        name,
-        initial_backoff_secs,
-        max_backoff_secs,
-        run_component,
-    ))
-}
+    #[tokio::test]
+    async fn compressed_state_round_trips() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = test_config(&tmp);
+        config.daemon.compress_state = true;
+        let path = state_file_path(&config);
+        assert_eq!(path, tmp.path().join("daemon_state.json.lz4"));
 
-async fn run_heartbeat_worker(config: Config) -> Result<()> {
-    let observer: std::sync::Arc<dyn crate::observability::Observer> =
-        std::sync::Arc::from(crate::observability::create_observer(&config.observability));
-    let engine = crate::heartbeat::engine::HeartbeatEngine::new(
-        config.heartbeat.clone(),
-        config.workspace_dir.clone(),
-        observer,
-    );
+        write_state_snapshot(&path, 0).await;
+        let raw = std::fs::read(&path).unwrap();
+        assert!(serde_json::from_slice::<serde_json::Value>(&raw).is_err());
+        let state = parse_state(&path, &raw).unwrap();
+        assert!(state["written_at"].is_string());
+    }
 
-    let interval_mins = config.heartbeat.interval_minutes.max(5);
-    let mut interval = tokio::time::interval(Duration::from_secs(u64::from(interval_mins) * 60));
+    #[tokio::test]
+    async fn state_history_disabled_by_default() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("daemon_state.json");
+        std::fs::write(&path, "0").unwrap();
 
-    loop {
-        interval.tick().await;
+        rotate_state_history(&path, Config::default().daemon.state_history).await;
+        assert!(!history_path(&path, 1).exists());
+    }
 
-        let tasks = engine.collect_tasks().await?;
-        if tasks.is_empty() {
-            continue;
-        }
+    #[tokio::test]
+    async fn final_snapshot_records_shutdown_reason() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("daemon_state.json");
+        let health = crate::health::HealthRegistry::new();
+        crate::health::scoped(health, async {
+            crate::health::mark_component_stopped(
+                "daemon",
+                format!("shutdown: {}", ShutdownReason::Terminate),
+            );
+            write_state_snapshot(&path, 0).await;
+        })
+        .await;
 
-        for task in tasks {
-            let prompt = format!("[Heartbeat Task] {task}");
-            let temp = config.default_temperature;
-            if let Err(e) = crate::agent::run(config.clone(), Some(prompt), None, None, temp).await
-            {
-                crate::health::mark_component_error("heartbeat", e.to_string());
-                tracing::warn!("Heartbeat task failed: {e}");
-            } else {
-                crate::health::mark_component_ok("heartbeat");
-            }
-        }
+        let state: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let daemon = &state["components"]["daemon"];
+        assert_eq!(daemon["status"], "stopped");
+        assert_eq!(daemon["last_error"], "shutdown: terminate");
+        assert!(state["written_at"].is_string());
     }
-}
 
-fn has_supervised_channels(config: &Config) -> bool {
-    config.channels_config.telegram.is_some()
-        || config.channels_config.discord.is_some()
-        || config.channels_config.slack.is_some()
-        || config.channels_config.imessage.is_some()
-        || config.channels_config.matrix.is_some()
-}
+    fn manual_signals() -> (
+        tokio::sync::watch::Sender<Option<ShutdownReason>>,
+        ShutdownSignals,
+    ) {
+        let (tx, rx) = tokio::sync::watch::channel(None);
+        (tx, ShutdownSignals { rx, listener: None })
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use fs2::FileExt;
-    use tempfile::TempDir;
+    #[tokio::test]
+    async fn signal_interrupts_a_slow_startup_step() {
+        let (tx, mut signals) = manual_signals();
+        assert_eq!(signals.interruptible(async { 7 }).await, Ok(7));
 
-    fn test_config(tmp: &TempDir) -> Config {
-        let mut config = Config::default();
-        config.workspace_dir = tmp.path().join("workspace");
-        config.config_path = tmp.path().join("config.toml");
-        std::fs::create_dir_all(&config.workspace_dir).unwrap();
-        config
+        let step = signals.interruptible(std::future::pending::<()>());
+        tx.send_replace(Some(ShutdownReason::Interrupt));
+        assert_eq!(
+            tokio::time::timeout(Duration::from_secs(5), step)
+                .await
+                .unwrap(),
+            Err(ShutdownReason::Interrupt)
+        );
+    }
+
+    #[tokio::test]
+    async fn signal_before_startup_step_skips_it() {
+        let (tx, mut signals) = manual_signals();
+        tx.send_replace(Some(ShutdownReason::Terminate));
+        let ran = signals.interruptible(async { true }).await;
+        assert_eq!(ran, Err(ShutdownReason::Terminate));
     }
 
     #[test]
-    fn state_file_path_uses_config_directory() {
+    fn abandoned_lock_is_removed_only_if_created() {
         let tmp = TempDir::new().unwrap();
-        let config = test_config(&tmp);
+        let lock_path = tmp.path().join("daemon.lock");
+        let open = || {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&lock_path)
+                .unwrap();
+            file.try_lock_exclusive().unwrap();
+            file
+        };
 
-        let path = state_file_path(&config);
-        assert_eq!(path, tmp.path().join("daemon_state.json"));
+        abandon_startup_lock(open(), &lock_path, true);
+        assert!(!lock_path.exists());
+
+        let file = open();
+        abandon_startup_lock(file, &lock_path, false);
+        assert!(lock_path.exists());
+        // Released: a fresh handle can take the lock.
+        let again = std::fs::File::open(&lock_path).unwrap();
+        again.try_lock_exclusive().unwrap();
     }
 
-    #[tokio::test]
-    async fn supervisor_marks_error_and_restart_on_failure() {
-        let handle = spawn_component_supervisor("daemon-test-fail", 1, 1, || async {
-            anyhow::bail!("boom")
-        });
+    #[test]
+    fn only_lock_loss_is_an_unrequested_stop() {
+        assert!(ShutdownReason::Interrupt.is_requested());
+        assert!(ShutdownReason::Terminate.is_requested());
+        assert!(ShutdownReason::Requested.is_requested());
+        assert!(!ShutdownReason::LockLost.is_requested());
+        assert_eq!(ShutdownReason::LockLost.to_string(), "lock_lost");
+    }
 
-        tokio::time::sleep(Duration::from_millis(50)).await;
-        handle.abort();
-        let _ = handle.await;
+    /// Run a supervisor briefly against a private health registry and return
+    /// what it recorded for `name`.
+    async fn supervise_briefly<F, Fut>(
+        name: &'static str,
+        run_component: F,
+    ) -> crate::health::ComponentHealth
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let health = crate::health::HealthRegistry::new();
+        crate::health::scoped(Arc::clone(&health), async {
+            let handle = spawn_component_supervisor(
+                name,
+                noop(),
+                1,
+                1,
+                None,
+                crate::agent::CancelToken::new(),
+                run_component,
+            );
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            handle.abort();
+            let _ = handle.await;
+        })
+        .await;
+        health.snapshot().components[name].clone()
+    }
 
-        let snapshot = crate::health::snapshot_json();
-        let component = &snapshot["components"]["daemon-test-fail"];
-        assert_eq!(component["status"], "error");
-        assert!(component["restart_count"].as_u64().unwrap_or(0) >= 1);
-        assert!(component["last_error"]
-            .as_str()
-            .unwrap_or("")
-            .contains("boom"));
+    #[tokio::test]
+    async fn supervisor_marks_error_and_restart_on_failure() {
+        let component = supervise_briefly("channels", || async { anyhow::bail!("boom") }).await;
+        assert_eq!(component.status, "error");
+        assert_eq!(component.restart_count, 1);
+        assert!(component.last_error.unwrap_or_default().contains("boom"));
     }
 
     #[tokio::test]
     async fn supervisor_marks_unexpected_exit_as_error() {
-        let handle = spawn_component_supervisor("daemon-test-exit", 1, 1, || async { Ok(()) });
+        let component = supervise_briefly("channels", || async { Ok(()) }).await;
+        assert_eq!(component.status, "error");
+        assert_eq!(component.restart_count, 1);
+        assert!(component
+            .last_error
+            .unwrap_or_default()
+            .contains("component exited unexpectedly"));
+    }
 
+    #[tokio::test]
+    async fn shutdown_interrupts_restart_backoff() {
+        let health = crate::health::HealthRegistry::new();
+        let shutdown = crate::agent::CancelToken::new();
+        let run = run_supervised_component(
+            "channels",
+            noop(),
+            3_600,
+            3_600,
+            None,
+            shutdown.clone(),
+            || async { anyhow::bail!("boom") },
+        );
+        let run = tokio::spawn(crate::health::scoped(Arc::clone(&health), run));
         tokio::time::sleep(Duration::from_millis(50)).await;
-        handle.abort();
-        let _ = handle.await;
-
-        let snapshot = crate::health::snapshot_json();
-        let component = &snapshot["components"]["daemon-test-exit"];
-        assert_eq!(component["status"], "error");
-        assert!(component["restart_count"].as_u64().unwrap_or(0) >= 1);
-        assert!(component["last_error"]
-            .as_str()
-            .unwrap_or("")
-            .contains("component exited unexpectedly"));
+
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(5), run)
+            .await
+            .expect("supervisor should leave its backoff on shutdown")
+            .unwrap();
+
+        let component = &health.snapshot().components["channels"];
+        assert_eq!(component.status, "stopped");
+        assert_eq!(component.restart_count, 1);
+    }
+
+    #[cfg(unix)]
+    fn held_lock(tmp: &TempDir) -> (PathBuf, std::fs::File) {
+        let lock_path = tmp.path().join("daemon.lock");
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&lock_path)
+            .unwrap();
+        file.try_lock_exclusive().unwrap();
+        write_lock_holder(&mut file).unwrap();
+        (lock_path, file)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn verify_lock_accepts_held_lock() {
+        let tmp = TempDir::new().unwrap();
+        let (lock_path, file) = held_lock(&tmp);
+        assert_eq!(verify_lock(&lock_path, &file), Ok(()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn verify_lock_detects_foreign_pid() {
+        let tmp = TempDir::new().unwrap();
+        let (lock_path, file) = held_lock(&tmp);
+        std::fs::write(&lock_path, "999999999").unwrap();
+        let err = verify_lock(&lock_path, &file).unwrap_err();
+        assert!(err.contains("999999999"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn verify_lock_detects_released_lock() {
+        let tmp = TempDir::new().unwrap();
+        let (lock_path, file) = held_lock(&tmp);
+        FileExt::unlock(&file).unwrap();
+        let err = verify_lock(&lock_path, &file).unwrap_err();
+        assert!(err.contains("no longer held"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn verify_lock_detects_replaced_file() {
+        let tmp = TempDir::new().unwrap();
+        let (lock_path, file) = held_lock(&tmp);
+        std::fs::remove_file(&lock_path).unwrap();
+        std::fs::write(&lock_path, LockHolder::current().render()).unwrap();
+        let err = verify_lock(&lock_path, &file).unwrap_err();
+        assert!(err.contains("replaced"), "{err}");
     }
 
     #[test]
@@ -362,6 +2077,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn lock_file_path_honours_config_override() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = test_config(&tmp);
+        assert_eq!(lock_file_path(&config), tmp.path().join("daemon.lock"));
+
+        config.daemon.lock_file = Some(PathBuf::from("run/baihu.lock"));
+        assert_eq!(lock_file_path(&config), tmp.path().join("run/baihu.lock"));
+
+        let absolute = tmp.path().join("elsewhere.lock");
+        config.daemon.lock_file = Some(absolute.clone());
+        assert_eq!(lock_file_path(&config), absolute);
+    }
+
+    #[test]
+    fn is_running_probes_without_taking_the_lock() {
+        let tmp = TempDir::new().unwrap();
+        let config = test_config(&tmp);
+        let lock_path = lock_file_path(&config);
+        assert!(!is_running(&config));
+        assert!(!lock_path.exists(), "probe must not create the lock file");
+
+        let daemon = std::fs::File::create(&lock_path).unwrap();
+        assert!(!is_running(&config));
+        daemon.try_lock_exclusive().unwrap();
+        assert!(is_running(&config));
+        assert!(is_running(&config), "probes don't contend with each other");
+
+        FileExt::unlock(&daemon).unwrap();
+        assert!(!is_running(&config));
+        // The probe left nothing behind that blocks the daemon.
+        daemon.try_lock_exclusive().unwrap();
+    }
+
+    #[test]
+    fn unknown_disabled_components_are_reported() {
+        let mut config = Config::default();
+        config.daemon.disabled_components =
+            vec!["Gateway".into(), "shceduler".into(), "cron".into()];
+        assert_eq!(unknown_disabled_components(&config), ["shceduler", "cron"]);
+    }
+
     #[test]
     fn detects_no_supervised_channels() {
         let config = Config::default();
@@ -377,4 +2134,466 @@ mod tests {
         });
         assert!(has_supervised_channels(&config));
     }
+
+    fn idle_config(tmp: &TempDir) -> Config {
+        let mut config = test_config(tmp);
+        config.providers.preflight = false;
+        config.heartbeat.enabled = false;
+        config.daemon.quiet = true;
+        config.daemon.disabled_components =
+            DAEMON_COMPONENTS.iter().map(|c| (*c).to_string()).collect();
+        config
+    }
+
+    #[tokio::test]
+    async fn handle_reloads_and_shuts_down_an_embedded_daemon() {
+        let registry = crate::health::HealthRegistry::new();
+        Box::pin(crate::health::scoped(Arc::clone(&registry), async {
+            let tmp = TempDir::new().unwrap();
+            let config = idle_config(&tmp);
+            let daemon = Daemon::builder(config.clone())
+                .port(0)
+                .start()
+                .await
+                .unwrap();
+            assert!(is_running(&config));
+            let health = daemon.health();
+            assert_eq!(health.components["daemon"].status, "ok");
+            assert_eq!(health.components["gateway"].status, "disabled");
+            assert!(daemon.gateway_addr().is_none());
+
+            let mut moved = config.clone();
+            moved.config_path = tmp.path().join("elsewhere").join("config.toml");
+            assert!(daemon.reload(moved).await.is_err());
+
+            let mut reloaded = config.clone();
+            reloaded
+                .daemon
+                .disabled_components
+                .retain(|c| c != "state_writer");
+            daemon.reload(reloaded).await.unwrap();
+
+            daemon.shutdown().await.unwrap();
+            assert!(!is_running(&config));
+            // Written on the way out because the reload enabled the writer.
+            assert!(state_file_path(&config).exists());
+        }))
+        .await;
+        assert_eq!(registry.snapshot().components["daemon"].status, "stopped");
+    }
+
+    #[tokio::test]
+    async fn reloads_are_reported_to_the_observer() {
+        let tmp = TempDir::new().unwrap();
+        let config = idle_config(&tmp);
+        let mut daemon = Daemon::builder(config.clone()).start().await.unwrap();
+        let recorder = Arc::new(RecordingObserver::default());
+        daemon.observer = recorder.clone();
+
+        daemon.reload(config.clone()).await.unwrap();
+        let unreadable = Err(anyhow::anyhow!("bad toml"));
+        assert!(daemon.apply_reload(unreadable).await.is_err());
+        daemon.shutdown().await.unwrap();
+
+        let events = recorder.0.lock().clone();
+        assert_eq!(
+            events,
+            vec![
+                "DaemonReload { error: None }".to_string(),
+                "DaemonReload { error: Some(\"bad toml\") }".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reload_swaps_the_provider_stack_or_keeps_it_on_error() {
+        let tmp = TempDir::new().unwrap();
+        let config = idle_config(&tmp);
+        let daemon = Daemon::builder(config.clone()).start().await.unwrap();
+        let started = daemon.provider.current();
+
+        daemon.reload(config.clone()).await.unwrap();
+        let reloaded = daemon.provider.current();
+        assert!(!Arc::ptr_eq(&started, &reloaded));
+
+        let mut broken = config.clone();
+        broken.default_provider = Some("no-such-provider".into());
+        assert!(daemon.reload(broken).await.is_err());
+        assert!(Arc::ptr_eq(&reloaded, &daemon.provider.current()));
+
+        daemon.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn start_reports_the_bound_gateway_and_fails_on_a_taken_port() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = idle_config(&tmp);
+        config.daemon.disabled_components.retain(|c| c != "gateway");
+
+        let held = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let taken = held.local_addr().unwrap().port();
+        let Err(err) = Daemon::builder(config.clone()).port(taken).start().await else {
+            panic!("start should fail while the port is held");
+        };
+        assert!(err.to_string().contains("already in use"), "{err}");
+        assert!(!is_running(&config));
+
+        let daemon = Daemon::builder(config.clone())
+            .port(0)
+            .start()
+            .await
+            .unwrap();
+        let addr = daemon.gateway_addr().unwrap();
+        assert_ne!(addr.port(), 0);
+        tokio::net::TcpStream::connect(addr).await.unwrap();
+        daemon.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn dropping_the_handle_stops_the_daemon() {
+        let tmp = TempDir::new().unwrap();
+        let config = idle_config(&tmp);
+        let daemon = Daemon::builder(config.clone()).start().await.unwrap();
+        drop(daemon);
+        for _ in 0..50 {
+            if !is_running(&config) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("daemon kept its lock after the handle was dropped");
+    }
+
+    #[test]
+    fn restart_budget_resets_after_a_long_run() {
+        let mut budget = RestartBudget::new(Some(2), 1);
+        assert!(budget.allow_restart(Duration::ZERO));
+        assert!(budget.allow_restart(Duration::ZERO));
+        assert!(!budget.allow_restart(Duration::ZERO));
+
+        // Up for longer than 3x the max backoff: the streak is over.
+        assert!(budget.allow_restart(Duration::from_secs(4)));
+        assert_eq!(budget.used, 1);
+
+        let mut unlimited = RestartBudget::new(None, 1);
+        assert!((0..1_000).all(|_| unlimited.allow_restart(Duration::ZERO)));
+    }
+
+    #[tokio::test]
+    async fn supervisor_gives_up_after_max_restarts() {
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = std::sync::Arc::clone(&calls);
+        let observer = Arc::new(RecordingObserver::default());
+        let run = run_supervised_component(
+            "channels",
+            Arc::clone(&observer) as Arc<dyn Observer>,
+            1,
+            1,
+            Some(2),
+            crate::agent::CancelToken::new(),
+            move || {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                async { anyhow::bail!("boom") }
+            },
+        );
+
+        let health = crate::health::HealthRegistry::new();
+        let run = crate::health::scoped(Arc::clone(&health), run);
+        tokio::time::timeout(Duration::from_secs(10), run)
+            .await
+            .expect("supervisor should give up once restarts run out");
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+        let snapshot = health.snapshot();
+        let component = &snapshot.components["channels"];
+        assert_eq!(component.status, "failed");
+        assert_eq!(component.restart_count, 2);
+        assert!(component
+            .last_error
+            .as_deref()
+            .unwrap_or_default()
+            .contains("after 2 restarts"));
+        let events = observer.0.lock();
+        assert!(events.last().unwrap().contains("will_restart: false"));
+    }
+
+    #[tokio::test]
+    async fn graceful_component_drains_before_the_grace_deadline() {
+        let health = crate::health::HealthRegistry::new();
+        let shutdown = crate::agent::CancelToken::new();
+        let drained = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let flag = Arc::clone(&drained);
+        let mut components = Components {
+            tasks: JoinSet::new(),
+            shutdown: shutdown.clone(),
+            grace: Duration::from_secs(5),
+        };
+        let run = run_graceful_component("gateway", noop(), 1, 1, None, shutdown, move |stop| {
+            let flag = Arc::clone(&flag);
+            async move {
+                stop.cancelled().await;
+                // Finish the request in flight when the signal came.
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                flag.store(true, std::sync::atomic::Ordering::SeqCst);
+                Ok(())
+            }
+        });
+        components
+            .tasks
+            .spawn(crate::health::scoped(Arc::clone(&health), run));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let started = std::time::Instant::now();
+        components.stop().await;
+        assert!(drained.load(std::sync::atomic::Ordering::SeqCst));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let component = &health.snapshot().components["gateway"];
+        assert_eq!(component.status, "stopped");
+        assert_eq!(component.restart_count, 0);
+    }
+
+    #[tokio::test]
+    async fn stragglers_are_aborted_after_the_grace_period() {
+        let shutdown = crate::agent::CancelToken::new();
+        let mut components = Components {
+            tasks: JoinSet::new(),
+            shutdown: shutdown.clone(),
+            grace: Duration::from_millis(100),
+        };
+        let run = run_graceful_component("gateway", noop(), 1, 1, None, shutdown, |_| async {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            Ok(())
+        });
+        components.tasks.spawn(crate::health::scoped(
+            crate::health::HealthRegistry::new(),
+            run,
+        ));
+
+        tokio::time::timeout(Duration::from_secs(5), components.stop())
+            .await
+            .expect("stop should abort what is still running at the deadline");
+        assert!(components.tasks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn plain_component_stops_at_once_on_shutdown() {
+        let health = crate::health::HealthRegistry::new();
+        let shutdown = crate::agent::CancelToken::new();
+        let run =
+            run_supervised_component("channels", noop(), 1, 1, None, shutdown.clone(), || async {
+                std::future::pending::<()>().await;
+                Ok(())
+            });
+        let run = tokio::spawn(crate::health::scoped(Arc::clone(&health), run));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(5), run)
+            .await
+            .unwrap()
+            .unwrap();
+        let component = &health.snapshot().components["channels"];
+        assert_eq!(component.status, "stopped");
+        assert_eq!(component.restart_count, 0);
+    }
+
+    /// Poll `done` for up to 5 seconds.
+    async fn eventually(done: impl Fn() -> bool) -> bool {
+        for _ in 0..250 {
+            if done() {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        done()
+    }
+
+    /// Set for the child process [`sighup_reloads_and_sigterm_stops_the_daemon`]
+    /// spawns: the config it runs the daemon on.
+    #[cfg(unix)]
+    const SIGNAL_CHILD_CONFIG: &str = "BAIHU_TEST_SIGNAL_CHILD_CONFIG";
+
+    #[cfg(unix)]
+    #[tokio::test]
+    #[ignore = "run as a child process by sighup_reloads_and_sigterm_stops_the_daemon"]
+    async fn signalled_daemon_child() {
+        let Some(path) = std::env::var_os(SIGNAL_CHILD_CONFIG) else {
+            return;
+        };
+        let mut config = Config::load_from(Path::new(&path)).unwrap();
+        config.config_path = PathBuf::from(path);
+        run(config, "127.0.0.1".into(), 0).await.unwrap();
+    }
+
+    // The daemon runs in a child test process: signals sent to this one
+    // would reach every other test running in it.
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sighup_reloads_and_sigterm_stops_the_daemon() {
+        let tmp = TempDir::new().unwrap();
+        let config = idle_config(&tmp);
+        config.save().unwrap();
+
+        let mut child = tokio::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "daemon::tests::signalled_daemon_child",
+                "--ignored",
+                "--quiet",
+            ])
+            .env(SIGNAL_CHILD_CONFIG, &config.config_path)
+            .stdout(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        let pid = child.id().unwrap().to_string();
+        let send = |signal: &str| {
+            let sent = std::process::Command::new("kill")
+                .args([signal, pid.as_str()])
+                .status()
+                .unwrap();
+            assert!(sent.success());
+        };
+        assert!(eventually(|| is_running(&config)).await);
+
+        let mut edited = config.clone();
+        edited
+            .daemon
+            .disabled_components
+            .retain(|c| c != "state_writer");
+        edited.save().unwrap();
+        send("-HUP");
+        assert!(
+            eventually(|| state_file_path(&config).exists()).await,
+            "SIGHUP should start the state writer the edited config enables"
+        );
+
+        send("-TERM");
+        let status = tokio::time::timeout(Duration::from_secs(10), child.wait())
+            .await
+            .expect("SIGTERM should stop the daemon")
+            .unwrap();
+        assert!(status.success(), "{status}");
+        assert!(!is_running(&config));
+        // The final snapshot records why the daemon stopped.
+        let path = state_file_path(&config);
+        let state = parse_state(&path, &std::fs::read(&path).unwrap()).unwrap();
+        let stopped = &state["components"]["daemon"];
+        assert_eq!(stopped["status"], "stopped");
+        assert_eq!(stopped["last_error"], "shutdown: terminate");
+    }
+
+    #[cfg(target_os = "linux")]
+    fn dead_pid() -> u32 {
+        // A PID that was just reaped stands in for a SIGKILLed daemon.
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let dead = child.id();
+        child.wait().unwrap();
+        assert_eq!(process_alive(dead), Some(false));
+        dead
+    }
+
+    /// The lingering lock: held through another handle, naming `holder`.
+    #[cfg(target_os = "linux")]
+    fn lingering_lock(lock_path: &Path, holder: &LockHolder) -> std::fs::File {
+        std::fs::write(lock_path, holder.render()).unwrap();
+        let lingering = std::fs::File::open(lock_path).unwrap();
+        lingering.try_lock_exclusive().unwrap();
+        lingering
+    }
+
+    #[test]
+    fn lock_holder_round_trips() {
+        let holder = LockHolder {
+            host: "box:with:colons".into(),
+            pid: 42,
+            scope: Some("boot pid:[1]".into()),
+        };
+        assert_eq!(LockHolder::parse(&holder.render()), Some(holder));
+        assert_eq!(LockHolder::parse("1234"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn lock_left_by_a_dead_pid_is_reclaimed() {
+        let tmp = TempDir::new().unwrap();
+        let lock_path = tmp.path().join("daemon.lock");
+        let holder = LockHolder {
+            pid: dead_pid(),
+            ..LockHolder::current()
+        };
+        let _lingering = lingering_lock(&lock_path, &holder);
+
+        let lock = StartupLock::acquire(&lock_path).unwrap();
+        assert!(lock.created);
+        assert_eq!(read_lock_holder(&lock_path), Some(LockHolder::current()));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn lock_from_another_host_or_namespace_is_not_reclaimed() {
+        let tmp = TempDir::new().unwrap();
+        let lock_path = tmp.path().join("daemon.lock");
+        let dead = dead_pid();
+        for holder in [
+            LockHolder {
+                host: "other-host".into(),
+                pid: dead,
+                ..LockHolder::current()
+            },
+            LockHolder {
+                pid: dead,
+                scope: Some("other-boot pid:[1]".into()),
+                ..LockHolder::current()
+            },
+            LockHolder {
+                pid: dead,
+                scope: None,
+                ..LockHolder::current()
+            },
+        ] {
+            let _lingering = lingering_lock(&lock_path, &holder);
+            let err = StartupLock::acquire(&lock_path).err().unwrap().to_string();
+            assert!(err.contains(&format!("PID {dead}")), "{err}");
+            assert_eq!(read_lock_holder(&lock_path), Some(holder));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn stale_lock_replaced_meanwhile_is_not_unlinked() {
+        let tmp = TempDir::new().unwrap();
+        let lock_path = tmp.path().join("daemon.lock");
+        let holder = LockHolder {
+            pid: dead_pid(),
+            ..LockHolder::current()
+        };
+        let _lingering = lingering_lock(&lock_path, &holder);
+        // We read the stale holder, then another starter reclaims first and
+        // locks a fresh file at the same path.
+        let seen = std::fs::File::open(&lock_path).unwrap();
+        assert!(reclaim_stale_lock(&lock_path).unwrap());
+        let winner = StartupLock::try_acquire(&lock_path).unwrap().unwrap();
+
+        assert!(!unlink_if_unchanged(&lock_path, &seen).unwrap());
+        assert!(StartupLock::acquire(&lock_path).is_err());
+        assert!(lock_path.exists());
+        drop(winner);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn lock_held_by_a_live_pid_names_it() {
+        let tmp = TempDir::new().unwrap();
+        let lock_path = tmp.path().join("daemon.lock");
+        let _held = StartupLock::acquire(&lock_path).unwrap();
+
+        let err = StartupLock::acquire(&lock_path).err().unwrap().to_string();
+        assert!(
+            err.contains(&format!("PID {}", std::process::id())),
+            "{err}"
+        );
+        assert!(lock_path.exists());
+    }
 }
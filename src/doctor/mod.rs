@@ -1,4 +1,5 @@
 use crate::config::Config;
+use crate::health::structured_error;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 
@@ -6,21 +7,100 @@ const DAEMON_STALE_SECONDS: i64 = 30;
 const SCHEDULER_STALE_SECONDS: i64 = 120;
 const CHANNEL_STALE_SECONDS: i64 = 300;
 
-pub fn run(config: &Config) -> Result<()> {
+pub mod preflight;
+
+/// `baihu doctor`: read-only checks, including a live probe of each provider.
+pub async fn run(config: &Config, host: &str) -> Result<()> {
+    println!("🩺 Baihu Doctor");
+    println!();
+    println!("Preflight:");
+    let checks = preflight::run_doctor_checks(config, host).await;
+    for check in &checks {
+        println!("  {} {}: {}", check.icon(), check.name, check.message);
+    }
+    println!();
+    println!("Daemon:");
+
+    report_daemon_state(config)?;
+
+    if preflight::has_failures(&checks) {
+        anyhow::bail!("Preflight failed — fix the ❌ items above");
+    }
+    Ok(())
+}
+
+/// Log preflight results at daemon startup. The daemon lock is already held.
+pub fn log_preflight(config: &Config, host: &str) {
+    for check in preflight::run_checks(config, host, false) {
+        match check.status {
+            preflight::CheckStatus::Pass => {
+                tracing::debug!("preflight {}: {}", check.name, check.message);
+            }
+            preflight::CheckStatus::Warn => {
+                tracing::warn!("preflight {}: {}", check.name, check.message);
+            }
+            preflight::CheckStatus::Fail => {
+                tracing::error!("preflight {}: {}", check.name, check.message);
+            }
+        }
+    }
+}
+
+/// Probe providers at startup when `providers.preflight` is on. Failures mark
+/// the provider degraded in health; with `strict_preflight` they stop startup.
+pub async fn run_provider_preflight(config: &Config) -> Result<()> {
+    let settings = &config.providers;
+    if !settings.preflight && !settings.strict_preflight {
+        return Ok(());
+    }
+
+    let mut failures = Vec::new();
+    for check in preflight::probe_providers(config).await {
+        if check.status == preflight::CheckStatus::Pass {
+            tracing::info!("preflight {}: {}", check.name, check.message);
+            crate::health::mark_component_ok(&check.name);
+        } else {
+            tracing::error!("preflight {}: {}", check.name, check.message);
+            crate::health::mark_component_degraded(&check.name, &check.message);
+            failures.push(check.message);
+        }
+    }
+
+    if settings.strict_preflight && !failures.is_empty() {
+        anyhow::bail!(
+            "Provider preflight failed (providers.strict_preflight is on):\n{}",
+            failures.join("\n")
+        );
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_lines)]
+fn report_daemon_state(config: &Config) -> Result<()> {
     let state_file = crate::daemon::state_file_path(config);
     if !state_file.exists() {
-        println!("🩺 Baihu Doctor");
         println!("  ❌ daemon state file not found: {}", state_file.display());
         println!("  💡 Start daemon with: baihu daemon");
         return Ok(());
     }
 
-    let raw = std::fs::read_to_string(&state_file)
+    let raw = std::fs::read(&state_file)
         .with_context(|| format!("Failed to read {}", state_file.display()))?;
-    let snapshot: serde_json::Value = serde_json::from_str(&raw)
-        .with_context(|| format!("Failed to parse {}", state_file.display()))?;
+    let snapshot = match crate::daemon::parse_state(&state_file, &raw) {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            println!(
+                "  ❌ {}",
+                structured_error(
+                    &format!("daemon state file is corrupt: {}", state_file.display()),
+                    &e.to_string(),
+                    "restart the daemon; it moves the damaged file aside and starts fresh",
+                )
+            );
+            return Ok(());
+        }
+    };
 
-    println!("🩺 Baihu Doctor");
     println!("  State file: {}", state_file.display());
 
     let updated_at = snapshot
@@ -32,7 +112,9 @@ pub fn run(config: &Config) -> Result<()> {
         let age = Utc::now()
             .signed_duration_since(ts.with_timezone(&Utc))
             .num_seconds();
-        if age <= DAEMON_STALE_SECONDS {
+        // A slow flush interval shouldn't read as a dead daemon.
+        let flush = i64::try_from(config.reliability.state_flush_secs).unwrap_or(i64::MAX);
+        if age <= DAEMON_STALE_SECONDS.max(flush.saturating_mul(3)) {
             println!("  ✅ daemon heartbeat fresh ({age}s ago)");
         } else {
             println!("  ❌ daemon heartbeat stale ({age}s ago)");
@@ -48,7 +130,15 @@ pub fn run(config: &Config) -> Result<()> {
         .get("components")
         .and_then(serde_json::Value::as_object)
     {
-        if let Some(scheduler) = components.get("scheduler") {
+        let scheduler_disabled = components
+            .get("scheduler")
+            .and_then(|c| c.get("status"))
+            .and_then(serde_json::Value::as_str)
+            .is_some_and(|s| s == "disabled");
+
+        if scheduler_disabled {
+            println!("  ⏸️ scheduler disabled in config");
+        } else if let Some(scheduler) = components.get("scheduler") {
             let scheduler_ok = scheduler
                 .get("status")
                 .and_then(serde_json::Value::as_str)
@@ -73,6 +163,30 @@ pub fn run(config: &Config) -> Result<()> {
             println!("  ❌ scheduler component missing");
         }
 
+        if let Some(observability) = components.get("observability") {
+            let status = observability
+                .get("status")
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or("unknown");
+            if status == "degraded" {
+                let reason = observability
+                    .get("last_error")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or("unknown");
+                println!("  ⚠️ observability degraded: {reason}");
+            }
+        }
+
+        for (name, component) in components {
+            if component.get("status").and_then(serde_json::Value::as_str) == Some("failed") {
+                let reason = component
+                    .get("last_error")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or("unknown");
+                println!("  ❌ {name} failed and will not be restarted: {reason}");
+            }
+        }
+
         for (name, component) in components {
             if !name.starts_with("channel:") {
                 continue;
@@ -0,0 +1,480 @@
+// Preflight checks run by `baihu doctor` and logged at daemon startup.
+//
+// Each check reports pass/warn/fail with a what/why/fix message so a
+// misconfiguration surfaces before the runtime trips over it.
+
+use crate::config::Config;
+use crate::health::structured_error;
+use crate::providers::Provider;
+use crate::security::pairing::is_public_bind;
+use crate::security::SecurityPolicy;
+use fs2::FileExt;
+use std::time::Duration;
+
+/// How long each provider gets to answer its startup probe.
+const PROVIDER_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+impl Check {
+    fn pass(name: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Pass,
+            message: message.into(),
+        }
+    }
+
+    fn warn(name: impl Into<String>, what: &str, why: &str, fix: &str) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Warn,
+            message: structured_error(what, why, fix),
+        }
+    }
+
+    fn fail(name: impl Into<String>, what: &str, why: &str, fix: &str) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Fail,
+            message: structured_error(what, why, fix),
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self.status {
+            CheckStatus::Pass => "✅",
+            CheckStatus::Warn => "⚠️",
+            CheckStatus::Fail => "❌",
+        }
+    }
+}
+
+/// Run every preflight check. `host` is the address the gateway would bind to.
+/// Set `check_lock` to false when the caller already holds the daemon lock.
+pub fn run_checks(config: &Config, host: &str, check_lock: bool) -> Vec<Check> {
+    let mut checks = vec![check_workspace_writable(config)];
+    checks.extend(check_providers(config));
+    checks.extend(check_channel_tokens(config));
+    checks.push(check_bind(config, host));
+    if check_lock {
+        checks.push(check_lock_acquirable(config));
+    }
+    checks
+}
+
+pub fn has_failures(checks: &[Check]) -> bool {
+    checks.iter().any(|c| c.status == CheckStatus::Fail)
+}
+
+/// [`Config::for_workspace`], with the workspace directory prepared like the
+/// default one.
+pub fn prepare_named_workspace(config: &Config, name: &str) -> anyhow::Result<Config> {
+    let mut scoped = config.for_workspace(name)?;
+    scoped.workspace_dir =
+        SecurityPolicy::from_config(&scoped.autonomy, &scoped.workspace_dir).prepare_workspace()?;
+    Ok(scoped)
+}
+
+/// Read-only: a missing workspace is reported, not created.
+fn check_workspace_writable(config: &Config) -> Check {
+    let dir = &config.workspace_dir;
+    match SecurityPolicy::from_config(&config.autonomy, dir).inspect_workspace() {
+        Ok(Some(canonical)) => {
+            Check::pass("workspace", format!("{} is writable", canonical.display()))
+        }
+        Ok(None) => Check::warn(
+            "workspace",
+            &format!("Workspace {} does not exist yet", dir.display()),
+            "nothing has run with this config",
+            "nothing to do: it is created when the agent, gateway or daemon starts",
+        ),
+        Err((why, fix)) => Check::fail(
+            "workspace",
+            &format!("Workspace {} is not usable", dir.display()),
+            &why,
+            fix,
+        ),
+    }
+}
+
+/// The primary provider followed by the fallback chain.
+fn provider_names(config: &Config) -> impl Iterator<Item = &str> {
+    let primary = config.default_provider.as_deref().unwrap_or("openrouter");
+    std::iter::once(primary).chain(
+        config
+            .reliability
+            .fallback_providers
+            .iter()
+            .map(String::as_str),
+    )
+}
+
+fn check_providers(config: &Config) -> Vec<Check> {
+    provider_names(config)
+        .map(|name| check_provider(name, config.api_key.as_deref()))
+        .collect()
+}
+
+/// Call every configured provider's [`Provider::preflight`]. Unlike
+/// [`run_checks`] this goes over the network, so the daemon only runs it
+/// when `providers.preflight` asks for it.
+pub async fn probe_providers(config: &Config) -> Vec<Check> {
+    let mut checks = Vec::new();
+    for name in provider_names(config) {
+        checks.push(probe_named_provider(config, name).await);
+    }
+    checks
+}
+
+/// [`run_checks`] for `baihu doctor`: providers that pass the static check
+/// are then probed over the network.
+pub async fn run_doctor_checks(config: &Config, host: &str) -> Vec<Check> {
+    let mut checks = run_checks(config, host, true);
+    for check in &mut checks {
+        if check.status != CheckStatus::Pass {
+            continue;
+        }
+        if let Some(name) = check.name.strip_prefix("provider:").map(str::to_string) {
+            *check = probe_named_provider(config, &name).await;
+        }
+    }
+    checks
+}
+
+async fn probe_named_provider(config: &Config, name: &str) -> Check {
+    match crate::providers::create_provider(name, config.api_key.as_deref()) {
+        Ok(provider) => probe_provider(name, provider.as_ref(), PROVIDER_PROBE_TIMEOUT).await,
+        Err(e) => Check::fail(
+            format!("provider:{name}"),
+            &format!("Provider '{name}' cannot be initialized"),
+            &e.to_string(),
+            "check default_provider / fallback_providers in config.toml",
+        ),
+    }
+}
+
+async fn probe_provider(name: &str, provider: &dyn Provider, limit: Duration) -> Check {
+    let label = format!("provider:{name}");
+    let why = match tokio::time::timeout(limit, provider.preflight()).await {
+        Ok(Ok(())) => return Check::pass(label, "reachable, key accepted"),
+        Ok(Err(e)) => e.to_string(),
+        Err(_) => format!("no answer within {}s", limit.as_secs()),
+    };
+    Check::fail(
+        label,
+        &format!("Provider '{name}' failed its startup check"),
+        &why,
+        "check api_key, network access and the provider's status page",
+    )
+}
+
+fn check_provider(name: &str, api_key: Option<&str>) -> Check {
+    let label = format!("provider:{name}");
+    if let Err(e) = crate::providers::create_provider(name, api_key) {
+        return Check::fail(
+            label,
+            &format!("Provider '{name}' cannot be initialized"),
+            &e.to_string(),
+            "check default_provider / fallback_providers in config.toml",
+        );
+    }
+
+    let needs_key = name != "ollama" && !name.starts_with("custom:");
+    if needs_key && api_key.is_none_or(|k| k.trim().is_empty()) {
+        return Check::fail(
+            label,
+            &format!("Provider '{name}' has no API key"),
+            "api_key is not set in config.toml",
+            "run `baihu onboard` or set api_key in config.toml",
+        );
+    }
+
+    Check::pass(label, "configured")
+}
+
+fn check_channel_tokens(config: &Config) -> Vec<Check> {
+    let channels = &config.channels_config;
+    let mut tokens: Vec<(&str, &str)> = Vec::new();
+    if let Some(ref tg) = channels.telegram {
+        tokens.push(("telegram", &tg.bot_token));
+    }
+    if let Some(ref dc) = channels.discord {
+        tokens.push(("discord", &dc.bot_token));
+    }
+    if let Some(ref sl) = channels.slack {
+        tokens.push(("slack", &sl.bot_token));
+    }
+    if let Some(ref mx) = channels.matrix {
+        tokens.push(("matrix", &mx.access_token));
+    }
+    if let Some(ref wa) = channels.whatsapp {
+        tokens.push(("whatsapp", &wa.access_token));
+    }
+
+    tokens
+        .into_iter()
+        .map(|(name, token)| {
+            let label = format!("channel:{name}");
+            if token.trim().is_empty() {
+                Check::fail(
+                    label,
+                    &format!("Channel '{name}' is configured without a token"),
+                    "the token field is empty",
+                    &format!("run `baihu channel add {name}` or fill in the token in config.toml"),
+                )
+            } else {
+                Check::pass(label, "token present")
+            }
+        })
+        .collect()
+}
+
+fn check_bind(config: &Config, host: &str) -> Check {
+    if !is_public_bind(host) {
+        return Check::pass("bind", format!("{host} is local-only"));
+    }
+    if config.tunnel.provider == "none" && !config.gateway.allow_public_bind {
+        return Check::fail(
+            "bind",
+            &format!("Gateway will refuse to bind to {host}"),
+            "public bind without a tunnel or allow_public_bind",
+            "use --host 127.0.0.1, configure a tunnel, or set [gateway] allow_public_bind = true",
+        );
+    }
+    if !config.gateway.require_pairing {
+        return Check::fail(
+            "bind",
+            &format!("Gateway on {host} accepts unauthenticated requests"),
+            "require_pairing is disabled on a public bind",
+            "set [gateway] require_pairing = true",
+        );
+    }
+    Check::warn(
+        "bind",
+        &format!("Gateway is reachable beyond localhost on {host}"),
+        "public bind is allowed",
+        "prefer binding to 127.0.0.1 behind a tunnel",
+    )
+}
+
+fn check_lock_acquirable(config: &Config) -> Check {
+    let lock_path = crate::daemon::lock_file_path(config);
+    if let Some(parent) = lock_path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    let file = match std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+    {
+        Ok(f) => f,
+        Err(e) => {
+            return Check::fail(
+                "lock",
+                &format!("Cannot open daemon lock {}", lock_path.display()),
+                &e.to_string(),
+                "fix permissions on the config directory",
+            )
+        }
+    };
+
+    if file.try_lock_exclusive().is_ok() {
+        let _ = FileExt::unlock(&file);
+        Check::pass("lock", "daemon lock is free")
+    } else {
+        Check::warn(
+            "lock",
+            "Daemon lock is held",
+            &format!("another instance holds {}", lock_path.display()),
+            "expected if the daemon is running; otherwise stop the stale process",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ChannelsConfig, TelegramConfig};
+    use tempfile::TempDir;
+
+    fn test_config(tmp: &TempDir) -> Config {
+        Config {
+            workspace_dir: tmp.path().join("workspace"),
+            config_path: tmp.path().join("config.toml"),
+            api_key: Some("sk-test".into()),
+            default_provider: Some("openrouter".into()),
+            ..Config::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn provider_probe_reports_preflight_failures() {
+        use crate::providers::testing::ScriptedProvider;
+        let limit = Duration::from_secs(1);
+
+        let ok = probe_provider("openai", &ScriptedProvider::new("ok"), limit).await;
+        assert_eq!(ok.status, CheckStatus::Pass);
+
+        let bad = ScriptedProvider::new("ok").with_preflight_error("401 invalid api key");
+        let check = probe_provider("openai", &bad, limit).await;
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert_eq!(check.name, "provider:openai");
+        assert!(
+            check.message.contains("401 invalid api key"),
+            "{}",
+            check.message
+        );
+    }
+
+    #[tokio::test]
+    async fn provider_probe_skips_network_for_unknown_provider() {
+        let tmp = TempDir::new().unwrap();
+        let config = Config {
+            default_provider: Some("nonexistent".into()),
+            ..test_config(&tmp)
+        };
+        let checks = probe_providers(&config).await;
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn healthy_config_has_no_failures() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("workspace")).unwrap();
+        let checks = run_checks(&test_config(&tmp), "127.0.0.1", true);
+        assert!(!has_failures(&checks), "{checks:?}");
+        assert!(!tmp
+            .path()
+            .join("workspace")
+            .join(crate::security::policy::WRITE_PROBE_FILE)
+            .exists());
+    }
+
+    #[test]
+    fn missing_workspace_warns_without_creating_it() {
+        let tmp = TempDir::new().unwrap();
+        let check = check_workspace_writable(&test_config(&tmp));
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(!tmp.path().join("workspace").exists());
+    }
+
+    #[tokio::test]
+    async fn doctor_probes_only_providers_that_pass_static_checks() {
+        let tmp = TempDir::new().unwrap();
+        let config = Config {
+            api_key: None,
+            ..test_config(&tmp)
+        };
+        // No key: the static failure stands and nothing goes over the network.
+        let checks = run_doctor_checks(&config, "127.0.0.1").await;
+        let provider = checks
+            .iter()
+            .find(|c| c.name == "provider:openrouter")
+            .unwrap();
+        assert_eq!(provider.status, CheckStatus::Fail);
+        assert!(
+            provider.message.contains("no API key"),
+            "{}",
+            provider.message
+        );
+    }
+
+    #[test]
+    fn workspace_check_fails_on_a_file() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("workspace");
+        std::fs::write(&file, "not a dir").unwrap();
+        let check = check_workspace_writable(&Config {
+            workspace_dir: file,
+            ..Config::default()
+        });
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(
+            check.message.contains("not a directory"),
+            "{}",
+            check.message
+        );
+    }
+
+    #[test]
+    fn missing_api_key_fails_provider_check() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = test_config(&tmp);
+        config.api_key = None;
+        let checks = check_providers(&config);
+        assert_eq!(checks[0].status, CheckStatus::Fail);
+        assert!(checks[0].message.contains("Fix:"));
+    }
+
+    #[test]
+    fn ollama_needs_no_key() {
+        assert_eq!(check_provider("ollama", None).status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn unknown_provider_fails() {
+        let check = check_provider("nope-not-real", Some("k"));
+        assert_eq!(check.status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn empty_channel_token_fails() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = test_config(&tmp);
+        config.channels_config = ChannelsConfig {
+            telegram: Some(TelegramConfig {
+                bot_token: "  ".into(),
+                allowed_users: vec![],
+            }),
+            ..ChannelsConfig::default()
+        };
+        let checks = check_channel_tokens(&config);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn public_bind_without_opt_in_fails() {
+        let tmp = TempDir::new().unwrap();
+        let config = test_config(&tmp);
+        assert_eq!(check_bind(&config, "0.0.0.0").status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn public_bind_without_pairing_fails() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = test_config(&tmp);
+        config.gateway.allow_public_bind = true;
+        config.gateway.require_pairing = false;
+        assert_eq!(check_bind(&config, "0.0.0.0").status, CheckStatus::Fail);
+
+        config.gateway.require_pairing = true;
+        assert_eq!(check_bind(&config, "0.0.0.0").status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn held_lock_warns() {
+        let tmp = TempDir::new().unwrap();
+        let config = test_config(&tmp);
+        let held = std::fs::File::create(crate::daemon::lock_file_path(&config)).unwrap();
+        held.try_lock_exclusive().unwrap();
+        assert_eq!(check_lock_acquirable(&config).status, CheckStatus::Warn);
+        FileExt::unlock(&held).unwrap();
+        assert_eq!(check_lock_acquirable(&config).status, CheckStatus::Pass);
+    }
+}
@@ -7,47 +7,157 @@
 //! - Request timeouts (30s) to prevent slow-loris attacks
 //! - Header sanitization (handled by axum/hyper)
 
-use crate::channels::{Channel, WhatsAppChannel};
+use crate::agent::CancelRegistry;
+use crate::channels::traits::ChannelMessage;
+use crate::channels::{Channel, OutputFormat, WhatsAppChannel};
+use crate::channels::{ChannelPersona, InboundSize};
+use crate::config::schema::OversizedInbound;
 use crate::config::Config;
+use crate::health::TerminalError;
 use crate::memory::{self, Memory, MemoryCategory};
-use crate::providers::{self, Provider};
-use crate::security::pairing::{constant_time_eq, is_public_bind, PairingGuard};
-use anyhow::Result;
+use crate::providers::origin::RequestOrigin;
+use crate::providers::stream::StreamOutcome;
+use crate::providers::{self, ChatClient, Provider, ProviderChainError, UnknownProviderError};
+use crate::security::pairing::{
+    constant_time_eq, is_public_bind, CodeFormat, PairingGuard, CHALLENGE_TTL,
+};
+use crate::security::token_store::{FileTokenStore, TokenStore};
+use crate::security::{SecretStore, SecurityPolicy};
+use crate::tools::ToolRegistry;
+use anyhow::{Context, Result};
 use axum::{
     body::Bytes,
-    extract::{Query, State},
+    extract::{Path, Query, State},
     http::{header, HeaderMap, StatusCode},
-    response::{IntoResponse, Json},
+    response::{IntoResponse, Json, Response},
     routing::{get, post},
     Router,
 };
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tower_http::limit::RequestBodyLimitLayer;
+use tracing::Instrument;
 
 /// Maximum request body size (64KB) — prevents memory exhaustion
 pub const MAX_BODY_SIZE: usize = 65_536;
 /// Request timeout (30s) — prevents slow-loris attacks
 pub const REQUEST_TIMEOUT_SECS: u64 = 30;
+/// Turns are cancelled this long before the HTTP timeout so the client gets a
+/// proper 504 and the provider chain stops, instead of the layer cutting us off.
+const TURN_DEADLINE_MARGIN_MS: u64 = 500;
 
 /// Shared state for all axum handlers
 #[derive(Clone)]
 pub struct AppState {
-    pub provider: Arc<dyn Provider>,
-    pub model: String,
-    pub temperature: f64,
+    /// Provider with the configured model and temperature.
+    pub chat: ChatClient,
     pub mem: Arc<dyn Memory>,
     pub auto_save: bool,
     pub webhook_secret: Option<Arc<str>>,
     pub pairing: Arc<PairingGuard>,
+    /// Tools exposed under the configured autonomy level
+    pub tools: Arc<ToolRegistry>,
     pub whatsapp: Option<Arc<WhatsAppChannel>>,
+    /// How `WhatsApp` messages are answered: `channels_config.personas["whatsapp"]`
+    /// resolved as `channels start` resolves it. Set whenever `whatsapp` is.
+    pub whatsapp_persona: Option<Arc<ChannelPersona>>,
+    /// What happens to inbound channel messages over their limit
+    pub oversized_inbound: OversizedInbound,
+    /// In-flight webhook requests, cancellable via `POST /cancel/{request_id}`
+    pub inflight: CancelRegistry,
+    /// Named workspaces from `[workspaces]`; `chat`, `mem` and `tools` above
+    /// are the default's
+    pub workspaces: Arc<HashMap<String, Workspace>>,
+    /// Default for a webhook's `provider_fallback`
+    pub preferred_provider_fallback: bool,
+}
+
+/// One workspace's chat client, memory and tools, selected per request by name.
+#[derive(Clone)]
+pub struct Workspace {
+    /// Answers webhooks; a named workspace's carries a system prompt built
+    /// from its own identity files and skills
+    pub chat: ChatClient,
+    pub mem: Arc<dyn Memory>,
+    pub tools: Arc<ToolRegistry>,
+}
+
+impl AppState {
+    /// The named workspace, or the default one for `None`. Unknown names
+    /// give `None`.
+    fn workspace(&self, name: Option<&str>) -> Option<Workspace> {
+        match name {
+            None => Some(Workspace {
+                chat: self.chat.clone(),
+                mem: Arc::clone(&self.mem),
+                tools: Arc::clone(&self.tools),
+            }),
+            Some(name) => self.workspaces.get(name).cloned(),
+        }
+    }
+}
+
+fn unknown_workspace(name: &str) -> (StatusCode, Json<serde_json::Value>) {
+    let err = serde_json::json!({"error": format!("Unknown workspace '{name}'")});
+    (StatusCode::NOT_FOUND, Json(err))
+}
+
+/// `?workspace=NAME` on endpoints that read a workspace
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct WorkspaceQuery {
+    #[serde(default)]
+    pub workspace: Option<String>,
 }
 
 /// Run the HTTP gateway using axum with proper HTTP/1.1 compliance.
-#[allow(clippy::too_many_lines)]
 pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
+    run_gateway_until(host, port, config, crate::agent::CancelToken::new()).await
+}
+
+/// [`run_gateway`] that stops accepting connections once `shutdown` fires and
+/// returns when the requests already being served have finished.
+pub async fn run_gateway_until(
+    host: &str,
+    port: u16,
+    config: Config,
+    shutdown: crate::agent::CancelToken,
+) -> Result<()> {
+    let listener = bind_gateway(host, port, &config).await?;
+    let provider = gateway_provider(&config)?;
+    serve_gateway(listener, host, config, provider, shutdown).await
+}
+
+/// The gateway's own provider chain, for when no caller shares one.
+fn gateway_provider(config: &Config) -> Result<Arc<dyn Provider>> {
+    Ok(Arc::from(providers::with_configured_tap(
+        providers::create_resilient_provider_with_state(
+            config.default_provider.as_deref().unwrap_or("openrouter"),
+            config.api_key.as_deref(),
+            &config.reliability,
+            &config.request_overrides,
+            Some(&providers::state_file_path(config, "gateway")),
+            Some(Arc::from(crate::observability::create_observer(
+                &config.observability,
+            ))),
+        )?,
+        config,
+    )))
+}
+
+/// Refuse unsafe bind settings, then bind the TCP port or Unix socket the
+/// gateway will serve on. Split from [`serve_gateway`] so a caller can
+/// learn the bound address, or fail on a bind error, before serving.
+pub async fn bind_gateway(host: &str, port: u16, config: &Config) -> Result<GatewayListener> {
+    let unix_socket = config.gateway.unix_socket.as_ref();
+
     // ── Security: refuse public bind without tunnel or explicit opt-in ──
-    if is_public_bind(host) && config.tunnel.provider == "none" && !config.gateway.allow_public_bind
+    // A Unix socket is never public; filesystem permissions gate it instead.
+    if unix_socket.is_none()
+        && is_public_bind(host)
+        && config.tunnel.provider == "none"
+        && !config.gateway.allow_public_bind
     {
         anyhow::bail!(
             "🛑 Refusing to bind to {host} — gateway would be exposed to the internet.\n\
@@ -56,26 +166,101 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         );
     }
 
-    let addr: SocketAddr = format!("{host}:{port}").parse()?;
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    let actual_port = listener.local_addr()?.port();
-    let display_addr = format!("{host}:{actual_port}");
+    let code_format = CodeFormat::new(
+        config.gateway.pairing_code_alphabet,
+        config.gateway.pairing_code_length,
+    );
+    if config.gateway.pairing_challenge_response && !code_format.supports_challenge_response() {
+        anyhow::bail!(
+            "{}",
+            crate::health::structured_error(
+                "Refusing to start gateway",
+                "pairing_challenge_response keys its HMAC with the pairing code, and a short or \
+                 numeric code can be brute-forced offline from one captured exchange",
+                &format!(
+                    "set gateway.pairing_code_alphabet = \"alphanumeric\" and \
+                     gateway.pairing_code_length >= {}",
+                    CodeFormat::MIN_CHALLENGE_LENGTH
+                ),
+            )
+        );
+    }
 
-    let provider: Arc<dyn Provider> = Arc::from(providers::create_resilient_provider(
-        config.default_provider.as_deref().unwrap_or("openrouter"),
-        config.api_key.as_deref(),
-        &config.reliability,
-    )?);
-    let model = config
-        .default_model
-        .clone()
-        .unwrap_or_else(|| "anthropic/claude-sonnet-4-20250514".into());
-    let temperature = config.default_temperature;
+    if let Some(path) = unix_socket {
+        GatewayListener::bind_unix(path)
+    } else {
+        GatewayListener::bind_tcp(host, port, config.gateway.port_search).await
+    }
+}
+
+/// Serve the gateway on a listener from [`bind_gateway`] until `shutdown`,
+/// answering through `provider`.
+#[allow(clippy::too_many_lines)]
+pub async fn serve_gateway(
+    listener: GatewayListener,
+    host: &str,
+    config: Config,
+    provider: Arc<dyn Provider>,
+    shutdown: crate::agent::CancelToken,
+) -> Result<()> {
+    let unix_socket = config.gateway.unix_socket.clone();
+    let code_format = CodeFormat::new(
+        config.gateway.pairing_code_alphabet,
+        config.gateway.pairing_code_length,
+    );
+    let actual_port = listener.port();
+    let display_addr = match (&unix_socket, actual_port) {
+        (Some(path), _) => format!("unix:{}", path.display()),
+        (None, Some(actual_port)) => format!("http://{host}:{actual_port}"),
+        (None, None) => format!("http://{host}"),
+    };
+
+    // Gateway turns are always user-initiated: answer fresh.
+    let chat = ChatClient::from_config(provider, &config).uncached();
     let mem: Arc<dyn Memory> = Arc::from(memory::create_memory(
         &config.memory,
         &config.workspace_dir,
         config.api_key.as_deref(),
     )?);
+    let security = Arc::new(SecurityPolicy::from_config(
+        &config.autonomy,
+        &config.workspace_dir,
+    ));
+    let tools = Arc::new(ToolRegistry::from_config(&config, &security, mem.clone()));
+
+    // Named workspaces: own directory, memory and security policy each
+    let mut workspaces = HashMap::new();
+    for name in config.workspaces.keys() {
+        let scoped = crate::doctor::preflight::prepare_named_workspace(&config, name)?;
+        let scoped_mem: Arc<dyn Memory> = Arc::from(memory::create_memory(
+            &scoped.memory,
+            &scoped.workspace_dir,
+            scoped.api_key.as_deref(),
+        )?);
+        let scoped_security = Arc::new(SecurityPolicy::from_config(
+            &scoped.autonomy,
+            &scoped.workspace_dir,
+        ));
+        let scoped_tools = Arc::new(ToolRegistry::from_config(
+            &scoped,
+            &scoped_security,
+            scoped_mem.clone(),
+        ));
+        let scoped_prompt = crate::channels::build_system_prompt(
+            &scoped.workspace_dir,
+            chat.model(),
+            &[],
+            &crate::skills::load_skills(&scoped.workspace_dir),
+        );
+        workspaces.insert(
+            name.clone(),
+            Workspace {
+                chat: chat.clone().with_system_prompt(scoped_prompt),
+                mem: scoped_mem,
+                tools: scoped_tools,
+            },
+        );
+    }
 
     // Extract webhook secret for authentication
     let webhook_secret: Option<Arc<str>> = config
@@ -88,56 +273,142 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
     // WhatsApp channel (if configured)
     let whatsapp_channel: Option<Arc<WhatsAppChannel>> =
         config.channels_config.whatsapp.as_ref().map(|wa| {
-            Arc::new(WhatsAppChannel::new(
-                wa.access_token.clone(),
-                wa.phone_number_id.clone(),
-                wa.verify_token.clone(),
-                wa.allowed_numbers.clone(),
-            ))
+            Arc::new(
+                WhatsAppChannel::new(
+                    wa.access_token.clone(),
+                    wa.phone_number_id.clone(),
+                    wa.verify_token.clone(),
+                    wa.allowed_numbers.clone(),
+                )
+                .with_app_secret(wa.app_secret.clone()),
+            )
         });
 
+    let whatsapp_persona = match &whatsapp_channel {
+        Some(wa) => Some(Arc::new(whatsapp_persona(
+            &config,
+            wa,
+            chat.model(),
+            &mem,
+            &workspaces,
+        )?)),
+        None => None,
+    };
+
     // ── Pairing guard ──────────────────────────────────────
-    let pairing = Arc::new(PairingGuard::new(
-        config.gateway.require_pairing,
-        &config.gateway.paired_tokens,
+    let require_pairing = config.gateway.require_pairing
+        && (unix_socket.is_none() || config.gateway.unix_socket_require_pairing);
+    let token_store = FileTokenStore::new(
+        config
+            .gateway
+            .token_store_path
+            .clone()
+            .unwrap_or_else(|| FileTokenStore::default_path(&config.config_path)),
+    )
+    .with_secret_store(SecretStore::new(
+        config
+            .config_path
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new(".")),
+        config.secrets.encrypt,
     ));
+    if !config.gateway.paired_tokens.is_empty() {
+        match token_store.import(&config.gateway.paired_tokens) {
+            Ok(()) => match Config::remove_legacy_paired_tokens(&config.config_path) {
+                Ok(_) => tracing::info!(
+                    path = %token_store.path().display(),
+                    "Moved paired tokens from config.toml to the token store"
+                ),
+                Err(e) => {
+                    tracing::warn!("Failed to remove imported paired tokens from config: {e}");
+                }
+            },
+            Err(e) => tracing::warn!(
+                path = %token_store.path().display(),
+                "Failed to import paired tokens from config: {e}"
+            ),
+        }
+    }
+    let mut pairing =
+        PairingGuard::new(require_pairing, Box::new(token_store)).with_code_format(code_format);
+    if config.gateway.pairing_code_ttl_secs > 0 {
+        pairing = pairing.with_code_ttl(Duration::from_secs(config.gateway.pairing_code_ttl_secs));
+    }
+    if config.gateway.pairing_challenge_response {
+        pairing = pairing.with_challenge_response();
+    }
+    let pairing = Arc::new(pairing);
 
     // ── Tunnel ────────────────────────────────────────────────
     let tunnel = crate::tunnel::create_tunnel(&config.tunnel)?;
     let mut tunnel_url: Option<String> = None;
 
     if let Some(ref tun) = tunnel {
-        println!("🔗 Starting {} tunnel...", tun.name());
-        match tun.start(host, actual_port).await {
-            Ok(url) => {
-                println!("🌐 Tunnel active: {url}");
-                tunnel_url = Some(url);
-            }
-            Err(e) => {
-                println!("⚠️  Tunnel failed to start: {e}");
-                println!("   Falling back to local-only mode.");
+        if let Some(actual_port) = actual_port {
+            println!("🔗 Starting {} tunnel...", tun.name());
+            match tun.start(host, actual_port).await {
+                Ok(url) => {
+                    println!("🌐 Tunnel active: {url}");
+                    tunnel_url = Some(url);
+                }
+                Err(e) => {
+                    println!("⚠️  Tunnel failed to start: {e}");
+                    println!("   Falling back to local-only mode.");
+                }
             }
+        } else {
+            println!(
+                "⚠️  {} tunnel skipped: tunnels need a TCP port, not a Unix socket.",
+                tun.name()
+            );
         }
     }
 
-    println!("🦀 Baihu Gateway listening on http://{display_addr}");
+    println!("🦀 Baihu Gateway listening on {display_addr}");
     if let Some(ref url) = tunnel_url {
         println!("  🌐 Public URL: {url}");
     }
-    println!("  POST /pair      — pair a new client (X-Pairing-Code header)");
+    if pairing.challenge_response_required() {
+        println!("  GET  /pair/challenge — nonce to answer when pairing");
+        println!(
+            "  POST /pair      — pair a new client (X-Pairing-Nonce + X-Pairing-Response headers)"
+        );
+    } else {
+        println!("  POST /pair      — pair a new client (X-Pairing-Code header)");
+    }
+    if pairing.require_pairing() {
+        println!("  POST /pair/new  — issue a fresh pairing code (paired clients only)");
+    }
     println!("  POST /webhook   — {{\"message\": \"your prompt\"}}");
+    println!("  POST /cancel/ID — abort the webhook request sent with X-Request-Id: ID");
     if whatsapp_channel.is_some() {
         println!("  GET  /whatsapp  — Meta webhook verification");
         println!("  POST /whatsapp  — WhatsApp message webhook");
     }
     println!("  GET  /health    — health check");
+    println!("  GET  /about     — version, features, sandboxes and compiled-in backends");
+    println!("  GET  /admin/provider-stats — cache and provider counters since start");
+    println!("  GET  /admin/provider-failures — recent failed provider calls");
+    println!("  GET  /admin/memory-stats — memory entries and sizes per category");
+    println!("  GET  /tools     — tools available to the agent, with parameter schemas");
     if let Some(code) = pairing.pairing_code() {
         println!();
         println!("  🔐 PAIRING REQUIRED — use this one-time code:");
         println!("     ┌──────────────┐");
         println!("     │  {code}  │");
         println!("     └──────────────┘");
-        println!("     Send: POST /pair with header X-Pairing-Code: {code}");
+        if pairing.challenge_response_required() {
+            println!("     Send: GET /pair/challenge, then POST /pair with headers");
+            println!("     X-Pairing-Nonce: <nonce> and X-Pairing-Response: hex(HMAC-SHA256(code, nonce))");
+        } else {
+            println!("     Send: POST /pair with header X-Pairing-Code: {code}");
+        }
+        if config.gateway.pairing_code_ttl_secs > 0 {
+            println!(
+                "     Expires in {}s if unused; a paired client can POST /pair/new for another.",
+                config.gateway.pairing_code_ttl_secs
+            );
+        }
     } else if pairing.require_pairing() {
         println!("  🔒 Pairing: ACTIVE (bearer token required)");
     } else {
@@ -146,20 +417,31 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
     if webhook_secret.is_some() {
         println!("  🔒 Webhook secret: ENABLED");
     }
+    if !config.workspaces.is_empty() {
+        let names: Vec<&str> = config.workspaces.keys().map(String::as_str).collect();
+        println!(
+            "  🗂️  Workspaces: {} (select with \"workspace\" or ?workspace=)",
+            names.join(", ")
+        );
+    }
     println!("  Press Ctrl+C to stop.\n");
 
     crate::health::mark_component_ok("gateway");
 
     // Build shared state
     let state = AppState {
-        provider,
-        model,
-        temperature,
+        chat,
         mem,
         auto_save: config.memory.auto_save,
         webhook_secret,
         pairing,
+        tools,
         whatsapp: whatsapp_channel,
+        whatsapp_persona,
+        oversized_inbound: config.channels_config.oversized_inbound,
+        inflight: CancelRegistry::new(),
+        workspaces: Arc::new(workspaces),
+        preferred_provider_fallback: config.reliability.preferred_provider_fallback,
     };
 
     // Build router with middleware
@@ -168,15 +450,196 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
     let app = Router::new()
         .route("/health", get(handle_health))
         .route("/pair", post(handle_pair))
+        .route("/pair/challenge", get(handle_pair_challenge))
+        .route("/pair/new", post(handle_pair_new))
         .route("/webhook", post(handle_webhook))
+        .route("/cancel/:request_id", post(handle_cancel))
+        .route("/admin/provider-stats", get(handle_provider_stats))
+        .route("/admin/provider-failures", get(handle_provider_failures))
+        .route("/admin/memory-stats", get(handle_memory_stats))
+        .route("/about", get(handle_about))
+        .route("/tools", get(handle_tools))
         .route("/whatsapp", get(handle_whatsapp_verify))
         .route("/whatsapp", post(handle_whatsapp_message))
         .with_state(state)
         .layer(RequestBodyLimitLayer::new(MAX_BODY_SIZE));
 
     // Run the server
-    axum::serve(listener, app).await?;
+    listener.serve(app, shutdown).await
+}
+
+/// Where the gateway accepts connections.
+pub enum GatewayListener {
+    Tcp(tokio::net::TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+impl GatewayListener {
+    /// Bind `host:port`, moving on through the next `search` ports while they
+    /// are taken. A port conflict is a [`TerminalError`]: retrying won't free it.
+    async fn bind_tcp(host: &str, port: u16, search: u16) -> Result<Self> {
+        let last = if port == 0 {
+            0
+        } else {
+            port.saturating_add(search)
+        };
+        for candidate in port..=last {
+            let addr: SocketAddr = format!("{host}:{candidate}").parse()?;
+            match tokio::net::TcpListener::bind(addr).await {
+                Ok(listener) => {
+                    if candidate != port {
+                        tracing::warn!("Gateway port {port} is in use; listening on {candidate}");
+                    }
+                    return Ok(Self::Tcp(listener));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let range = if last == port {
+            format!("{host}:{port}")
+        } else {
+            format!("{host}:{port}-{last}")
+        };
+        Err(TerminalError(crate::health::structured_error(
+            &format!("Gateway port {port} is already in use"),
+            &format!("another process is listening on {range}"),
+            "stop that process, pass --port with a free port, or set [gateway] port_search \
+             to try the next few ports",
+        ))
+        .into())
+    }
+
+    /// Bind `path` with mode 0600, replacing a stale socket left by a previous run.
+    ///
+    /// The socket is bound inside a private 0700 directory and renamed into
+    /// place once its mode is set, so nobody else can connect in between.
+    #[cfg(unix)]
+    fn bind_unix(path: &std::path::Path) -> Result<Self> {
+        use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+
+        if let Ok(meta) = std::fs::symlink_metadata(path) {
+            if !meta.file_type().is_socket() {
+                anyhow::bail!(
+                    "Refusing to replace {}: it exists and is not a socket",
+                    path.display()
+                );
+            }
+            std::fs::remove_file(path)?;
+        }
+        let parent = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => std::path::Path::new("."),
+        };
+        std::fs::create_dir_all(parent)?;
+
+        let staging = parent.join(format!(".gateway-{}", std::process::id()));
+        std::fs::DirBuilder::new()
+            .mode(0o700)
+            .create(&staging)
+            .with_context(|| format!("Failed to create {}", staging.display()))?;
+        let staged = staging.join("sock");
+        let bound = tokio::net::UnixListener::bind(&staged)
+            .map_err(anyhow::Error::from)
+            .and_then(|listener| {
+                std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+                std::fs::rename(&staged, path)?;
+                Ok(listener)
+            });
+        let _ = std::fs::remove_file(&staged);
+        let _ = std::fs::remove_dir(&staging);
+        Ok(Self::Unix(bound?))
+    }
+
+    #[cfg(not(unix))]
+    fn bind_unix(_path: &std::path::Path) -> Result<Self> {
+        anyhow::bail!("gateway.unix_socket is only supported on Unix platforms")
+    }
+
+    /// TCP port actually bound; `None` for a Unix socket.
+    fn port(&self) -> Option<u16> {
+        self.local_addr().map(|a| a.port())
+    }
 
+    /// TCP address actually bound; `None` for a Unix socket.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Self::Tcp(listener) => listener.local_addr().ok(),
+            #[cfg(unix)]
+            Self::Unix(_) => None,
+        }
+    }
+
+    async fn serve(self, app: Router, shutdown: crate::agent::CancelToken) -> Result<()> {
+        match self {
+            Self::Tcp(listener) => {
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(async move { shutdown.cancelled().await })
+                    .await?;
+            }
+            #[cfg(unix)]
+            Self::Unix(listener) => serve_unix(listener, app, shutdown).await?,
+        }
+        Ok(())
+    }
+}
+
+/// Back-off after a failed `accept()` on the Unix socket.
+#[cfg(unix)]
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Accept loop for Unix socket connections; axum 0.7 only serves TCP itself.
+/// On shutdown, stops accepting and waits for open connections to finish
+/// their current request.
+#[cfg(unix)]
+async fn serve_unix(
+    listener: tokio::net::UnixListener,
+    app: Router,
+    shutdown: crate::agent::CancelToken,
+) -> Result<()> {
+    use hyper_util::rt::TokioIo;
+    use hyper_util::service::TowerToHyperService;
+
+    let mut connections = tokio::task::JoinSet::new();
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            () = shutdown.cancelled() => break,
+        };
+        let stream = match accepted {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                // Usually EMFILE/ENFILE: wait for connections to close
+                // instead of taking the gateway down.
+                tracing::warn!("Gateway socket accept failed: {e}; retrying");
+                tokio::select! {
+                    () = tokio::time::sleep(ACCEPT_RETRY_DELAY) => continue,
+                    () = shutdown.cancelled() => break,
+                }
+            }
+        };
+        while connections.try_join_next().is_some() {}
+        let service = TowerToHyperService::new(app.clone());
+        let shutdown = shutdown.clone();
+        connections.spawn(async move {
+            let connection = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service);
+            tokio::pin!(connection);
+            let served = tokio::select! {
+                served = connection.as_mut() => served,
+                () = shutdown.cancelled() => {
+                    connection.as_mut().graceful_shutdown();
+                    connection.await
+                }
+            };
+            if let Err(e) = served {
+                tracing::debug!("Gateway socket connection ended with error: {e}");
+            }
+        });
+    }
+    while connections.join_next().await.is_some() {}
     Ok(())
 }
 
@@ -194,14 +657,47 @@ async fn handle_health(State(state): State<AppState>) -> impl IntoResponse {
     Json(body)
 }
 
-/// POST /pair — exchange one-time code for bearer token
+/// GET /pair/challenge — single-use nonce for challenge-response pairing
+async fn handle_pair_challenge(State(state): State<AppState>) -> impl IntoResponse {
+    if !state.pairing.challenge_response_required() {
+        let err = serde_json::json!({"error": "Challenge-response pairing is not enabled"});
+        return (StatusCode::NOT_FOUND, Json(err));
+    }
+    let Some(nonce) = state.pairing.issue_challenge() else {
+        let err = serde_json::json!({"error": "No pairing code is outstanding"});
+        return (StatusCode::GONE, Json(err));
+    };
+    let body = serde_json::json!({
+        "nonce": nonce,
+        "expires_in": CHALLENGE_TTL.as_secs(),
+    });
+    (StatusCode::OK, Json(body))
+}
+
+/// POST /pair — exchange one-time code (or a challenge answer) for bearer token
 async fn handle_pair(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
-    let code = headers
-        .get("X-Pairing-Code")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+    };
 
-    match state.pairing.try_pair(code) {
+    let attempt = if state.pairing.challenge_response_required() {
+        if headers.contains_key("X-Pairing-Code") {
+            let err = serde_json::json!({
+                "error": "Send X-Pairing-Nonce and X-Pairing-Response instead of the raw code (see GET /pair/challenge)"
+            });
+            return (StatusCode::BAD_REQUEST, Json(err));
+        }
+        state
+            .pairing
+            .try_pair_response(header("X-Pairing-Nonce"), header("X-Pairing-Response"))
+    } else {
+        state.pairing.try_pair(header("X-Pairing-Code"))
+    };
+
+    match attempt {
         Ok(Some(token)) => {
             tracing::info!("🔐 New client paired successfully");
             let body = serde_json::json!({
@@ -211,6 +707,13 @@ async fn handle_pair(State(state): State<AppState>, headers: HeaderMap) -> impl
             });
             (StatusCode::OK, Json(body))
         }
+        Ok(None) if state.pairing.code_expired() => {
+            tracing::warn!("🔐 Pairing attempt after the pairing code expired");
+            let err = serde_json::json!({
+                "error": "Pairing code expired. A paired client can POST /pair/new for a fresh one; otherwise restart the gateway."
+            });
+            (StatusCode::GONE, Json(err))
+        }
         Ok(None) => {
             tracing::warn!("🔐 Pairing attempt with invalid code");
             let err = serde_json::json!({"error": "Invalid pairing code"});
@@ -229,18 +732,52 @@ async fn handle_pair(State(state): State<AppState>, headers: HeaderMap) -> impl
     }
 }
 
+/// POST /pair/new — replace the outstanding pairing code with a fresh one,
+/// so a paired client can bring another device in without a restart
+async fn handle_pair_new(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    if let Err(rejection) = authorize(&state, &headers) {
+        return rejection;
+    }
+    let Some(code) = state.pairing.regenerate_code() else {
+        let err = serde_json::json!({"error": "Pairing is disabled"});
+        return (StatusCode::NOT_FOUND, Json(err));
+    };
+    tracing::info!("🔐 Pairing code reissued by a paired client");
+    let mut body = serde_json::json!({ "code": code });
+    if let Some(ttl) = state.pairing.code_ttl() {
+        body["expires_in"] = serde_json::json!(ttl.as_secs());
+    }
+    (StatusCode::OK, Json(body))
+}
+
 /// Webhook request body
 #[derive(serde::Deserialize)]
 pub struct WebhookBody {
     pub message: String,
+    /// Reply format; omitted means the model's Markdown is returned as-is
+    #[serde(default)]
+    pub format: Option<OutputFormat>,
+    /// Named workspace to work in; omitted means the default workspace
+    #[serde(default)]
+    pub workspace: Option<String>,
+    /// Chain entry to send this request to first (e.g. "anthropic")
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Whether the rest of the chain may answer if `provider` fails;
+    /// omitted means `reliability.preferred_provider_fallback`
+    #[serde(default)]
+    pub provider_fallback: Option<bool>,
+    /// Stream the completion, so a reply cut off part-way is returned with
+    /// `"truncated": true` instead of failing the request
+    #[serde(default)]
+    pub stream: bool,
 }
 
-/// POST /webhook — main webhook endpoint
-async fn handle_webhook(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    body: Result<Json<WebhookBody>, axum::extract::rejection::JsonRejection>,
-) -> impl IntoResponse {
+/// Pairing bearer token + optional webhook secret, shared by webhook-style endpoints.
+fn authorize(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
     // ── Bearer token auth (pairing) ──
     if state.pairing.require_pairing() {
         let auth = headers
@@ -253,7 +790,7 @@ async fn handle_webhook(
             let err = serde_json::json!({
                 "error": "Unauthorized — pair first via POST /pair, then send Authorization: Bearer <token>"
             });
-            return (StatusCode::UNAUTHORIZED, Json(err));
+            return Err((StatusCode::UNAUTHORIZED, Json(err)));
         }
     }
 
@@ -267,11 +804,27 @@ async fn handle_webhook(
             _ => {
                 tracing::warn!("Webhook: rejected request — invalid or missing X-Webhook-Secret");
                 let err = serde_json::json!({"error": "Unauthorized — invalid or missing X-Webhook-Secret header"});
-                return (StatusCode::UNAUTHORIZED, Json(err));
+                return Err((StatusCode::UNAUTHORIZED, Json(err)));
             }
         }
     }
 
+    Ok(())
+}
+
+/// POST /webhook — main webhook endpoint
+///
+/// Clients may send `X-Request-Id` to be able to cancel the request while it runs;
+/// otherwise one is generated. Either way it is echoed in the response body.
+async fn handle_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Result<Json<WebhookBody>, axum::extract::rejection::JsonRejection>,
+) -> Response {
+    if let Err(rejection) = authorize(&state, &headers) {
+        return rejection.into_response();
+    }
+
     // ── Parse body ──
     let Json(webhook_body) = match body {
         Ok(b) => b,
@@ -279,35 +832,267 @@ async fn handle_webhook(
             let err = serde_json::json!({
                 "error": format!("Invalid JSON: {e}. Expected: {{\"message\": \"...\"}}")
             });
-            return (StatusCode::BAD_REQUEST, Json(err));
+            return (StatusCode::BAD_REQUEST, Json(err)).into_response();
         }
     };
 
+    let Some(workspace) = state.workspace(webhook_body.workspace.as_deref()) else {
+        return unknown_workspace(webhook_body.workspace.as_deref().unwrap_or_default())
+            .into_response();
+    };
+
     let message = &webhook_body.message;
+    let request_id = headers
+        .get("X-Request-Id")
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| !id.is_empty())
+        .map_or_else(|| uuid::Uuid::new_v4().to_string(), String::from);
+    // Registration is dropped (and the id freed) when this handler returns,
+    // including when the client disconnects and axum drops the future. The
+    // deadline fires just before the HTTP timeout so we can still answer.
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(REQUEST_TIMEOUT_SECS)
+        - Duration::from_millis(TURN_DEADLINE_MARGIN_MS);
+    let (cancel, _registration) = state.inflight.register_with_deadline(&request_id, deadline);
 
     if state.auto_save {
-        let _ = state
+        let _ = workspace
             .mem
             .store("webhook_msg", message, MemoryCategory::Conversation)
             .await;
     }
 
-    match state
-        .provider
-        .chat(message, &state.model, state.temperature)
-        .await
-    {
-        Ok(response) => {
-            let body = serde_json::json!({"response": response, "model": state.model});
-            (StatusCode::OK, Json(body))
+    let chat = match &webhook_body.provider {
+        Some(provider) => workspace.chat.clone().preferring(
+            provider.as_str(),
+            webhook_body
+                .provider_fallback
+                .unwrap_or(state.preferred_provider_fallback),
+        ),
+        None => workspace.chat.clone(),
+    };
+    let system_prompt = webhook_system_prompt(chat.system_prompt(), webhook_body.format);
+    let call = async {
+        if webhook_body.stream {
+            chat.ask_streamed(system_prompt.as_deref(), message).await
+        } else {
+            chat.ask_with_system(system_prompt.as_deref(), message)
+                .await
+                .map(StreamOutcome::Complete)
         }
+    };
+    let call = providers::origin::scope(RequestOrigin::new("gateway"), call)
+        .instrument(tracing::info_span!("webhook_turn", request_id = %request_id));
+    let result = tokio::select! {
+        result = call => Some(result),
+        () = cancel.cancelled() => None,
+    };
+
+    match result {
+        Some(Ok(outcome)) => {
+            let response = webhook_body
+                .format
+                .unwrap_or_default()
+                .render(outcome.text());
+            let mut body = serde_json::json!({
+                "response": response,
+                "model": workspace.chat.model(),
+                "request_id": request_id,
+            });
+            if webhook_body.stream {
+                body["truncated"] = (!outcome.is_complete()).into();
+            }
+            if let StreamOutcome::Truncated { error, .. } = &outcome {
+                tracing::warn!(request_id = %request_id, "Webhook reply truncated: {error:#}");
+                body["error"] = format!("LLM error: {error:#}").into();
+            }
+            (StatusCode::OK, Json(body)).into_response()
+        }
+        Some(Err(e)) => llm_error_response(&e, &request_id),
+        None if cancel.is_expired() => {
+            tracing::warn!(request_id = %request_id, "Webhook request hit its deadline");
+            let err = serde_json::json!({
+                "error": "Request deadline exceeded",
+                "request_id": request_id,
+            });
+            (StatusCode::GATEWAY_TIMEOUT, Json(err)).into_response()
+        }
+        None => {
+            tracing::info!(request_id = %request_id, "Webhook request cancelled");
+            let err = serde_json::json!({
+                "error": "Request cancelled",
+                "request_id": request_id,
+            });
+            (StatusCode::CONFLICT, Json(err)).into_response()
+        }
+    }
+}
+
+/// The workspace's system prompt, if any, followed by the requested format's
+/// instructions.
+fn webhook_system_prompt(base: Option<&str>, format: Option<OutputFormat>) -> Option<String> {
+    match format {
+        Some(format) => Some(format!(
+            "{}{}",
+            base.unwrap_or_default(),
+            format.prompt_section()
+        )),
+        None => base.map(str::to_string),
+    }
+}
+
+/// 429 with `Retry-After` when the provider chain failed mostly on rate
+/// limits, so clients can back off; 400 for a preferred provider that isn't
+/// configured; any other failure is a 500.
+fn llm_error_response(e: &anyhow::Error, request_id: &str) -> Response {
+    if let Some(unknown) = e.downcast_ref::<UnknownProviderError>() {
+        let body = serde_json::json!({
+            "error": unknown.to_string(),
+            "request_id": request_id,
+        });
+        return (StatusCode::BAD_REQUEST, Json(body)).into_response();
+    }
+    let mut body = serde_json::json!({
+        "error": format!("LLM error: {e}"),
+        "request_id": request_id,
+    });
+    let Some(chain) = e
+        .downcast_ref::<ProviderChainError>()
+        .filter(|chain| chain.rate_limited)
+    else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response();
+    };
+
+    let mut response_headers = HeaderMap::new();
+    if let Some(after) = chain.retry_after {
+        let secs = after.as_secs() + u64::from(after.subsec_nanos() > 0);
+        body["retry_after"] = secs.into();
+        response_headers.insert(header::RETRY_AFTER, secs.into());
+    }
+    (StatusCode::TOO_MANY_REQUESTS, response_headers, Json(body)).into_response()
+}
+
+/// POST /cancel/`{request_id}` — abort an in-flight webhook request
+async fn handle_cancel(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(request_id): Path<String>,
+) -> impl IntoResponse {
+    if let Err(rejection) = authorize(&state, &headers) {
+        return rejection;
+    }
+
+    if state.inflight.cancel(&request_id) {
+        (
+            StatusCode::OK,
+            Json(serde_json::json!({"cancelled": true, "request_id": request_id})),
+        )
+    } else {
+        (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "cancelled": false,
+                "error": "No in-flight request with that id",
+            })),
+        )
+    }
+}
+
+/// GET /admin/provider-stats — cumulative cache and provider counters
+async fn handle_provider_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(rejection) = authorize(&state, &headers) {
+        return rejection;
+    }
+
+    match state.chat.provider().stats() {
+        Some(snapshot) => (StatusCode::OK, Json(serde_json::json!(snapshot))),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Provider does not track stats"})),
+        ),
+    }
+}
+
+/// GET /admin/provider-failures — recent failed provider calls, oldest first
+async fn handle_provider_failures(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(rejection) = authorize(&state, &headers) {
+        return rejection;
+    }
+
+    match state.chat.provider().recent_failures() {
+        Some(failures) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "failures": failures })),
+        ),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Provider does not track failures"})),
+        ),
+    }
+}
+
+/// GET /about — what this build supports
+async fn handle_about(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    if let Err(rejection) = authorize(&state, &headers) {
+        return rejection;
+    }
+    (
+        StatusCode::OK,
+        Json(serde_json::json!(crate::about::BuildInfo::current())),
+    )
+}
+
+/// GET /admin/memory-stats — entry counts and sizes per category
+/// (`?workspace=NAME` for a named workspace)
+async fn handle_memory_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<WorkspaceQuery>,
+) -> impl IntoResponse {
+    if let Err(rejection) = authorize(&state, &headers) {
+        return rejection;
+    }
+    let Some(workspace) = state.workspace(query.workspace.as_deref()) else {
+        return unknown_workspace(query.workspace.as_deref().unwrap_or_default());
+    };
+
+    match workspace.mem.stats().await {
+        Ok(summary) => (StatusCode::OK, Json(serde_json::json!(summary))),
         Err(e) => {
-            let err = serde_json::json!({"error": format!("LLM error: {e}")});
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(err))
+            tracing::error!("Failed to read memory stats: {e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": "Failed to read memory stats"})),
+            )
         }
     }
 }
 
+/// GET /tools — name, description and parameter schema of every tool the
+/// configured autonomy level exposes
+async fn handle_tools(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<WorkspaceQuery>,
+) -> impl IntoResponse {
+    if let Err(rejection) = authorize(&state, &headers) {
+        return rejection;
+    }
+    let Some(workspace) = state.workspace(query.workspace.as_deref()) else {
+        return unknown_workspace(query.workspace.as_deref().unwrap_or_default());
+    };
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "tools": workspace.tools.specs() })),
+    )
+}
+
 /// `WhatsApp` verification query params
 #[derive(serde::Deserialize)]
 pub struct WhatsAppVerifyQuery {
@@ -344,14 +1129,29 @@ async fn handle_whatsapp_verify(
 }
 
 /// POST /whatsapp — incoming message webhook
-async fn handle_whatsapp_message(State(state): State<AppState>, body: Bytes) -> impl IntoResponse {
-    let Some(ref wa) = state.whatsapp else {
+async fn handle_whatsapp_message(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let (Some(wa), Some(persona)) = (&state.whatsapp, &state.whatsapp_persona) else {
         return (
             StatusCode::NOT_FOUND,
             Json(serde_json::json!({"error": "WhatsApp not configured"})),
         );
     };
 
+    let signature = headers
+        .get("X-Hub-Signature-256")
+        .and_then(|v| v.to_str().ok());
+    if !wa.verify_payload_signature(&body, signature) {
+        tracing::warn!("WhatsApp webhook: rejected — invalid or missing X-Hub-Signature-256");
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({"error": "Invalid or missing X-Hub-Signature-256"})),
+        );
+    }
+
     // Parse JSON body
     let Ok(payload) = serde_json::from_slice::<serde_json::Value>(&body) else {
         return (
@@ -369,55 +1169,130 @@ async fn handle_whatsapp_message(State(state): State<AppState>, body: Bytes) ->
     }
 
     // Process each message
-    for msg in &messages {
-        tracing::info!(
-            "WhatsApp message from {}: {}",
-            msg.sender,
-            if msg.content.len() > 50 {
-                format!("{}...", &msg.content[..50])
-            } else {
-                msg.content.clone()
-            }
-        );
+    for msg in messages {
+        let sender = msg.sender.clone();
+        let reply = whatsapp_reply(&state, persona, msg).await;
+        if let Err(e) = wa.send(&reply, &sender).await {
+            tracing::error!("Failed to send WhatsApp reply: {e}");
+        }
+    }
 
-        // Auto-save to memory
-        if state.auto_save {
-            let _ = state
-                .mem
-                .store(
-                    &format!("whatsapp_{}", msg.sender),
-                    &msg.content,
-                    MemoryCategory::Conversation,
-                )
-                .await;
+    // Acknowledge the webhook
+    (StatusCode::OK, Json(serde_json::json!({"status": "ok"})))
+}
+
+/// `channels_config.personas["whatsapp"]` resolved the way `channels start`
+/// resolves it: prompt, model, temperature and limit, with the tools its own
+/// autonomy allows, in its own workspace when it names one.
+fn whatsapp_persona(
+    config: &Config,
+    wa: &WhatsAppChannel,
+    default_model: &str,
+    mem: &Arc<dyn Memory>,
+    workspaces: &HashMap<String, Workspace>,
+) -> Result<ChannelPersona> {
+    let named = config
+        .channels_config
+        .personas
+        .get("whatsapp")
+        .and_then(|persona| persona.workspace.as_deref());
+    let (persona_config, persona_mem) = match named {
+        Some(name) => {
+            let scoped = crate::doctor::preflight::prepare_named_workspace(config, name)?;
+            let scoped_mem = workspaces
+                .get(name)
+                .map_or_else(|| Arc::clone(mem), |ws| Arc::clone(&ws.mem));
+            (scoped, scoped_mem)
         }
+        None => (config.clone(), Arc::clone(mem)),
+    };
+    Ok(crate::channels::resolve_persona(
+        &persona_config,
+        "whatsapp",
+        wa.output_format(),
+        wa.max_inbound_chars(),
+        default_model,
+        &|dir, security, model| {
+            crate::channels::persona_prompt(
+                &persona_config,
+                dir,
+                security,
+                Arc::clone(&persona_mem),
+                model,
+            )
+        },
+    ))
+}
 
-        // Call the LLM
-        match state
-            .provider
-            .chat(&msg.content, &state.model, state.temperature)
-            .await
-        {
-            Ok(response) => {
-                // Send reply via WhatsApp
-                if let Err(e) = wa.send(&response, &msg.sender).await {
-                    tracing::error!("Failed to send WhatsApp reply: {e}");
-                }
-            }
-            Err(e) => {
-                tracing::error!("LLM error for WhatsApp message: {e}");
-                let _ = wa.send(&format!("⚠️ Error: {e}"), &msg.sender).await;
-            }
+/// The reply to one `WhatsApp` message in the persona's format: the model's
+/// answer, an error notice, or a notice that the message was too long.
+async fn whatsapp_reply(
+    state: &AppState,
+    persona: &ChannelPersona,
+    mut msg: ChannelMessage,
+) -> String {
+    let format = persona.format;
+    let max_chars = persona.max_inbound_chars;
+    match crate::channels::limit_inbound(&mut msg.content, max_chars, state.oversized_inbound) {
+        InboundSize::Fits => {}
+        InboundSize::Truncated { chars } => tracing::warn!(
+            sender = %msg.sender,
+            chars,
+            limit = max_chars,
+            "Truncated oversized WhatsApp message"
+        ),
+        InboundSize::Rejected { chars } => {
+            tracing::warn!(
+                sender = %msg.sender,
+                chars,
+                limit = max_chars,
+                "Rejected oversized WhatsApp message"
+            );
+            return format.render(&crate::channels::oversized_notice(chars, max_chars));
         }
     }
 
-    // Acknowledge the webhook
-    (StatusCode::OK, Json(serde_json::json!({"status": "ok"})))
+    tracing::info!(
+        "WhatsApp message from {}: {}",
+        msg.sender,
+        if msg.content.len() > 50 {
+            format!("{}...", &msg.content[..50])
+        } else {
+            msg.content.clone()
+        }
+    );
+
+    // Auto-save to memory, in the persona's workspace
+    if state.auto_save {
+        let mem = state
+            .workspace(persona.workspace.as_deref())
+            .map_or_else(|| Arc::clone(&state.mem), |ws| ws.mem);
+        let _ = mem
+            .store(
+                &format!("whatsapp_{}", msg.sender),
+                &msg.content,
+                MemoryCategory::Conversation,
+            )
+            .await;
+    }
+
+    // Call the LLM with the persona (identity + soul + tools + overrides)
+    let chat = persona.chat_client(state.chat.provider());
+    let reply =
+        providers::origin::scope(RequestOrigin::channel("whatsapp"), chat.ask(&msg.content));
+    match reply.await {
+        Ok(response) => format.render(&response),
+        Err(e) => {
+            tracing::error!("LLM error for WhatsApp message: {e}");
+            format.render(&format!("⚠️ Error: {e}"))
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::schema::ChannelPersonaConfig;
 
     #[test]
     fn security_body_limit_is_64kb() {
@@ -429,6 +1304,172 @@ mod tests {
         assert_eq!(REQUEST_TIMEOUT_SECS, 30);
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_is_owner_only_and_replaces_stale_socket() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("run").join("gateway.sock");
+
+        let first = GatewayListener::bind_unix(&path).unwrap();
+        assert_eq!(first.port(), None);
+        drop(first);
+
+        // The socket file outlives the listener; rebinding must clean it up.
+        let _second = GatewayListener::bind_unix(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        // The private staging directory is cleaned up after the rename.
+        let entries: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, vec![std::ffi::OsString::from("gateway.sock")]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_refuses_to_replace_regular_file() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("not-a-socket");
+        std::fs::write(&path, "keep me").unwrap();
+
+        assert!(GatewayListener::bind_unix(&path).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_serves_http() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("gateway.sock");
+        let listener = GatewayListener::bind_unix(&path).unwrap();
+        let app = Router::new().route("/health", get(|| async { "ok" }));
+        let server = tokio::spawn(listener.serve(app, crate::agent::CancelToken::new()));
+
+        let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        stream
+            .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("ok"));
+        server.abort();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn shutdown_finishes_requests_in_flight() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("gateway.sock");
+        let listener = GatewayListener::bind_unix(&path).unwrap();
+        let app = Router::new().route(
+            "/slow",
+            get(|| async {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                "done"
+            }),
+        );
+        let shutdown = crate::agent::CancelToken::new();
+        let server = tokio::spawn(listener.serve(app, shutdown.clone()));
+
+        let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        stream
+            .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        shutdown.cancel();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("done"));
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("server should return once the request is served")
+            .unwrap()
+            .unwrap();
+        assert!(tokio::net::UnixStream::connect(&path).await.is_err());
+    }
+
+    #[test]
+    fn rate_limited_chain_maps_to_429_with_retry_after() {
+        let err: anyhow::Error = ProviderChainError {
+            attempts: vec!["openrouter attempt 1/1: 429".into()],
+            rate_limited: true,
+            retry_after: Some(Duration::from_millis(12_300)),
+        }
+        .into();
+        let response = llm_error_response(&err, "req-1");
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "13");
+    }
+
+    #[test]
+    fn other_llm_failures_map_to_500() {
+        let chain: anyhow::Error = ProviderChainError {
+            attempts: vec!["openrouter attempt 1/1: 401".into()],
+            rate_limited: false,
+            retry_after: None,
+        }
+        .into();
+        let response = llm_error_response(&chain, "req-1");
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(response.headers().get(header::RETRY_AFTER).is_none());
+
+        let plain = anyhow::anyhow!("boom");
+        assert_eq!(
+            llm_error_response(&plain, "req-2").status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[test]
+    fn unknown_preferred_provider_maps_to_400() {
+        let err: anyhow::Error = UnknownProviderError {
+            requested: "anthropic".into(),
+            available: vec!["openrouter".into()],
+        }
+        .into();
+        assert_eq!(
+            llm_error_response(&err, "req-1").status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn taken_port_is_terminal_without_search() {
+        let held = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = held.local_addr().unwrap().port();
+
+        let Err(err) = GatewayListener::bind_tcp("127.0.0.1", port, 0).await else {
+            panic!("bind should fail while the port is held");
+        };
+        assert!(err.is::<TerminalError>());
+        assert!(err.to_string().contains("Fix:"));
+    }
+
+    #[tokio::test]
+    async fn taken_port_falls_through_to_next_free_one() {
+        let held = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = held.local_addr().unwrap().port();
+
+        let listener = GatewayListener::bind_tcp("127.0.0.1", port, 5)
+            .await
+            .unwrap();
+        let bound = listener.port().unwrap();
+        assert!(bound > port && bound <= port + 5, "{bound}");
+    }
+
     #[test]
     fn webhook_body_requires_message_field() {
         let valid = r#"{"message": "hello"}"#;
@@ -441,6 +1482,20 @@ mod tests {
         assert!(parsed.is_err());
     }
 
+    #[test]
+    fn webhook_body_accepts_optional_format() {
+        let parsed: WebhookBody =
+            serde_json::from_str(r#"{"message": "hi", "format": "plain"}"#).unwrap();
+        assert_eq!(parsed.format, Some(OutputFormat::Plain));
+
+        let parsed: WebhookBody = serde_json::from_str(r#"{"message": "hi"}"#).unwrap();
+        assert!(parsed.format.is_none());
+
+        let unknown: Result<WebhookBody, _> =
+            serde_json::from_str(r#"{"message": "hi", "format": "html"}"#);
+        assert!(unknown.is_err());
+    }
+
     #[test]
     fn whatsapp_query_fields_are_optional() {
         let q = WhatsAppVerifyQuery {
@@ -451,6 +1506,160 @@ mod tests {
         assert!(q.mode.is_none());
     }
 
+    #[test]
+    fn webhook_system_prompt_keeps_the_workspace_prompt() {
+        assert_eq!(webhook_system_prompt(None, None), None);
+        assert_eq!(
+            webhook_system_prompt(Some("## Project\n\n"), None).as_deref(),
+            Some("## Project\n\n")
+        );
+        let combined =
+            webhook_system_prompt(Some("## Project\n\n"), Some(OutputFormat::Plain)).unwrap();
+        assert!(combined.starts_with("## Project\n\n## Output Format"));
+        assert!(webhook_system_prompt(None, Some(OutputFormat::Plain))
+            .unwrap()
+            .starts_with("## Output Format"));
+    }
+
+    fn whatsapp_state(
+        config: &Config,
+        provider: Arc<crate::providers::testing::ScriptedProvider>,
+        oversized_inbound: OversizedInbound,
+    ) -> AppState {
+        let mem_cfg = crate::config::MemoryConfig {
+            backend: "markdown".into(),
+            ..crate::config::MemoryConfig::default()
+        };
+        let mem: Arc<dyn Memory> =
+            Arc::from(memory::create_memory(&mem_cfg, &config.workspace_dir, None).unwrap());
+        let wa = Arc::new(WhatsAppChannel::new(
+            "token".into(),
+            "123456789".into(),
+            "verify".into(),
+            vec!["*".into()],
+        ));
+        let persona = whatsapp_persona(config, &wa, "test-model", &mem, &HashMap::new()).unwrap();
+        AppState {
+            chat: ChatClient::new(provider, "test-model", 0.0),
+            mem,
+            auto_save: false,
+            webhook_secret: None,
+            pairing: Arc::new(PairingGuard::new(
+                false,
+                Box::new(crate::security::token_store::MemoryTokenStore::new(vec![])),
+            )),
+            tools: Arc::new(ToolRegistry::new(vec![])),
+            whatsapp: Some(wa),
+            whatsapp_persona: Some(Arc::new(persona)),
+            oversized_inbound,
+            inflight: CancelRegistry::new(),
+            workspaces: Arc::new(HashMap::new()),
+            preferred_provider_fallback: false,
+        }
+    }
+
+    /// A config whose workspace is `tmp`.
+    fn whatsapp_config(tmp: &tempfile::TempDir) -> Config {
+        Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        }
+    }
+
+    /// A webhook payload carrying one text message from +1234567890.
+    fn whatsapp_payload(body: &str) -> serde_json::Value {
+        serde_json::json!({
+            "object": "whatsapp_business_account",
+            "entry": [{
+                "id": "123",
+                "changes": [{
+                    "value": {
+                        "messaging_product": "whatsapp",
+                        "metadata": {"phone_number_id": "123456789"},
+                        "messages": [{
+                            "from": "1234567890",
+                            "id": "wamid.xxx",
+                            "timestamp": "1699999999",
+                            "type": "text",
+                            "text": {"body": body}
+                        }]
+                    },
+                    "field": "messages"
+                }]
+            }]
+        })
+    }
+
+    #[tokio::test]
+    async fn oversized_whatsapp_messages_are_limited_before_the_model() {
+        use crate::providers::testing::ScriptedProvider;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut config = whatsapp_config(&tmp);
+        config.channels_config.personas.insert(
+            "whatsapp".into(),
+            ChannelPersonaConfig {
+                max_inbound_chars: Some(4_096),
+                ..ChannelPersonaConfig::default()
+            },
+        );
+        let long = "x".repeat(5_000);
+
+        let provider = Arc::new(ScriptedProvider::new("answer"));
+        let state = whatsapp_state(&config, provider.clone(), OversizedInbound::Reject);
+        let wa = state.whatsapp.clone().unwrap();
+        let persona = state.whatsapp_persona.clone().unwrap();
+        let msg = wa.parse_webhook_payload(&whatsapp_payload(&long)).remove(0);
+        let reply = whatsapp_reply(&state, &persona, msg).await;
+        assert!(
+            reply.contains("Message too long (5000 characters, limit 4096)"),
+            "{reply}"
+        );
+        assert_eq!(provider.call_count(), 0);
+
+        let provider = Arc::new(ScriptedProvider::new("answer"));
+        let state = whatsapp_state(&config, provider.clone(), OversizedInbound::Truncate);
+        let msg = wa.parse_webhook_payload(&whatsapp_payload(&long)).remove(0);
+        let reply = whatsapp_reply(&state, &persona, msg).await;
+        assert_eq!(reply, "answer");
+        let calls = provider.calls();
+        assert_eq!(calls.len(), 1);
+        assert!(calls[0].message.starts_with(&"x".repeat(4_096)));
+        assert!(!calls[0].message.contains(&long));
+    }
+
+    #[tokio::test]
+    async fn whatsapp_messages_are_answered_with_the_configured_persona() {
+        use crate::providers::testing::ScriptedProvider;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut config = whatsapp_config(&tmp);
+        config.channels_config.personas.insert(
+            "whatsapp".into(),
+            ChannelPersonaConfig {
+                system_prompt: Some("Answer in one sentence.".into()),
+                model: Some("wa-model".into()),
+                temperature: Some(0.2),
+                ..ChannelPersonaConfig::default()
+            },
+        );
+
+        let provider = Arc::new(ScriptedProvider::new("answer"));
+        let state = whatsapp_state(&config, provider.clone(), OversizedInbound::Reject);
+        let wa = state.whatsapp.clone().unwrap();
+        let persona = state.whatsapp_persona.clone().unwrap();
+        let msg = wa.parse_webhook_payload(&whatsapp_payload("hi")).remove(0);
+        assert_eq!(whatsapp_reply(&state, &persona, msg).await, "answer");
+
+        let calls = provider.calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].model, "wa-model");
+        assert!((calls[0].temperature - 0.2).abs() < f64::EPSILON);
+        let prompt = calls[0].system_prompt.as_deref().unwrap_or_default();
+        assert!(prompt.contains("## Channel Persona"), "{prompt}");
+        assert!(prompt.contains("Answer in one sentence."), "{prompt}");
+    }
+
     #[test]
     fn app_state_is_clone() {
         fn assert_clone<T: Clone>() {}
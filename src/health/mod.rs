@@ -2,8 +2,18 @@ use chrono::Utc;
 use parking_lot::Mutex;
 use serde::Serialize;
 use std::collections::BTreeMap;
-use std::sync::OnceLock;
-use std::time::Instant;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+/// Burst of transitions inside this window is published as one snapshot.
+const PUBLISH_DEBOUNCE: Duration = Duration::from_millis(250);
+const SUBSCRIBER_CAPACITY: usize = 16;
+/// How long a serialized snapshot is reused when nothing has changed.
+const SNAPSHOT_JSON_TTL: Duration = Duration::from_millis(250);
+/// Missed heartbeats before a running component is reported `stale`.
+const STALE_AFTER_BEATS: u32 = 3;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ComponentHealth {
@@ -14,26 +24,213 @@ pub struct ComponentHealth {
     pub restart_count: u64,
 }
 
+/// Timestamps (`updated_at`, `started_at`) are wall-clock and can jump with
+/// NTP corrections or VM resume; `uptime_seconds` comes from the monotonic
+/// clock and is the authoritative measure of how long the process has run.
 #[derive(Debug, Clone, Serialize)]
 pub struct HealthSnapshot {
     pub pid: u32,
     pub updated_at: String,
+    /// Wall-clock time the process started, for display only
+    pub started_at: String,
+    /// Monotonic seconds since start; unaffected by clock changes
     pub uptime_seconds: u64,
     pub components: BTreeMap<String, ComponentHealth>,
+    /// Memory store bytes vs `memory.max_total_bytes`, when a cap is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_usage: Option<crate::memory::quota::MemoryUsage>,
 }
 
-struct HealthRegistry {
+/// Component health for one process, or for one test.
+///
+/// The daemon and its components report into a process-wide registry through
+/// the free functions in this module. [`scoped`] swaps in a private registry
+/// for the duration of a future, so tests that reuse component names don't
+/// see each other's restarts and errors.
+pub struct HealthRegistry {
     started_at: Instant,
+    started_at_wall: String,
     components: Mutex<BTreeMap<String, ComponentHealth>>,
+    updates: broadcast::Sender<HealthSnapshot>,
+    /// Set while a debounced publish is scheduled. Treated as stale after a few
+    /// debounce windows in case the runtime that owned the task went away.
+    publish_pending_since: Mutex<Option<Instant>>,
+    /// Last `snapshot_json` result; cleared whenever a component is updated.
+    cached_json: Mutex<Option<(Instant, serde_json::Value)>>,
+    /// Components that promised periodic heartbeats, and when they last sent one
+    heartbeats: Mutex<BTreeMap<String, Heartbeat>>,
+}
+
+struct Heartbeat {
+    last: Instant,
+    stale_after: Duration,
 }
 
-static REGISTRY: OnceLock<HealthRegistry> = OnceLock::new();
+static REGISTRY: OnceLock<Arc<HealthRegistry>> = OnceLock::new();
+
+tokio::task_local! {
+    static SCOPE: Arc<HealthRegistry>;
+}
+
+impl HealthRegistry {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            started_at: Instant::now(),
+            started_at_wall: now_rfc3339(),
+            components: Mutex::new(BTreeMap::new()),
+            updates: broadcast::channel(SUBSCRIBER_CAPACITY).0,
+            publish_pending_since: Mutex::new(None),
+            cached_json: Mutex::new(None),
+            heartbeats: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// Running components whose heartbeat is overdue read as `stale`; the
+    /// stored status is left alone so the next heartbeat restores it.
+    pub fn snapshot(&self) -> HealthSnapshot {
+        let mut components = self.components.lock().clone();
+        for (name, beat) in self.heartbeats.lock().iter() {
+            if beat.last.elapsed() <= beat.stale_after {
+                continue;
+            }
+            if let Some(entry) = components.get_mut(name) {
+                if matches!(entry.status.as_str(), "ok" | "degraded") {
+                    entry.status = "stale".into();
+                }
+            }
+        }
+
+        HealthSnapshot {
+            pid: std::process::id(),
+            updated_at: now_rfc3339(),
+            started_at: self.started_at_wall.clone(),
+            uptime_seconds: self.started_at.elapsed().as_secs(),
+            components,
+            memory_usage: crate::memory::quota::current_usage(),
+        }
+    }
+
+    /// [`HealthRegistry::snapshot`] as JSON, reused for up to
+    /// [`SNAPSHOT_JSON_TTL`] unless a component changes. Concurrent callers
+    /// wait for one serialization instead of each doing their own.
+    pub fn snapshot_json(&self) -> serde_json::Value {
+        let mut cached = self.cached_json.lock();
+        if let Some((at, json)) = cached.as_ref() {
+            if at.elapsed() < SNAPSHOT_JSON_TTL {
+                return json.clone();
+            }
+        }
+        let json = serde_json::to_value(self.snapshot()).unwrap_or_else(|_| {
+            serde_json::json!({
+                "status": "error",
+                "message": "failed to serialize health snapshot"
+            })
+        });
+        *cached = Some((Instant::now(), json.clone()));
+        json
+    }
+
+    fn upsert_component<F>(self: &Arc<Self>, component: &str, update: F)
+    where
+        F: FnOnce(&mut ComponentHealth),
+    {
+        let changed = {
+            let mut map = self.components.lock();
+            let now = now_rfc3339();
+            let before = map.get(component).cloned();
+            let entry = map
+                .entry(component.to_string())
+                .or_insert_with(|| ComponentHealth {
+                    status: "starting".into(),
+                    updated_at: now.clone(),
+                    last_ok: None,
+                    last_error: None,
+                    restart_count: 0,
+                });
+            update(entry);
+            entry.updated_at = now;
+            is_meaningful_transition(before.as_ref(), entry)
+        };
+        *self.cached_json.lock() = None;
+
+        if changed {
+            self.schedule_publish();
+        }
+    }
+
+    fn expect_heartbeat(self: &Arc<Self>, component: &str, every: Duration) {
+        self.heartbeats.lock().insert(
+            component.to_string(),
+            Heartbeat {
+                last: Instant::now(),
+                stale_after: every.saturating_mul(STALE_AFTER_BEATS),
+            },
+        );
+        *self.cached_json.lock() = None;
+    }
+
+    fn heartbeat(self: &Arc<Self>, component: &str) {
+        let expected = match self.heartbeats.lock().get_mut(component) {
+            Some(beat) => {
+                beat.last = Instant::now();
+                true
+            }
+            None => false,
+        };
+        if expected {
+            // Refreshes `updated_at`; not a transition, so nothing is published.
+            self.upsert_component(component, |_| {});
+        }
+    }
+
+    /// Publish a fresh snapshot to subscribers, coalescing bursts of transitions.
+    fn schedule_publish(self: &Arc<Self>) {
+        if self.updates.receiver_count() == 0 {
+            return;
+        }
+        {
+            let mut pending = self.publish_pending_since.lock();
+            if pending.is_some_and(|since| since.elapsed() < PUBLISH_DEBOUNCE * 4) {
+                return;
+            }
+            *pending = Some(Instant::now());
+        }
+
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let registry = Arc::clone(self);
+            handle.spawn(async move {
+                tokio::time::sleep(PUBLISH_DEBOUNCE).await;
+                registry.publish_now();
+            });
+        } else {
+            self.publish_now();
+        }
+    }
+
+    fn publish_now(&self) {
+        *self.publish_pending_since.lock() = None;
+        let _ = self.updates.send(self.snapshot());
+    }
+}
 
-fn registry() -> &'static HealthRegistry {
-    REGISTRY.get_or_init(|| HealthRegistry {
-        started_at: Instant::now(),
-        components: Mutex::new(BTreeMap::new()),
-    })
+/// The registry the current task reports into: the one installed by
+/// [`scoped`], or the process-wide registry.
+pub fn current() -> Arc<HealthRegistry> {
+    SCOPE
+        .try_with(Arc::clone)
+        .unwrap_or_else(|_| Arc::clone(REGISTRY.get_or_init(HealthRegistry::new)))
+}
+
+/// Run `fut` with health reports going to `registry` instead of the
+/// process-wide one. Tasks spawned from inside do not inherit the scope;
+/// wrap them with [`in_current_scope`].
+pub async fn scoped<F: Future>(registry: Arc<HealthRegistry>, fut: F) -> F::Output {
+    SCOPE.scope(registry, fut).await
+}
+
+/// Bind `fut` to the caller's registry, for handing to `tokio::spawn`.
+pub fn in_current_scope<F: Future>(fut: F) -> impl Future<Output = F::Output> {
+    SCOPE.scope(current(), fut)
 }
 
 fn now_rfc3339() -> String {
@@ -44,19 +241,18 @@ fn upsert_component<F>(component: &str, update: F)
 where
     F: FnOnce(&mut ComponentHealth),
 {
-    let mut map = registry().components.lock();
-    let now = now_rfc3339();
-    let entry = map
-        .entry(component.to_string())
-        .or_insert_with(|| ComponentHealth {
-            status: "starting".into(),
-            updated_at: now.clone(),
-            last_ok: None,
-            last_error: None,
-            restart_count: 0,
-        });
-    update(entry);
-    entry.updated_at = now;
+    current().upsert_component(component, update);
+}
+
+/// Status flips and restarts are worth pushing; timestamp-only refreshes are not.
+fn is_meaningful_transition(before: Option<&ComponentHealth>, after: &ComponentHealth) -> bool {
+    before
+        .is_none_or(|prev| prev.status != after.status || prev.restart_count != after.restart_count)
+}
+
+/// Receive a snapshot whenever any component's status or restart count changes.
+pub fn subscribe() -> broadcast::Receiver<HealthSnapshot> {
+    current().updates.subscribe()
 }
 
 pub fn mark_component_ok(component: &str) {
@@ -76,6 +272,56 @@ pub fn mark_component_error(component: &str, error: impl ToString) {
     });
 }
 
+/// Component is running but with reduced functionality (e.g. telemetry disabled).
+#[allow(clippy::needless_pass_by_value)]
+pub fn mark_component_degraded(component: &str, reason: impl ToString) {
+    let reason = reason.to_string();
+    upsert_component(component, move |entry| {
+        entry.status = "degraded".into();
+        entry.last_error = Some(reason);
+    });
+}
+
+/// Component hit a condition restarting cannot fix and was stopped for good.
+#[allow(clippy::needless_pass_by_value)]
+pub fn mark_component_failed(component: &str, reason: impl ToString) {
+    let reason = reason.to_string();
+    upsert_component(component, move |entry| {
+        entry.status = "failed".into();
+        entry.last_error = Some(reason);
+    });
+}
+
+/// Component shut down on purpose; `reason` says why.
+#[allow(clippy::needless_pass_by_value)]
+pub fn mark_component_stopped(component: &str, reason: impl ToString) {
+    let reason = reason.to_string();
+    upsert_component(component, move |entry| {
+        entry.status = "stopped".into();
+        entry.last_error = Some(reason);
+    });
+}
+
+/// Component was switched off in config and will not be started.
+pub fn mark_component_disabled(component: &str) {
+    upsert_component(component, |entry| {
+        entry.status = "disabled".into();
+        entry.last_error = None;
+    });
+}
+
+/// Declare that `component` calls [`mark_component_heartbeat`] about every
+/// `every`. Once three go missing while it claims to be running (say its task
+/// was killed without reporting), snapshots show it as `stale`.
+pub fn expect_component_heartbeat(component: &str, every: Duration) {
+    current().expect_heartbeat(component, every);
+}
+
+/// Proof of life for a component registered with [`expect_component_heartbeat`].
+pub fn mark_component_heartbeat(component: &str) {
+    current().heartbeat(component);
+}
+
 pub fn bump_component_restart(component: &str) {
     upsert_component(component, |entry| {
         entry.restart_count = entry.restart_count.saturating_add(1);
@@ -83,29 +329,20 @@ pub fn bump_component_restart(component: &str) {
 }
 
 pub fn snapshot() -> HealthSnapshot {
-    let components = registry().components.lock().clone();
-
-    HealthSnapshot {
-        pid: std::process::id(),
-        updated_at: now_rfc3339(),
-        uptime_seconds: registry().started_at.elapsed().as_secs(),
-        components,
-    }
+    current().snapshot()
 }
 
-/// Structured error message: what happened, why, and how to fix it.
-/// Produces consistent "what/why/fix" format across the codebase.
-pub fn structured_error(what: &str, why: &str, fix: &str) -> String {
-    format!("{what}\n  Cause: {why}\n  Fix: {fix}")
-}
+// Lives in `util` so library modules can report recoverable errors too.
+pub use crate::util::structured_error;
+
+/// Returned by a component to tell its supervisor that retrying is pointless
+/// (e.g. its port is taken). The message should be a [`structured_error`].
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct TerminalError(pub String);
 
 pub fn snapshot_json() -> serde_json::Value {
-    serde_json::to_value(snapshot()).unwrap_or_else(|_| {
-        serde_json::json!({
-            "status": "error",
-            "message": "failed to serialize health snapshot"
-        })
-    })
+    current().snapshot_json()
 }
 
 #[cfg(test)]
@@ -122,6 +359,153 @@ mod tests {
         assert!(msg.contains("Fix:"));
     }
 
+    fn component(status: &str, restart_count: u64) -> ComponentHealth {
+        ComponentHealth {
+            status: status.into(),
+            updated_at: now_rfc3339(),
+            last_ok: None,
+            last_error: None,
+            restart_count,
+        }
+    }
+
+    #[test]
+    fn snapshot_reports_wall_and_monotonic_start() {
+        let snap = snapshot();
+        let started = chrono::DateTime::parse_from_rfc3339(&snap.started_at).unwrap();
+        let updated = chrono::DateTime::parse_from_rfc3339(&snap.updated_at).unwrap();
+        assert!(started <= updated);
+
+        let json = snapshot_json();
+        assert!(json["started_at"].is_string());
+        assert!(json["uptime_seconds"].is_u64());
+    }
+
+    #[test]
+    fn transition_detection() {
+        let ok = component("ok", 0);
+        assert!(is_meaningful_transition(None, &ok));
+        assert!(!is_meaningful_transition(Some(&ok), &component("ok", 0)));
+        assert!(is_meaningful_transition(Some(&ok), &component("error", 0)));
+        assert!(is_meaningful_transition(Some(&ok), &component("ok", 1)));
+    }
+
+    #[test]
+    fn degraded_status_recorded() {
+        mark_component_degraded("health-test-degraded", "exporter unreachable");
+        let snap = snapshot();
+        let entry = &snap.components["health-test-degraded"];
+        assert_eq!(entry.status, "degraded");
+        assert_eq!(entry.last_error.as_deref(), Some("exporter unreachable"));
+    }
+
+    #[test]
+    fn disabled_status_recorded() {
+        mark_component_disabled("health-test-disabled");
+        let snap = snapshot();
+        let entry = &snap.components["health-test-disabled"];
+        assert_eq!(entry.status, "disabled");
+        assert!(entry.last_error.is_none());
+    }
+
+    #[tokio::test]
+    async fn subscribe_receives_status_change() {
+        let mut rx = subscribe();
+        mark_component_error("health-test-subscribe", "boom");
+
+        let snap = tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                let snap = rx.recv().await.unwrap();
+                if snap.components.contains_key("health-test-subscribe") {
+                    return snap;
+                }
+            }
+        })
+        .await
+        .expect("snapshot should be published");
+        assert_eq!(snap.components["health-test-subscribe"].status, "error");
+    }
+
+    #[tokio::test]
+    async fn scoped_registry_is_isolated() {
+        mark_component_error("health-test-scoped", "global failure");
+
+        let registry = HealthRegistry::new();
+        let snap = scoped(Arc::clone(&registry), async {
+            assert!(snapshot().components.is_empty());
+            mark_component_ok("health-test-scoped");
+            bump_component_restart("health-test-scoped");
+            // A spawned task only reports here when bound to the scope.
+            tokio::spawn(in_current_scope(async {
+                mark_component_disabled("health-test-scoped-child");
+            }))
+            .await
+            .unwrap();
+            snapshot()
+        })
+        .await;
+
+        assert_eq!(snap.components.len(), 2);
+        let entry = &snap.components["health-test-scoped"];
+        assert_eq!(entry.status, "ok");
+        assert_eq!(entry.restart_count, 1);
+        assert_eq!(registry.snapshot().components.len(), 2);
+
+        let global = &snapshot().components["health-test-scoped"];
+        assert_eq!(global.status, "error");
+        assert_eq!(global.restart_count, 0);
+        assert!(!snapshot()
+            .components
+            .contains_key("health-test-scoped-child"));
+    }
+
+    #[test]
+    fn snapshot_json_is_cached_until_a_component_changes() {
+        let registry = HealthRegistry::new();
+        registry.upsert_component("cache-test", |entry| entry.status = "ok".into());
+        let first = registry.snapshot_json();
+        assert_eq!(registry.snapshot_json(), first);
+
+        registry.upsert_component("cache-test", |entry| entry.status = "error".into());
+        let second = registry.snapshot_json();
+        assert_eq!(second["components"]["cache-test"]["status"], "error");
+
+        *registry.cached_json.lock() = Some((
+            Instant::now().checked_sub(SNAPSHOT_JSON_TTL).unwrap(),
+            serde_json::json!({"stale": true}),
+        ));
+        assert!(registry.snapshot_json()["stale"].is_null());
+    }
+
+    #[tokio::test]
+    async fn missed_heartbeats_read_as_stale() {
+        let registry = HealthRegistry::new();
+        scoped(Arc::clone(&registry), async {
+            mark_component_ok("gateway");
+            mark_component_ok("scheduler");
+            mark_component_stopped("channels", "shutdown");
+            expect_component_heartbeat("gateway", Duration::from_millis(10));
+            expect_component_heartbeat("scheduler", Duration::from_secs(60));
+            expect_component_heartbeat("channels", Duration::from_millis(10));
+            tokio::time::sleep(Duration::from_millis(50)).await;
+
+            let snap = snapshot();
+            assert_eq!(snap.components["gateway"].status, "stale");
+            assert_eq!(snap.components["scheduler"].status, "ok");
+            // Only components claiming to run can go stale.
+            assert_eq!(snap.components["channels"].status, "stopped");
+            // The overlay is computed, not stored.
+            assert_eq!(registry.components.lock()["gateway"].status, "ok");
+
+            mark_component_heartbeat("gateway");
+            assert_eq!(snapshot().components["gateway"].status, "ok");
+
+            mark_component_heartbeat("unregistered");
+            assert!(!snapshot().components.contains_key("unregistered"));
+        })
+        .await;
+    }
+
     #[test]
     fn structured_error_format() {
         let msg = structured_error("what", "why", "fix");
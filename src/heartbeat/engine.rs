@@ -6,6 +6,104 @@ use std::sync::Arc;
 use tokio::time::{self, Duration};
 use tracing::{info, warn};
 
+/// Intervals below this trigger a warning; it is the floor for `interval_minutes`.
+const MIN_RECOMMENDED_INTERVAL_SECS: u64 = 300;
+
+/// What a heartbeat task does when it runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskAction {
+    /// Hand this prompt to the agent.
+    Prompt(String),
+    /// Call one tool directly, without the model, under the same security
+    /// policy the agent's tools get.
+    Tool {
+        name: String,
+        args: serde_json::Value,
+    },
+}
+
+/// One HEARTBEAT.md task, with optional per-task overrides of the configured
+/// model and temperature.
+///
+/// Overrides go in a leading bracket: `- [model=gpt-4o-mini, temperature=0.2]
+/// Summarize yesterday's notes`. `- [tool=shell] {"command": "df -h"}` runs a
+/// tool instead, with the JSON object after the bracket as its arguments. A
+/// bracket that isn't a valid option list (a `[ ]` checkbox, a typo) stays
+/// part of the prompt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeartbeatTask {
+    pub action: TaskAction,
+    pub model: Option<String>,
+    pub temperature: Option<f64>,
+}
+
+impl HeartbeatTask {
+    /// Parse the text after a task's `- ` bullet.
+    pub fn parse(text: &str) -> Self {
+        let plain = Self {
+            action: TaskAction::Prompt(text.to_string()),
+            model: None,
+            temperature: None,
+        };
+        let Some((options, rest)) = text.strip_prefix('[').and_then(|rest| rest.split_once(']'))
+        else {
+            return plain;
+        };
+        let rest = rest.trim();
+        if !options.contains('=') {
+            return plain;
+        }
+
+        let mut task = Self {
+            action: TaskAction::Prompt(rest.to_string()),
+            model: None,
+            temperature: None,
+        };
+        let mut tool = None;
+        for option in options.split(',') {
+            let (key, value) = option.split_once('=').unwrap_or((option, ""));
+            let value = value.trim();
+            match key.trim() {
+                "model" if !value.is_empty() => task.model = Some(value.to_string()),
+                "temperature" => match value.parse::<f64>() {
+                    Ok(t) if (0.0..=2.0).contains(&t) => task.temperature = Some(t),
+                    _ => return Self::ignore_options(plain, option),
+                },
+                "tool" if !value.is_empty() => tool = Some(value.to_string()),
+                _ => return Self::ignore_options(plain, option),
+            }
+        }
+
+        let Some(name) = tool else {
+            return if rest.is_empty() { plain } else { task };
+        };
+        if task.model.is_some() || task.temperature.is_some() {
+            return Self::ignore_options(plain, "model/temperature on a tool task");
+        }
+        let args = if rest.is_empty() {
+            serde_json::json!({})
+        } else {
+            match serde_json::from_str::<serde_json::Value>(rest) {
+                Ok(args) if args.is_object() => args,
+                _ => return Self::ignore_options(plain, "tool arguments must be a JSON object"),
+            }
+        };
+        task.action = TaskAction::Tool { name, args };
+        task
+    }
+
+    fn ignore_options(plain: Self, option: &str) -> Self {
+        if let TaskAction::Prompt(prompt) = &plain.action {
+            warn!(
+                "💓 Heartbeat task option {:?} is not valid; running the task as written: {}",
+                option.trim(),
+                prompt
+            );
+        }
+        plain
+    }
+}
+
 /// Heartbeat engine — reads HEARTBEAT.md and executes tasks periodically
 pub struct HeartbeatEngine {
     config: HeartbeatConfig,
@@ -26,6 +124,24 @@ impl HeartbeatEngine {
         }
     }
 
+    /// Effective tick cadence: `interval_seconds` if set (min 1s, warning when
+    /// aggressive), otherwise `interval_minutes` with its 5-minute floor.
+    pub fn interval(config: &HeartbeatConfig) -> Duration {
+        match config.interval_seconds {
+            Some(secs) => {
+                let secs = secs.max(1);
+                if secs < MIN_RECOMMENDED_INTERVAL_SECS {
+                    warn!(
+                        "💓 Heartbeat interval is {secs}s; every tick may run agent turns \
+                         and spend provider quota"
+                    );
+                }
+                Duration::from_secs(secs)
+            }
+            None => Duration::from_secs(u64::from(config.interval_minutes.max(5)) * 60),
+        }
+    }
+
     /// Start the heartbeat loop (runs until cancelled)
     pub async fn run(&self) -> Result<()> {
         if !self.config.enabled {
@@ -33,10 +149,10 @@ impl HeartbeatEngine {
             return Ok(());
         }
 
-        let interval_mins = self.config.interval_minutes.max(5);
-        info!("💓 Heartbeat started: every {} minutes", interval_mins);
+        let period = Self::interval(&self.config);
+        info!("💓 Heartbeat started: every {}s", period.as_secs());
 
-        let mut interval = time::interval(Duration::from_secs(u64::from(interval_mins) * 60));
+        let mut interval = time::interval(period);
 
         loop {
             interval.tick().await;
@@ -65,13 +181,16 @@ impl HeartbeatEngine {
     }
 
     /// Read HEARTBEAT.md and return all parsed tasks.
-    pub async fn collect_tasks(&self) -> Result<Vec<String>> {
+    pub async fn collect_tasks(&self) -> Result<Vec<HeartbeatTask>> {
         let heartbeat_path = self.workspace_dir.join("HEARTBEAT.md");
         if !heartbeat_path.exists() {
             return Ok(Vec::new());
         }
         let content = tokio::fs::read_to_string(&heartbeat_path).await?;
-        Ok(Self::parse_tasks(&content))
+        Ok(Self::parse_tasks(&content)
+            .iter()
+            .map(|task| HeartbeatTask::parse(task))
+            .collect())
     }
 
     /// Parse tasks from HEARTBEAT.md (lines starting with `- `)
@@ -96,7 +215,13 @@ impl HeartbeatEngine {
                            # Examples:\n\
                            # - Check my email for important messages\n\
                            # - Review my calendar for upcoming events\n\
-                           # - Check the weather forecast\n";
+                           # - Check the weather forecast\n\
+                           #\n\
+                           # Override the model or temperature for one task:\n\
+                           # - [model=gpt-4o-mini, temperature=0.2] Summarize today's notes\n\
+                           #\n\
+                           # Run a tool directly, without the model:\n\
+                           # - [tool=shell] {\"command\": \"df -h\"}\n";
             tokio::fs::write(&path, default).await?;
         }
         Ok(())
@@ -204,6 +329,76 @@ mod tests {
         assert_eq!(tasks[99], "Task 99");
     }
 
+    #[test]
+    fn task_without_overrides_keeps_defaults() {
+        let task = HeartbeatTask::parse("Check email");
+        assert_eq!(task.action, TaskAction::Prompt("Check email".into()));
+        assert!(task.model.is_none());
+        assert!(task.temperature.is_none());
+    }
+
+    #[test]
+    fn task_overrides_model_and_temperature() {
+        let task = HeartbeatTask::parse("[model=gpt-4o-mini, temperature=0.2] Summarize notes");
+        assert_eq!(task.action, TaskAction::Prompt("Summarize notes".into()));
+        assert_eq!(task.model.as_deref(), Some("gpt-4o-mini"));
+        assert_eq!(task.temperature, Some(0.2));
+
+        let task = HeartbeatTask::parse("[temperature=1.1] Write a poem");
+        assert!(task.model.is_none());
+        assert_eq!(task.temperature, Some(1.1));
+    }
+
+    #[test]
+    fn task_invalid_overrides_stay_in_prompt() {
+        for text in [
+            "[ ] Unchecked checkbox",
+            "[temperature=hot] Write a poem",
+            "[temperature=5] Write a poem",
+            "[colour=blue] Paint",
+            "[model=gpt-4o]",
+            "[unclosed=1 task",
+        ] {
+            let task = HeartbeatTask::parse(text);
+            assert_eq!(task.action, TaskAction::Prompt(text.into()));
+            assert!(task.model.is_none() && task.temperature.is_none(), "{text}");
+        }
+    }
+
+    #[test]
+    fn task_can_call_a_tool_directly() {
+        let task = HeartbeatTask::parse(r#"[tool=shell] {"command": "df -h"}"#);
+        assert_eq!(
+            task.action,
+            TaskAction::Tool {
+                name: "shell".into(),
+                args: serde_json::json!({"command": "df -h"}),
+            }
+        );
+
+        let task = HeartbeatTask::parse("[tool=memory_inspect]");
+        assert_eq!(
+            task.action,
+            TaskAction::Tool {
+                name: "memory_inspect".into(),
+                args: serde_json::json!({}),
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_tool_task_stays_a_prompt() {
+        for text in [
+            "[tool=shell] df -h",
+            r#"[tool=shell] ["df"]"#,
+            r#"[tool=shell, model=gpt-4o] {"command": "ls"}"#,
+            "[tool=] Check disk",
+        ] {
+            let task = HeartbeatTask::parse(text);
+            assert_eq!(task.action, TaskAction::Prompt(text.into()), "{text}");
+        }
+    }
+
     #[tokio::test]
     async fn ensure_heartbeat_file_creates_file() {
         let dir = std::env::temp_dir().join("baihu_test_heartbeat");
@@ -248,6 +443,7 @@ mod tests {
             HeartbeatConfig {
                 enabled: true,
                 interval_minutes: 30,
+                interval_seconds: None,
             },
             dir.clone(),
             observer,
@@ -273,6 +469,7 @@ mod tests {
             HeartbeatConfig {
                 enabled: true,
                 interval_minutes: 30,
+                interval_seconds: None,
             },
             dir.clone(),
             observer,
@@ -283,6 +480,32 @@ mod tests {
         let _ = tokio::fs::remove_dir_all(&dir).await;
     }
 
+    #[test]
+    fn interval_minutes_keeps_five_minute_floor() {
+        let config = HeartbeatConfig {
+            enabled: true,
+            interval_minutes: 1,
+            interval_seconds: None,
+        };
+        assert_eq!(HeartbeatEngine::interval(&config), Duration::from_secs(300));
+    }
+
+    #[test]
+    fn interval_seconds_overrides_floor() {
+        let config = HeartbeatConfig {
+            enabled: true,
+            interval_minutes: 30,
+            interval_seconds: Some(10),
+        };
+        assert_eq!(HeartbeatEngine::interval(&config), Duration::from_secs(10));
+
+        let zero = HeartbeatConfig {
+            interval_seconds: Some(0),
+            ..config
+        };
+        assert_eq!(HeartbeatEngine::interval(&zero), Duration::from_secs(1));
+    }
+
     #[tokio::test]
     async fn run_returns_immediately_when_disabled() {
         let observer: Arc<dyn Observer> = Arc::new(crate::observability::NoopObserver);
@@ -290,6 +513,7 @@ mod tests {
             HeartbeatConfig {
                 enabled: false,
                 interval_minutes: 30,
+                interval_seconds: None,
             },
             std::env::temp_dir(),
             observer,
@@ -11,6 +11,8 @@
     dead_code
 )]
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod config;
 pub mod heartbeat;
 pub mod memory;
@@ -18,3 +20,4 @@ pub mod observability;
 pub mod providers;
 pub mod runtime;
 pub mod security;
+pub mod util;
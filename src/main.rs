@@ -16,6 +16,7 @@ use clap::{Parser, Subcommand};
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
+mod about;
 mod agent;
 mod channels;
 mod config;
@@ -37,6 +38,7 @@ mod service;
 mod skills;
 mod tools;
 mod tunnel;
+mod util;
 
 use config::Config;
 
@@ -107,6 +109,14 @@ enum Commands {
         /// Temperature (0.0 - 2.0)
         #[arg(short, long, default_value = "0.7")]
         temperature: f64,
+
+        /// Reply format (markdown, plain, json, slack-mrkdwn, telegram-markdown-v2)
+        #[arg(long, value_enum, default_value = "markdown")]
+        format: channels::OutputFormat,
+
+        /// Named workspace from [workspaces] to work in
+        #[arg(long)]
+        workspace: Option<String>,
     },
 
     /// Start the gateway server (webhooks, websockets)
@@ -137,12 +147,23 @@ enum Commands {
         service_command: ServiceCommands,
     },
 
-    /// Run diagnostics for daemon/scheduler/channel freshness
-    Doctor,
+    /// Run preflight checks and diagnostics for daemon/scheduler/channel freshness
+    Doctor {
+        /// Host the gateway would bind to (checked for bind safety)
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
 
     /// Show system status (full details)
     Status,
 
+    /// Show what this build supports: features, sandboxes, backends
+    Features {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Configure and manage scheduled tasks
     Cron {
         #[command(subcommand)]
@@ -167,6 +188,12 @@ enum Commands {
         skill_command: SkillCommands,
     },
 
+    /// Maintain the memory store
+    Memory {
+        #[command(subcommand)]
+        memory_command: MemoryCommands,
+    },
+
     /// Migrate data from other agent runtimes
     Migrate {
         #[command(subcommand)]
@@ -174,6 +201,20 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum MemoryCommands {
+    /// Reclaim disk space and rebuild search indexes
+    Compact,
+    /// Show entry counts and sizes per category
+    Stats,
+    /// Rewrite lz4-compressed entries as plain text; safe to interrupt and rerun
+    Migrate {
+        /// Entries rewritten between progress reports
+        #[arg(long, default_value_t = 100)]
+        batch_size: usize,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 enum MigrateCommands {
     /// Import memory from a legacy workspace
@@ -296,7 +337,14 @@ async fn main() -> Result<()> {
     }
 
     // All other commands need config loaded first
-    let config = Config::load_or_init()?;
+    let mut config = Config::load_or_init()?;
+    providers::http_client::configure_tls(&config)?;
+    // Doctor reports a broken workspace instead of refusing to start
+    if !matches!(cli.command, Commands::Doctor { .. }) {
+        config.workspace_dir =
+            security::SecurityPolicy::from_config(&config.autonomy, &config.workspace_dir)
+                .prepare_workspace()?;
+    }
 
     match cli.command {
         Commands::Onboard { .. } => unreachable!(),
@@ -306,7 +354,19 @@ async fn main() -> Result<()> {
             provider,
             model,
             temperature,
-        } => agent::run(config, message, provider, model, temperature).await,
+            format,
+            workspace,
+        } => {
+            let config = match workspace {
+                Some(name) => doctor::preflight::prepare_named_workspace(&config, &name)?,
+                None => config,
+            };
+            providers::origin::scope(
+                providers::origin::RequestOrigin::new("cli"),
+                agent::run(config, message, provider, model, temperature, format),
+            )
+            .await
+        }
 
         Commands::Gateway { port, host } => {
             if port == 0 {
@@ -326,12 +386,32 @@ async fn main() -> Result<()> {
             daemon::run(config, host, port).await
         }
 
+        Commands::Features { json } => {
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&about::BuildInfo::current())?
+                );
+            } else {
+                about::print();
+            }
+            Ok(())
+        }
+
         Commands::Status => {
             println!("🦀 Baihu Status");
             println!();
             println!("Version:     {}", env!("CARGO_PKG_VERSION"));
             println!("Workspace:   {}", config.workspace_dir.display());
             println!("Config:      {}", config.config_path.display());
+            println!(
+                "Daemon:      {}",
+                if daemon::is_running(&config) {
+                    "running"
+                } else {
+                    "not running"
+                }
+            );
             println!();
             println!(
                 "🤖 Provider:      {}",
@@ -346,10 +426,12 @@ async fn main() -> Result<()> {
             println!("⚙️  Runtime:       {}", config.runtime.kind);
             println!(
                 "💓 Heartbeat:      {}",
-                if config.heartbeat.enabled {
-                    format!("every {}min", config.heartbeat.interval_minutes)
-                } else {
+                if !config.heartbeat.enabled {
                     "disabled".into()
+                } else if let Some(secs) = config.heartbeat.interval_seconds {
+                    format!("every {secs}s")
+                } else {
+                    format!("every {}min", config.heartbeat.interval_minutes)
                 }
             );
             println!(
@@ -399,7 +481,7 @@ async fn main() -> Result<()> {
 
         Commands::Service { service_command } => service::handle_command(&service_command, &config),
 
-        Commands::Doctor => doctor::run(&config),
+        Commands::Doctor { host } => doctor::run(&config, &host).await,
 
         Commands::Channel { channel_command } => match channel_command {
             ChannelCommands::Start => channels::start_channels(config).await,
@@ -415,6 +497,88 @@ async fn main() -> Result<()> {
             skills::handle_command(skill_command, &config.workspace_dir)
         }
 
+        Commands::Memory { memory_command } => match memory_command {
+            MemoryCommands::Compact => {
+                let mem = memory::create_memory(
+                    &config.memory,
+                    &config.workspace_dir,
+                    config.api_key.as_deref(),
+                )?;
+                let report = mem.compact().await?;
+                println!(
+                    "🧹 Compacted {} memory: {} bytes reclaimed, {} stale entries removed",
+                    mem.name(),
+                    report.bytes_reclaimed,
+                    report.entries_removed
+                );
+                Ok(())
+            }
+            MemoryCommands::Stats => {
+                let mem = memory::create_memory(
+                    &config.memory,
+                    &config.workspace_dir,
+                    config.api_key.as_deref(),
+                )?;
+                let stats = mem.stats().await?;
+                println!(
+                    "🧠 {} memory: {} entries, {} bytes",
+                    mem.name(),
+                    stats.total_entries,
+                    stats.total_bytes
+                );
+                for (category, entry) in &stats.categories {
+                    println!(
+                        "  {category:<20} {:>6} entries {:>10} bytes",
+                        entry.count, entry.bytes
+                    );
+                }
+                if let (Some(oldest), Some(newest)) = (&stats.oldest, &stats.newest) {
+                    println!("  Oldest: {oldest}");
+                    println!("  Newest: {newest}");
+                }
+                if stats.compressed_entries > 0 {
+                    println!(
+                        "  Compressed: {} entries, {} bytes saved",
+                        stats.compressed_entries, stats.compression_savings_bytes
+                    );
+                }
+                Ok(())
+            }
+            MemoryCommands::Migrate { batch_size } => {
+                let mem = memory::create_memory(
+                    &config.memory,
+                    &config.workspace_dir,
+                    config.api_key.as_deref(),
+                )?;
+                let done = memory::migrate_compression(
+                    mem.as_ref(),
+                    memory::Compression::None,
+                    batch_size,
+                    config.memory.max_decompressed_bytes,
+                    |p| {
+                        println!(
+                            "  … {}/{} scanned, {} rewritten",
+                            p.scanned, p.total, p.rewritten
+                        );
+                    },
+                )
+                .await?;
+                println!(
+                    "🗜️  Migrated {} memory: {} of {} entries rewritten",
+                    mem.name(),
+                    done.rewritten,
+                    done.total
+                );
+                if done.failed > 0 {
+                    println!(
+                        "⚠️  {} entries could not be decoded and were left unchanged",
+                        done.failed
+                    );
+                }
+                Ok(())
+            }
+        },
+
         Commands::Migrate { migrate_command } => {
             migration::handle_command(migrate_command, &config).await
         }
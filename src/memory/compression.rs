@@ -3,9 +3,18 @@
 // Content >1KB is compressed and stored with "lz4:" prefix.
 // FTS5 still indexes the uncompressed text (stored in a separate column).
 
+use super::traits::Memory;
+use crate::util::{hex_decode, hex_encode, hex_nibble};
+use std::io::Write;
+
 const COMPRESSION_THRESHOLD: usize = 1024; // 1KB
 const LZ4_PREFIX: &str = "lz4:";
 
+/// Upper bound on the size a stored entry may claim to decompress to.
+/// The size prefix is attacker-controlled if the DB is ever shared or synced,
+/// so it is checked before lz4 allocates the output buffer.
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024; // 64MB
+
 /// Compress content if it exceeds the threshold.
 /// Returns (`stored_content`, `is_compressed`).
 pub fn maybe_compress(content: &str) -> (String, bool) {
@@ -20,8 +29,19 @@ pub fn maybe_compress(content: &str) -> (String, bool) {
 
 /// Decompress content if it has the LZ4 prefix, otherwise return as-is.
 pub fn maybe_decompress(stored: &str) -> anyhow::Result<String> {
+    maybe_decompress_with_limit(stored, DEFAULT_MAX_DECOMPRESSED_SIZE)
+}
+
+/// Like [`maybe_decompress`], but rejects entries whose size prefix exceeds `max_size`.
+pub fn maybe_decompress_with_limit(stored: &str, max_size: usize) -> anyhow::Result<String> {
     if let Some(hex) = stored.strip_prefix(LZ4_PREFIX) {
         let compressed = hex_decode(hex)?;
+        let claimed = prepended_size(&compressed)?;
+        if claimed > max_size {
+            anyhow::bail!(
+                "Compressed entry claims {claimed} bytes decompressed, exceeding limit of {max_size}"
+            );
+        }
         let decompressed = lz4_flex::decompress_size_prepended(&compressed)
             .map_err(|e| anyhow::anyhow!("LZ4 decompression failed: {e}"))?;
         String::from_utf8(decompressed)
@@ -31,32 +51,291 @@ pub fn maybe_decompress(stored: &str) -> anyhow::Result<String> {
     }
 }
 
+/// Decompress straight into `writer` without materializing the whole entry.
+/// Only the 64KB LZ4 back-reference window is held in memory. Returns bytes written.
+pub fn decompress_to_writer<W: Write>(
+    stored: &str,
+    writer: &mut W,
+    max_size: usize,
+) -> anyhow::Result<usize> {
+    let Some(hex) = stored.strip_prefix(LZ4_PREFIX) else {
+        writer.write_all(stored.as_bytes())?;
+        return Ok(stored.len());
+    };
+    let mut written = 0;
+    decode_block(hex, max_size, |chunk| {
+        writer.write_all(chunk)?;
+        written += chunk.len();
+        Ok(true)
+    })?;
+    Ok(written)
+}
+
+/// First `max_chars` characters of an entry, for list views. Compressed entries
+/// are only decoded as far as needed to fill the preview.
+pub fn preview(stored: &str, max_chars: usize) -> anyhow::Result<String> {
+    let Some(hex) = stored.strip_prefix(LZ4_PREFIX) else {
+        return Ok(stored.chars().take(max_chars).collect());
+    };
+    // A char is at most 4 bytes, so this many bytes always covers `max_chars`.
+    let budget = max_chars.saturating_mul(4);
+    let mut head = Vec::new();
+    decode_block(hex, DEFAULT_MAX_DECOMPRESSED_SIZE, |chunk| {
+        head.extend_from_slice(chunk);
+        Ok(head.len() < budget)
+    })?;
+    let text = match std::str::from_utf8(&head) {
+        Ok(text) => text,
+        // Stopping mid-character is expected; anything else is real corruption.
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&head[..e.valid_up_to()])?,
+        Err(e) => anyhow::bail!("Decompressed data is not valid UTF-8: {e}"),
+    };
+    Ok(text.chars().take(max_chars).collect())
+}
+
+/// Size `stored` decompresses to, read from its size header without
+/// decoding. Plain entries are their own length.
+pub fn decompressed_len(stored: &str) -> anyhow::Result<usize> {
+    let Some(hex) = stored.strip_prefix(LZ4_PREFIX) else {
+        return Ok(stored.len());
+    };
+    let header = crate::util::hex_decode(hex.get(..8).unwrap_or(hex))?;
+    prepended_size(&header)
+}
+
+/// Compress a whole file body (e.g. `daemon_state.json.lz4`): the raw LZ4
+/// block with its size header, without the prefix and hex used for entries.
+pub fn compress_bytes(data: &[u8]) -> Vec<u8> {
+    lz4_flex::compress_prepend_size(data)
+}
+
+/// Inverse of [`compress_bytes`], rejecting data whose size header exceeds `max_size`.
+pub fn decompress_bytes(data: &[u8], max_size: usize) -> anyhow::Result<Vec<u8>> {
+    let claimed = prepended_size(data)?;
+    if claimed > max_size {
+        anyhow::bail!(
+            "Compressed data claims {claimed} bytes decompressed, exceeding limit of {max_size}"
+        );
+    }
+    lz4_flex::decompress_size_prepended(data)
+        .map_err(|e| anyhow::anyhow!("LZ4 decompression failed: {e}"))
+}
+
 /// Returns true if content is LZ4-compressed.
 pub fn is_compressed(stored: &str) -> bool {
     stored.starts_with(LZ4_PREFIX)
 }
 
-fn hex_encode(data: &[u8]) -> String {
-    let mut s = String::with_capacity(data.len() * 2);
-    for b in data {
-        use std::fmt::Write;
-        let _ = write!(s, "{b:02x}");
+/// Read the little-endian u32 size header written by `compress_prepend_size`.
+fn prepended_size(compressed: &[u8]) -> anyhow::Result<usize> {
+    let header: [u8; 4] = compressed
+        .get(..4)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| anyhow::anyhow!("Compressed entry is missing its size header"))?;
+    Ok(u32::from_le_bytes(header) as usize)
+}
+
+/// Largest back-reference distance in the LZ4 block format.
+const LZ4_WINDOW: usize = 64 * 1024;
+
+/// Reads bytes out of a hex string on demand instead of decoding it up front.
+struct HexReader<'a> {
+    hex: &'a [u8],
+    pos: usize,
+}
+
+impl HexReader<'_> {
+    fn is_empty(&self) -> bool {
+        self.pos >= self.hex.len()
+    }
+
+    fn byte(&mut self) -> anyhow::Result<u8> {
+        let pair = self
+            .hex
+            .get(self.pos..self.pos + 2)
+            .ok_or_else(|| anyhow::anyhow!("Compressed entry is truncated"))?;
+        let (Some(hi), Some(lo)) = (hex_nibble(pair[0]), hex_nibble(pair[1])) else {
+            anyhow::bail!("Invalid hex at position {}", self.pos);
+        };
+        self.pos += 2;
+        Ok(hi << 4 | lo)
+    }
+
+    /// LZ4 length field: a nibble of 15 continues into following bytes.
+    fn length(&mut self, nibble: u8) -> anyhow::Result<usize> {
+        let mut len = usize::from(nibble);
+        if nibble == 15 {
+            loop {
+                let extra = self.byte()?;
+                len += usize::from(extra);
+                if extra != 255 {
+                    break;
+                }
+            }
+        }
+        Ok(len)
     }
-    s
 }
 
-fn hex_decode(hex: &str) -> anyhow::Result<Vec<u8>> {
+/// Incrementally decode a hex-encoded, size-prepended LZ4 block, handing each
+/// finished run of output to `emit`. Decoding stops early once `emit` returns false.
+fn decode_block<F>(hex: &str, max_size: usize, mut emit: F) -> anyhow::Result<()>
+where
+    F: FnMut(&[u8]) -> anyhow::Result<bool>,
+{
     #[allow(clippy::manual_is_multiple_of)]
     if hex.len() % 2 != 0 {
         anyhow::bail!("Hex string has odd length");
     }
-    (0..hex.len())
-        .step_by(2)
-        .map(|i| {
-            u8::from_str_radix(&hex[i..i + 2], 16)
-                .map_err(|e| anyhow::anyhow!("Invalid hex at position {i}: {e}"))
-        })
-        .collect()
+    let mut input = HexReader {
+        hex: hex.as_bytes(),
+        pos: 0,
+    };
+    let header = [input.byte()?, input.byte()?, input.byte()?, input.byte()?];
+    let claimed = prepended_size(&header)?;
+    if claimed > max_size {
+        anyhow::bail!(
+            "Compressed entry claims {claimed} bytes decompressed, exceeding limit of {max_size}"
+        );
+    }
+
+    let mut window: Vec<u8> = Vec::new();
+    let mut produced = 0_usize;
+    let mut emitted = 0_usize;
+    while !input.is_empty() {
+        let token = input.byte()?;
+        let literals = input.length(token >> 4)?;
+        produced += literals;
+        if produced > claimed {
+            anyhow::bail!("Compressed entry decodes past its declared size");
+        }
+        for _ in 0..literals {
+            window.push(input.byte()?);
+        }
+
+        if !input.is_empty() {
+            let offset = usize::from(input.byte()?) | usize::from(input.byte()?) << 8;
+            if offset == 0 || offset > window.len() {
+                anyhow::bail!("LZ4 decompression failed: invalid match offset {offset}");
+            }
+            let match_len = input.length(token & 0x0f)? + 4;
+            produced += match_len;
+            if produced > claimed {
+                anyhow::bail!("Compressed entry decodes past its declared size");
+            }
+            let start = window.len() - offset;
+            for i in 0..match_len {
+                window.push(window[start + i]);
+            }
+        }
+
+        if !emit(&window[emitted..])? {
+            return Ok(());
+        }
+        if window.len() > 2 * LZ4_WINDOW {
+            window.drain(..window.len() - LZ4_WINDOW);
+        }
+        emitted = window.len();
+    }
+
+    if produced != claimed {
+        anyhow::bail!("Compressed entry is truncated");
+    }
+    Ok(())
+}
+
+/// Stored format an entry can be migrated to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Plain text
+    None,
+    /// `lz4:` prefix, for content over the compression threshold
+    Lz4,
+}
+
+/// Rewrite one stored entry in `target` format. `None` means the entry is
+/// already there, which is what makes repeated migrations no-ops. Entries
+/// claiming to decompress past `max_size` bytes are rejected.
+pub fn recompress(
+    stored: &str,
+    target: Compression,
+    max_size: usize,
+) -> anyhow::Result<Option<String>> {
+    match target {
+        Compression::None if is_compressed(stored) => {
+            maybe_decompress_with_limit(stored, max_size).map(Some)
+        }
+        Compression::Lz4 if !is_compressed(stored) => {
+            let (rewritten, compressed) = maybe_compress(stored);
+            Ok(compressed.then_some(rewritten))
+        }
+        Compression::None | Compression::Lz4 => Ok(None),
+    }
+}
+
+/// Counts reported after each batch of [`migrate_compression`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MigrationProgress {
+    pub total: usize,
+    pub scanned: usize,
+    pub rewritten: usize,
+    /// Entries that could not be decoded; they are left as they were
+    pub failed: usize,
+}
+
+/// Rewrite every entry of `mem` in `target` format, `batch_size` entries at
+/// a time, calling `on_batch` after each batch. `max_size` bounds the
+/// decompressed size of any one entry (`memory.max_decompressed_bytes`).
+///
+/// Each entry is written back with a single `store`, so an interrupted run
+/// leaves every entry either old or new, never half-written. Entries already
+/// in `target` format are skipped: rerunning picks up where the last run
+/// stopped.
+pub async fn migrate_compression<F>(
+    mem: &dyn Memory,
+    target: Compression,
+    batch_size: usize,
+    max_size: usize,
+    mut on_batch: F,
+) -> anyhow::Result<MigrationProgress>
+where
+    F: FnMut(&MigrationProgress),
+{
+    let batch_size = batch_size.max(1);
+    let mut progress = MigrationProgress {
+        total: mem.count().await?,
+        ..MigrationProgress::default()
+    };
+
+    // Paged by key: rewriting an entry keeps its key, so pages stay stable.
+    let mut after: Option<String> = None;
+    loop {
+        let batch = mem.list_page(after.as_deref(), batch_size).await?;
+        let Some(last) = batch.last() else {
+            break;
+        };
+        after = Some(last.key.clone());
+        for entry in &batch {
+            progress.scanned += 1;
+            match recompress(&entry.content, target, max_size) {
+                Ok(Some(rewritten)) => {
+                    mem.store(&entry.key, &rewritten, entry.category.clone())
+                        .await?;
+                    progress.rewritten += 1;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(key = %entry.key, "Skipping memory entry during migration: {e}");
+                    progress.failed += 1;
+                }
+            }
+        }
+        on_batch(&progress);
+        if batch.len() < batch_size {
+            break;
+        }
+    }
+    Ok(progress)
 }
 
 #[cfg(test)]
@@ -102,6 +381,25 @@ mod tests {
         assert!(!is_compressed(""));
     }
 
+    #[test]
+    fn decompressed_len_reads_header_only() {
+        let content = "abc".repeat(1000);
+        let (stored, _) = maybe_compress(&content);
+        assert_eq!(decompressed_len(&stored).unwrap(), content.len());
+        assert_eq!(decompressed_len("plain").unwrap(), 5);
+        assert!(decompressed_len("lz4:ab").is_err());
+    }
+
+    #[test]
+    fn file_bytes_roundtrip() {
+        let body = br#"{"components": {}}"#.repeat(100);
+        let packed = compress_bytes(&body);
+        assert!(packed.len() < body.len());
+        assert_eq!(decompress_bytes(&packed, body.len()).unwrap(), body);
+        assert!(decompress_bytes(&packed, body.len() - 1).is_err());
+        assert!(decompress_bytes(b"{}", DEFAULT_MAX_DECOMPRESSED_SIZE).is_err());
+    }
+
     #[test]
     fn exact_threshold_not_compressed() {
         let content = "a".repeat(COMPRESSION_THRESHOLD);
@@ -109,10 +407,200 @@ mod tests {
         assert!(!compressed);
     }
 
+    #[test]
+    fn oversized_size_prefix_rejected() {
+        // Claims 4GB decompressed with a tiny payload behind it.
+        let mut blob = u32::MAX.to_le_bytes().to_vec();
+        blob.extend_from_slice(&[0x10, b'a']);
+        let stored = format!("{LZ4_PREFIX}{}", hex_encode(&blob));
+
+        let err = maybe_decompress(&stored).unwrap_err();
+        assert!(err.to_string().contains("exceeding limit"));
+    }
+
+    #[test]
+    fn custom_limit_enforced() {
+        let content = "hello world! ".repeat(200);
+        let (stored, _) = maybe_compress(&content);
+
+        assert!(maybe_decompress_with_limit(&stored, content.len() - 1).is_err());
+        assert_eq!(
+            maybe_decompress_with_limit(&stored, content.len()).unwrap(),
+            content
+        );
+    }
+
+    #[test]
+    fn missing_size_header_rejected() {
+        let stored = format!("{LZ4_PREFIX}{}", hex_encode(&[0x01, 0x02]));
+        assert!(maybe_decompress(&stored).is_err());
+    }
+
     #[test]
     fn just_over_threshold_compressed() {
         let content = "a".repeat(COMPRESSION_THRESHOLD + 1);
         let (_, compressed) = maybe_compress(&content);
         assert!(compressed);
     }
+
+    fn varied_text(lines: usize) -> String {
+        use std::fmt::Write as _;
+        (0..lines).fold(String::new(), |mut s, i| {
+            let _ = writeln!(s, "entry {i}: value {} — ünïcode", (i * 7919) % 1000);
+            s
+        })
+    }
+
+    #[test]
+    fn decompress_to_writer_matches_full_decompress() {
+        // Large enough to exercise window compaction.
+        let content = varied_text(20_000);
+        let (stored, compressed) = maybe_compress(&content);
+        assert!(compressed);
+
+        let mut out = Vec::new();
+        let written =
+            decompress_to_writer(&stored, &mut out, DEFAULT_MAX_DECOMPRESSED_SIZE).unwrap();
+        assert_eq!(written, content.len());
+        assert_eq!(String::from_utf8(out).unwrap(), content);
+    }
+
+    #[test]
+    fn decompress_to_writer_enforces_limit() {
+        let content = varied_text(200);
+        let (stored, _) = maybe_compress(&content);
+        let mut out = Vec::new();
+        assert!(decompress_to_writer(&stored, &mut out, content.len() - 1).is_err());
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn preview_returns_leading_chars() {
+        let content = varied_text(5_000);
+        let (stored, _) = maybe_compress(&content);
+        let expected: String = content.chars().take(100).collect();
+        assert_eq!(preview(&stored, 100).unwrap(), expected);
+        assert_eq!(preview("plain text", 5).unwrap(), "plain");
+    }
+
+    #[test]
+    fn preview_longer_than_content_returns_everything() {
+        let content = "hello world! ".repeat(200);
+        let (stored, _) = maybe_compress(&content);
+        assert_eq!(preview(&stored, 100_000).unwrap(), content);
+    }
+
+    #[test]
+    fn recompress_is_idempotent() {
+        let content = varied_text(200);
+        let packed = recompress(&content, Compression::Lz4, DEFAULT_MAX_DECOMPRESSED_SIZE)
+            .unwrap()
+            .unwrap();
+        assert!(is_compressed(&packed));
+        assert!(
+            recompress(&packed, Compression::Lz4, DEFAULT_MAX_DECOMPRESSED_SIZE)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            recompress("short", Compression::Lz4, DEFAULT_MAX_DECOMPRESSED_SIZE)
+                .unwrap()
+                .is_none()
+        );
+
+        assert_eq!(
+            recompress(&packed, Compression::None, DEFAULT_MAX_DECOMPRESSED_SIZE)
+                .unwrap()
+                .as_deref(),
+            Some(content.as_str())
+        );
+        assert!(
+            recompress(&content, Compression::None, DEFAULT_MAX_DECOMPRESSED_SIZE)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn migrate_rewrites_in_batches_and_resumes() {
+        use crate::memory::{MemoryCategory, SqliteMemory};
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mem = SqliteMemory::new(tmp.path()).unwrap();
+        let big = varied_text(200);
+        let (packed, _) = maybe_compress(&big);
+        for key in ["a", "b", "c"] {
+            mem.store(key, &packed, MemoryCategory::Core).await.unwrap();
+        }
+        mem.store("plain", "already plain", MemoryCategory::Daily)
+            .await
+            .unwrap();
+        mem.store("broken", "lz4:zz", MemoryCategory::Daily)
+            .await
+            .unwrap();
+
+        let mut batches = Vec::new();
+        let done = migrate_compression(
+            &mem,
+            Compression::None,
+            2,
+            DEFAULT_MAX_DECOMPRESSED_SIZE,
+            |p| batches.push(*p),
+        )
+        .await
+        .unwrap();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0].scanned, 2);
+        assert_eq!(
+            done,
+            MigrationProgress {
+                total: 5,
+                scanned: 5,
+                rewritten: 3,
+                failed: 1,
+            }
+        );
+        let entry = mem.get("b").await.unwrap().unwrap();
+        assert_eq!(entry.content, big);
+        assert_eq!(entry.category, MemoryCategory::Core);
+        assert_eq!(mem.get("broken").await.unwrap().unwrap().content, "lz4:zz");
+
+        let again = migrate_compression(
+            &mem,
+            Compression::None,
+            2,
+            DEFAULT_MAX_DECOMPRESSED_SIZE,
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(again.rewritten, 0);
+    }
+
+    #[tokio::test]
+    async fn migrate_honours_configured_size_limit() {
+        use crate::memory::{MemoryCategory, SqliteMemory};
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mem = SqliteMemory::new(tmp.path()).unwrap();
+        let big = varied_text(200);
+        let (packed, _) = maybe_compress(&big);
+        mem.store("big", &packed, MemoryCategory::Core)
+            .await
+            .unwrap();
+
+        let done = migrate_compression(&mem, Compression::None, 10, big.len() - 1, |_| {})
+            .await
+            .unwrap();
+        assert_eq!((done.rewritten, done.failed), (0, 1));
+        assert_eq!(mem.get("big").await.unwrap().unwrap().content, packed);
+    }
+
+    #[test]
+    fn streaming_rejects_truncated_payload() {
+        let content = varied_text(200);
+        let (stored, _) = maybe_compress(&content);
+        let truncated = &stored[..stored.len() - 20];
+        assert!(decompress_to_writer(truncated, &mut Vec::new(), usize::MAX).is_err());
+    }
 }
@@ -1,6 +1,8 @@
 use super::traits::{Memory, MemoryCategory, MemoryEntry};
 use async_trait::async_trait;
 use chrono::Local;
+use fs2::FileExt;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 
@@ -38,29 +40,36 @@ impl MarkdownMemory {
         Ok(())
     }
 
+    /// Add `content` as a new paragraph. The file is held under an exclusive
+    /// lock for one append-mode write, so concurrent writers, in this
+    /// process or another, never lose or interleave each other's entries.
     async fn append_to_file(&self, path: &Path, content: &str) -> anyhow::Result<()> {
         self.ensure_dirs().await?;
 
-        let existing = if path.exists() {
-            fs::read_to_string(path).await.unwrap_or_default()
+        let header = if path == self.core_path() {
+            "# Long-Term Memory\n".to_string()
         } else {
-            String::new()
+            let date = Local::now().format("%Y-%m-%d").to_string();
+            format!("# Daily Log — {date}\n")
         };
-
-        let updated = if existing.is_empty() {
-            let header = if path == self.core_path() {
-                "# Long-Term Memory\n\n"
-            } else {
-                let date = Local::now().format("%Y-%m-%d").to_string();
-                &format!("# Daily Log — {date}\n\n")
-            };
-            format!("{header}{content}\n")
-        } else {
-            format!("{existing}\n{content}\n")
-        };
-
-        fs::write(path, updated).await?;
-        Ok(())
+        let path = path.to_path_buf();
+        let entry = format!("\n{content}\n");
+        tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)?;
+            file.lock_exclusive()?;
+            let mut chunk = String::new();
+            if file.metadata()?.len() == 0 {
+                chunk.push_str(&header);
+            }
+            chunk.push_str(&entry);
+            let written = file.write_all(chunk.as_bytes());
+            FileExt::unlock(&file)?;
+            Ok(written?)
+        })
+        .await?
     }
 
     fn parse_entries_from_file(
@@ -91,6 +100,7 @@ impl MarkdownMemory {
                     timestamp: filename.to_string(),
                     session_id: None,
                     score: None,
+                    match_explanation: None,
                 }
             })
             .collect()
@@ -152,13 +162,34 @@ impl Memory for MarkdownMemory {
         self.append_to_file(&path, &entry).await
     }
 
+    /// Entries are never rewritten here, so appending is storing: the new
+    /// entry goes to the end of the day's log (or `MEMORY.md`).
+    async fn append(
+        &self,
+        key: &str,
+        content: &str,
+        category: MemoryCategory,
+    ) -> anyhow::Result<()> {
+        self.store(key, content, category).await
+    }
+
     async fn recall(&self, query: &str, limit: usize) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.recall_in(query, limit, None).await
+    }
+
+    async fn recall_in(
+        &self,
+        query: &str,
+        limit: usize,
+        category: Option<&MemoryCategory>,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
         let all = self.read_all_entries().await?;
         let query_lower = query.to_lowercase();
         let keywords: Vec<&str> = query_lower.split_whitespace().collect();
 
         let mut scored: Vec<MemoryEntry> = all
             .into_iter()
+            .filter(|entry| category.is_none_or(|c| &entry.category == c))
             .filter_map(|mut entry| {
                 let content_lower = entry.content.to_lowercase();
                 let matched = keywords
@@ -261,6 +292,31 @@ mod tests {
         assert!(content.contains("Finished tests"));
     }
 
+    #[tokio::test]
+    async fn concurrent_appends_keep_every_entry_and_one_header() {
+        let tmp = TempDir::new().unwrap();
+        let writes = (0..20).map(|i| {
+            // Separate instances, as separate components would hold.
+            let mem = MarkdownMemory::new(tmp.path());
+            tokio::spawn(async move {
+                mem.append("log", &format!("entry {i}"), MemoryCategory::Daily)
+                    .await
+            })
+        });
+        for write in writes.collect::<Vec<_>>() {
+            write.await.unwrap().unwrap();
+        }
+
+        let mem = MarkdownMemory::new(tmp.path());
+        let content = sync_fs::read_to_string(mem.daily_path()).unwrap();
+        assert!(content.starts_with("# Daily Log"));
+        assert_eq!(content.matches("# Daily Log").count(), 1);
+        for i in 0..20 {
+            assert!(content.contains(&format!("- **log**: entry {i}\n")), "{i}");
+        }
+        assert_eq!(mem.list(None).await.unwrap().len(), 20);
+    }
+
     #[tokio::test]
     async fn markdown_recall_keyword() {
         let (_tmp, mem) = temp_workspace();
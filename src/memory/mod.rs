@@ -3,15 +3,17 @@ pub mod compression;
 pub mod embeddings;
 pub mod hygiene;
 pub mod markdown;
+pub mod quota;
+pub mod ranking;
+pub mod secret_scan;
 pub mod sqlite;
 pub mod traits;
 pub mod vector;
 
+pub use compression::{migrate_compression, Compression};
 pub use markdown::MarkdownMemory;
 pub use sqlite::SqliteMemory;
-pub use traits::Memory;
-#[allow(unused_imports)]
-pub use traits::{MemoryCategory, MemoryEntry};
+pub use traits::{Memory, MemoryCategory, MemoryEntry};
 
 use crate::config::MemoryConfig;
 use std::path::Path;
@@ -28,7 +30,7 @@ pub fn create_memory(
         tracing::warn!("memory hygiene skipped: {e}");
     }
 
-    match config.backend.as_str() {
+    let mem: Box<dyn Memory> = match config.backend.as_str() {
         "sqlite" => {
             let embedder: Arc<dyn embeddings::EmbeddingProvider> =
                 Arc::from(embeddings::create_embedding_provider(
@@ -46,14 +48,34 @@ pub fn create_memory(
                 config.keyword_weight as f32,
                 config.embedding_cache_size,
             )?;
-            Ok(Box::new(mem))
+            Box::new(mem)
         }
-        "markdown" | "none" => Ok(Box::new(MarkdownMemory::new(workspace_dir))),
+        "markdown" | "none" => Box::new(MarkdownMemory::new(workspace_dir)),
         other => {
             tracing::warn!("Unknown memory backend '{other}', falling back to markdown");
-            Ok(Box::new(MarkdownMemory::new(workspace_dir)))
+            Box::new(MarkdownMemory::new(workspace_dir))
         }
+    };
+
+    let mem: Box<dyn Memory> = if config.max_total_bytes == 0 {
+        mem
+    } else {
+        Box::new(quota::BoundedMemory::new(
+            mem,
+            config.max_total_bytes,
+            config.when_full,
+        ))
+    };
+
+    // Scan outermost so the size guard counts what is actually stored.
+    if config.secret_scan == secret_scan::SecretScanMode::Off {
+        return Ok(mem);
     }
+    Ok(Box::new(secret_scan::SecretScanMemory::new(
+        mem,
+        config.secret_scan,
+        api_key.map(str::to_string).into_iter().collect(),
+    )))
 }
 
 #[cfg(test)]
@@ -94,6 +116,23 @@ mod tests {
         assert_eq!(mem.name(), "markdown");
     }
 
+    #[tokio::test]
+    async fn factory_applies_size_limit() {
+        let tmp = TempDir::new().unwrap();
+        let cfg = MemoryConfig {
+            backend: "sqlite".into(),
+            max_total_bytes: 8,
+            ..MemoryConfig::default()
+        };
+        let mem = create_memory(&cfg, tmp.path(), None).unwrap();
+        assert_eq!(mem.name(), "sqlite");
+        let err = mem
+            .store("key", "far too long", MemoryCategory::Core)
+            .await
+            .unwrap_err();
+        assert!(err.is::<quota::MemoryFullError>());
+    }
+
     #[test]
     fn factory_unknown_falls_back_to_markdown() {
         let tmp = TempDir::new().unwrap();
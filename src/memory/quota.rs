@@ -0,0 +1,489 @@
+// Memory size guard — keeps a runaway agent from filling the disk.
+//
+// `BoundedMemory` wraps any backend and tracks the bytes of stored keys and
+// content (not on-disk overhead such as indexes or embeddings). A write that
+// would push the total past `memory.max_total_bytes` is either rejected with
+// `MemoryFullError` or makes room by evicting the oldest entries of the most
+// disposable categories first. Core memories are never evicted.
+
+use super::traits::{
+    CompactionReport, Memory, MemoryCategory, MemoryEntry, MemoryStats, RecallOptions,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Share of the limit at which usage is logged as nearly full.
+const WARN_FRACTION: f64 = 0.9;
+
+/// What `store` does when a write would exceed the limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryFullStrategy {
+    /// Fail the write with a [`MemoryFullError`]
+    #[default]
+    Reject,
+    /// Forget old conversation, then daily, then custom entries to make room
+    Evict,
+}
+
+/// Bytes in use against the configured cap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MemoryUsage {
+    pub used_bytes: u64,
+    pub max_bytes: u64,
+}
+
+/// A write was refused because the store is at its size limit.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Memory full: storing '{key}' needs {needed} bytes but {used} of {max} bytes are in use. \
+     Forget some entries, raise memory.max_total_bytes, or set memory.when_full = \"evict\""
+)]
+pub struct MemoryFullError {
+    pub key: String,
+    pub needed: u64,
+    pub used: u64,
+    pub max: u64,
+}
+
+static USAGE: OnceLock<parking_lot::Mutex<Option<MemoryUsage>>> = OnceLock::new();
+
+/// Latest usage reported by a bounded store in this process, for the health
+/// snapshot. `None` until a bounded store has been written to.
+pub fn current_usage() -> Option<MemoryUsage> {
+    *USAGE.get_or_init(Default::default).lock()
+}
+
+fn publish_usage(usage: MemoryUsage) {
+    *USAGE.get_or_init(Default::default).lock() = Some(usage);
+}
+
+/// Bytes an entry counts against the limit.
+fn entry_size(key: &str, content: &str) -> u64 {
+    (key.len() + content.len()) as u64
+}
+
+/// Lower ranks are evicted first; `None` is never evicted.
+fn eviction_rank(category: &MemoryCategory) -> Option<u8> {
+    match category {
+        MemoryCategory::Conversation => Some(0),
+        MemoryCategory::Daily => Some(1),
+        MemoryCategory::Custom(_) => Some(2),
+        MemoryCategory::Core => None,
+    }
+}
+
+/// Size-capped wrapper around another memory backend.
+pub struct BoundedMemory {
+    inner: Box<dyn Memory>,
+    max_bytes: u64,
+    strategy: MemoryFullStrategy,
+    /// Bytes in use; `None` until first measured. Held across writes so
+    /// concurrent stores can't both squeeze under the limit.
+    used: tokio::sync::Mutex<Option<u64>>,
+}
+
+impl BoundedMemory {
+    pub fn new(inner: Box<dyn Memory>, max_bytes: u64, strategy: MemoryFullStrategy) -> Self {
+        Self {
+            inner,
+            max_bytes,
+            strategy,
+            used: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    async fn measure(&self) -> anyhow::Result<u64> {
+        let entries = self.inner.list(None).await?;
+        Ok(entries.iter().map(|e| entry_size(&e.key, &e.content)).sum())
+    }
+
+    /// Forget entries, most disposable first, until `needed` bytes are freed.
+    /// Returns the bytes actually freed.
+    async fn evict(&self, needed: u64, keep_key: &str) -> anyhow::Result<u64> {
+        let mut candidates: Vec<(u8, MemoryEntry)> = self
+            .inner
+            .list(None)
+            .await?
+            .into_iter()
+            .filter(|e| e.key != keep_key)
+            .filter_map(|e| eviction_rank(&e.category).map(|rank| (rank, e)))
+            .collect();
+        candidates
+            .sort_by(|(ra, a), (rb, b)| ra.cmp(rb).then_with(|| a.timestamp.cmp(&b.timestamp)));
+
+        let mut freed = 0;
+        for (_, entry) in candidates {
+            if freed >= needed {
+                break;
+            }
+            if self.inner.forget(&entry.key).await? {
+                freed += entry_size(&entry.key, &entry.content);
+                tracing::info!(key = %entry.key, category = %entry.category, "Evicted memory to stay under max_total_bytes");
+            }
+        }
+        Ok(freed)
+    }
+
+    fn report(&self, used: u64) {
+        let usage = MemoryUsage {
+            used_bytes: used,
+            max_bytes: self.max_bytes,
+        };
+        #[allow(clippy::cast_precision_loss)]
+        if used as f64 >= self.max_bytes as f64 * WARN_FRACTION {
+            tracing::warn!(
+                used_bytes = used,
+                max_bytes = self.max_bytes,
+                "Memory store is nearly full"
+            );
+        }
+        publish_usage(usage);
+    }
+
+    /// Bytes in use once `incoming` bytes replace `replaced` at `key`,
+    /// evicting first when the strategy allows. Fails with
+    /// [`MemoryFullError`] if they still don't fit.
+    async fn make_room(
+        &self,
+        guard: &mut Option<u64>,
+        key: &str,
+        replaced: u64,
+        incoming: u64,
+    ) -> anyhow::Result<u64> {
+        let mut used = match *guard {
+            Some(used) => used,
+            None => self.measure().await?,
+        };
+        let base = used.saturating_sub(replaced);
+        // Bigger than the whole store: evicting everything still wouldn't fit it.
+        let evictable = self.strategy == MemoryFullStrategy::Evict && incoming <= self.max_bytes;
+        if base + incoming > self.max_bytes {
+            let overflow = base + incoming - self.max_bytes;
+            if evictable {
+                let freed = self.evict(overflow, key).await?;
+                used = used.saturating_sub(freed);
+                *guard = Some(used);
+            }
+            let base = used.saturating_sub(replaced);
+            if base + incoming > self.max_bytes {
+                self.report(used);
+                return Err(MemoryFullError {
+                    key: key.to_string(),
+                    needed: incoming,
+                    used,
+                    max: self.max_bytes,
+                }
+                .into());
+            }
+        }
+        Ok(used)
+    }
+
+    pub async fn usage(&self) -> anyhow::Result<MemoryUsage> {
+        let mut guard = self.used.lock().await;
+        let used = match *guard {
+            Some(used) => used,
+            None => self.measure().await?,
+        };
+        *guard = Some(used);
+        Ok(MemoryUsage {
+            used_bytes: used,
+            max_bytes: self.max_bytes,
+        })
+    }
+}
+
+#[async_trait]
+impl Memory for BoundedMemory {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn store(
+        &self,
+        key: &str,
+        content: &str,
+        category: MemoryCategory,
+    ) -> anyhow::Result<()> {
+        let mut guard = self.used.lock().await;
+        let replaced = self
+            .inner
+            .get(key)
+            .await?
+            .map_or(0, |e| entry_size(&e.key, &e.content));
+        let incoming = entry_size(key, content);
+        let used = self.make_room(&mut guard, key, replaced, incoming).await?;
+
+        self.inner.store(key, content, category).await?;
+        let used = used.saturating_sub(replaced) + incoming;
+        *guard = Some(used);
+        self.report(used);
+        Ok(())
+    }
+
+    /// Grows the entry by the appended text and its separating newline. The
+    /// quota lock is held throughout, so concurrent appends are counted
+    /// exactly; atomicity itself comes from the inner backend.
+    async fn append(
+        &self,
+        key: &str,
+        content: &str,
+        category: MemoryCategory,
+    ) -> anyhow::Result<()> {
+        let mut guard = self.used.lock().await;
+        let (replaced, incoming) = match self.inner.get(key).await? {
+            Some(entry) => {
+                let size = entry_size(&entry.key, &entry.content);
+                (size, size + (content.len() + 1) as u64)
+            }
+            None => (0, entry_size(key, content)),
+        };
+        let used = self.make_room(&mut guard, key, replaced, incoming).await?;
+
+        self.inner.append(key, content, category).await?;
+        let used = used.saturating_sub(replaced) + incoming;
+        *guard = Some(used);
+        self.report(used);
+        Ok(())
+    }
+
+    async fn recall(&self, query: &str, limit: usize) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.inner.recall(query, limit).await
+    }
+
+    async fn recall_in(
+        &self,
+        query: &str,
+        limit: usize,
+        category: Option<&MemoryCategory>,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.inner.recall_in(query, limit, category).await
+    }
+
+    async fn recall_with(
+        &self,
+        query: &str,
+        options: &RecallOptions,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.inner.recall_with(query, options).await
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Option<MemoryEntry>> {
+        self.inner.get(key).await
+    }
+
+    async fn list(&self, category: Option<&MemoryCategory>) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.inner.list(category).await
+    }
+
+    async fn list_page(
+        &self,
+        after: Option<&str>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.inner.list_page(after, limit).await
+    }
+
+    async fn forget(&self, key: &str) -> anyhow::Result<bool> {
+        let mut guard = self.used.lock().await;
+        let size = self
+            .inner
+            .get(key)
+            .await?
+            .map_or(0, |e| entry_size(&e.key, &e.content));
+        let forgotten = self.inner.forget(key).await?;
+        if forgotten {
+            if let Some(used) = guard.as_mut() {
+                *used = used.saturating_sub(size);
+                let used = *used;
+                self.report(used);
+            }
+        }
+        Ok(forgotten)
+    }
+
+    async fn count(&self) -> anyhow::Result<usize> {
+        self.inner.count().await
+    }
+
+    async fn health_check(&self) -> bool {
+        self.inner.health_check().await
+    }
+
+    async fn compact(&self) -> anyhow::Result<CompactionReport> {
+        let mut guard = self.used.lock().await;
+        let report = self.inner.compact().await;
+        // Compaction may merge or drop entries; measure again on the next write.
+        *guard = None;
+        report
+    }
+
+    async fn stats(&self) -> anyhow::Result<MemoryStats> {
+        self.inner.stats().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::SqliteMemory;
+    use tempfile::TempDir;
+
+    fn bounded(max_bytes: u64, strategy: MemoryFullStrategy) -> (TempDir, BoundedMemory) {
+        let tmp = TempDir::new().unwrap();
+        let inner = SqliteMemory::new(tmp.path()).unwrap();
+        (
+            tmp,
+            BoundedMemory::new(Box::new(inner), max_bytes, strategy),
+        )
+    }
+
+    #[tokio::test]
+    async fn append_counts_only_the_growth() {
+        let (_tmp, mem) = bounded(20, MemoryFullStrategy::Reject);
+        mem.append("log", "abcdef", MemoryCategory::Daily)
+            .await
+            .unwrap();
+        mem.append("log", "ghijk", MemoryCategory::Daily)
+            .await
+            .unwrap();
+        // "log" + "abcdef\nghijk" = 15 bytes
+        assert_eq!(mem.usage().await.unwrap().used_bytes, 15);
+
+        let err = mem
+            .append("log", "0123456789", MemoryCategory::Daily)
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<MemoryFullError>().is_some());
+        assert_eq!(
+            mem.get("log").await.unwrap().unwrap().content,
+            "abcdef\nghijk"
+        );
+    }
+
+    #[tokio::test]
+    async fn reject_strategy_refuses_overflowing_write() {
+        let (_tmp, mem) = bounded(20, MemoryFullStrategy::Reject);
+        mem.store("a", "0123456789", MemoryCategory::Core)
+            .await
+            .unwrap();
+
+        let err = mem
+            .store("b", "0123456789", MemoryCategory::Core)
+            .await
+            .unwrap_err();
+        let full = err.downcast_ref::<MemoryFullError>().unwrap();
+        assert_eq!((full.needed, full.used, full.max), (11, 11, 20));
+        assert!(err.to_string().contains("memory.max_total_bytes"));
+        assert!(mem.get("b").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn evict_strategy_rejects_an_entry_larger_than_the_store() {
+        let (_tmp, mem) = bounded(20, MemoryFullStrategy::Evict);
+        mem.store("old", "0123456789", MemoryCategory::Conversation)
+            .await
+            .unwrap();
+
+        let err = mem
+            .store("huge", &"x".repeat(30), MemoryCategory::Conversation)
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<MemoryFullError>().is_some());
+        // Nothing was evicted for a write that could never fit.
+        assert!(mem.get("old").await.unwrap().is_some());
+
+        let err = mem
+            .append("old", "0123456789", MemoryCategory::Conversation)
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<MemoryFullError>().is_some());
+        assert_eq!(mem.get("old").await.unwrap().unwrap().content, "0123456789");
+    }
+
+    #[tokio::test]
+    async fn compact_remeasures_usage() {
+        let (_tmp, mem) = bounded(100, MemoryFullStrategy::Reject);
+        mem.store("a", "0123456789", MemoryCategory::Core)
+            .await
+            .unwrap();
+        assert_eq!(mem.usage().await.unwrap().used_bytes, 11);
+        mem.compact().await.unwrap();
+        assert!(mem.used.lock().await.is_none());
+        assert_eq!(mem.usage().await.unwrap().used_bytes, 11);
+    }
+
+    #[tokio::test]
+    async fn overwriting_a_key_counts_only_the_difference() {
+        let (_tmp, mem) = bounded(12, MemoryFullStrategy::Reject);
+        mem.store("a", "0123456789", MemoryCategory::Core)
+            .await
+            .unwrap();
+        mem.store("a", "9876543210", MemoryCategory::Core)
+            .await
+            .unwrap();
+        assert_eq!(mem.usage().await.unwrap().used_bytes, 11);
+    }
+
+    #[tokio::test]
+    async fn evict_strategy_drops_disposable_entries_first() {
+        let (_tmp, mem) = bounded(40, MemoryFullStrategy::Evict);
+        mem.store("core", "keep me!", MemoryCategory::Core)
+            .await
+            .unwrap();
+        mem.store("daily", "day note", MemoryCategory::Daily)
+            .await
+            .unwrap();
+        mem.store("chat", "hi there", MemoryCategory::Conversation)
+            .await
+            .unwrap();
+
+        mem.store("new", "fresh", MemoryCategory::Core)
+            .await
+            .unwrap();
+        assert!(mem.get("chat").await.unwrap().is_none());
+        assert!(mem.get("daily").await.unwrap().is_some());
+        assert!(mem.get("core").await.unwrap().is_some());
+        assert!(mem.usage().await.unwrap().used_bytes <= 40);
+    }
+
+    #[tokio::test]
+    async fn evict_never_touches_core() {
+        let (_tmp, mem) = bounded(15, MemoryFullStrategy::Evict);
+        mem.store("core", "keep me!", MemoryCategory::Core)
+            .await
+            .unwrap();
+        let err = mem
+            .store("more", "too big", MemoryCategory::Core)
+            .await
+            .unwrap_err();
+        assert!(err.is::<MemoryFullError>());
+        assert!(mem.get("core").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn forget_frees_space_and_usage_is_published() {
+        let (_tmp, mem) = bounded(100, MemoryFullStrategy::Reject);
+        mem.store("a", "0123456789", MemoryCategory::Core)
+            .await
+            .unwrap();
+        assert!(mem.forget("a").await.unwrap());
+        assert_eq!(mem.usage().await.unwrap().used_bytes, 0);
+        assert!(current_usage().is_some());
+    }
+
+    #[tokio::test]
+    async fn existing_entries_are_measured_on_first_use() {
+        let tmp = TempDir::new().unwrap();
+        let inner = SqliteMemory::new(tmp.path()).unwrap();
+        inner
+            .store("old", "0123456789", MemoryCategory::Core)
+            .await
+            .unwrap();
+
+        let mem = BoundedMemory::new(Box::new(inner), 100, MemoryFullStrategy::Reject);
+        assert_eq!(mem.usage().await.unwrap().used_bytes, 13);
+    }
+}
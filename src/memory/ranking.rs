@@ -0,0 +1,212 @@
+// Recall ranking — blend normalized relevance with recency.
+//
+// Backends score relevance on different scales (BM25, cosine, keyword ratio),
+// so both signals are min-max normalized over the candidate set before mixing.
+
+use super::traits::{MatchExplanation, MemoryEntry};
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Re-score `entries` as `(1 - w) * relevance + w * recency` and sort best-first.
+/// `recency_weight` is clamped to 0.0–1.0; 0.0 keeps the relevance order.
+pub fn blend_recency(entries: &mut [MemoryEntry], recency_weight: f64) {
+    blend(entries, recency_weight, None);
+}
+
+/// Like [`blend_recency`], also recording on each entry which terms of
+/// `query` matched and how much each signal contributed.
+pub fn blend_recency_explained(entries: &mut [MemoryEntry], recency_weight: f64, query: &str) {
+    blend(entries, recency_weight, Some(query));
+}
+
+fn blend(entries: &mut [MemoryEntry], recency_weight: f64, explain_query: Option<&str>) {
+    if entries.is_empty() {
+        return;
+    }
+    let weight = if recency_weight.is_finite() {
+        recency_weight.clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    let relevance: Vec<Option<f64>> = entries.iter().map(|e| e.score).collect();
+    #[allow(clippy::cast_precision_loss)]
+    let recency: Vec<Option<f64>> = entries
+        .iter()
+        .map(|e| parse_timestamp(&e.timestamp).map(|t| t as f64))
+        .collect();
+
+    let normalized_relevance = normalize(&relevance);
+    let normalized_recency = normalize(&recency);
+    let terms = explain_query.map(query_terms);
+
+    for (i, entry) in entries.iter_mut().enumerate() {
+        let relevance_part = (1.0 - weight) * normalized_relevance[i];
+        let recency_part = weight * normalized_recency[i];
+        entry.score = Some(relevance_part + recency_part);
+        if let Some(terms) = &terms {
+            entry.match_explanation = Some(MatchExplanation {
+                matched_terms: matched_terms(terms, entry),
+                raw_relevance: relevance[i],
+                relevance_contribution: relevance_part,
+                recency_contribution: recency_part,
+            });
+        }
+    }
+
+    entries.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Lowercased alphanumeric words of `query`, deduplicated in order.
+fn query_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for word in query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        let word = word.to_lowercase();
+        if !terms.contains(&word) {
+            terms.push(word);
+        }
+    }
+    terms
+}
+
+fn matched_terms(terms: &[String], entry: &MemoryEntry) -> Vec<String> {
+    let haystack = format!("{} {}", entry.key, entry.content).to_lowercase();
+    terms
+        .iter()
+        .filter(|term| haystack.contains(term.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Unix seconds for RFC 3339 timestamps or `YYYY-MM-DD`-prefixed names.
+fn parse_timestamp(raw: &str) -> Option<i64> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(raw) {
+        return Some(dt.with_timezone(&Utc).timestamp());
+    }
+    let date = NaiveDate::parse_from_str(raw.get(..10)?, "%Y-%m-%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc().timestamp())
+}
+
+/// Min-max normalize to 0.0–1.0. Unknown values map to 0.0 and a flat set
+/// maps to 1.0 so it doesn't penalize anything.
+fn normalize(values: &[Option<f64>]) -> Vec<f64> {
+    let (min, max) = values
+        .iter()
+        .flatten()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| {
+            (lo.min(v), hi.max(v))
+        });
+
+    values
+        .iter()
+        .map(|v| match v {
+            None => 0.0,
+            Some(_) if (max - min).abs() < f64::EPSILON => 1.0,
+            Some(v) => (v - min) / (max - min),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryCategory;
+
+    fn entry(key: &str, timestamp: &str, score: f64) -> MemoryEntry {
+        MemoryEntry {
+            id: key.into(),
+            key: key.into(),
+            content: format!("notes about {key}"),
+            category: MemoryCategory::Core,
+            timestamp: timestamp.into(),
+            session_id: None,
+            score: Some(score),
+            match_explanation: None,
+        }
+    }
+
+    #[test]
+    fn zero_weight_keeps_relevance_order() {
+        let mut entries = vec![
+            entry("old_relevant", "2024-01-01T00:00:00+00:00", 10.0),
+            entry("new_weak", "2025-01-01T00:00:00+00:00", 1.0),
+        ];
+        blend_recency(&mut entries, 0.0);
+        assert_eq!(entries[0].key, "old_relevant");
+        assert!((entries[0].score.unwrap() - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn full_weight_prefers_newest() {
+        let mut entries = vec![
+            entry("old_relevant", "2024-01-01T00:00:00+00:00", 10.0),
+            entry("new_weak", "2025-01-01T00:00:00+00:00", 1.0),
+        ];
+        blend_recency(&mut entries, 1.0);
+        assert_eq!(entries[0].key, "new_weak");
+    }
+
+    #[test]
+    fn date_named_entries_parse() {
+        assert!(parse_timestamp("2025-03-04").is_some());
+        assert!(parse_timestamp("2025-03-04_session.md").is_some());
+        assert!(parse_timestamp("MEMORY").is_none());
+    }
+
+    #[test]
+    fn unparseable_timestamp_ranks_as_oldest() {
+        let mut entries = vec![
+            entry("unknown", "MEMORY", 1.0),
+            entry("dated", "2020-01-01", 1.0),
+        ];
+        blend_recency(&mut entries, 1.0);
+        assert_eq!(entries[0].key, "dated");
+    }
+
+    #[test]
+    fn weight_is_clamped() {
+        let mut entries = vec![entry("a", "2024-01-01", 1.0)];
+        blend_recency(&mut entries, 5.0);
+        assert!(entries[0].score.unwrap() <= 1.0);
+        blend_recency(&mut entries, f64::NAN);
+        assert!(entries[0].score.unwrap().is_finite());
+    }
+
+    #[test]
+    fn plain_blend_leaves_no_explanation() {
+        let mut entries = vec![entry("a", "2024-01-01", 1.0)];
+        blend_recency(&mut entries, 0.5);
+        assert!(entries[0].match_explanation.is_none());
+    }
+
+    #[test]
+    fn explanation_breaks_down_the_score() {
+        let mut entries = vec![
+            entry("rust_tips", "2024-01-01T00:00:00+00:00", 8.0),
+            entry("python_tips", "2025-01-01T00:00:00+00:00", 2.0),
+        ];
+        blend_recency_explained(&mut entries, 0.25, "Rust notes, rust!");
+
+        for e in &entries {
+            let why = e.match_explanation.as_ref().unwrap();
+            let total = why.relevance_contribution + why.recency_contribution;
+            assert!((e.score.unwrap() - total).abs() < 1e-9);
+        }
+        let top = entries[0].match_explanation.as_ref().unwrap();
+        assert_eq!(entries[0].key, "rust_tips");
+        assert_eq!(top.matched_terms, vec!["rust", "notes"]);
+        assert_eq!(top.raw_relevance, Some(8.0));
+        assert!((top.relevance_contribution - 0.75).abs() < 1e-9);
+        assert!(top.recency_contribution.abs() < 1e-9);
+
+        let other = entries[1].match_explanation.as_ref().unwrap();
+        assert_eq!(other.matched_terms, vec!["notes"]);
+        assert!((other.recency_contribution - 0.25).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,230 @@
+// Pre-store secret scan — keeps credentials out of the memory store.
+//
+// An agent that saves command output or logs can sweep API keys into memory,
+// where every later recall would leak them. `SecretScanMemory` checks content
+// before it reaches the backend and either redacts what looks like a
+// credential or refuses the write. Keys are stored as given.
+
+use super::traits::{
+    CompactionReport, Memory, MemoryCategory, MemoryEntry, MemoryStats, RecallOptions,
+};
+use crate::security::redact::{contains_secret, redact};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// What to do with content that looks like it holds a credential.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretScanMode {
+    /// Store content as given
+    #[default]
+    Off,
+    /// Replace each detected secret with `[REDACTED]` and store the rest
+    Redact,
+    /// Refuse the write
+    Reject,
+}
+
+/// Memory wrapper that scans content for secrets before storing it.
+pub struct SecretScanMemory {
+    inner: Box<dyn Memory>,
+    mode: SecretScanMode,
+    /// Exact values to catch even when they match no known format.
+    known: Vec<String>,
+}
+
+impl SecretScanMemory {
+    pub fn new(inner: Box<dyn Memory>, mode: SecretScanMode, known: Vec<String>) -> Self {
+        Self { inner, mode, known }
+    }
+
+    /// `content` as it may be written under `key`: unchanged, redacted, or
+    /// refused, depending on the mode.
+    fn screen<'a>(&self, key: &str, content: &'a str) -> anyhow::Result<Cow<'a, str>> {
+        match self.mode {
+            SecretScanMode::Off => Ok(Cow::Borrowed(content)),
+            SecretScanMode::Redact => {
+                let scrubbed = redact(content, &self.known);
+                if scrubbed == content {
+                    return Ok(Cow::Borrowed(content));
+                }
+                tracing::warn!(
+                    key,
+                    "Redacted what looked like a secret before storing memory"
+                );
+                Ok(Cow::Owned(scrubbed))
+            }
+            SecretScanMode::Reject => {
+                if contains_secret(content, &self.known) {
+                    anyhow::bail!(
+                        "Refusing to store memory '{key}': the content looks like it contains \
+                         a credential. Remove it, or set memory.secret_scan = \"redact\""
+                    );
+                }
+                Ok(Cow::Borrowed(content))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Memory for SecretScanMemory {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn store(
+        &self,
+        key: &str,
+        content: &str,
+        category: MemoryCategory,
+    ) -> anyhow::Result<()> {
+        let content = self.screen(key, content)?;
+        self.inner.store(key, &content, category).await
+    }
+
+    async fn append(
+        &self,
+        key: &str,
+        content: &str,
+        category: MemoryCategory,
+    ) -> anyhow::Result<()> {
+        let content = self.screen(key, content)?;
+        self.inner.append(key, &content, category).await
+    }
+
+    async fn recall(&self, query: &str, limit: usize) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.inner.recall(query, limit).await
+    }
+
+    async fn recall_in(
+        &self,
+        query: &str,
+        limit: usize,
+        category: Option<&MemoryCategory>,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.inner.recall_in(query, limit, category).await
+    }
+
+    async fn recall_with(
+        &self,
+        query: &str,
+        options: &RecallOptions,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.inner.recall_with(query, options).await
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Option<MemoryEntry>> {
+        self.inner.get(key).await
+    }
+
+    async fn list(&self, category: Option<&MemoryCategory>) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.inner.list(category).await
+    }
+
+    async fn list_page(
+        &self,
+        after: Option<&str>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.inner.list_page(after, limit).await
+    }
+
+    async fn forget(&self, key: &str) -> anyhow::Result<bool> {
+        self.inner.forget(key).await
+    }
+
+    async fn count(&self) -> anyhow::Result<usize> {
+        self.inner.count().await
+    }
+
+    async fn health_check(&self) -> bool {
+        self.inner.health_check().await
+    }
+
+    async fn compact(&self) -> anyhow::Result<CompactionReport> {
+        self.inner.compact().await
+    }
+
+    async fn stats(&self) -> anyhow::Result<MemoryStats> {
+        self.inner.stats().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::SqliteMemory;
+    use tempfile::TempDir;
+
+    const OUTPUT: &str = "export OPENAI_API_KEY=sk-proj-abcdefghijklmnopqrst # done";
+
+    fn scanned(mode: SecretScanMode) -> (TempDir, SecretScanMemory) {
+        let tmp = TempDir::new().unwrap();
+        let inner = SqliteMemory::new(tmp.path()).unwrap();
+        let mem = SecretScanMemory::new(Box::new(inner), mode, vec!["my-private-key".into()]);
+        (tmp, mem)
+    }
+
+    #[tokio::test]
+    async fn appends_are_screened_too() {
+        let (_tmp, mem) = scanned(SecretScanMode::Redact);
+        mem.append("log", "first", MemoryCategory::Daily)
+            .await
+            .unwrap();
+        mem.append("log", OUTPUT, MemoryCategory::Daily)
+            .await
+            .unwrap();
+        let log = mem.get("log").await.unwrap().unwrap();
+        assert!(log.content.starts_with("first\n"));
+        assert!(!log.content.contains("sk-proj-"));
+
+        let (_tmp, strict) = scanned(SecretScanMode::Reject);
+        assert!(strict
+            .append("log", OUTPUT, MemoryCategory::Daily)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn redact_mode_scrubs_before_storing() {
+        let (_tmp, mem) = scanned(SecretScanMode::Redact);
+        mem.store("env", OUTPUT, MemoryCategory::Daily)
+            .await
+            .unwrap();
+        mem.store("cfg", "key is my-private-key", MemoryCategory::Daily)
+            .await
+            .unwrap();
+
+        let env = mem.get("env").await.unwrap().unwrap();
+        assert_eq!(env.content, "export OPENAI_API_KEY=[REDACTED] # done");
+        let cfg = mem.get("cfg").await.unwrap().unwrap();
+        assert_eq!(cfg.content, "key is [REDACTED]");
+    }
+
+    #[tokio::test]
+    async fn reject_mode_refuses_secrets_but_stores_clean_content() {
+        let (_tmp, mem) = scanned(SecretScanMode::Reject);
+        let err = mem
+            .store("env", OUTPUT, MemoryCategory::Daily)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("memory.secret_scan"));
+        assert!(mem.get("env").await.unwrap().is_none());
+
+        mem.store("note", "prefers tabs", MemoryCategory::Core)
+            .await
+            .unwrap();
+        assert!(mem.get("note").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn off_mode_stores_verbatim() {
+        let (_tmp, mem) = scanned(SecretScanMode::Off);
+        mem.store("env", OUTPUT, MemoryCategory::Daily)
+            .await
+            .unwrap();
+        assert_eq!(mem.get("env").await.unwrap().unwrap().content, OUTPUT);
+    }
+}
@@ -1,5 +1,5 @@
 use super::embeddings::EmbeddingProvider;
-use super::traits::{Memory, MemoryCategory, MemoryEntry};
+use super::traits::{CompactionReport, Memory, MemoryCategory, MemoryEntry, MemoryStats};
 use super::vector;
 use async_trait::async_trait;
 use chrono::Local;
@@ -50,8 +50,24 @@ impl SqliteMemory {
             std::fs::create_dir_all(parent)?;
         }
 
-        let conn = Connection::open(&db_path)?;
-        Self::init_schema(&conn)?;
+        let conn = match Self::open(&db_path) {
+            Ok(conn) => conn,
+            Err(e) if is_corruption(&e) => {
+                // A crash mid-write or a hand-edited file: keep the damaged
+                // copy for inspection and start with an empty brain.
+                crate::util::recover_corrupt(&db_path, "memory database", &e.to_string());
+                for sidecar in ["-wal", "-shm"] {
+                    let mut name = db_path.as_os_str().to_owned();
+                    name.push(sidecar);
+                    let path = PathBuf::from(name);
+                    if path.exists() {
+                        let _ = crate::util::quarantine_corrupt(&path);
+                    }
+                }
+                Self::open(&db_path)?
+            }
+            Err(e) => return Err(e),
+        };
 
         Ok(Self {
             conn: Mutex::new(conn),
@@ -63,6 +79,12 @@ impl SqliteMemory {
         })
     }
 
+    fn open(db_path: &Path) -> anyhow::Result<Connection> {
+        let conn = Connection::open(db_path)?;
+        Self::init_schema(&conn)?;
+        Ok(conn)
+    }
+
     /// Initialize all tables: memories, FTS5, `embedding_cache`
     fn init_schema(conn: &Connection) -> anyhow::Result<()> {
         conn.execute_batch(
@@ -113,21 +135,12 @@ impl SqliteMemory {
     }
 
     fn category_to_str(cat: &MemoryCategory) -> String {
-        match cat {
-            MemoryCategory::Core => "core".into(),
-            MemoryCategory::Daily => "daily".into(),
-            MemoryCategory::Conversation => "conversation".into(),
-            MemoryCategory::Custom(name) => name.clone(),
-        }
+        cat.to_string()
     }
 
     fn str_to_category(s: &str) -> MemoryCategory {
-        match s {
-            "core" => MemoryCategory::Core,
-            "daily" => MemoryCategory::Daily,
-            "conversation" => MemoryCategory::Conversation,
-            other => MemoryCategory::Custom(other.to_string()),
-        }
+        let Ok(category) = s.parse();
+        category
     }
 
     /// Simple content hash for embedding cache
@@ -196,11 +209,12 @@ impl SqliteMemory {
         Ok(Some(embedding))
     }
 
-    /// FTS5 BM25 keyword search
+    /// FTS5 BM25 keyword search, optionally restricted to one category
     fn fts5_search(
         conn: &Connection,
         query: &str,
         limit: usize,
+        category: Option<&str>,
     ) -> anyhow::Result<Vec<(String, f32)>> {
         // Escape FTS5 special chars and build query
         let fts_query: String = query
@@ -217,6 +231,7 @@ impl SqliteMemory {
                    FROM memories_fts f
                    JOIN memories m ON m.rowid = f.rowid
                    WHERE memories_fts MATCH ?1
+                     AND (?3 IS NULL OR m.category = ?3)
                    ORDER BY score
                    LIMIT ?2";
 
@@ -224,7 +239,7 @@ impl SqliteMemory {
         #[allow(clippy::cast_possible_wrap)]
         let limit_i64 = limit as i64;
 
-        let rows = stmt.query_map(params![fts_query, limit_i64], |row| {
+        let rows = stmt.query_map(params![fts_query, limit_i64, category], |row| {
             let id: String = row.get(0)?;
             let score: f64 = row.get(1)?;
             // BM25 returns negative scores (lower = better), negate for ranking
@@ -244,11 +259,14 @@ impl SqliteMemory {
         conn: &Connection,
         query_embedding: &[f32],
         limit: usize,
+        category: Option<&str>,
     ) -> anyhow::Result<Vec<(String, f32)>> {
-        let mut stmt =
-            conn.prepare("SELECT id, embedding FROM memories WHERE embedding IS NOT NULL")?;
+        let mut stmt = conn.prepare(
+            "SELECT id, embedding FROM memories
+             WHERE embedding IS NOT NULL AND (?1 IS NULL OR category = ?1)",
+        )?;
 
-        let rows = stmt.query_map([], |row| {
+        let rows = stmt.query_map(params![category], |row| {
             let id: String = row.get(0)?;
             let blob: Vec<u8> = row.get(1)?;
             Ok((id, blob))
@@ -269,6 +287,61 @@ impl SqliteMemory {
         Ok(scored)
     }
 
+    /// Substring fallback when neither FTS5 nor vectors match
+    fn like_search(
+        conn: &Connection,
+        query: &str,
+        limit: usize,
+        category: Option<&str>,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        let keywords: Vec<String> = query.split_whitespace().map(|w| format!("%{w}%")).collect();
+        let mut results = Vec::new();
+        if !keywords.is_empty() {
+            let conditions: Vec<String> = keywords
+                .iter()
+                .enumerate()
+                .map(|(i, _)| format!("(content LIKE ?{} OR key LIKE ?{})", i * 2 + 1, i * 2 + 2))
+                .collect();
+            let where_clause = conditions.join(" OR ");
+            let category_param = keywords.len() * 2 + 2;
+            let sql = format!(
+                "SELECT id, key, content, category, created_at FROM memories
+                 WHERE ({where_clause})
+                   AND (?{category_param} IS NULL OR category = ?{category_param})
+                 ORDER BY updated_at DESC
+                 LIMIT ?{}",
+                keywords.len() * 2 + 1
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+            for kw in &keywords {
+                param_values.push(Box::new(kw.clone()));
+                param_values.push(Box::new(kw.clone()));
+            }
+            #[allow(clippy::cast_possible_wrap)]
+            param_values.push(Box::new(limit as i64));
+            param_values.push(Box::new(category.map(str::to_string)));
+            let params_ref: Vec<&dyn rusqlite::types::ToSql> =
+                param_values.iter().map(AsRef::as_ref).collect();
+            let rows = stmt.query_map(params_ref.as_slice(), |row| {
+                Ok(MemoryEntry {
+                    id: row.get(0)?,
+                    key: row.get(1)?,
+                    content: row.get(2)?,
+                    category: Self::str_to_category(&row.get::<_, String>(3)?),
+                    timestamp: row.get(4)?,
+                    session_id: None,
+                    score: Some(1.0),
+                    match_explanation: None,
+                })
+            })?;
+            for row in rows {
+                results.push(row?);
+            }
+        }
+        Ok(results)
+    }
+
     /// Safe reindex: rebuild FTS5 + embeddings with rollback on failure
     #[allow(dead_code)]
     pub async fn reindex(&self) -> anyhow::Result<usize> {
@@ -349,10 +422,53 @@ impl Memory for SqliteMemory {
         Ok(())
     }
 
+    /// One upsert statement, so concurrent appends serialize inside `SQLite`
+    /// even across connections. Only the appended chunk is embedded, before
+    /// the write as in [`store`](Self::store), so appending stays cheap as the
+    /// entry grows and a failed embedding leaves nothing half-written. The
+    /// entry's vector tracks its latest chunk; keyword search covers it all.
+    async fn append(
+        &self,
+        key: &str,
+        content: &str,
+        category: MemoryCategory,
+    ) -> anyhow::Result<()> {
+        let embedding_bytes = self
+            .get_or_compute_embedding(content)
+            .await?
+            .map(|emb| vector::vec_to_bytes(&emb));
+
+        let conn = self.conn.lock();
+        let now = Local::now().to_rfc3339();
+        let cat = Self::category_to_str(&category);
+        let id = Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO memories (id, key, content, category, embedding, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+             ON CONFLICT(key) DO UPDATE SET
+                content = memories.content || char(10) || excluded.content,
+                embedding = excluded.embedding,
+                updated_at = excluded.updated_at",
+            params![id, key, content, cat, embedding_bytes, now],
+        )?;
+        Ok(())
+    }
+
     async fn recall(&self, query: &str, limit: usize) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.recall_in(query, limit, None).await
+    }
+
+    async fn recall_in(
+        &self,
+        query: &str,
+        limit: usize,
+        category: Option<&MemoryCategory>,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
         if query.trim().is_empty() {
             return Ok(Vec::new());
         }
+        let category = category.map(Self::category_to_str);
+        let category = category.as_deref();
 
         // Compute query embedding (async, before lock)
         let query_embedding = self.get_or_compute_embedding(query).await?;
@@ -360,11 +476,12 @@ impl Memory for SqliteMemory {
         let conn = self.conn.lock();
 
         // FTS5 BM25 keyword search
-        let keyword_results = Self::fts5_search(&conn, query, limit * 2).unwrap_or_default();
+        let keyword_results =
+            Self::fts5_search(&conn, query, limit * 2, category).unwrap_or_default();
 
         // Vector similarity search (if embeddings available)
         let vector_results = if let Some(ref qe) = query_embedding {
-            Self::vector_search(&conn, qe, limit * 2).unwrap_or_default()
+            Self::vector_search(&conn, qe, limit * 2, category).unwrap_or_default()
         } else {
             Vec::new()
         };
@@ -406,6 +523,7 @@ impl Memory for SqliteMemory {
                     timestamp: row.get(4)?,
                     session_id: None,
                     score: Some(f64::from(scored.final_score)),
+                    match_explanation: None,
                 })
             }) {
                 results.push(entry);
@@ -414,49 +532,7 @@ impl Memory for SqliteMemory {
 
         // If hybrid returned nothing, fall back to LIKE search
         if results.is_empty() {
-            let keywords: Vec<String> =
-                query.split_whitespace().map(|w| format!("%{w}%")).collect();
-            if !keywords.is_empty() {
-                let conditions: Vec<String> = keywords
-                    .iter()
-                    .enumerate()
-                    .map(|(i, _)| {
-                        format!("(content LIKE ?{} OR key LIKE ?{})", i * 2 + 1, i * 2 + 2)
-                    })
-                    .collect();
-                let where_clause = conditions.join(" OR ");
-                let sql = format!(
-                    "SELECT id, key, content, category, created_at FROM memories
-                     WHERE {where_clause}
-                     ORDER BY updated_at DESC
-                     LIMIT ?{}",
-                    keywords.len() * 2 + 1
-                );
-                let mut stmt = conn.prepare(&sql)?;
-                let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
-                for kw in &keywords {
-                    param_values.push(Box::new(kw.clone()));
-                    param_values.push(Box::new(kw.clone()));
-                }
-                #[allow(clippy::cast_possible_wrap)]
-                param_values.push(Box::new(limit as i64));
-                let params_ref: Vec<&dyn rusqlite::types::ToSql> =
-                    param_values.iter().map(AsRef::as_ref).collect();
-                let rows = stmt.query_map(params_ref.as_slice(), |row| {
-                    Ok(MemoryEntry {
-                        id: row.get(0)?,
-                        key: row.get(1)?,
-                        content: row.get(2)?,
-                        category: Self::str_to_category(&row.get::<_, String>(3)?),
-                        timestamp: row.get(4)?,
-                        session_id: None,
-                        score: Some(1.0),
-                    })
-                })?;
-                for row in rows {
-                    results.push(row?);
-                }
-            }
+            results = Self::like_search(&conn, query, limit, category)?;
         }
 
         results.truncate(limit);
@@ -479,6 +555,7 @@ impl Memory for SqliteMemory {
                 timestamp: row.get(4)?,
                 session_id: None,
                 score: None,
+                match_explanation: None,
             })
         })?;
 
@@ -502,6 +579,7 @@ impl Memory for SqliteMemory {
                 timestamp: row.get(4)?,
                 session_id: None,
                 score: None,
+                match_explanation: None,
             })
         };
 
@@ -529,6 +607,34 @@ impl Memory for SqliteMemory {
         Ok(results)
     }
 
+    async fn list_page(
+        &self,
+        after: Option<&str>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT id, key, content, category, created_at FROM memories
+             WHERE ?1 IS NULL OR key > ?1
+             ORDER BY key
+             LIMIT ?2",
+        )?;
+        #[allow(clippy::cast_possible_wrap)]
+        let rows = stmt.query_map(params![after, limit as i64], |row| {
+            Ok(MemoryEntry {
+                id: row.get(0)?,
+                key: row.get(1)?,
+                content: row.get(2)?,
+                category: Self::str_to_category(&row.get::<_, String>(3)?),
+                timestamp: row.get(4)?,
+                session_id: None,
+                score: None,
+                match_explanation: None,
+            })
+        })?;
+        rows.collect::<Result<_, _>>().map_err(Into::into)
+    }
+
     async fn forget(&self, key: &str) -> anyhow::Result<bool> {
         let conn = self.conn.lock();
         let affected = conn.execute("DELETE FROM memories WHERE key = ?1", params![key])?;
@@ -542,14 +648,84 @@ impl Memory for SqliteMemory {
         Ok(count as usize)
     }
 
+    /// Sizes come from `SQLite`; only compressed rows are loaded, to read
+    /// their size headers.
+    async fn stats(&self) -> anyhow::Result<MemoryStats> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT category, LENGTH(CAST(content AS BLOB)), created_at,
+                    CASE WHEN content LIKE 'lz4:%' THEN content END
+             FROM memories",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut stats = MemoryStats::default();
+        while let Some(row) = rows.next()? {
+            let category = Self::str_to_category(&row.get::<_, String>(0)?);
+            let len: i64 = row.get(1)?;
+            let created_at: String = row.get(2)?;
+            let compressed: Option<String> = row.get(3)?;
+            #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+            stats.add_sized(
+                category.to_string(),
+                len as usize,
+                compressed.as_deref(),
+                &created_at,
+            );
+        }
+        Ok(stats)
+    }
+
     async fn health_check(&self) -> bool {
         self.conn.lock().execute_batch("SELECT 1").is_ok()
     }
+
+    /// Trim the embedding cache to `cache_max`, merge FTS5 segments, then
+    /// VACUUM so freed pages go back to the filesystem.
+    async fn compact(&self) -> anyhow::Result<CompactionReport> {
+        let size_before = std::fs::metadata(&self.db_path).map_or(0, |m| m.len());
+
+        let conn = self.conn.lock();
+        #[allow(clippy::cast_possible_wrap)]
+        let max = self.cache_max as i64;
+        let entries_removed = conn.execute(
+            "DELETE FROM embedding_cache WHERE content_hash IN (
+                SELECT content_hash FROM embedding_cache
+                ORDER BY accessed_at ASC
+                LIMIT MAX(0, (SELECT COUNT(*) FROM embedding_cache) - ?1)
+            )",
+            params![max],
+        )?;
+        conn.execute_batch(
+            "INSERT INTO memories_fts(memories_fts) VALUES('optimize');
+             VACUUM;",
+        )?;
+        drop(conn);
+
+        let size_after = std::fs::metadata(&self.db_path).map_or(0, |m| m.len());
+        Ok(CompactionReport {
+            bytes_reclaimed: size_before.saturating_sub(size_after),
+            entries_removed,
+        })
+    }
+}
+
+/// Whether opening failed because the file isn't a usable database, as
+/// opposed to e.g. a permissions problem that moving it aside wouldn't fix.
+fn is_corruption(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<rusqlite::Error>(),
+        Some(rusqlite::Error::SqliteFailure(failure, _))
+            if matches!(
+                failure.code,
+                rusqlite::ErrorCode::NotADatabase | rusqlite::ErrorCode::DatabaseCorrupt
+            )
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::memory::traits::RecallOptions;
     use tempfile::TempDir;
 
     fn temp_sqlite() -> (TempDir, SqliteMemory) {
@@ -558,6 +734,29 @@ mod tests {
         (tmp, mem)
     }
 
+    #[tokio::test]
+    async fn corrupt_database_is_moved_aside() {
+        let tmp = TempDir::new().unwrap();
+        let db_dir = tmp.path().join("memory");
+        std::fs::create_dir_all(&db_dir).unwrap();
+        std::fs::write(
+            db_dir.join("brain.db"),
+            b"definitely not sqlite, just junk bytes",
+        )
+        .unwrap();
+
+        let mem = SqliteMemory::new(tmp.path()).unwrap();
+        assert_eq!(mem.count().await.unwrap(), 0);
+        mem.store("k", "v", MemoryCategory::Core).await.unwrap();
+
+        let backups = std::fs::read_dir(&db_dir)
+            .unwrap()
+            .filter_map(|e| e.unwrap().file_name().into_string().ok())
+            .filter(|name| name.starts_with("brain.db.corrupt-"))
+            .count();
+        assert_eq!(backups, 1);
+    }
+
     #[tokio::test]
     async fn sqlite_name() {
         let (_tmp, mem) = temp_sqlite();
@@ -570,6 +769,198 @@ mod tests {
         assert!(mem.health_check().await);
     }
 
+    #[tokio::test]
+    async fn append_creates_then_extends_in_order() {
+        let (_tmp, mem) = temp_sqlite();
+        mem.append("journal", "woke up", MemoryCategory::Daily)
+            .await
+            .unwrap();
+        // The category of an existing entry wins.
+        mem.append("journal", "shipped it", MemoryCategory::Core)
+            .await
+            .unwrap();
+
+        let entry = mem.get("journal").await.unwrap().unwrap();
+        assert_eq!(entry.content, "woke up\nshipped it");
+        assert_eq!(entry.category, MemoryCategory::Daily);
+        assert_eq!(mem.recall("shipped", 5).await.unwrap().len(), 1);
+    }
+
+    /// Records what it was asked to embed; fails on "boom".
+    struct RecordingEmbedder {
+        seen: parking_lot::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for RecordingEmbedder {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        fn dimensions(&self) -> usize {
+            2
+        }
+
+        async fn embed(&self, texts: &[&str]) -> anyhow::Result<Vec<Vec<f32>>> {
+            if texts.contains(&"boom") {
+                anyhow::bail!("embedding service down");
+            }
+            self.seen
+                .lock()
+                .extend(texts.iter().map(|text| (*text).to_string()));
+            Ok(texts.iter().map(|_| vec![1.0, 0.0]).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn append_embeds_only_the_new_chunk() {
+        let tmp = TempDir::new().unwrap();
+        let embedder = Arc::new(RecordingEmbedder {
+            seen: parking_lot::Mutex::default(),
+        });
+        let mem =
+            SqliteMemory::with_embedder(tmp.path(), embedder.clone(), 0.7, 0.3, 1000).unwrap();
+        mem.append("log", "first", MemoryCategory::Daily)
+            .await
+            .unwrap();
+        mem.append("log", "second", MemoryCategory::Daily)
+            .await
+            .unwrap();
+        assert_eq!(*embedder.seen.lock(), ["first", "second"]);
+
+        // A failed embedding fails the append before anything is written.
+        assert!(mem
+            .append("log", "boom", MemoryCategory::Daily)
+            .await
+            .is_err());
+        assert_eq!(
+            mem.get("log").await.unwrap().unwrap().content,
+            "first\nsecond"
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_appends_all_land() {
+        let tmp = TempDir::new().unwrap();
+        let mem = Arc::new(SqliteMemory::new(tmp.path()).unwrap());
+        // A second connection, like another process sharing the database.
+        let other = Arc::new(SqliteMemory::new(tmp.path()).unwrap());
+
+        let writes = (0..40).map(|i| {
+            let mem = if i % 2 == 0 {
+                Arc::clone(&mem)
+            } else {
+                Arc::clone(&other)
+            };
+            tokio::spawn(async move {
+                mem.append("daily", &format!("line {i}"), MemoryCategory::Daily)
+                    .await
+            })
+        });
+        for write in writes.collect::<Vec<_>>() {
+            write.await.unwrap().unwrap();
+        }
+
+        let content = mem.get("daily").await.unwrap().unwrap().content;
+        let mut lines: Vec<&str> = content.lines().collect();
+        lines.sort_unstable();
+        let mut expected: Vec<String> = (0..40).map(|i| format!("line {i}")).collect();
+        expected.sort_unstable();
+        assert_eq!(lines, expected);
+    }
+
+    #[tokio::test]
+    async fn stats_break_down_by_category() {
+        let (_tmp, mem) = temp_sqlite();
+        mem.store("a", "core fact", MemoryCategory::Core)
+            .await
+            .unwrap();
+        mem.store("b", "dé", MemoryCategory::Core).await.unwrap();
+        mem.store("c", "daily note", MemoryCategory::Daily)
+            .await
+            .unwrap();
+        let (compressed, _) = super::super::compression::maybe_compress(&"q".repeat(4096));
+        mem.store("d", &compressed, MemoryCategory::Custom("blob".into()))
+            .await
+            .unwrap();
+
+        let stats = mem.stats().await.unwrap();
+        assert_eq!(stats.total_entries, 4);
+        assert_eq!(stats.categories["core"].count, 2);
+        // Byte lengths, not chars.
+        assert_eq!(stats.categories["core"].bytes, 9 + 3);
+        assert_eq!(stats.categories["daily"].bytes, 10);
+        assert_eq!(stats.categories["blob"].bytes, 4096);
+        assert_eq!(stats.compressed_entries, 1);
+        assert!(stats.oldest <= stats.newest);
+        assert_eq!(
+            stats,
+            mem.list(None)
+                .await
+                .unwrap()
+                .into_iter()
+                .fold(MemoryStats::default(), |mut acc, e| {
+                    acc.add(e.category.to_string(), &e.content, &e.timestamp);
+                    acc
+                })
+        );
+    }
+
+    #[tokio::test]
+    async fn compact_reclaims_space_and_keeps_entries() {
+        let (_tmp, mem) = temp_sqlite();
+        let filler = "x".repeat(4096);
+        for i in 0..200 {
+            mem.store(&format!("bulk_{i}"), &filler, MemoryCategory::Daily)
+                .await
+                .unwrap();
+        }
+        mem.store("keep", "Prefers Rust", MemoryCategory::Core)
+            .await
+            .unwrap();
+        for i in 0..200 {
+            mem.forget(&format!("bulk_{i}")).await.unwrap();
+        }
+
+        let report = mem.compact().await.unwrap();
+        assert!(report.bytes_reclaimed > 0);
+        assert_eq!(mem.count().await.unwrap(), 1);
+        assert_eq!(mem.recall("Rust", 5).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn compact_trims_embedding_cache_to_limit() {
+        let tmp = TempDir::new().unwrap();
+        let mem = SqliteMemory::with_embedder(
+            tmp.path(),
+            Arc::new(super::super::embeddings::NoopEmbedding),
+            0.7,
+            0.3,
+            2,
+        )
+        .unwrap();
+        {
+            let conn = mem.conn.lock();
+            for i in 0..5 {
+                conn.execute(
+                    "INSERT INTO embedding_cache (content_hash, embedding, created_at, accessed_at)
+                     VALUES (?1, x'00', ?2, ?2)",
+                    params![format!("h{i}"), format!("2026-01-0{}T00:00:00Z", i + 1)],
+                )
+                .unwrap();
+            }
+        }
+
+        let report = mem.compact().await.unwrap();
+        assert_eq!(report.entries_removed, 3);
+        let remaining: i64 = mem
+            .conn
+            .lock()
+            .query_row("SELECT COUNT(*) FROM embedding_cache", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(remaining, 2);
+    }
+
     #[tokio::test]
     async fn sqlite_store_and_get() {
         let (_tmp, mem) = temp_sqlite();
@@ -978,6 +1369,81 @@ mod tests {
 
     // ── Recall limit test ────────────────────────────────────────
 
+    #[tokio::test]
+    async fn recall_with_filters_category_and_limit() {
+        let (_tmp, mem) = temp_sqlite();
+        mem.store("a", "rust tips", MemoryCategory::Core)
+            .await
+            .unwrap();
+        mem.store("b", "rust news", MemoryCategory::Daily)
+            .await
+            .unwrap();
+        mem.store("c", "rust crates", MemoryCategory::Core)
+            .await
+            .unwrap();
+
+        let opts = RecallOptions {
+            limit: 1,
+            recency_weight: 0.5,
+            category_filter: Some(MemoryCategory::Core),
+            explain: false,
+        };
+        let results = mem.recall_with("rust", &opts).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].category, MemoryCategory::Core);
+        let score = results[0].score.unwrap();
+        assert!((0.0..=1.0).contains(&score));
+    }
+
+    #[tokio::test]
+    async fn recall_with_finds_category_matches_outranked_by_others() {
+        let (_tmp, mem) = temp_sqlite();
+        mem.store(
+            "core",
+            "a long note that mentions rust once among many other words",
+            MemoryCategory::Core,
+        )
+        .await
+        .unwrap();
+        for i in 0..10 {
+            mem.store(&format!("daily{i}"), "rust rust", MemoryCategory::Daily)
+                .await
+                .unwrap();
+        }
+
+        let opts = RecallOptions {
+            limit: 1,
+            category_filter: Some(MemoryCategory::Core),
+            ..RecallOptions::default()
+        };
+        let results = mem.recall_with("rust", &opts).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].key, "core");
+    }
+
+    #[tokio::test]
+    async fn recall_with_explain_attaches_match_details() {
+        let (_tmp, mem) = temp_sqlite();
+        mem.store("lang", "rust is fast", MemoryCategory::Core)
+            .await
+            .unwrap();
+
+        let opts = RecallOptions {
+            explain: true,
+            ..RecallOptions::default()
+        };
+        let results = mem.recall_with("rust speed", &opts).await.unwrap();
+        let why = results[0].match_explanation.as_ref().unwrap();
+        assert_eq!(why.matched_terms, vec!["rust"]);
+        assert!(why.raw_relevance.is_some());
+
+        let plain = mem
+            .recall_with("rust", &RecallOptions::default())
+            .await
+            .unwrap();
+        assert!(plain[0].match_explanation.is_none());
+    }
+
     #[tokio::test]
     async fn recall_respects_limit() {
         let (_tmp, mem) = temp_sqlite();
@@ -1299,8 +1765,37 @@ mod tests {
         assert_eq!(back, MemoryCategory::Custom(String::new()));
     }
 
+    #[tokio::test]
+    async fn custom_category_named_like_builtin_stays_custom() {
+        let (_tmp, mem) = temp_sqlite();
+        let shadow = MemoryCategory::Custom("core".into());
+        mem.store("k", "v", shadow.clone()).await.unwrap();
+
+        assert_eq!(mem.get("k").await.unwrap().unwrap().category, shadow);
+        assert!(mem
+            .list(Some(&MemoryCategory::Core))
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(mem.list(Some(&shadow)).await.unwrap().len(), 1);
+    }
+
     // ── Edge cases: list ─────────────────────────────────────────
 
+    #[tokio::test]
+    async fn list_page_walks_keys_in_order() {
+        let (_tmp, mem) = temp_sqlite();
+        for key in ["c", "a", "e", "b", "d"] {
+            mem.store(key, key, MemoryCategory::Core).await.unwrap();
+        }
+
+        let keys = |page: Vec<MemoryEntry>| page.into_iter().map(|e| e.key).collect::<Vec<_>>();
+        assert_eq!(keys(mem.list_page(None, 2).await.unwrap()), ["a", "b"]);
+        assert_eq!(keys(mem.list_page(Some("b"), 2).await.unwrap()), ["c", "d"]);
+        assert_eq!(keys(mem.list_page(Some("d"), 2).await.unwrap()), ["e"]);
+        assert!(mem.list_page(Some("e"), 2).await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn list_custom_category() {
         let (_tmp, mem) = temp_sqlite();
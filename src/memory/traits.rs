@@ -1,5 +1,6 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// A single memory entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +12,23 @@ pub struct MemoryEntry {
     pub timestamp: String,
     pub session_id: Option<String>,
     pub score: Option<f64>,
+    /// Why this entry ranked where it did; filled only when
+    /// [`RecallOptions::explain`] is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_explanation: Option<MatchExplanation>,
+}
+
+/// Breakdown of a recall score, for tuning ranking weights
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchExplanation {
+    /// Query terms found in the key or content (lowercased)
+    pub matched_terms: Vec<String>,
+    /// The backend's own relevance score, before normalization and blending
+    pub raw_relevance: Option<f64>,
+    /// `(1 - recency_weight) * normalized relevance`
+    pub relevance_contribution: f64,
+    /// `recency_weight * normalized recency`
+    pub recency_contribution: f64,
 }
 
 /// Memory categories for organization
@@ -27,17 +45,156 @@ pub enum MemoryCategory {
     Custom(String),
 }
 
+/// Marks a custom category in string form when its bare name would read
+/// back as a built-in (`custom:core`) or as another prefixed name.
+const CUSTOM_PREFIX: &str = "custom:";
+
+/// String form used by storage backends; [`str::parse`] reverses it exactly.
 impl std::fmt::Display for MemoryCategory {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Core => write!(f, "core"),
             Self::Daily => write!(f, "daily"),
             Self::Conversation => write!(f, "conversation"),
+            Self::Custom(name)
+                if matches!(name.as_str(), "core" | "daily" | "conversation")
+                    || name.starts_with(CUSTOM_PREFIX) =>
+            {
+                write!(f, "{CUSTOM_PREFIX}{name}")
+            }
             Self::Custom(name) => write!(f, "{name}"),
         }
     }
 }
 
+impl std::str::FromStr for MemoryCategory {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "core" => Self::Core,
+            "daily" => Self::Daily,
+            "conversation" => Self::Conversation,
+            other => Self::Custom(
+                other
+                    .strip_prefix(CUSTOM_PREFIX)
+                    .unwrap_or(other)
+                    .to_string(),
+            ),
+        })
+    }
+}
+
+/// Tuning knobs for [`Memory::recall_with`]
+#[derive(Debug, Clone)]
+pub struct RecallOptions {
+    /// Max entries to return
+    pub limit: usize,
+    /// 0.0 ranks purely by relevance, 1.0 purely by recency
+    pub recency_weight: f64,
+    /// Only return entries in this category
+    pub category_filter: Option<MemoryCategory>,
+    /// Attach a [`MatchExplanation`] to each returned entry
+    pub explain: bool,
+}
+
+impl Default for RecallOptions {
+    fn default() -> Self {
+        Self {
+            limit: 5,
+            recency_weight: 0.0,
+            category_filter: None,
+            explain: false,
+        }
+    }
+}
+
+/// Outcome of a [`Memory::compact`] pass
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CompactionReport {
+    /// Bytes returned to the filesystem (0 if the store did not shrink)
+    pub bytes_reclaimed: u64,
+    /// Stale rows dropped along the way (orphaned cache entries, etc.)
+    pub entries_removed: usize,
+}
+
+/// How a memory store is distributed, from [`Memory::stats`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MemoryStats {
+    pub total_entries: usize,
+    /// Content bytes as written, before any compression
+    pub total_bytes: u64,
+    /// Keyed by the category's string form
+    pub categories: BTreeMap<String, CategoryStats>,
+    /// Timestamps of the oldest and newest entries, as the backend stores them
+    pub oldest: Option<String>,
+    pub newest: Option<String>,
+    /// Entries held in compressed (`lz4:`) form
+    pub compressed_entries: usize,
+    /// Bytes compression saves on disk; negative when the hex encoding
+    /// costs more than lz4 wins back
+    pub compression_savings_bytes: i64,
+}
+
+/// One category's share of a [`MemoryStats`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CategoryStats {
+    pub count: usize,
+    /// Content bytes before compression
+    pub bytes: u64,
+}
+
+impl MemoryStats {
+    /// Fold in one entry as stored: `stored` is its content exactly as the
+    /// backend holds it, possibly compressed.
+    pub(crate) fn add(&mut self, category: String, stored: &str, timestamp: &str) {
+        self.add_sized(
+            category,
+            stored.len(),
+            super::compression::is_compressed(stored).then_some(stored),
+            timestamp,
+        );
+    }
+
+    /// Like [`Self::add`] for backends that know the stored length without
+    /// loading the content; `compressed` is the content only when it is.
+    pub(crate) fn add_sized(
+        &mut self,
+        category: String,
+        stored_len: usize,
+        compressed: Option<&str>,
+        timestamp: &str,
+    ) {
+        let len = match compressed {
+            Some(stored) => {
+                let len = super::compression::decompressed_len(stored).unwrap_or(stored_len);
+                self.compressed_entries += 1;
+                #[allow(clippy::cast_possible_wrap)]
+                {
+                    self.compression_savings_bytes += len as i64 - stored_len as i64;
+                }
+                len
+            }
+            None => stored_len,
+        };
+        self.total_entries += 1;
+        self.total_bytes += len as u64;
+        let slot = self.categories.entry(category).or_default();
+        slot.count += 1;
+        slot.bytes += len as u64;
+        if self.oldest.as_deref().is_none_or(|t| timestamp < t) {
+            self.oldest = Some(timestamp.to_string());
+        }
+        if self.newest.as_deref().is_none_or(|t| timestamp > t) {
+            self.newest = Some(timestamp.to_string());
+        }
+    }
+}
+
+/// Extra candidates fetched per requested result so filtering and re-ranking
+/// have something to work with.
+const RECALL_CANDIDATE_FACTOR: usize = 4;
+
 /// Core memory trait — implement for any persistence backend
 #[async_trait]
 pub trait Memory: Send + Sync {
@@ -48,21 +205,199 @@ pub trait Memory: Send + Sync {
     async fn store(&self, key: &str, content: &str, category: MemoryCategory)
         -> anyhow::Result<()>;
 
+    /// Add `content` to the end of the entry at `key`, after a newline, or
+    /// create it in `category` if there is none; an existing entry keeps its
+    /// category.
+    ///
+    /// Backends make this atomic: concurrent appends to one key, from this
+    /// process or another, all land, each whole and in the order they
+    /// commit. The default read-modify-write gives no such guarantee and is
+    /// only for backends that are never shared.
+    async fn append(
+        &self,
+        key: &str,
+        content: &str,
+        category: MemoryCategory,
+    ) -> anyhow::Result<()> {
+        match self.get(key).await? {
+            Some(existing) => {
+                let combined = format!("{}\n{content}", existing.content);
+                self.store(key, &combined, existing.category).await
+            }
+            None => self.store(key, content, category).await,
+        }
+    }
+
     /// Recall memories matching a query (keyword search)
     async fn recall(&self, query: &str, limit: usize) -> anyhow::Result<Vec<MemoryEntry>>;
 
+    /// Recall restricted to one category. Backends that can filter in
+    /// their query should override this; the default filters after the
+    /// fact, so it may return fewer than `limit` entries.
+    async fn recall_in(
+        &self,
+        query: &str,
+        limit: usize,
+        category: Option<&MemoryCategory>,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        let mut entries = self.recall(query, limit).await?;
+        if let Some(category) = category {
+            entries.retain(|e| &e.category == category);
+        }
+        Ok(entries)
+    }
+
+    /// Recall with recency/relevance blending and category filtering.
+    /// `score` on each entry carries the blended value, and
+    /// `match_explanation` its breakdown when `options.explain` is set.
+    async fn recall_with(
+        &self,
+        query: &str,
+        options: &RecallOptions,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        let pool = options.limit.saturating_mul(RECALL_CANDIDATE_FACTOR);
+        let mut entries = self
+            .recall_in(query, pool, options.category_filter.as_ref())
+            .await?;
+        if options.explain {
+            super::ranking::blend_recency_explained(&mut entries, options.recency_weight, query);
+        } else {
+            super::ranking::blend_recency(&mut entries, options.recency_weight);
+        }
+        entries.truncate(options.limit);
+        Ok(entries)
+    }
+
     /// Get a specific memory by key
     async fn get(&self, key: &str) -> anyhow::Result<Option<MemoryEntry>>;
 
     /// List all memory keys, optionally filtered by category
     async fn list(&self, category: Option<&MemoryCategory>) -> anyhow::Result<Vec<MemoryEntry>>;
 
+    /// Up to `limit` entries with keys after `after` (from the start when
+    /// `None`), in key order, for walking a store without loading all of it.
+    /// The default pages through [`Memory::list`]; backends with a key index
+    /// should query a page at a time.
+    async fn list_page(
+        &self,
+        after: Option<&str>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        let mut entries = self.list(None).await?;
+        entries.retain(|e| after.is_none_or(|after| e.key.as_str() > after));
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        entries.truncate(limit);
+        Ok(entries)
+    }
+
     /// Remove a memory by key
     async fn forget(&self, key: &str) -> anyhow::Result<bool>;
 
     /// Count total memories
     async fn count(&self) -> anyhow::Result<usize>;
 
+    /// Entry counts and sizes per category, age range and compression
+    /// savings. The default walks [`Memory::list`]; backends with an index
+    /// should aggregate in place.
+    async fn stats(&self) -> anyhow::Result<MemoryStats> {
+        let mut stats = MemoryStats::default();
+        for entry in self.list(None).await? {
+            stats.add(entry.category.to_string(), &entry.content, &entry.timestamp);
+        }
+        Ok(stats)
+    }
+
     /// Health check
     async fn health_check(&self) -> bool;
+
+    /// Reclaim space and rebuild indexes. Backends without anything to
+    /// compact keep the default no-op.
+    async fn compact(&self) -> anyhow::Result<CompactionReport> {
+        Ok(CompactionReport::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_cases() -> Vec<MemoryCategory> {
+        vec![
+            MemoryCategory::Core,
+            MemoryCategory::Daily,
+            MemoryCategory::Conversation,
+            MemoryCategory::Custom("project".into()),
+            MemoryCategory::Custom("my custom category".into()),
+            MemoryCategory::Custom(String::new()),
+            MemoryCategory::Custom("core".into()),
+            MemoryCategory::Custom("daily".into()),
+            MemoryCategory::Custom("conversation".into()),
+            MemoryCategory::Custom("custom".into()),
+            MemoryCategory::Custom("custom:core".into()),
+            MemoryCategory::Custom("Core".into()),
+        ]
+    }
+
+    #[test]
+    fn string_form_roundtrips_every_category() {
+        for category in all_cases() {
+            let text = category.to_string();
+            assert_eq!(text.parse::<MemoryCategory>().unwrap(), category, "{text}");
+        }
+    }
+
+    #[test]
+    fn shadowing_custom_names_are_prefixed() {
+        assert_eq!(
+            MemoryCategory::Custom("core".into()).to_string(),
+            "custom:core"
+        );
+        assert_eq!(
+            MemoryCategory::Custom("custom:x".into()).to_string(),
+            "custom:custom:x"
+        );
+        assert_eq!(
+            MemoryCategory::Custom("project".into()).to_string(),
+            "project"
+        );
+        assert_eq!(MemoryCategory::Core.to_string(), "core");
+    }
+
+    #[test]
+    fn serde_roundtrips_every_category() {
+        for category in all_cases() {
+            let json = serde_json::to_string(&category).unwrap();
+            let back: MemoryCategory = serde_json::from_str(&json).unwrap();
+            assert_eq!(back, category, "{json}");
+        }
+        assert_eq!(
+            serde_json::to_string(&MemoryCategory::Custom("daily".into())).unwrap(),
+            r#"{"custom":"daily"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&MemoryCategory::Daily).unwrap(),
+            r#""daily""#
+        );
+    }
+
+    #[test]
+    fn stats_fold_entries_by_category() {
+        let mut stats = MemoryStats::default();
+        stats.add("core".into(), "hello", "2026-01-02");
+        stats.add("core".into(), "hi", "2026-01-01");
+        let (compressed, _) = super::super::compression::maybe_compress(&"z".repeat(4096));
+        stats.add("daily".into(), &compressed, "2026-01-03");
+
+        assert_eq!(stats.total_entries, 3);
+        assert_eq!(stats.total_bytes, 7 + 4096);
+        assert_eq!(
+            stats.categories["core"],
+            CategoryStats { count: 2, bytes: 7 }
+        );
+        assert_eq!(stats.categories["daily"].bytes, 4096);
+        assert_eq!(stats.oldest.as_deref(), Some("2026-01-01"));
+        assert_eq!(stats.newest.as_deref(), Some("2026-01-03"));
+        assert_eq!(stats.compressed_entries, 1);
+        assert!(stats.compression_savings_bytes > 0);
+    }
 }
@@ -37,9 +37,56 @@ impl Observer for LogObserver {
             ObserverEvent::HeartbeatTick => {
                 info!("heartbeat.tick");
             }
+            ObserverEvent::CacheHit { model, origin } => {
+                info!(model = %model, origin = ?origin, "cache.hit");
+            }
+            ObserverEvent::ProviderCall {
+                provider,
+                model,
+                origin,
+                duration,
+                success,
+            } => {
+                let ms = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+                info!(
+                    provider = %provider,
+                    model = %model,
+                    origin = ?origin,
+                    duration_ms = ms,
+                    success = success,
+                    "provider.call"
+                );
+            }
             ObserverEvent::Error { component, message } => {
                 info!(component = %component, error = %message, "error");
             }
+            ObserverEvent::DaemonStart { components } => {
+                info!(components = %components.join(","), "daemon.start");
+            }
+            ObserverEvent::DaemonStop { reason } => {
+                info!(reason = %reason, "daemon.stop");
+            }
+            ObserverEvent::DaemonReload { error } => {
+                info!(success = error.is_none(), error = ?error, "daemon.reload");
+            }
+            ObserverEvent::ComponentStart {
+                component,
+                restarts,
+            } => {
+                info!(component = %component, restarts = restarts, "component.start");
+            }
+            ObserverEvent::ComponentStop {
+                component,
+                reason,
+                will_restart,
+            } => {
+                info!(
+                    component = %component,
+                    reason = %reason,
+                    will_restart = will_restart,
+                    "component.stop"
+                );
+            }
         }
     }
 
@@ -101,10 +148,39 @@ mod tests {
             direction: "outbound".into(),
         });
         obs.record_event(&ObserverEvent::HeartbeatTick);
+        obs.record_event(&ObserverEvent::CacheHit {
+            model: "claude-sonnet".into(),
+            origin: None,
+        });
+        obs.record_event(&ObserverEvent::ProviderCall {
+            provider: "openrouter".into(),
+            model: "claude-sonnet".into(),
+            origin: Some("channel:telegram".into()),
+            duration: Duration::from_millis(250),
+            success: true,
+        });
         obs.record_event(&ObserverEvent::Error {
             component: "provider".into(),
             message: "timeout".into(),
         });
+        obs.record_event(&ObserverEvent::DaemonStart {
+            components: vec!["gateway".into(), "scheduler".into()],
+        });
+        obs.record_event(&ObserverEvent::ComponentStart {
+            component: "gateway".into(),
+            restarts: 2,
+        });
+        obs.record_event(&ObserverEvent::ComponentStop {
+            component: "gateway".into(),
+            reason: "port in use".into(),
+            will_restart: false,
+        });
+        obs.record_event(&ObserverEvent::DaemonReload {
+            error: Some("lock moved".into()),
+        });
+        obs.record_event(&ObserverEvent::DaemonStop {
+            reason: "shutdown requested".into(),
+        });
     }
 
     #[test]
@@ -9,17 +9,25 @@ pub use traits::{Observer, ObserverEvent};
 
 use crate::config::ObservabilityConfig;
 
-/// Factory: create the right observer from config
+/// Factory: create the right observer from config, falling back to noop
+/// (with a loud error) if the configured backend cannot be initialized.
 pub fn create_observer(config: &ObservabilityConfig) -> Box<dyn Observer> {
+    try_create_observer(config).unwrap_or_else(|e| {
+        tracing::error!(
+            backend = %config.backend,
+            "Observability backend failed to initialize, telemetry disabled: {e}"
+        );
+        Box::new(NoopObserver)
+    })
+}
+
+/// Factory that surfaces initialization failures instead of swallowing them.
+pub fn try_create_observer(config: &ObservabilityConfig) -> anyhow::Result<Box<dyn Observer>> {
     match config.backend.as_str() {
-        "log" => Box::new(LogObserver::new()),
-        "none" | "noop" => Box::new(NoopObserver),
-        _ => {
-            tracing::warn!(
-                "Unknown observability backend '{}', falling back to noop",
-                config.backend
-            );
-            Box::new(NoopObserver)
+        "log" => Ok(Box::new(LogObserver::new())),
+        "none" | "noop" => Ok(Box::new(NoopObserver)),
+        other => {
+            anyhow::bail!("unknown observability backend '{other}' (expected \"none\" or \"log\")")
         }
     }
 }
@@ -32,6 +40,7 @@ mod tests {
     fn factory_none_returns_noop() {
         let cfg = ObservabilityConfig {
             backend: "none".into(),
+            ..ObservabilityConfig::default()
         };
         assert_eq!(create_observer(&cfg).name(), "noop");
     }
@@ -40,6 +49,7 @@ mod tests {
     fn factory_noop_returns_noop() {
         let cfg = ObservabilityConfig {
             backend: "noop".into(),
+            ..ObservabilityConfig::default()
         };
         assert_eq!(create_observer(&cfg).name(), "noop");
     }
@@ -48,6 +58,7 @@ mod tests {
     fn factory_log_returns_log() {
         let cfg = ObservabilityConfig {
             backend: "log".into(),
+            ..ObservabilityConfig::default()
         };
         assert_eq!(create_observer(&cfg).name(), "log");
     }
@@ -56,14 +67,37 @@ mod tests {
     fn factory_unknown_falls_back_to_noop() {
         let cfg = ObservabilityConfig {
             backend: "prometheus".into(),
+            ..ObservabilityConfig::default()
         };
         assert_eq!(create_observer(&cfg).name(), "noop");
     }
 
+    #[test]
+    fn try_factory_reports_unknown_backend() {
+        let cfg = ObservabilityConfig {
+            backend: "prometheus".into(),
+            ..ObservabilityConfig::default()
+        };
+        let err = try_create_observer(&cfg).err().expect("should fail");
+        assert!(err.to_string().contains("prometheus"));
+    }
+
+    #[test]
+    fn try_factory_builds_known_backends() {
+        for backend in ["none", "noop", "log"] {
+            let cfg = ObservabilityConfig {
+                backend: backend.into(),
+                ..ObservabilityConfig::default()
+            };
+            assert!(try_create_observer(&cfg).is_ok(), "{backend} should init");
+        }
+    }
+
     #[test]
     fn factory_empty_string_falls_back_to_noop() {
         let cfg = ObservabilityConfig {
             backend: String::new(),
+            ..ObservabilityConfig::default()
         };
         assert_eq!(create_observer(&cfg).name(), "noop");
     }
@@ -72,6 +106,7 @@ mod tests {
     fn factory_garbage_falls_back_to_noop() {
         let cfg = ObservabilityConfig {
             backend: "xyzzy_garbage_123".into(),
+            ..ObservabilityConfig::default()
         };
         assert_eq!(create_observer(&cfg).name(), "noop");
     }
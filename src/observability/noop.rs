@@ -50,6 +50,10 @@ mod tests {
             channel: "cli".into(),
             direction: "inbound".into(),
         });
+        obs.record_event(&ObserverEvent::CacheHit {
+            model: "test".into(),
+            origin: None,
+        });
         obs.record_event(&ObserverEvent::Error {
             component: "test".into(),
             message: "boom".into(),
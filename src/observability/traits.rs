@@ -21,10 +21,48 @@ pub enum ObserverEvent {
         direction: String,
     },
     HeartbeatTick,
+    /// A provider response was served from the cache instead of a fresh call.
+    CacheHit {
+        model: String,
+        /// Who asked, e.g. `channel:telegram`; see `providers::origin`.
+        origin: Option<String>,
+    },
+    /// One call to one provider in the chain (each retry counts).
+    ProviderCall {
+        provider: String,
+        model: String,
+        origin: Option<String>,
+        duration: Duration,
+        success: bool,
+    },
     Error {
         component: String,
         message: String,
     },
+    /// The daemon finished startup with these components enabled.
+    DaemonStart {
+        components: Vec<String>,
+    },
+    /// The daemon is shutting down.
+    DaemonStop {
+        reason: String,
+    },
+    /// The daemon was asked to reload its config; `error` is set when the
+    /// new config was refused and the running one kept.
+    DaemonReload {
+        error: Option<String>,
+    },
+    /// A supervised daemon component was (re)started; `restarts` is 0 on first start.
+    ComponentStart {
+        component: String,
+        restarts: u64,
+    },
+    /// A supervised daemon component stopped, and whether it will be restarted.
+    ComponentStop {
+        component: String,
+        reason: String,
+        will_restart: bool,
+    },
 }
 
 /// Numeric metrics
@@ -4,6 +4,8 @@ use crate::config::{
     HeartbeatConfig, IMessageConfig, MatrixConfig, MemoryConfig, ObservabilityConfig,
     RuntimeConfig, SecretsConfig, SlackConfig, TelegramConfig, WebhookConfig,
 };
+use crate::memory::quota::MemoryFullStrategy;
+use crate::memory::secret_scan::SecretScanMode;
 use anyhow::{Context, Result};
 use console::style;
 use dialoguer::{Confirm, Input, Select};
@@ -96,7 +98,9 @@ pub fn run_wizard() -> Result<Config> {
         autonomy: AutonomyConfig::default(),
         runtime: RuntimeConfig::default(),
         reliability: crate::config::ReliabilityConfig::default(),
+        providers: crate::config::ProvidersConfig::default(),
         heartbeat: HeartbeatConfig::default(),
+        daemon: crate::config::DaemonConfig::default(),
         channels_config,
         memory: memory_config, // User-selected memory backend
         tunnel: tunnel_config,
@@ -105,6 +109,8 @@ pub fn run_wizard() -> Result<Config> {
         secrets: secrets_config,
         browser: BrowserConfig::default(),
         identity: crate::config::IdentityConfig::default(),
+        request_overrides: std::collections::HashMap::new(),
+        workspaces: std::collections::BTreeMap::new(),
     };
 
     println!(
@@ -271,6 +277,10 @@ pub fn run_quick_setup(
             0
         },
         chunk_max_tokens: 512,
+        max_decompressed_bytes: crate::memory::compression::DEFAULT_MAX_DECOMPRESSED_SIZE,
+        max_total_bytes: 0,
+        when_full: MemoryFullStrategy::default(),
+        secret_scan: SecretScanMode::default(),
     };
 
     let config = Config {
@@ -284,7 +294,9 @@ pub fn run_quick_setup(
         autonomy: AutonomyConfig::default(),
         runtime: RuntimeConfig::default(),
         reliability: crate::config::ReliabilityConfig::default(),
+        providers: crate::config::ProvidersConfig::default(),
         heartbeat: HeartbeatConfig::default(),
+        daemon: crate::config::DaemonConfig::default(),
         channels_config: ChannelsConfig::default(),
         memory: memory_config,
         tunnel: crate::config::TunnelConfig::default(),
@@ -293,6 +305,8 @@ pub fn run_quick_setup(
         secrets: SecretsConfig::default(),
         browser: BrowserConfig::default(),
         identity: crate::config::IdentityConfig::default(),
+        request_overrides: std::collections::HashMap::new(),
+        workspaces: std::collections::BTreeMap::new(),
     };
 
     config.save()?;
@@ -1031,6 +1045,10 @@ fn setup_memory() -> Result<MemoryConfig> {
         keyword_weight: 0.3,
         embedding_cache_size: if backend == "sqlite" { 10000 } else { 0 },
         chunk_max_tokens: 512,
+        max_decompressed_bytes: crate::memory::compression::DEFAULT_MAX_DECOMPRESSED_SIZE,
+        max_total_bytes: 0,
+        when_full: MemoryFullStrategy::default(),
+        secret_scan: SecretScanMode::default(),
     })
 }
 
@@ -1051,6 +1069,8 @@ fn setup_channels() -> Result<ChannelsConfig> {
         imessage: None,
         matrix: None,
         whatsapp: None,
+        personas: std::collections::HashMap::new(),
+        oversized_inbound: crate::config::schema::OversizedInbound::default(),
     };
 
     loop {
@@ -1615,11 +1635,21 @@ fn setup_channels() -> Result<ChannelsConfig> {
                     users_str.split(',').map(|s| s.trim().to_string()).collect()
                 };
 
+                let app_secret: String = Input::new()
+                    .with_prompt("  App secret for webhook signatures (optional, Enter to skip)")
+                    .allow_empty(true)
+                    .interact_text()?;
+
                 config.whatsapp = Some(WhatsAppConfig {
                     access_token: access_token.trim().to_string(),
                     phone_number_id: phone_number_id.trim().to_string(),
                     verify_token: verify_token.trim().to_string(),
                     allowed_numbers,
+                    app_secret: if app_secret.trim().is_empty() {
+                        None
+                    } else {
+                        Some(app_secret.trim().to_string())
+                    },
                 });
             }
             6 => {
@@ -2019,6 +2049,9 @@ fn scaffold_workspace(workspace_dir: &Path, ctx: &ProjectContext) -> Result<()>
          - **memory_recall** — Search memory\n\
            - Use when: you need prior decisions, user preferences, or historical context.\n\
            - Don't use when: the answer is already in current files/conversation.\n\
+         - **memory_inspect** — Read memory without changing it\n\
+           - Use when: checking a specific key, or browsing entries by category.\n\
+           - Don't use when: a keyword search is enough (prefer memory_recall).\n\
          - **memory_forget** — Delete a memory entry\n\
            - Use when: memory is incorrect, stale, or explicitly requested to be removed.\n\
            - Don't use when: uncertain about impact; verify before deleting.\n\n\
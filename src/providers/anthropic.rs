@@ -1,4 +1,6 @@
-use crate::providers::traits::Provider;
+use crate::providers::error::ProviderError;
+use crate::providers::request_body::RequestBody;
+use crate::providers::traits::{Provider, ToolFormat};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -6,6 +8,7 @@ use serde::{Deserialize, Serialize};
 pub struct AnthropicProvider {
     api_key: Option<String>,
     client: Client,
+    body: RequestBody,
 }
 
 #[derive(Debug, Serialize)]
@@ -39,8 +42,15 @@ impl AnthropicProvider {
         Self {
             api_key: api_key.map(ToString::to_string),
             client: super::http_client::build_ssrf_safe_client(),
+            body: RequestBody::default(),
         }
     }
+
+    /// Strip and merge fields in every request payload.
+    pub fn with_request_body(mut self, body: RequestBody) -> Self {
+        self.body = body;
+        self
+    }
 }
 
 #[async_trait]
@@ -72,14 +82,16 @@ impl Provider for AnthropicProvider {
             .post("https://api.anthropic.com/v1/messages")
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
+            .headers(self.body.header_map()?)
             .header("content-type", "application/json")
-            .json(&request)
+            .json(&self.body.apply(&request)?)
             .send()
             .await?;
 
         if !response.status().is_success() {
-            let error = response.text().await?;
-            anyhow::bail!("Anthropic API error: {error}");
+            return Err(ProviderError::from_response("Anthropic", response)
+                .await
+                .into());
         }
 
         let chat_response: ChatResponse = response.json().await?;
@@ -91,6 +103,34 @@ impl Provider for AnthropicProvider {
             .map(|c| c.text)
             .ok_or_else(|| anyhow::anyhow!("No response from Anthropic"))
     }
+
+    fn context_window(&self, model: &str) -> Option<usize> {
+        super::known_context_window(model)
+    }
+
+    fn tool_format(&self) -> ToolFormat {
+        ToolFormat::Anthropic
+    }
+
+    async fn preflight(&self) -> anyhow::Result<()> {
+        let api_key = self.api_key.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("Anthropic API key not set. Set ANTHROPIC_API_KEY or edit config.toml.")
+        })?;
+        let response = self
+            .client
+            .get("https://api.anthropic.com/v1/models")
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01")
+            .headers(self.body.header_map()?)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response("Anthropic", response)
+                .await
+                .into());
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
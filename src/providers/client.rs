@@ -0,0 +1,216 @@
+use super::stream::{collect_stream, StreamOutcome};
+use super::traits::Provider;
+use super::traits::ProviderPin;
+use crate::config::Config;
+use std::sync::Arc;
+
+/// Model used when neither the caller nor `default_model` names one.
+pub const DEFAULT_MODEL: &str = "anthropic/claude-sonnet-4-20250514";
+
+/// `default_model` from config, or [`DEFAULT_MODEL`].
+pub fn default_model(config: &Config) -> &str {
+    config.default_model.as_deref().unwrap_or(DEFAULT_MODEL)
+}
+
+/// A provider bundled with the model, temperature and system prompt to send
+/// with every request, so call sites only pass the message.
+///
+/// ```ignore
+/// let client = ChatClient::from_config(provider, &config).with_system_prompt(prompt);
+/// let reply = client.ask("hello").await?;
+/// ```
+#[derive(Clone)]
+pub struct ChatClient {
+    provider: Arc<dyn Provider>,
+    model: String,
+    temperature: f64,
+    system_prompt: Option<String>,
+    /// Skip the provider response cache (user-initiated turns).
+    uncached: bool,
+    /// Keep every call on the provider that answered the first.
+    pin: Option<ProviderPin>,
+}
+
+impl ChatClient {
+    pub fn new(provider: Arc<dyn Provider>, model: impl Into<String>, temperature: f64) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+            temperature,
+            system_prompt: None,
+            uncached: false,
+            pin: None,
+        }
+    }
+
+    /// Use `default_model` and `default_temperature` from config.
+    pub fn from_config(provider: Arc<dyn Provider>, config: &Config) -> Self {
+        Self::new(provider, default_model(config), config.default_temperature)
+    }
+
+    #[must_use]
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    #[must_use]
+    pub fn with_temperature(mut self, temperature: f64) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    #[must_use]
+    pub fn with_system_prompt(mut self, system_prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(system_prompt.into());
+        self
+    }
+
+    /// Always ask the provider for a fresh completion.
+    #[must_use]
+    pub fn uncached(mut self) -> Self {
+        self.uncached = true;
+        self
+    }
+
+    /// Send every request through `pin`, so the conversation stays on one
+    /// provider once it has an answer. Pinned calls are never cached.
+    #[must_use]
+    pub fn pinned(mut self, pin: ProviderPin) -> Self {
+        self.pin = Some(pin);
+        self
+    }
+
+    /// Start every request on the chain entry `provider`, trying the rest of
+    /// the chain after it only with `fallback`. See [`ProviderPin::prefer`].
+    #[must_use]
+    pub fn preferring(self, provider: impl Into<String>, fallback: bool) -> Self {
+        self.pinned(ProviderPin::prefer(provider, fallback))
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn temperature(&self) -> f64 {
+        self.temperature
+    }
+
+    pub fn system_prompt(&self) -> Option<&str> {
+        self.system_prompt.as_deref()
+    }
+
+    pub fn provider(&self) -> &Arc<dyn Provider> {
+        &self.provider
+    }
+
+    /// Context window of the configured model, when known.
+    pub fn context_window(&self) -> Option<usize> {
+        self.provider.context_window(&self.model)
+    }
+
+    /// Send `message` with the configured system prompt.
+    pub async fn ask(&self, message: &str) -> anyhow::Result<String> {
+        self.ask_with_system(self.system_prompt.as_deref(), message)
+            .await
+    }
+
+    /// Send `message` with `system_prompt` in place of the configured one.
+    pub async fn ask_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+    ) -> anyhow::Result<String> {
+        if let Some(pin) = &self.pin {
+            self.provider
+                .chat_pinned(pin, system_prompt, message, &self.model, self.temperature)
+                .await
+        } else if self.uncached {
+            self.provider
+                .chat_with_system_uncached(system_prompt, message, &self.model, self.temperature)
+                .await
+        } else {
+            self.provider
+                .chat_with_system(system_prompt, message, &self.model, self.temperature)
+                .await
+        }
+    }
+
+    /// Like [`Self::ask_with_system`], but streams the reply so one cut off
+    /// part-way comes back as [`StreamOutcome::Truncated`] instead of an
+    /// error. Streams aren't pinned, so a pinned client asks as usual.
+    pub async fn ask_streamed(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+    ) -> anyhow::Result<StreamOutcome> {
+        if self.pin.is_some() {
+            return self
+                .ask_with_system(system_prompt, message)
+                .await
+                .map(StreamOutcome::Complete);
+        }
+        collect_stream(
+            self.provider.as_ref(),
+            system_prompt,
+            message,
+            &self.model,
+            self.temperature,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::testing::{ScriptStep, ScriptedProvider};
+
+    #[test]
+    fn from_config_uses_configured_defaults() {
+        let provider: Arc<dyn Provider> = Arc::new(ScriptedProvider::new("ok"));
+        let config = Config {
+            default_model: None,
+            default_temperature: 0.2,
+            ..Config::default()
+        };
+        let client = ChatClient::from_config(provider, &config);
+        assert_eq!(client.model(), DEFAULT_MODEL);
+        assert!((client.temperature() - 0.2).abs() < f64::EPSILON);
+        assert!(client.system_prompt().is_none());
+    }
+
+    #[tokio::test]
+    async fn ask_sends_stored_defaults() {
+        let scripted = Arc::new(ScriptedProvider::new("ok"));
+        let client = ChatClient::new(scripted.clone(), "model-x", 0.4).with_system_prompt("sys");
+        client.ask("hello").await.unwrap();
+        client.ask_with_system(None, "again").await.unwrap();
+
+        let calls = scripted.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].system_prompt.as_deref(), Some("sys"));
+        assert_eq!(calls[0].message, "hello");
+        assert_eq!(calls[0].model, "model-x");
+        assert!(calls[1].system_prompt.is_none());
+    }
+
+    #[tokio::test]
+    async fn ask_streamed_reports_truncation() {
+        let scripted = Arc::new(
+            ScriptedProvider::new("ok").with_script([ScriptStep::Interrupt {
+                partial: "half an ans".into(),
+                error: "connection reset".into(),
+            }]),
+        );
+        let client = ChatClient::new(scripted, "model-x", 0.0);
+
+        let outcome = client.ask_streamed(None, "hello").await.unwrap();
+        assert!(!outcome.is_complete());
+        assert_eq!(outcome.text(), "half an ans");
+
+        let outcome = client.ask_streamed(None, "again").await.unwrap();
+        assert!(outcome.is_complete());
+        assert_eq!(outcome.text(), "ok");
+    }
+}
@@ -2,9 +2,11 @@
 //! Most LLM APIs follow the same `/v1/chat/completions` format.
 //! This module provides a single implementation that works for all of them.
 
+use crate::providers::error::ProviderError;
+use crate::providers::request_body::RequestBody;
 use crate::providers::traits::Provider;
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
 use serde::{Deserialize, Serialize};
 
 /// A provider that speaks the OpenAI-compatible chat completions API.
@@ -16,6 +18,7 @@ pub struct OpenAiCompatibleProvider {
     pub(crate) api_key: Option<String>,
     pub(crate) auth_header: AuthStyle,
     client: Client,
+    body: RequestBody,
 }
 
 /// How the provider expects the API key to be sent.
@@ -37,8 +40,37 @@ impl OpenAiCompatibleProvider {
             api_key: api_key.map(ToString::to_string),
             auth_header: auth_style,
             client: super::http_client::build_ssrf_safe_client(),
+            body: RequestBody::default(),
         }
     }
+
+    /// Strip and merge fields in every request payload.
+    pub fn with_request_body(mut self, body: RequestBody) -> Self {
+        self.body = body;
+        self
+    }
+
+    fn api_key(&self) -> anyhow::Result<&str> {
+        self.api_key.as_deref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "{} API key not set. Run `baihu onboard` or set the appropriate env var.",
+                self.name
+            )
+        })
+    }
+
+    /// Attach the API key, in `auth_header` from config when one is set,
+    /// followed by the configured extra headers.
+    fn with_auth(&self, req: RequestBuilder, api_key: &str) -> anyhow::Result<RequestBuilder> {
+        let req = match (&self.body.auth_header, &self.auth_header) {
+            (Some(header), _) | (None, AuthStyle::Custom(header)) => {
+                req.header(header.as_str(), api_key)
+            }
+            (None, AuthStyle::Bearer) => req.header("Authorization", format!("Bearer {api_key}")),
+            (None, AuthStyle::XApiKey) => req.header("x-api-key", api_key),
+        };
+        Ok(req.headers(self.body.header_map()?))
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -78,12 +110,7 @@ impl Provider for OpenAiCompatibleProvider {
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<String> {
-        let api_key = self.api_key.as_ref().ok_or_else(|| {
-            anyhow::anyhow!(
-                "{} API key not set. Run `baihu onboard` or set the appropriate env var.",
-                self.name
-            )
-        })?;
+        let api_key = self.api_key()?;
 
         let mut messages = Vec::new();
 
@@ -112,25 +139,13 @@ impl Provider for OpenAiCompatibleProvider {
             anyhow::bail!("{} SSRF blocked: {reason}", self.name);
         }
 
-        let mut req = self.client.post(&url).json(&request);
-
-        match &self.auth_header {
-            AuthStyle::Bearer => {
-                req = req.header("Authorization", format!("Bearer {api_key}"));
-            }
-            AuthStyle::XApiKey => {
-                req = req.header("x-api-key", api_key.as_str());
-            }
-            AuthStyle::Custom(header) => {
-                req = req.header(header.as_str(), api_key.as_str());
-            }
-        }
-
-        let response = req.send().await?;
+        let req = self.client.post(&url).json(&self.body.apply(&request)?);
+        let response = self.with_auth(req, api_key)?.send().await?;
 
         if !response.status().is_success() {
-            let error = response.text().await?;
-            anyhow::bail!("{} API error: {error}", self.name);
+            return Err(ProviderError::from_response(&self.name, response)
+                .await
+                .into());
         }
 
         let chat_response: ChatResponse = response.json().await?;
@@ -142,6 +157,28 @@ impl Provider for OpenAiCompatibleProvider {
             .map(|c| c.message.content)
             .ok_or_else(|| anyhow::anyhow!("No response from {}", self.name))
     }
+
+    fn context_window(&self, model: &str) -> Option<usize> {
+        super::known_context_window(model)
+    }
+
+    async fn preflight(&self) -> anyhow::Result<()> {
+        let api_key = self.api_key()?;
+        let url = format!("{}/v1/models", self.base_url);
+        if let Err(reason) = super::http_client::validate_url_not_private(&url) {
+            anyhow::bail!("{} SSRF blocked: {reason}", self.name);
+        }
+        let response = self
+            .with_auth(self.client.get(&url), api_key)?
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response(&self.name, response)
+                .await
+                .into());
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -243,6 +280,27 @@ mod tests {
         assert!(matches!(p.auth_header, AuthStyle::Custom(_)));
     }
 
+    #[test]
+    fn configured_auth_header_and_headers_replace_defaults() {
+        let mut body = RequestBody {
+            auth_header: Some("api-key".into()),
+            ..RequestBody::default()
+        };
+        body.headers
+            .insert("Authorization".into(), "Bearer gateway".into());
+        let p = make_provider("Custom", "https://example.openai.azure.com", Some("az-key"))
+            .with_request_body(body);
+
+        let req = p
+            .with_auth(p.client.get("https://example.openai.azure.com"), "az-key")
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(req.headers()["api-key"], "az-key");
+        let auth: Vec<_> = req.headers().get_all("authorization").iter().collect();
+        assert_eq!(auth, ["Bearer gateway"]);
+    }
+
     #[tokio::test]
     async fn all_compatible_providers_fail_without_key() {
         let providers = vec![
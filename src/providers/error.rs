@@ -0,0 +1,222 @@
+//! Structured provider failures, so callers can tell "slow down" from "broken".
+
+use std::time::Duration;
+
+/// Broad cause of a failed provider call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderErrorKind {
+    /// HTTP 429: the key or account is over its rate limit.
+    RateLimited,
+    /// HTTP 401/403: missing, invalid or unauthorized key.
+    Auth,
+    /// Any other 4xx: the request itself was rejected.
+    Client,
+    /// 5xx: the provider is having trouble.
+    Server,
+}
+
+impl ProviderErrorKind {
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            429 => Self::RateLimited,
+            401 | 403 => Self::Auth,
+            400..=499 => Self::Client,
+            _ => Self::Server,
+        }
+    }
+}
+
+/// A non-success HTTP response from a provider API.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{provider} API error ({status}): {body}")]
+pub struct ProviderError {
+    pub provider: String,
+    pub status: u16,
+    pub kind: ProviderErrorKind,
+    /// Parsed from the `Retry-After` header, when present.
+    pub retry_after: Option<Duration>,
+    pub body: String,
+}
+
+impl ProviderError {
+    /// Consume an unsuccessful response into a structured error.
+    pub async fn from_response(provider: &str, response: reqwest::Response) -> Self {
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_retry_after);
+        let body = response.text().await.unwrap_or_default();
+        Self {
+            provider: provider.to_string(),
+            status,
+            kind: ProviderErrorKind::from_status(status),
+            retry_after,
+            body,
+        }
+    }
+
+    pub fn is_rate_limited(&self) -> bool {
+        self.kind == ProviderErrorKind::RateLimited
+    }
+}
+
+/// `Retry-After` is either delay-seconds or an HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let secs = (at.timestamp() - chrono::Utc::now().timestamp()).max(0);
+    Some(Duration::from_secs(secs.unsigned_abs()))
+}
+
+/// Every provider in a resilient chain failed.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("All providers failed. Attempts:\n{}", attempts.join("\n"))]
+pub struct ProviderChainError {
+    /// One line per attempt or skipped provider, in order.
+    pub attempts: Vec<String>,
+    /// At least half of the failed calls were rate-limited.
+    pub rate_limited: bool,
+    /// Soonest `Retry-After` any rate-limited provider asked for.
+    pub retry_after: Option<Duration>,
+}
+
+/// A request preferred a provider that isn't in the chain.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Provider '{requested}' is not configured (chain: {})", available.join(", "))]
+pub struct UnknownProviderError {
+    pub requested: String,
+    pub available: Vec<String>,
+}
+
+/// Collects failures while a chain runs, then summarizes them.
+#[derive(Debug, Default)]
+pub(crate) struct ChainFailures {
+    attempts: Vec<String>,
+    failed_calls: usize,
+    rate_limited_calls: usize,
+    retry_after: Option<Duration>,
+}
+
+impl ChainFailures {
+    /// Note something that wasn't a provider call (a skip, an exhausted budget).
+    pub(crate) fn note(&mut self, line: String) {
+        self.attempts.push(line);
+    }
+
+    /// Record failed call `attempt` of `max_attempts` on `provider` and classify it.
+    pub(crate) fn record(
+        &mut self,
+        provider: &str,
+        attempt: u32,
+        max_attempts: u32,
+        error: &anyhow::Error,
+    ) {
+        self.attempts.push(format!(
+            "{provider} attempt {attempt}/{max_attempts}: {error}"
+        ));
+        self.failed_calls += 1;
+        if let Some(e) = error.downcast_ref::<ProviderError>() {
+            if e.is_rate_limited() {
+                self.rate_limited_calls += 1;
+                if let Some(after) = e.retry_after {
+                    self.retry_after =
+                        Some(self.retry_after.map_or(after, |soonest| soonest.min(after)));
+                }
+            }
+        }
+    }
+
+    pub(crate) fn into_error(self) -> ProviderChainError {
+        let rate_limited =
+            self.rate_limited_calls > 0 && self.rate_limited_calls * 2 >= self.failed_calls;
+        ProviderChainError {
+            attempts: self.attempts,
+            rate_limited,
+            retry_after: if rate_limited { self.retry_after } else { None },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn http_error(status: u16, retry_after: Option<u64>) -> anyhow::Error {
+        ProviderError {
+            provider: "Test".into(),
+            status,
+            kind: ProviderErrorKind::from_status(status),
+            retry_after: retry_after.map(Duration::from_secs),
+            body: "nope".into(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn status_classification() {
+        assert_eq!(
+            ProviderErrorKind::from_status(429),
+            ProviderErrorKind::RateLimited
+        );
+        assert_eq!(ProviderErrorKind::from_status(401), ProviderErrorKind::Auth);
+        assert_eq!(
+            ProviderErrorKind::from_status(404),
+            ProviderErrorKind::Client
+        );
+        assert_eq!(
+            ProviderErrorKind::from_status(503),
+            ProviderErrorKind::Server
+        );
+    }
+
+    #[test]
+    fn retry_after_seconds_and_dates() {
+        assert_eq!(parse_retry_after("30"), Some(Duration::from_secs(30)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[test]
+    fn mostly_rate_limited_chain_reports_soonest_retry() {
+        let mut failures = ChainFailures::default();
+        failures.record("a", 1, 1, &http_error(429, Some(30)));
+        failures.record("b", 1, 1, &http_error(429, Some(5)));
+        failures.record("c", 1, 1, &http_error(500, None));
+        failures.note("d: circuit open, skipped".into());
+
+        let err = failures.into_error();
+        assert!(err.rate_limited);
+        assert_eq!(err.retry_after, Some(Duration::from_secs(5)));
+        let message = err.to_string();
+        assert!(message
+            .starts_with("All providers failed. Attempts:\na attempt 1/1: Test API error (429)"));
+        assert!(message.ends_with("\nd: circuit open, skipped"));
+    }
+
+    #[test]
+    fn auth_dominated_chain_is_not_rate_limited() {
+        let mut failures = ChainFailures::default();
+        failures.record("a", 1, 1, &http_error(401, None));
+        failures.record("b", 1, 1, &http_error(401, None));
+        failures.record("c", 1, 1, &http_error(429, Some(10)));
+
+        let err = failures.into_error();
+        assert!(!err.rate_limited);
+        assert_eq!(err.retry_after, None);
+    }
+
+    #[test]
+    fn plain_errors_are_not_rate_limited() {
+        let mut failures = ChainFailures::default();
+        failures.record("a", 1, 1, &anyhow::anyhow!("connection reset"));
+        assert!(!failures.into_error().rate_limited);
+    }
+}
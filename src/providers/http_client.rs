@@ -5,9 +5,23 @@
 //
 // The redirect policy validates each 302/3xx hop to prevent DNS rebinding
 // and redirect-to-localhost attacks (attacker URL -> 302 -> http://127.0.0.1).
-
-use reqwest::{redirect, Client, Url};
-use std::net::IpAddr;
+//
+// Host strings can't catch everything (homograph names, public names that
+// resolve to private addresses), so the client's resolver also refuses to
+// hand out private addresses. The configured HTTP(S)_PROXY host is the one
+// exception, so proxies on private networks keep working.
+//
+// TLS trust can be extended once per process with `configure_tls` (a private
+// CA bundle, or dev-only skipping of verification); it never relaxes SSRF.
+
+use crate::config::Config;
+use anyhow::Context;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{redirect, Certificate, Client, ClientBuilder, Url};
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
 
 /// Known private/internal hostnames that should never be reachable from providers.
 const BLOCKED_HOSTS: &[&str] = &[
@@ -17,6 +31,10 @@ const BLOCKED_HOSTS: &[&str] = &[
     "instance-data",
 ];
 
+/// Top-level domains reserved for local or private use (RFC 6761, RFC 6762,
+/// ICANN's `.internal`); nothing under them is a public provider.
+const INTERNAL_TLDS: &[&str] = &["localhost", "local", "internal"];
+
 /// Returns true if the IP address is in a private, loopback, or link-local range.
 pub fn is_private_ip(ip: IpAddr) -> bool {
     match ip {
@@ -25,11 +43,15 @@ pub fn is_private_ip(ip: IpAddr) -> bool {
                 || v4.is_private()        // 10.0.0.0/8, 172.16.0.0/12, 192.168.0.0/16
                 || v4.is_link_local()     // 169.254.0.0/16
                 || v4.is_broadcast()      // 255.255.255.255
-                || v4.is_unspecified()    // 0.0.0.0
+                || v4.octets()[0] == 0    // 0.0.0.0/8 ("this network")
                 || v4.octets()[0] == 100 && v4.octets()[1] >= 64 && v4.octets()[1] <= 127
             // CGNAT 100.64.0.0/10
         }
         IpAddr::V6(v6) => {
+            // ::ffff:127.0.0.1 reaches the IPv4 loopback
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_private_ip(IpAddr::V4(v4));
+            }
             v6.is_loopback()              // ::1
                 || v6.is_unspecified()    // ::
                 || {
@@ -43,38 +65,171 @@ pub fn is_private_ip(ip: IpAddr) -> bool {
     }
 }
 
+/// Why `url` must not be fetched, when its host is internal.
+fn blocked_reason(url: &Url) -> Option<String> {
+    // `Url` has already applied IDNA mapping (so `ⅼocalhost` and fullwidth
+    // letters fold to `localhost`), punycoded what is left, and normalized
+    // IPv4 spellings like `0x7f.1`. A trailing dot names the same host.
+    let host = url.host_str()?.trim_end_matches('.').to_ascii_lowercase();
+    if let Ok(ip) = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        return is_private_ip(ip).then(|| format!("private IP: {ip}"));
+    }
+
+    let internal = BLOCKED_HOSTS.iter().any(|blocked| {
+        host == *blocked
+            || host
+                .strip_suffix(blocked)
+                .is_some_and(|rest| rest.ends_with('.'))
+    });
+    // Single-label names only resolve through hosts files and search
+    // domains, and homographs of `localhost` (`xn--lcalhost-…`) are single
+    // labels too.
+    let tld = host.rsplit('.').next().unwrap_or_default();
+    if internal || !host.contains('.') || INTERNAL_TLDS.contains(&tld) {
+        return Some(format!("internal hostname: {host}"));
+    }
+    None
+}
+
+/// Environment variables reqwest reads its system proxy from.
+const PROXY_ENV_VARS: &[&str] = &[
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+/// Hosts of the proxies configured through `PROXY_ENV_VARS`, lowercased.
+fn proxy_hosts(var: impl Fn(&str) -> Option<String>) -> HashSet<String> {
+    PROXY_ENV_VARS
+        .iter()
+        .filter_map(|key| var(key))
+        .filter_map(|value| {
+            // reqwest accepts a bare `host:port` and assumes http://
+            let url = Url::parse(&value)
+                .ok()
+                .filter(Url::has_host)
+                .or_else(|| Url::parse(&format!("http://{value}")).ok())?;
+            Some(url.host_str()?.trim_end_matches('.').to_ascii_lowercase())
+        })
+        .collect()
+}
+
+/// Resolver for the SSRF-safe client: names resolving to private addresses
+/// are refused at connect time, whatever they looked like as strings. IP
+/// literals never reach a resolver; [`blocked_reason`] covers those.
+///
+/// The operator's own proxy (`HTTPS_PROXY` and friends) is exempt: corporate
+/// proxies usually live on private addresses, and the proxy is trusted
+/// configuration rather than request input. Request URLs are still checked
+/// by [`blocked_reason`], so a proxy doesn't open a path to internal hosts.
+struct PublicOnlyResolver {
+    proxy_hosts: HashSet<String>,
+}
+
+impl PublicOnlyResolver {
+    fn from_env() -> Self {
+        Self {
+            proxy_hosts: proxy_hosts(|key| std::env::var(key).ok()),
+        }
+    }
+}
+
+impl Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let trusted = self
+            .proxy_hosts
+            .contains(&name.as_str().trim_end_matches('.').to_ascii_lowercase());
+        Box::pin(async move {
+            let host = name.as_str();
+            let public: Vec<SocketAddr> = tokio::net::lookup_host((host, 0))
+                .await?
+                .filter(|addr| trusted || !is_private_ip(addr.ip()))
+                .collect();
+            if public.is_empty() {
+                return Err(format!("SSRF: {host} resolves only to private addresses").into());
+            }
+            Ok(Box::new(public.into_iter()) as Addrs)
+        })
+    }
+}
+
 /// Validates that a URL does not point to a private/internal address.
 /// Returns Ok(()) if safe, Err with reason if blocked.
 pub fn validate_url_not_private(url: &str) -> Result<(), String> {
     let parsed = Url::parse(url).map_err(|e| format!("Invalid URL: {e}"))?;
+    match blocked_reason(&parsed) {
+        Some(reason) => Err(format!("Blocked {reason}")),
+        None => Ok(()),
+    }
+}
 
-    let host = parsed.host_str().unwrap_or("");
+/// Extra TLS trust applied to every SSRF-safe client.
+#[derive(Default)]
+struct TlsSettings {
+    roots: Vec<Certificate>,
+    accept_invalid_certs: bool,
+}
 
-    // Block known internal hostnames
-    let host_lower = host.to_lowercase();
-    for blocked in BLOCKED_HOSTS {
-        if host_lower == *blocked || host_lower.ends_with(&format!(".{blocked}")) {
-            return Err(format!("Blocked internal hostname: {host}"));
-        }
+static TLS_SETTINGS: OnceLock<TlsSettings> = OnceLock::new();
+
+/// Install `[providers]` TLS settings for every provider client built after
+/// this call. Call once at startup, before any provider is created; the
+/// first call wins.
+pub fn configure_tls(config: &Config) -> anyhow::Result<()> {
+    let settings = tls_settings(config)?;
+    if settings.accept_invalid_certs {
+        tracing::warn!(
+            "⚠️  providers.danger_accept_invalid_certs is ON: provider TLS certificates are NOT verified. \
+             Prompts, replies and API keys can be intercepted. Use for local development only."
+        );
     }
+    let _ = TLS_SETTINGS.set(settings);
+    Ok(())
+}
 
-    // If it parses as an IP, check directly
-    if let Ok(ip) = host.parse::<IpAddr>() {
-        if is_private_ip(ip) {
-            return Err(format!("Blocked private IP: {ip}"));
+fn tls_settings(config: &Config) -> anyhow::Result<TlsSettings> {
+    let roots = match &config.providers.ca_cert_path {
+        Some(path) => {
+            let config_dir = config.config_path.parent().unwrap_or(Path::new("."));
+            load_ca_bundle(&config_dir.join(path))?
         }
-    }
+        None => Vec::new(),
+    };
+    let settings = TlsSettings {
+        roots,
+        accept_invalid_certs: config.providers.danger_accept_invalid_certs,
+    };
+    // Fail at startup rather than silently falling back to a default client.
+    apply_tls(Client::builder(), &settings)
+        .build()
+        .context("Failed to build a provider client with providers.ca_cert_path")?;
+    Ok(settings)
+}
 
-    // Bracket-stripped IPv6 check (e.g., [::1])
-    if host.starts_with('[') && host.ends_with(']') {
-        if let Ok(ip) = host[1..host.len() - 1].parse::<IpAddr>() {
-            if is_private_ip(ip) {
-                return Err(format!("Blocked private IP: {ip}"));
-            }
-        }
+/// Every certificate in a PEM bundle; a bundle without any is an error.
+fn load_ca_bundle(path: &Path) -> anyhow::Result<Vec<Certificate>> {
+    let pem = std::fs::read(path)
+        .with_context(|| format!("Failed to read CA bundle {}", path.display()))?;
+    let certs = Certificate::from_pem_bundle(&pem)
+        .with_context(|| format!("Invalid PEM in CA bundle {}", path.display()))?;
+    if certs.is_empty() {
+        anyhow::bail!("CA bundle {} contains no certificates", path.display());
     }
+    Ok(certs)
+}
 
-    Ok(())
+fn apply_tls(mut builder: ClientBuilder, settings: &TlsSettings) -> ClientBuilder {
+    for cert in &settings.roots {
+        builder = builder.add_root_certificate(cert.clone());
+    }
+    builder.danger_accept_invalid_certs(settings.accept_invalid_certs)
 }
 
 /// Build a reqwest `Client` with SSRF-safe redirect policy and standard timeouts.
@@ -87,42 +242,18 @@ pub fn validate_url_not_private(url: &str) -> Result<(), String> {
 /// Max 10 redirects. Providers that intentionally target localhost (e.g. Ollama)
 /// should NOT use this — use `Client::builder()` directly instead.
 pub fn build_ssrf_safe_client() -> Client {
-    Client::builder()
+    build_with_tls(TLS_SETTINGS.get().unwrap_or(&TlsSettings::default()))
+}
+
+fn build_with_tls(tls: &TlsSettings) -> Client {
+    let builder = Client::builder()
         .timeout(std::time::Duration::from_secs(120))
         .connect_timeout(std::time::Duration::from_secs(10))
+        .dns_resolver(Arc::new(PublicOnlyResolver::from_env()))
         .redirect(redirect::Policy::custom(|attempt| {
             // Extract host info before consuming `attempt`
-            let reject_reason = {
-                let url = attempt.url();
-                url.host_str().and_then(|host| {
-                    let host_lower = host.to_lowercase();
-
-                    // Block known internal hostnames
-                    for blocked in BLOCKED_HOSTS {
-                        if host_lower == *blocked || host_lower.ends_with(&format!(".{blocked}")) {
-                            return Some(format!("SSRF: redirect to blocked host: {host}"));
-                        }
-                    }
-
-                    // Block private IPs
-                    if let Ok(ip) = host.parse::<IpAddr>() {
-                        if is_private_ip(ip) {
-                            return Some(format!("SSRF: redirect to private IP: {ip}"));
-                        }
-                    }
-
-                    // Bracket-stripped IPv6
-                    if host.starts_with('[') && host.ends_with(']') {
-                        if let Ok(ip) = host[1..host.len() - 1].parse::<IpAddr>() {
-                            if is_private_ip(ip) {
-                                return Some(format!("SSRF: redirect to private IP: {ip}"));
-                            }
-                        }
-                    }
-
-                    None
-                })
-            };
+            let reject_reason =
+                blocked_reason(attempt.url()).map(|reason| format!("SSRF: redirect to {reason}"));
 
             if let Some(reason) = reject_reason {
                 return attempt.error(std::io::Error::new(
@@ -137,7 +268,8 @@ pub fn build_ssrf_safe_client() -> Client {
             } else {
                 attempt.follow()
             }
-        }))
+        }));
+    apply_tls(builder, tls)
         .build()
         .unwrap_or_else(|_| Client::new())
 }
@@ -236,6 +368,89 @@ mod tests {
         assert!(validate_url_not_private("https://openrouter.ai/api/v1/chat").is_ok());
     }
 
+    #[test]
+    fn blocks_disguised_internal_hosts() {
+        for url in [
+            "http://localhost./",
+            "http://LOCALHOST/",
+            "http://ｌｏｃａｌｈｏｓｔ/",
+            "http://metadata.google.internal./",
+            "http://[::ffff:127.0.0.1]/",
+            "http://[::ffff:169.254.169.254]/",
+            "http://0x7f.1/",
+            "http://2130706433/",
+            "http://0.0.0.0/",
+        ] {
+            assert!(validate_url_not_private(url).is_err(), "{url}");
+        }
+    }
+
+    #[test]
+    fn blocks_homograph_hosts() {
+        for url in [
+            // U+217C SMALL ROMAN NUMERAL FIFTY folds to "l"
+            "http://\u{217c}ocalhost/",
+            // Cyrillic о: punycodes to a single label
+            "http://l\u{43e}calhost/",
+            "http://xn--lcalhost-0fg/",
+            // Cyrillic о in google, still under .internal
+            "http://metadata.g\u{43e}\u{43e}gle.internal/",
+            "http://printer.local/",
+            "http://api.localhost/",
+        ] {
+            assert!(validate_url_not_private(url).is_err(), "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn resolver_refuses_names_with_only_private_addresses() {
+        let resolver = PublicOnlyResolver {
+            proxy_hosts: HashSet::new(),
+        };
+        let err = resolver
+            .resolve("localhost".parse().unwrap())
+            .await
+            .err()
+            .expect("localhost must not resolve");
+        assert!(err.to_string().contains("private addresses"));
+    }
+
+    #[tokio::test]
+    async fn resolver_lets_the_configured_proxy_resolve_privately() {
+        let resolver = PublicOnlyResolver {
+            proxy_hosts: proxy_hosts(|key| {
+                (key == "HTTPS_PROXY").then(|| "http://LocalHost:3128".to_string())
+            }),
+        };
+        let addrs: Vec<SocketAddr> = resolver
+            .resolve("localhost".parse().unwrap())
+            .await
+            .expect("proxy host must resolve")
+            .collect();
+        assert!(addrs.iter().all(|addr| addr.ip().is_loopback()));
+    }
+
+    #[test]
+    fn proxy_hosts_accept_bare_host_port() {
+        let hosts = proxy_hosts(|key| match key {
+            "http_proxy" => Some("proxy.corp.internal:8080".into()),
+            "ALL_PROXY" => Some("socks5://10.0.0.5:1080".into()),
+            "HTTPS_PROXY" => Some("not a url".into()),
+            _ => None,
+        });
+        assert_eq!(
+            hosts,
+            HashSet::from(["proxy.corp.internal".to_string(), "10.0.0.5".to_string()])
+        );
+    }
+
+    #[test]
+    fn lookalike_hosts_are_not_blocked() {
+        // Only whole labels match: "notlocalhost" is somebody else's domain
+        assert!(validate_url_not_private("https://notlocalhost.com/").is_ok());
+        assert!(validate_url_not_private("https://[2607:f8b0::1]/").is_ok());
+    }
+
     #[test]
     fn rejects_invalid_url() {
         assert!(validate_url_not_private("not a url").is_err());
@@ -249,4 +464,62 @@ mod tests {
         // Smoke test — client should construct without panic
         drop(client);
     }
+
+    // ── configure_tls ─────────────────────────────────────────
+
+    const TEST_CA: &str = "-----BEGIN CERTIFICATE-----
+MIIBhjCCAS2gAwIBAgIUFpQDGOgbbYZsygjgksCk1zvvCJQwCgYIKoZIzj0EAwIw
+GDEWMBQGA1UEAwwNYmFpaHUgdGVzdCBDQTAgFw0yNjEwMTcyMjIyMTBaGA8yMTI2
+MDkyMzIyMjIxMFowGDEWMBQGA1UEAwwNYmFpaHUgdGVzdCBDQTBZMBMGByqGSM49
+AgEGCCqGSM49AwEHA0IABH9+hf/+pz+2FlanR0UVeyNLFHZoYhUaWz6wEstCVDe1
+Y6wZZ2E5dZvfp1nEitdrLv60JELiGtTi1JtTUiJG8TKjUzBRMB0GA1UdDgQWBBTp
+yeVtZ+ADujKMIk+F/ViEYd40CDAfBgNVHSMEGDAWgBTpyeVtZ+ADujKMIk+F/ViE
+Yd40CDAPBgNVHRMBAf8EBTADAQH/MAoGCCqGSM49BAMCA0cAMEQCIEnvMCdz1t3B
+qZ2OTcYZWVAix0X5lmS3qeXfxngr54GKAiAF+ZgvGZvQ6NaKuUskEWsQ0bZdLAbe
+W+d8xNw7JkX9IQ==
+-----END CERTIFICATE-----
+";
+
+    fn config_in(dir: &Path) -> Config {
+        Config {
+            config_path: dir.join("config.toml"),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn ca_bundle_path_is_relative_to_config_dir() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("corp-ca.pem"), TEST_CA.repeat(2)).unwrap();
+        let mut config = config_in(tmp.path());
+        config.providers.ca_cert_path = Some("corp-ca.pem".into());
+
+        let settings = tls_settings(&config).unwrap();
+        assert_eq!(settings.roots.len(), 2);
+        assert!(!settings.accept_invalid_certs);
+        drop(build_with_tls(&settings));
+    }
+
+    #[test]
+    fn unusable_ca_bundle_is_an_error() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut config = config_in(tmp.path());
+
+        config.providers.ca_cert_path = Some("missing.pem".into());
+        let err = tls_settings(&config).err().unwrap();
+        assert!(format!("{err:#}").contains("missing.pem"));
+
+        std::fs::write(tmp.path().join("empty.pem"), "not a certificate\n").unwrap();
+        config.providers.ca_cert_path = Some("empty.pem".into());
+        let err = tls_settings(&config).err().unwrap();
+        assert!(err.to_string().contains("no certificates"));
+    }
+
+    #[test]
+    fn tls_defaults_add_nothing() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let settings = tls_settings(&config_in(tmp.path())).unwrap();
+        assert!(settings.roots.is_empty());
+        assert!(!settings.accept_invalid_certs);
+    }
 }
@@ -1,95 +1,135 @@
 pub mod anthropic;
+pub mod client;
 pub mod compatible;
+pub mod error;
 pub mod http_client;
 pub mod ollama;
 pub mod openai;
 pub mod openrouter;
+pub mod origin;
 pub mod reliable;
+pub mod request_body;
+pub mod stream;
+pub mod swap;
+pub mod tap;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod traits;
 
-pub use traits::Provider;
+pub use client::ChatClient;
+pub use error::{ProviderChainError, UnknownProviderError};
+pub use reliable::SelectionStrategy;
+pub use swap::SwappableProvider;
+pub use traits::{Provider, ToolFormat};
 
 use compatible::{AuthStyle, OpenAiCompatibleProvider};
 use reliable::ReliableProvider;
+use request_body::RequestBody;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Semaphore;
+
+static REQUEST_LIMITER: OnceLock<Arc<Semaphore>> = OnceLock::new();
+
+/// Process-wide limiter shared by every resilient provider chain, so bursts
+/// across channels, heartbeat and cron all queue behind the same cap.
+/// The first caller's `max_concurrent` wins.
+fn request_limiter(max_concurrent: usize) -> Arc<Semaphore> {
+    Arc::clone(REQUEST_LIMITER.get_or_init(|| Arc::new(Semaphore::new(max_concurrent.max(1)))))
+}
+
+static PROVIDER_LIMITERS: OnceLock<parking_lot::Mutex<HashMap<String, Arc<Semaphore>>>> =
+    OnceLock::new();
+
+/// Process-wide limiter for the chain entry `name`, shared by every chain
+/// that calls it, so the cap holds per provider rather than per chain.
+/// The first caller's `max_concurrent` wins.
+fn provider_limiter(name: &str, max_concurrent: usize) -> Arc<Semaphore> {
+    let limiters = PROVIDER_LIMITERS.get_or_init(parking_lot::Mutex::default);
+    Arc::clone(
+        limiters
+            .lock()
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(max_concurrent.max(1)))),
+    )
+}
 
 /// Factory: create the right provider from config
-#[allow(clippy::too_many_lines)]
 pub fn create_provider(name: &str, api_key: Option<&str>) -> anyhow::Result<Box<dyn Provider>> {
+    create_provider_with_body(name, api_key, &RequestBody::default())
+}
+
+/// Like [`create_provider`], applying `body` overrides to every request payload.
+#[allow(clippy::too_many_lines)]
+pub fn create_provider_with_body(
+    name: &str,
+    api_key: Option<&str>,
+    body: &RequestBody,
+) -> anyhow::Result<Box<dyn Provider>> {
+    // Surface bad header overrides now rather than on the first request.
+    body.header_map()
+        .map_err(|e| anyhow::anyhow!("Invalid request_overrides for {name}: {e:#}"))?;
+
+    let compat = |label: &str, base_url: &str, auth| -> anyhow::Result<Box<dyn Provider>> {
+        Ok(Box::new(
+            OpenAiCompatibleProvider::new(label, base_url, api_key, auth)
+                .with_request_body(body.clone()),
+        ))
+    };
+
     match name {
         // ── Primary providers (custom implementations) ───────
-        "openrouter" => Ok(Box::new(openrouter::OpenRouterProvider::new(api_key))),
-        "anthropic" => Ok(Box::new(anthropic::AnthropicProvider::new(api_key))),
-        "openai" => Ok(Box::new(openai::OpenAiProvider::new(api_key))),
-        "ollama" => Ok(Box::new(ollama::OllamaProvider::new(
-            api_key.filter(|k| !k.is_empty()),
-        ))),
+        "openrouter" => Ok(Box::new(
+            openrouter::OpenRouterProvider::new(api_key).with_request_body(body.clone()),
+        )),
+        "anthropic" => Ok(Box::new(
+            anthropic::AnthropicProvider::new(api_key).with_request_body(body.clone()),
+        )),
+        "openai" => Ok(Box::new(
+            openai::OpenAiProvider::new(api_key).with_request_body(body.clone()),
+        )),
+        "ollama" => Ok(Box::new(
+            ollama::OllamaProvider::new(api_key.filter(|k| !k.is_empty()))
+                .with_request_body(body.clone()),
+        )),
 
         // ── OpenAI-compatible providers ──────────────────────
-        "venice" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "Venice", "https://api.venice.ai", api_key, AuthStyle::Bearer,
-        ))),
-        "vercel" | "vercel-ai" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "Vercel AI Gateway", "https://api.vercel.ai", api_key, AuthStyle::Bearer,
-        ))),
-        "cloudflare" | "cloudflare-ai" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "Cloudflare AI Gateway",
-            "https://gateway.ai.cloudflare.com/v1",
-            api_key,
-            AuthStyle::Bearer,
-        ))),
-        "moonshot" | "kimi" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "Moonshot", "https://api.moonshot.cn", api_key, AuthStyle::Bearer,
-        ))),
-        "synthetic" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "Synthetic", "https://api.synthetic.com", api_key, AuthStyle::Bearer,
-        ))),
-        "opencode" | "opencode-zen" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "OpenCode Zen", "https://api.opencode.ai", api_key, AuthStyle::Bearer,
-        ))),
-        "zai" | "z.ai" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "Z.AI", "https://api.z.ai", api_key, AuthStyle::Bearer,
-        ))),
-        "glm" | "zhipu" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "GLM", "https://open.bigmodel.cn/api/paas", api_key, AuthStyle::Bearer,
-        ))),
-        "minimax" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "MiniMax", "https://api.minimax.chat", api_key, AuthStyle::Bearer,
-        ))),
-        "bedrock" | "aws-bedrock" => Ok(Box::new(OpenAiCompatibleProvider::new(
+        "venice" => compat("Venice", "https://api.venice.ai", AuthStyle::Bearer),
+        "vercel" | "vercel-ai" => compat(
+            "Vercel AI Gateway", "https://api.vercel.ai", AuthStyle::Bearer,
+        ),
+        "cloudflare" | "cloudflare-ai" => compat(
+            "Cloudflare AI Gateway", "https://gateway.ai.cloudflare.com/v1", AuthStyle::Bearer,
+        ),
+        "moonshot" | "kimi" => compat("Moonshot", "https://api.moonshot.cn", AuthStyle::Bearer),
+        "synthetic" => compat("Synthetic", "https://api.synthetic.com", AuthStyle::Bearer),
+        "opencode" | "opencode-zen" => compat(
+            "OpenCode Zen", "https://api.opencode.ai", AuthStyle::Bearer,
+        ),
+        "zai" | "z.ai" => compat("Z.AI", "https://api.z.ai", AuthStyle::Bearer),
+        "glm" | "zhipu" => compat("GLM", "https://open.bigmodel.cn/api/paas", AuthStyle::Bearer),
+        "minimax" => compat("MiniMax", "https://api.minimax.chat", AuthStyle::Bearer),
+        "bedrock" | "aws-bedrock" => compat(
             "Amazon Bedrock",
             "https://bedrock-runtime.us-east-1.amazonaws.com",
-            api_key,
             AuthStyle::Bearer,
-        ))),
-        "qianfan" | "baidu" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "Qianfan", "https://aip.baidubce.com", api_key, AuthStyle::Bearer,
-        ))),
+        ),
+        "qianfan" | "baidu" => compat("Qianfan", "https://aip.baidubce.com", AuthStyle::Bearer),
 
         // ── Extended ecosystem (community favorites) ─────────
-        "groq" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "Groq", "https://api.groq.com/openai", api_key, AuthStyle::Bearer,
-        ))),
-        "mistral" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "Mistral", "https://api.mistral.ai", api_key, AuthStyle::Bearer,
-        ))),
-        "xai" | "grok" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "xAI", "https://api.x.ai", api_key, AuthStyle::Bearer,
-        ))),
-        "deepseek" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "DeepSeek", "https://api.deepseek.com", api_key, AuthStyle::Bearer,
-        ))),
-        "together" | "together-ai" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "Together AI", "https://api.together.xyz", api_key, AuthStyle::Bearer,
-        ))),
-        "fireworks" | "fireworks-ai" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "Fireworks AI", "https://api.fireworks.ai/inference", api_key, AuthStyle::Bearer,
-        ))),
-        "perplexity" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "Perplexity", "https://api.perplexity.ai", api_key, AuthStyle::Bearer,
-        ))),
-        "cohere" => Ok(Box::new(OpenAiCompatibleProvider::new(
-            "Cohere", "https://api.cohere.com/compatibility", api_key, AuthStyle::Bearer,
-        ))),
+        "groq" => compat("Groq", "https://api.groq.com/openai", AuthStyle::Bearer),
+        "mistral" => compat("Mistral", "https://api.mistral.ai", AuthStyle::Bearer),
+        "xai" | "grok" => compat("xAI", "https://api.x.ai", AuthStyle::Bearer),
+        "deepseek" => compat("DeepSeek", "https://api.deepseek.com", AuthStyle::Bearer),
+        "together" | "together-ai" => compat(
+            "Together AI", "https://api.together.xyz", AuthStyle::Bearer,
+        ),
+        "fireworks" | "fireworks-ai" => compat(
+            "Fireworks AI", "https://api.fireworks.ai/inference", AuthStyle::Bearer,
+        ),
+        "perplexity" => compat("Perplexity", "https://api.perplexity.ai", AuthStyle::Bearer),
+        "cohere" => compat("Cohere", "https://api.cohere.com/compatibility", AuthStyle::Bearer),
 
         // ── Bring Your Own Provider (custom URL) ───────────
         // Format: "custom:https://your-api.com" or "custom:http://localhost:1234"
@@ -98,12 +138,7 @@ pub fn create_provider(name: &str, api_key: Option<&str>) -> anyhow::Result<Box<
             if base_url.is_empty() {
                 anyhow::bail!("Custom provider requires a URL. Format: custom:https://your-api.com");
             }
-            Ok(Box::new(OpenAiCompatibleProvider::new(
-                "Custom",
-                base_url,
-                api_key,
-                AuthStyle::Bearer,
-            )))
+            compat("Custom", base_url, AuthStyle::Bearer)
         }
 
         _ => anyhow::bail!(
@@ -113,25 +148,125 @@ pub fn create_provider(name: &str, api_key: Option<&str>) -> anyhow::Result<Box<
     }
 }
 
+/// Context windows by model-name prefix, longest prefix first within each
+/// family. Lookups take the longest match anyway, so `gpt-4o` and `gpt-4.1`
+/// never fall through to the 8K `gpt-4`.
+const CONTEXT_WINDOWS: &[(&str, usize)] = &[
+    ("claude", 200_000),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4o", 128_000),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4", 200_000),
+    ("gemini-1.5", 1_048_576),
+    ("gemini-2", 1_048_576),
+    ("mistral-large", 131_072),
+    ("llama-3.1", 131_072),
+    ("llama-3.3", 131_072),
+    ("deepseek", 65_536),
+];
+
+/// Published context windows for common hosted models, matched on the model
+/// name with any `vendor/` prefix stripped. Unknown models return `None`.
+pub fn known_context_window(model: &str) -> Option<usize> {
+    let name = model
+        .rsplit('/')
+        .next()
+        .unwrap_or(model)
+        .to_ascii_lowercase();
+    CONTEXT_WINDOWS
+        .iter()
+        .filter(|(prefix, _)| name.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, window)| *window)
+}
+
+/// Rough token count for budgeting: ~4 characters per token.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Where the resilient provider chain of `instance` (the subsystem that
+/// owns it, e.g. `gateway`) persists breaker/latency stats between restarts.
+/// Each chain gets its own file, so chains in one process don't overwrite
+/// each other's stats.
+pub fn state_file_path(config: &crate::config::Config, instance: &str) -> PathBuf {
+    let instance: String = instance
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    config
+        .config_path
+        .parent()
+        .map_or_else(|| PathBuf::from("."), PathBuf::from)
+        .join(format!("provider_state.{instance}.json"))
+}
+
 /// Create provider chain with retry and fallback behavior.
 pub fn create_resilient_provider(
     primary_name: &str,
     api_key: Option<&str>,
     reliability: &crate::config::ReliabilityConfig,
 ) -> anyhow::Result<Box<dyn Provider>> {
+    create_resilient_provider_with_state(
+        primary_name,
+        api_key,
+        reliability,
+        &HashMap::new(),
+        None,
+        None,
+    )
+}
+
+/// Like [`create_resilient_provider`], restoring and persisting runtime stats
+/// at `state_path` so failover decisions survive restarts. Cache hits are
+/// reported to `observer` when one is given. `request_overrides` is keyed by
+/// provider name, as in config.
+#[allow(clippy::implicit_hasher)]
+pub fn create_resilient_provider_with_state(
+    primary_name: &str,
+    api_key: Option<&str>,
+    reliability: &crate::config::ReliabilityConfig,
+    request_overrides: &HashMap<String, RequestBody>,
+    state_path: Option<&Path>,
+    observer: Option<Arc<dyn crate::observability::Observer>>,
+) -> anyhow::Result<Box<dyn Provider>> {
+    let no_overrides = RequestBody::default();
+    let body_for = |name: &str| request_overrides.get(name).unwrap_or(&no_overrides);
     let mut providers: Vec<(String, Box<dyn Provider>)> = Vec::new();
 
     providers.push((
         primary_name.to_string(),
-        create_provider(primary_name, api_key)?,
+        create_provider_with_body(primary_name, api_key, body_for(primary_name))?,
     ));
 
+    let mut model_map = reliability.model_map.clone();
+    for (i, key) in reliability.primary_api_keys.iter().enumerate() {
+        let name = format!("{primary_name}#{}", i + 2);
+        if let Some(primary_map) = reliability.model_map.get(primary_name) {
+            model_map.insert(name.clone(), primary_map.clone());
+        }
+        providers.push((
+            name,
+            create_provider_with_body(primary_name, Some(key), body_for(primary_name))?,
+        ));
+    }
+
     for fallback in &reliability.fallback_providers {
         if fallback == primary_name || providers.iter().any(|(name, _)| name == fallback) {
             continue;
         }
 
-        match create_provider(fallback, api_key) {
+        match create_provider_with_body(fallback, api_key, body_for(fallback)) {
             Ok(provider) => providers.push((fallback.clone(), provider)),
             Err(e) => {
                 tracing::warn!(
@@ -142,11 +277,85 @@ pub fn create_resilient_provider(
         }
     }
 
-    Ok(Box::new(ReliableProvider::new(
+    let mut reliable = ReliableProvider::new(
         providers,
         reliability.provider_retries,
         reliability.provider_backoff_ms,
-    )))
+    )
+    .with_concurrency_limit(request_limiter(reliability.max_concurrent_requests))
+    .with_stream_restart_limit(reliability.stream_restart_max_chars);
+    if let Some(per_provider) = reliability.max_concurrent_per_provider {
+        reliable = reliable.with_provider_limiters(|name| provider_limiter(name, per_provider));
+    }
+    if reliability.max_total_attempts.is_some() || reliability.total_deadline_ms.is_some() {
+        reliable = reliable.with_retry_budget(
+            reliability.max_total_attempts,
+            reliability
+                .total_deadline_ms
+                .map(std::time::Duration::from_millis),
+        );
+    }
+    if reliability.strategy != SelectionStrategy::Fallback {
+        reliable =
+            reliable.with_strategy(reliability.strategy, reliability.provider_weights.clone());
+    }
+    if !model_map.is_empty() {
+        reliable = reliable.with_model_map(model_map);
+    }
+    if let Some(path) = state_path {
+        reliable = reliable.with_state_file(path.to_path_buf());
+    }
+    if let Some(observer) = observer {
+        reliable = reliable.with_observer(observer);
+    }
+
+    Ok(Box::new(reliable))
+}
+
+/// Wrap `provider` in a [`tap::TapProvider`] when `[observability] provider_tap`
+/// is on. A tap file that can't be opened is logged and skipped rather than
+/// stopping startup.
+pub fn with_configured_tap(
+    provider: Box<dyn Provider>,
+    config: &crate::config::Config,
+) -> Box<dyn Provider> {
+    if !config.observability.provider_tap {
+        return provider;
+    }
+    let path = config
+        .observability
+        .provider_tap_path
+        .clone()
+        .unwrap_or_else(|| tap::FileTap::default_path(&config.config_path));
+    match tap::FileTap::open(&path) {
+        Ok(file_tap) => {
+            tracing::warn!(path = %path.display(), "Provider tap enabled: prompts and replies are being recorded");
+            Box::new(tap::TapProvider::new(
+                provider,
+                Arc::new(file_tap),
+                tap_secrets(config),
+            ))
+        }
+        Err(e) => {
+            tracing::error!("Provider tap disabled: {e:#}");
+            provider
+        }
+    }
+}
+
+/// Every credential the chain may send: the primary and extra API keys, and
+/// the `[request_overrides]` headers that carry fallback providers' auth.
+fn tap_secrets(config: &crate::config::Config) -> Vec<String> {
+    let mut secrets: Vec<String> = config
+        .api_key
+        .iter()
+        .chain(&config.reliability.primary_api_keys)
+        .cloned()
+        .collect();
+    for overrides in config.request_overrides.values() {
+        secrets.extend(overrides.secret_values());
+    }
+    secrets
 }
 
 #[cfg(test)]
@@ -155,6 +364,52 @@ mod tests {
 
     // ── Primary providers ────────────────────────────────────
 
+    #[test]
+    fn tap_secrets_include_override_headers() {
+        let mut config = crate::config::Config {
+            api_key: Some("sk-primary".into()),
+            ..crate::config::Config::default()
+        };
+        config.reliability.primary_api_keys = vec!["sk-second".into()];
+        config.request_overrides.insert(
+            "anthropic".into(),
+            RequestBody {
+                headers: HashMap::from([("x-api-key".into(), "sk-ant-fallback".into())]),
+                ..RequestBody::default()
+            },
+        );
+        assert_eq!(
+            tap_secrets(&config),
+            ["sk-primary", "sk-second", "sk-ant-fallback"]
+        );
+    }
+
+    #[test]
+    fn provider_limiter_is_shared_by_name() {
+        let a = provider_limiter("limiter-test-a", 2);
+        assert!(Arc::ptr_eq(&a, &provider_limiter("limiter-test-a", 5)));
+        assert_eq!(a.available_permits(), 2);
+        assert!(!Arc::ptr_eq(&a, &provider_limiter("limiter-test-b", 2)));
+    }
+
+    #[test]
+    fn state_file_is_per_instance() {
+        let config = crate::config::Config {
+            config_path: PathBuf::from("/etc/baihu/config.toml"),
+            ..crate::config::Config::default()
+        };
+        let gateway = state_file_path(&config, "gateway");
+        assert_eq!(
+            gateway,
+            PathBuf::from("/etc/baihu/provider_state.gateway.json")
+        );
+        assert_ne!(gateway, state_file_path(&config, "channels"));
+        assert_eq!(
+            state_file_path(&config, "agent-../x"),
+            PathBuf::from("/etc/baihu/provider_state.agent-___x.json")
+        );
+    }
+
     #[test]
     fn factory_openrouter() {
         assert!(create_provider("openrouter", Some("sk-test")).is_ok());
@@ -319,6 +574,50 @@ mod tests {
 
     // ── Error cases ──────────────────────────────────────────
 
+    #[test]
+    fn known_context_windows() {
+        assert_eq!(
+            known_context_window("anthropic/claude-sonnet-4-20250514"),
+            Some(200_000)
+        );
+        assert_eq!(known_context_window("gpt-4o-mini"), Some(128_000));
+        assert_eq!(known_context_window("gpt-4.1"), Some(1_047_576));
+        assert_eq!(known_context_window("gpt-4"), Some(8_192));
+        assert_eq!(known_context_window("gpt-4-0613"), Some(8_192));
+        assert_eq!(
+            known_context_window("openai/gpt-4o-2024-08-06"),
+            Some(128_000)
+        );
+        assert_eq!(known_context_window("GPT-4.1-mini"), Some(1_047_576));
+        assert_eq!(known_context_window("gpt-4-turbo-preview"), Some(128_000));
+        assert_eq!(known_context_window("my-local-finetune"), None);
+    }
+
+    #[test]
+    fn context_window_advertised_by_hosted_providers() {
+        let anthropic = create_provider("anthropic", Some("k")).unwrap();
+        assert_eq!(anthropic.context_window("claude-3-5-haiku"), Some(200_000));
+        let ollama = create_provider("ollama", None).unwrap();
+        assert_eq!(ollama.context_window("llama3"), None);
+    }
+
+    #[test]
+    fn tool_format_matches_vendor_api() {
+        let anthropic = create_provider("anthropic", Some("k")).unwrap();
+        assert_eq!(anthropic.tool_format(), ToolFormat::Anthropic);
+        for name in ["openai", "openrouter", "ollama", "groq"] {
+            let provider = create_provider(name, Some("k")).unwrap();
+            assert_eq!(provider.tool_format(), ToolFormat::OpenAi, "{name}");
+        }
+    }
+
+    #[test]
+    fn estimate_tokens_rounds_up() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abc"), 1);
+        assert_eq!(estimate_tokens("abcdefgh"), 2);
+    }
+
     #[test]
     fn factory_unknown_provider_errors() {
         let p = create_provider("nonexistent", None);
@@ -348,12 +647,26 @@ mod tests {
             channel_max_backoff_secs: 60,
             scheduler_poll_secs: 15,
             scheduler_retries: 2,
+            ..crate::config::ReliabilityConfig::default()
         };
 
         let provider = create_resilient_provider("openrouter", Some("sk-test"), &reliability);
         assert!(provider.is_ok());
     }
 
+    #[test]
+    fn resilient_provider_accepts_extra_primary_keys() {
+        let reliability = crate::config::ReliabilityConfig {
+            strategy: SelectionStrategy::RoundRobin,
+            primary_api_keys: vec!["sk-two".into(), "sk-three".into()],
+            provider_weights: [("openrouter#2".to_string(), 2)].into(),
+            ..crate::config::ReliabilityConfig::default()
+        };
+
+        let provider = create_resilient_provider("openrouter", Some("sk-one"), &reliability);
+        assert!(provider.is_ok());
+    }
+
     #[test]
     fn resilient_provider_errors_for_invalid_primary() {
         let reliability = crate::config::ReliabilityConfig::default();
@@ -1,3 +1,4 @@
+use crate::providers::request_body::RequestBody;
 use crate::providers::traits::Provider;
 use async_trait::async_trait;
 use reqwest::Client;
@@ -6,6 +7,7 @@ use serde::{Deserialize, Serialize};
 pub struct OllamaProvider {
     base_url: String,
     client: Client,
+    body: RequestBody,
 }
 
 #[derive(Debug, Serialize)]
@@ -49,8 +51,15 @@ impl OllamaProvider {
                 .connect_timeout(std::time::Duration::from_secs(10))
                 .build()
                 .unwrap_or_else(|_| Client::new()),
+            body: RequestBody::default(),
         }
     }
+
+    /// Strip and merge fields in every request payload.
+    pub fn with_request_body(mut self, body: RequestBody) -> Self {
+        self.body = body;
+        self
+    }
 }
 
 #[async_trait]
@@ -85,7 +94,13 @@ impl Provider for OllamaProvider {
 
         let url = format!("{}/api/chat", self.base_url);
 
-        let response = self.client.post(&url).json(&request).send().await?;
+        let response = self
+            .client
+            .post(&url)
+            .headers(self.body.header_map()?)
+            .json(&self.body.apply(&request)?)
+            .send()
+            .await?;
 
         if !response.status().is_success() {
             let error = response.text().await?;
@@ -97,6 +112,27 @@ impl Provider for OllamaProvider {
         let chat_response: ChatResponse = response.json().await?;
         Ok(chat_response.message.content)
     }
+
+    async fn preflight(&self) -> anyhow::Result<()> {
+        let url = format!("{}/api/tags", self.base_url);
+        let response = self
+            .client
+            .get(&url)
+            .headers(self.body.header_map()?)
+            .timeout(std::time::Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Ollama is not reachable at {}: {e}. Is `ollama serve` running?",
+                    self.base_url
+                )
+            })?;
+        if !response.status().is_success() {
+            anyhow::bail!("Ollama answered {} at {url}", response.status());
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
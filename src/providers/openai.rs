@@ -1,3 +1,5 @@
+use crate::providers::error::ProviderError;
+use crate::providers::request_body::RequestBody;
 use crate::providers::traits::Provider;
 use async_trait::async_trait;
 use reqwest::Client;
@@ -6,6 +8,7 @@ use serde::{Deserialize, Serialize};
 pub struct OpenAiProvider {
     api_key: Option<String>,
     client: Client,
+    body: RequestBody,
 }
 
 #[derive(Debug, Serialize)]
@@ -41,8 +44,15 @@ impl OpenAiProvider {
         Self {
             api_key: api_key.map(ToString::to_string),
             client: super::http_client::build_ssrf_safe_client(),
+            body: RequestBody::default(),
         }
     }
+
+    /// Strip and merge fields in every request payload.
+    pub fn with_request_body(mut self, body: RequestBody) -> Self {
+        self.body = body;
+        self
+    }
 }
 
 #[async_trait]
@@ -82,13 +92,15 @@ impl Provider for OpenAiProvider {
             .client
             .post("https://api.openai.com/v1/chat/completions")
             .header("Authorization", format!("Bearer {api_key}"))
-            .json(&request)
+            .headers(self.body.header_map()?)
+            .json(&self.body.apply(&request)?)
             .send()
             .await?;
 
         if !response.status().is_success() {
-            let error = response.text().await?;
-            anyhow::bail!("OpenAI API error: {error}");
+            return Err(ProviderError::from_response("OpenAI", response)
+                .await
+                .into());
         }
 
         let chat_response: ChatResponse = response.json().await?;
@@ -100,6 +112,29 @@ impl Provider for OpenAiProvider {
             .map(|c| c.message.content)
             .ok_or_else(|| anyhow::anyhow!("No response from OpenAI"))
     }
+
+    fn context_window(&self, model: &str) -> Option<usize> {
+        super::known_context_window(model)
+    }
+
+    async fn preflight(&self) -> anyhow::Result<()> {
+        let api_key = self.api_key.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("OpenAI API key not set. Set OPENAI_API_KEY or edit config.toml.")
+        })?;
+        let response = self
+            .client
+            .get("https://api.openai.com/v1/models")
+            .header("Authorization", format!("Bearer {api_key}"))
+            .headers(self.body.header_map()?)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response("OpenAI", response)
+                .await
+                .into());
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
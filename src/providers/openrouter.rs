@@ -1,3 +1,5 @@
+use crate::providers::error::ProviderError;
+use crate::providers::request_body::RequestBody;
 use crate::providers::traits::Provider;
 use async_trait::async_trait;
 use reqwest::Client;
@@ -6,6 +8,7 @@ use serde::{Deserialize, Serialize};
 pub struct OpenRouterProvider {
     api_key: Option<String>,
     client: Client,
+    body: RequestBody,
 }
 
 #[derive(Debug, Serialize)]
@@ -41,8 +44,15 @@ impl OpenRouterProvider {
         Self {
             api_key: api_key.map(ToString::to_string),
             client: super::http_client::build_ssrf_safe_client(),
+            body: RequestBody::default(),
         }
     }
+
+    /// Strip and merge fields in every request payload.
+    pub fn with_request_body(mut self, body: RequestBody) -> Self {
+        self.body = body;
+        self
+    }
 }
 
 #[async_trait]
@@ -83,13 +93,15 @@ impl Provider for OpenRouterProvider {
             .header("Authorization", format!("Bearer {api_key}"))
             .header("HTTP-Referer", "https://github.com/visualstudioblyat/baihu")
             .header("X-Title", "Baihu")
-            .json(&request)
+            .headers(self.body.header_map()?)
+            .json(&self.body.apply(&request)?)
             .send()
             .await?;
 
         if !response.status().is_success() {
-            let error = response.text().await?;
-            anyhow::bail!("OpenRouter API error: {error}");
+            return Err(ProviderError::from_response("OpenRouter", response)
+                .await
+                .into());
         }
 
         let chat_response: ChatResponse = response.json().await?;
@@ -101,4 +113,27 @@ impl Provider for OpenRouterProvider {
             .map(|c| c.message.content)
             .ok_or_else(|| anyhow::anyhow!("No response from OpenRouter"))
     }
+
+    fn context_window(&self, model: &str) -> Option<usize> {
+        super::known_context_window(model)
+    }
+
+    /// `/models` is public, so check the key itself instead.
+    async fn preflight(&self) -> anyhow::Result<()> {
+        let api_key = self.api_key.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("OpenRouter API key not set. Run `baihu onboard` or set OPENROUTER_API_KEY env var."))?;
+        let response = self
+            .client
+            .get("https://openrouter.ai/api/v1/key")
+            .header("Authorization", format!("Bearer {api_key}"))
+            .headers(self.body.header_map()?)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response("OpenRouter", response)
+                .await
+                .into());
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,79 @@
+//! Where a provider call came from, for cost attribution and debugging.
+//!
+//! Callers wrap their work in [`scope`] (a channel message, a heartbeat task,
+//! a webhook request); the provider layer reads [`current`] and stamps the
+//! label on `ProviderCall`/`CacheHit` observer events and tap records. The
+//! label is task-local: a future handed to `tokio::spawn` must be wrapped
+//! again to keep it.
+
+use std::future::Future;
+use std::sync::Arc;
+
+/// Short label such as `channel:telegram`, `heartbeat`, `gateway` or `cli`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestOrigin(Arc<str>);
+
+impl RequestOrigin {
+    pub fn new(label: impl Into<Arc<str>>) -> Self {
+        Self(label.into())
+    }
+
+    /// A message that arrived on channel `name`.
+    pub fn channel(name: &str) -> Self {
+        Self::new(format!("channel:{name}"))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for RequestOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+tokio::task_local! {
+    static ORIGIN: RequestOrigin;
+}
+
+/// Run `fut` with provider calls attributed to `origin`.
+pub async fn scope<F: Future>(origin: RequestOrigin, fut: F) -> F::Output {
+    ORIGIN.scope(origin, fut).await
+}
+
+/// Origin of the work running on this task, if a caller set one.
+pub fn current() -> Option<RequestOrigin> {
+    ORIGIN.try_with(Clone::clone).ok()
+}
+
+/// [`current`] as a plain label, for events and records.
+pub fn current_label() -> Option<String> {
+    current().map(|origin| origin.as_str().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn label_is_visible_only_inside_scope() {
+        assert_eq!(current(), None);
+        let inside = scope(RequestOrigin::channel("telegram"), async {
+            current_label()
+        })
+        .await;
+        assert_eq!(inside.as_deref(), Some("channel:telegram"));
+        assert_eq!(current_label(), None);
+    }
+
+    #[tokio::test]
+    async fn inner_scope_wins() {
+        let label = scope(RequestOrigin::new("gateway"), async {
+            scope(RequestOrigin::new("heartbeat"), async { current_label() }).await
+        })
+        .await;
+        assert_eq!(label.as_deref(), Some("heartbeat"));
+    }
+}
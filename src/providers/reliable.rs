@@ -1,25 +1,190 @@
-use super::Provider;
+use super::error::{ChainFailures, UnknownProviderError};
+use super::stream::{is_retryable, StreamChunk, StreamInterrupted, STREAM_BUFFER};
+use super::traits::{ProviderFailure, ProviderPin, ProviderStatsSnapshot};
+use super::{Provider, ToolFormat};
+use crate::observability::{Observer, ObserverEvent};
+use crate::security::redact::redact;
+use crate::util::{jittered_backoff, random_u32, DEFAULT_JITTER_FRACTION};
 use async_trait::async_trait;
 use dashmap::DashMap;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::hash::{Hash, Hasher};
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Semaphore};
 
 /// Cached provider response with TTL.
 struct CachedResponse {
+    /// Full request the response belongs to, checked on every hit so a
+    /// 64-bit key collision can never serve another request's answer.
+    request: CacheRequest,
     content: String,
     created_at: Instant,
 }
 
+/// Everything that shapes a completion.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheRequest {
+    system_prompt: Option<String>,
+    message: String,
+    model: String,
+    temperature_bits: u64,
+}
+
+impl CacheRequest {
+    fn new(system_prompt: Option<&str>, message: &str, model: &str, temperature: f64) -> Self {
+        Self {
+            system_prompt: system_prompt.map(str::to_string),
+            message: message.to_string(),
+            model: model.to_string(),
+            temperature_bits: temperature.to_bits(),
+        }
+    }
+
+    fn key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
 const CACHE_TTL_SECS: u64 = 60;
+/// Consecutive failed attempts before a provider's breaker opens.
+const BREAKER_THRESHOLD: u32 = 3;
+/// How long an open breaker keeps a provider out of rotation.
+const BREAKER_COOLDOWN_SECS: i64 = 30;
+/// Minimum spacing between stats snapshots written to disk.
+const STATS_FLUSH_SECS: u64 = 30;
+/// Weight of the newest sample in the latency moving average.
+const LATENCY_EWMA_ALPHA: f64 = 0.2;
+/// Reply characters a failed stream may have sent and still be restarted.
+pub const DEFAULT_STREAM_RESTART_CHARS: usize = 500;
+/// Failed calls kept for [`ReliableProvider::recent_failures`].
+const FAILURE_HISTORY: usize = 100;
+/// Longest error message kept per failure.
+const FAILURE_MESSAGE_MAX_CHARS: usize = 500;
+
+/// Which provider in the chain takes the first attempt of a request.
+/// Whatever is picked, the remaining providers are still tried on failure.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionStrategy {
+    /// Always start with the first provider (strict fallback order).
+    #[default]
+    Fallback,
+    /// Rotate the starting provider on every request.
+    RoundRobin,
+    /// Pick the starting provider at random, in proportion to its weight.
+    Weighted,
+}
+
+/// Round-robin position for a chain, shared by every [`ReliableProvider`]
+/// over the same providers in this process. Channels, the gateway and cron
+/// each build their own chain, and a per-instance counter would start each
+/// of them at the head.
+fn round_robin_counter(chain: Vec<String>) -> Arc<AtomicUsize> {
+    static COUNTERS: OnceLock<Mutex<HashMap<Vec<String>, Arc<AtomicUsize>>>> = OnceLock::new();
+    let mut counters = COUNTERS.get_or_init(Mutex::default).lock();
+    Arc::clone(counters.entry(chain).or_default())
+}
+
+/// Runtime health of one provider in the chain.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProviderStats {
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    /// Moving average latency of successful calls.
+    pub avg_latency_ms: Option<f64>,
+    /// Unix timestamp until which the breaker is open.
+    pub open_until: Option<i64>,
+}
+
+/// Everything worth carrying across a restart.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReliabilitySnapshot {
+    pub providers: BTreeMap<String, ProviderStats>,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+}
+
+/// Classifies an HTTP-successful response as usable or not. Returning `Err`
+/// turns the response into a retryable failure so failover kicks in.
+pub type ResponseValidator = Arc<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+/// Default validator: an empty or whitespace-only completion is a failure.
+pub fn reject_empty_response(response: &str) -> Result<(), String> {
+    if response.trim().is_empty() {
+        Err("provider returned an empty response".into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Decides whether a successful response may be stored in the response cache.
+pub type CacheFilter = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Markers of a tool call in a reply: the text tool protocols models use, and
+/// native tool-call JSON passed through as text.
+const TOOL_CALL_MARKERS: &[&str] = &[
+    "<tool_call",
+    "<function_calls",
+    "<invoke",
+    "\"tool_calls\"",
+    "\"tool_use\"",
+];
+
+/// Default cache filter: never cache a reply that asks for a tool call.
+/// Replaying one from the cache would skip running the tool, so the next
+/// identical prompt must go to the provider again.
+pub fn cacheable_response(response: &str) -> bool {
+    !TOOL_CALL_MARKERS
+        .iter()
+        .any(|marker| response.contains(marker))
+}
 
 /// Provider wrapper with retry + fallback behavior + response caching.
 pub struct ReliableProvider {
     providers: Vec<(String, Box<dyn Provider>)>,
     max_retries: u32,
     base_backoff_ms: u64,
+    strategy: SelectionStrategy,
+    /// Weights for [`SelectionStrategy::Weighted`], keyed by provider name (default 1).
+    weights: HashMap<String, u32>,
+    /// Next starting provider for [`SelectionStrategy::RoundRobin`], shared
+    /// with other instances over the same chain.
+    next_provider: Arc<AtomicUsize>,
     cache: Arc<DashMap<u64, CachedResponse>>,
+    /// Caps in-flight calls across the whole chain (may be shared between instances).
+    limiter: Option<Arc<Semaphore>>,
+    /// Optional per-provider caps, keyed by provider name.
+    provider_limiters: HashMap<String, Arc<Semaphore>>,
+    /// Requested model → provider-specific model name, keyed by provider name.
+    model_maps: HashMap<String, HashMap<String, String>>,
+    /// Cap on calls per request across the whole chain, on top of per-provider retries.
+    max_total_attempts: Option<u32>,
+    /// Wall-clock budget per request across the whole chain.
+    total_deadline: Option<Duration>,
+    validator: ResponseValidator,
+    cache_filter: CacheFilter,
+    /// Past this many streamed characters a failure ends the reply as
+    /// truncated instead of restarting it.
+    stream_restart_chars: usize,
+    observer: Option<Arc<dyn Observer>>,
+    stats: Mutex<BTreeMap<String, ProviderStats>>,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    /// Counters since start, separate from the persisted `stats`.
+    session: Mutex<ProviderStatsSnapshot>,
+    /// The last [`FAILURE_HISTORY`] failed calls, oldest first.
+    failures: Mutex<VecDeque<ProviderFailure>>,
+    state_path: Option<PathBuf>,
+    last_flush: Mutex<Option<Instant>>,
 }
 
 impl ReliableProvider {
@@ -28,52 +193,453 @@ impl ReliableProvider {
         max_retries: u32,
         base_backoff_ms: u64,
     ) -> Self {
+        let chain = providers.iter().map(|(name, _)| name.clone()).collect();
         Self {
             providers,
             max_retries,
             base_backoff_ms: base_backoff_ms.max(50),
+            strategy: SelectionStrategy::Fallback,
+            weights: HashMap::new(),
+            next_provider: round_robin_counter(chain),
             cache: Arc::new(DashMap::new()),
+            limiter: None,
+            provider_limiters: HashMap::new(),
+            model_maps: HashMap::new(),
+            max_total_attempts: None,
+            total_deadline: None,
+            validator: Arc::new(reject_empty_response),
+            cache_filter: Arc::new(cacheable_response),
+            stream_restart_chars: DEFAULT_STREAM_RESTART_CHARS,
+            observer: None,
+            stats: Mutex::new(BTreeMap::new()),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            session: Mutex::new(ProviderStatsSnapshot::default()),
+            failures: Mutex::new(VecDeque::with_capacity(FAILURE_HISTORY)),
+            state_path: None,
+            last_flush: Mutex::new(None),
         }
     }
 
-    fn cache_key(message: &str, model: &str) -> u64 {
-        let mut hasher = DefaultHasher::new();
-        message.hash(&mut hasher);
-        model.hash(&mut hasher);
-        hasher.finish()
+    /// Restore stats from `path` (best-effort) and periodically snapshot them back.
+    pub fn with_state_file(mut self, path: PathBuf) -> Self {
+        if let Some(snapshot) = load_snapshot(&path) {
+            self.restore(snapshot);
+        }
+        self.state_path = Some(path);
+        self
+    }
+
+    /// Seed breaker and latency state, e.g. from a previous run.
+    pub fn restore(&self, snapshot: ReliabilitySnapshot) {
+        let mut stats = self.stats.lock();
+        for (name, provider_stats) in snapshot.providers {
+            if self.providers.iter().any(|(n, _)| *n == name) {
+                stats.insert(name, provider_stats);
+            }
+        }
+        self.cache_hits
+            .store(snapshot.cache_hits, Ordering::Relaxed);
+        self.cache_misses
+            .store(snapshot.cache_misses, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> ReliabilitySnapshot {
+        ReliabilitySnapshot {
+            providers: self.stats.lock().clone(),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Cache and per-provider outcome counters since this process started.
+    pub fn stats(&self) -> ProviderStatsSnapshot {
+        self.session.lock().clone()
+    }
+
+    fn breaker_open(&self, provider: &str) -> bool {
+        let now = chrono::Utc::now().timestamp();
+        self.stats
+            .lock()
+            .get(provider)
+            .and_then(|s| s.open_until)
+            .is_some_and(|until| until > now)
+    }
+
+    fn record_success(&self, provider: &str, latency: Duration) {
+        #[allow(clippy::cast_precision_loss)]
+        let sample = latency.as_millis() as f64;
+        {
+            let mut session = self.session.lock();
+            let entry = session.providers.entry(provider.to_string()).or_default();
+            entry.successes += 1;
+            #[allow(clippy::cast_precision_loss)]
+            let n = entry.successes as f64;
+            let avg = entry.avg_latency_ms.unwrap_or(0.0);
+            entry.avg_latency_ms = Some(avg + (sample - avg) / n);
+        }
+
+        let mut stats = self.stats.lock();
+        let entry = stats.entry(provider.to_string()).or_default();
+        entry.successes += 1;
+        entry.consecutive_failures = 0;
+        entry.open_until = None;
+        entry.avg_latency_ms = Some(match entry.avg_latency_ms {
+            Some(avg) => avg + LATENCY_EWMA_ALPHA * (sample - avg),
+            None => sample,
+        });
+    }
+
+    /// Failed calls since start, oldest first, capped at the last
+    /// [`FAILURE_HISTORY`]. Not persisted.
+    pub fn recent_failures(&self) -> Vec<ProviderFailure> {
+        self.failures.lock().iter().cloned().collect()
+    }
+
+    fn record_failure(&self, provider: &str, will_retry: bool, error: &anyhow::Error) {
+        {
+            let mut history = self.failures.lock();
+            if history.len() == FAILURE_HISTORY {
+                history.pop_front();
+            }
+            history.push_back(ProviderFailure {
+                provider: provider.to_string(),
+                at: chrono::Utc::now().to_rfc3339(),
+                // Key-shaped strings in echoed error bodies stay out of the log.
+                error: redact(&error.to_string(), &[])
+                    .chars()
+                    .take(FAILURE_MESSAGE_MAX_CHARS)
+                    .collect(),
+                retried: will_retry,
+            });
+        }
+        {
+            let mut session = self.session.lock();
+            let entry = session.providers.entry(provider.to_string()).or_default();
+            entry.failures += 1;
+            if will_retry {
+                entry.retries += 1;
+            }
+        }
+
+        let mut stats = self.stats.lock();
+        let entry = stats.entry(provider.to_string()).or_default();
+        entry.failures += 1;
+        entry.consecutive_failures = entry.consecutive_failures.saturating_add(1);
+        if entry.consecutive_failures >= BREAKER_THRESHOLD {
+            entry.open_until = Some(chrono::Utc::now().timestamp() + BREAKER_COOLDOWN_SECS);
+        }
+    }
+
+    /// Write a snapshot if a state file is configured and enough time has passed.
+    fn maybe_flush(&self) {
+        let Some(path) = &self.state_path else {
+            return;
+        };
+        {
+            let mut last = self.last_flush.lock();
+            if last.is_some_and(|at| at.elapsed() < Duration::from_secs(STATS_FLUSH_SECS)) {
+                return;
+            }
+            *last = Some(Instant::now());
+        }
+        let Ok(data) = serde_json::to_vec_pretty(&self.snapshot()) else {
+            return;
+        };
+        let path = path.clone();
+        tokio::spawn(async move {
+            if let Err(e) = crate::security::atomic_write::atomic_write_async(&path, data).await {
+                tracing::debug!("Failed to persist provider stats: {e}");
+            }
+        });
+    }
+
+    /// Replace the check deciding which responses are cached (default:
+    /// [`cacheable_response`]).
+    pub fn with_cache_filter(mut self, filter: CacheFilter) -> Self {
+        self.cache_filter = filter;
+        self
+    }
+
+    /// Replace the post-response validator (default: [`reject_empty_response`]).
+    pub fn with_response_validator(mut self, validator: ResponseValidator) -> Self {
+        self.validator = validator;
+        self
+    }
+
+    /// Restart a failed stream from scratch, on the same provider or the next,
+    /// only while it has sent at most `max_chars` characters. Past that the
+    /// consumer keeps the partial reply and the stream ends truncated.
+    pub fn with_stream_restart_limit(mut self, max_chars: usize) -> Self {
+        self.stream_restart_chars = max_chars;
+        self
+    }
+
+    /// Queue calls behind `limiter` so at most its permit count run concurrently.
+    pub fn with_concurrency_limit(mut self, limiter: Arc<Semaphore>) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    /// Additionally cap in-flight calls to each individual provider in the
+    /// chain, queueing them behind the semaphore `limiter_for` returns for its
+    /// name. Hand out shared semaphores to cap a provider across chains.
+    pub fn with_provider_limiters(mut self, limiter_for: impl Fn(&str) -> Arc<Semaphore>) -> Self {
+        self.provider_limiters = self
+            .providers
+            .iter()
+            .map(|(name, _)| (name.clone(), limiter_for(name)))
+            .collect();
+        self
+    }
+
+    /// Spread first attempts across the chain instead of always starting at the
+    /// head. `weights` only matter for [`SelectionStrategy::Weighted`]; a weight
+    /// of 0 keeps a provider as fallback-only.
+    pub fn with_strategy(
+        mut self,
+        strategy: SelectionStrategy,
+        weights: HashMap<String, u32>,
+    ) -> Self {
+        self.strategy = strategy;
+        self.weights = weights;
+        self
+    }
+
+    /// Indices into `providers` in the order this request should try them.
+    /// A set pin naming a provider in the chain leaves only that provider;
+    /// a preferred one moves to the front.
+    fn provider_order(
+        &self,
+        pin: Option<&ProviderPin>,
+    ) -> Result<Vec<usize>, UnknownProviderError> {
+        let position = |name: &str| self.providers.iter().position(|(n, _)| n == name);
+        if let Some(pin) = pin {
+            if let Some(preferred) = &pin.preferred {
+                if position(preferred).is_none() {
+                    return Err(UnknownProviderError {
+                        requested: preferred.clone(),
+                        available: self.providers.iter().map(|(n, _)| n.clone()).collect(),
+                    });
+                }
+            }
+            if let Some(name) = pin.get() {
+                if let Some(index) = position(&name) {
+                    return Ok(vec![index]);
+                }
+                tracing::warn!(provider = %name, "Pinned provider is not in the chain, ignoring pin");
+            }
+            if let Some(index) = pin.preferred.as_deref().and_then(position) {
+                let mut order: Vec<usize> = (0..self.providers.len()).collect();
+                order[..=index].rotate_right(1);
+                return Ok(order);
+            }
+        }
+        let mut order: Vec<usize> = (0..self.providers.len()).collect();
+        match self.strategy {
+            _ if order.len() < 2 => {}
+            SelectionStrategy::Fallback => {}
+            SelectionStrategy::RoundRobin => {
+                let start = self.next_provider.fetch_add(1, Ordering::Relaxed) % order.len();
+                order.rotate_left(start);
+            }
+            SelectionStrategy::Weighted => {
+                // Keep the rest in configured order so fallback stays predictable.
+                let first = self.weighted_pick();
+                order[..=first].rotate_right(1);
+            }
+        }
+        Ok(order)
+    }
+
+    fn weighted_pick(&self) -> usize {
+        let weights: Vec<u64> = self
+            .providers
+            .iter()
+            .map(|(name, _)| u64::from(self.weights.get(name).copied().unwrap_or(1)))
+            .collect();
+        let total: u64 = weights.iter().sum();
+        if total == 0 {
+            return 0;
+        }
+        let mut roll = u64::from(random_u32()) % total;
+        for (index, weight) in weights.iter().enumerate() {
+            if roll < *weight {
+                return index;
+            }
+            roll -= weight;
+        }
+        0
+    }
+
+    /// Translate requested model names per provider before each call.
+    /// The cache stays keyed on the caller's model name.
+    pub fn with_model_map(mut self, model_maps: HashMap<String, HashMap<String, String>>) -> Self {
+        self.model_maps = model_maps;
+        self
+    }
+
+    /// Share one attempt/time budget across all providers. Once either runs out the
+    /// call fails with the aggregated error, even if some providers were never tried.
+    pub fn with_retry_budget(
+        mut self,
+        max_total_attempts: Option<u32>,
+        total_deadline: Option<Duration>,
+    ) -> Self {
+        self.max_total_attempts = max_total_attempts.map(|n| n.max(1));
+        self.total_deadline = total_deadline;
+        self
+    }
+
+    fn budget_exhausted(
+        &self,
+        attempts: u32,
+        deadline: Option<tokio::time::Instant>,
+    ) -> Option<String> {
+        if self.max_total_attempts.is_some_and(|max| attempts >= max) {
+            return Some(format!("{attempts} total attempts used"));
+        }
+        if deadline.is_some_and(|at| tokio::time::Instant::now() >= at) {
+            return Some("total deadline reached".into());
+        }
+        None
+    }
+
+    fn provider_model<'a>(&'a self, provider: &str, model: &'a str) -> &'a str {
+        self.model_maps
+            .get(provider)
+            .and_then(|map| map.get(model))
+            .map_or(model, String::as_str)
+    }
+
+    /// Report cache hits and each provider call to this observer, tagged with
+    /// the caller's [`origin`](super::origin).
+    pub fn with_observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    fn report_call(&self, provider: &str, model: &str, duration: Duration, success: bool) {
+        if let Some(observer) = &self.observer {
+            observer.record_event(&ObserverEvent::ProviderCall {
+                provider: provider.to_string(),
+                model: model.to_string(),
+                origin: super::origin::current_label(),
+                duration,
+                success,
+            });
+        }
+    }
+
+    /// Fresh cached response for `request`, counting the hit or miss.
+    fn cached(&self, key: u64, request: &CacheRequest) -> Option<String> {
+        if let Some(entry) = self.cache.get(&key) {
+            if entry.request == *request && entry.created_at.elapsed().as_secs() < CACHE_TTL_SECS {
+                self.cache_hits.fetch_add(1, Ordering::Relaxed);
+                self.session.lock().cache_hits += 1;
+                tracing::debug!(model = %request.model, "Serving cached provider response");
+                if let Some(observer) = &self.observer {
+                    observer.record_event(&ObserverEvent::CacheHit {
+                        model: request.model.clone(),
+                        origin: super::origin::current_label(),
+                    });
+                }
+                return Some(entry.content.clone());
+            }
+            // Expired, or a different request that happens to share the key.
+            drop(entry);
+            self.cache.remove(&key);
+        }
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
+        self.session.lock().cache_misses += 1;
+        None
     }
 }
 
-#[async_trait]
-impl Provider for ReliableProvider {
-    async fn chat_with_system(
+impl ReliableProvider {
+    /// Run the chain. With `use_cache` off the lookup is skipped, but a fresh
+    /// answer still refreshes the cache for later cached callers. With a
+    /// `pin`, only the pinned provider is tried, or the first to succeed
+    /// becomes the pinned one.
+    #[allow(clippy::too_many_lines)]
+    async fn complete(
         &self,
         system_prompt: Option<&str>,
         message: &str,
         model: &str,
         temperature: f64,
+        use_cache: bool,
+        pin: Option<&ProviderPin>,
     ) -> anyhow::Result<String> {
-        // Check cache first
-        let key = Self::cache_key(message, model);
-        if let Some(entry) = self.cache.get(&key) {
-            if entry.created_at.elapsed().as_secs() < CACHE_TTL_SECS {
-                return Ok(entry.content.clone());
+        let request = CacheRequest::new(system_prompt, message, model, temperature);
+        let key = request.key();
+        if use_cache {
+            if let Some(content) = self.cached(key, &request) {
+                return Ok(content);
             }
-            drop(entry);
-            self.cache.remove(&key);
         }
 
-        let mut failures = Vec::new();
+        // Held across retries and fallbacks; released when the call returns.
+        let _permit = match &self.limiter {
+            Some(limiter) => Some(limiter.acquire().await?),
+            None => None,
+        };
+
+        let mut failures = ChainFailures::default();
+        let order = self.provider_order(pin)?;
+        // If every breaker is open, try them all anyway rather than failing outright.
+        let all_open = order
+            .iter()
+            .all(|&i| self.breaker_open(&self.providers[i].0));
+        let deadline = self.total_deadline.map(|d| tokio::time::Instant::now() + d);
+        let mut total_attempts = 0_u32;
 
-        for (provider_name, provider) in &self.providers {
+        'providers: for (provider_name, provider) in order.iter().map(|&i| &self.providers[i]) {
+            if !all_open && self.breaker_open(provider_name) {
+                failures.note(format!("{provider_name}: circuit open, skipped"));
+                continue;
+            }
+            let _provider_permit = match self.provider_limiters.get(provider_name) {
+                Some(limiter) => Some(limiter.acquire().await?),
+                None => None,
+            };
             let mut backoff_ms = self.base_backoff_ms;
+            let provider_model = self.provider_model(provider_name, model);
 
             for attempt in 0..=self.max_retries {
-                match provider
-                    .chat_with_system(system_prompt, message, model, temperature)
-                    .await
-                {
+                if let Some(reason) = self.budget_exhausted(total_attempts, deadline) {
+                    failures.note(format!("retry budget exhausted: {reason}"));
+                    break 'providers;
+                }
+                total_attempts += 1;
+
+                let started = Instant::now();
+                let call =
+                    provider.chat_with_system(system_prompt, message, provider_model, temperature);
+                let result = match deadline {
+                    Some(at) => tokio::time::timeout_at(at, call)
+                        .await
+                        .unwrap_or_else(|_| Err(anyhow::anyhow!("total deadline reached"))),
+                    None => call.await,
+                };
+                let result = result.and_then(|resp| match (self.validator)(&resp) {
+                    Ok(()) => Ok(resp),
+                    Err(reason) => Err(anyhow::anyhow!("invalid response: {reason}")),
+                });
+
+                self.report_call(
+                    provider_name,
+                    provider_model,
+                    started.elapsed(),
+                    result.is_ok(),
+                );
+                match result {
                     Ok(resp) => {
+                        self.record_success(provider_name, started.elapsed());
+                        self.maybe_flush();
+                        if let Some(pin) = pin {
+                            pin.set_if_unset(provider_name);
+                        }
                         if attempt > 0 {
                             tracing::info!(
                                 provider = provider_name,
@@ -81,22 +647,24 @@ impl Provider for ReliableProvider {
                                 "Provider recovered after retries"
                             );
                         }
-                        // Cache the successful response
-                        self.cache.insert(
-                            key,
-                            CachedResponse {
-                                content: resp.clone(),
-                                created_at: Instant::now(),
-                            },
-                        );
+                        if (self.cache_filter)(&resp) {
+                            self.cache.insert(
+                                key,
+                                CachedResponse {
+                                    request: request.clone(),
+                                    content: resp.clone(),
+                                    created_at: Instant::now(),
+                                },
+                            );
+                        } else {
+                            // A stale entry must not outlive a reply we refused to cache.
+                            self.cache.remove(&key);
+                        }
                         return Ok(resp);
                     }
                     Err(e) => {
-                        failures.push(format!(
-                            "{provider_name} attempt {}/{}: {e}",
-                            attempt + 1,
-                            self.max_retries + 1
-                        ));
+                        self.record_failure(provider_name, attempt < self.max_retries, &e);
+                        failures.record(provider_name, attempt + 1, self.max_retries + 1, &e);
 
                         if attempt < self.max_retries {
                             tracing::warn!(
@@ -105,8 +673,11 @@ impl Provider for ReliableProvider {
                                 max_retries = self.max_retries,
                                 "Provider call failed, retrying"
                             );
-                            let jittered = apply_jitter(backoff_ms);
-                            tokio::time::sleep(Duration::from_millis(jittered)).await;
+                            let jittered = jittered_backoff(backoff_ms, DEFAULT_JITTER_FRACTION);
+                            let wake =
+                                tokio::time::Instant::now() + Duration::from_millis(jittered);
+                            tokio::time::sleep_until(deadline.map_or(wake, |at| wake.min(at)))
+                                .await;
                             backoff_ms = (backoff_ms.saturating_mul(2)).min(10_000);
                         }
                     }
@@ -116,29 +687,260 @@ impl Provider for ReliableProvider {
             tracing::warn!(provider = provider_name, "Switching to fallback provider");
         }
 
-        anyhow::bail!("All providers failed. Attempts:\n{}", failures.join("\n"))
+        self.maybe_flush();
+
+        Err(failures.into_error().into())
+    }
+
+    /// Stream through the chain. A failed attempt is retried like
+    /// [`Self::complete`] (after a [`StreamChunk::Reset`] if it sent text)
+    /// unless it already sent more than `stream_restart_chars`, or the error
+    /// won't go away on a retry, in which case the next provider gets a turn.
+    /// Responses are neither cached nor validated: the consumer sees them as
+    /// they arrive.
+    #[allow(clippy::too_many_lines)]
+    async fn stream(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        out: &mpsc::Sender<StreamChunk>,
+    ) -> anyhow::Result<()> {
+        let _permit = match &self.limiter {
+            Some(limiter) => Some(limiter.acquire().await?),
+            None => None,
+        };
+
+        let mut failures = ChainFailures::default();
+        let all_open = self
+            .providers
+            .iter()
+            .all(|(name, _)| self.breaker_open(name));
+        let deadline = self.total_deadline.map(|d| tokio::time::Instant::now() + d);
+        let mut total_attempts = 0_u32;
+        // Characters of the current attempt the consumer holds.
+        let mut emitted = 0_usize;
+
+        let order = self.provider_order(None)?;
+        'providers: for (provider_name, provider) in order.iter().map(|&i| &self.providers[i]) {
+            if !all_open && self.breaker_open(provider_name) {
+                failures.note(format!("{provider_name}: circuit open, skipped"));
+                continue;
+            }
+            let _provider_permit = match self.provider_limiters.get(provider_name) {
+                Some(limiter) => Some(limiter.acquire().await?),
+                None => None,
+            };
+            let mut backoff_ms = self.base_backoff_ms;
+            let provider_model = self.provider_model(provider_name, model);
+
+            for attempt in 0..=self.max_retries {
+                if let Some(reason) = self.budget_exhausted(total_attempts, deadline) {
+                    failures.note(format!("retry budget exhausted: {reason}"));
+                    break 'providers;
+                }
+                total_attempts += 1;
+                if emitted > 0 {
+                    let _ = out.send(StreamChunk::Reset).await;
+                    emitted = 0;
+                }
+
+                let started = Instant::now();
+                let call = forward_stream(
+                    provider.as_ref(),
+                    system_prompt,
+                    message,
+                    provider_model,
+                    temperature,
+                    out,
+                    &mut emitted,
+                );
+                let result = match deadline {
+                    Some(at) => tokio::time::timeout_at(at, call)
+                        .await
+                        .unwrap_or_else(|_| Err(anyhow::anyhow!("total deadline reached"))),
+                    None => call.await,
+                };
+
+                self.report_call(
+                    provider_name,
+                    provider_model,
+                    started.elapsed(),
+                    result.is_ok(),
+                );
+                let e = match result {
+                    Ok(()) => {
+                        self.record_success(provider_name, started.elapsed());
+                        self.maybe_flush();
+                        return Ok(());
+                    }
+                    Err(e) => e,
+                };
+                let retryable = is_retryable(&e);
+                if emitted > self.stream_restart_chars {
+                    self.record_failure(provider_name, false, &e);
+                    self.maybe_flush();
+                    return Err(StreamInterrupted { emitted, source: e }.into());
+                }
+                let will_retry = retryable && attempt < self.max_retries;
+                self.record_failure(provider_name, will_retry, &e);
+                failures.record(provider_name, attempt + 1, self.max_retries + 1, &e);
+                if !will_retry {
+                    break;
+                }
+                tracing::warn!(
+                    provider = provider_name,
+                    attempt = attempt + 1,
+                    emitted,
+                    "Provider stream failed, restarting"
+                );
+                let jittered = jittered_backoff(backoff_ms, DEFAULT_JITTER_FRACTION);
+                let wake = tokio::time::Instant::now() + Duration::from_millis(jittered);
+                tokio::time::sleep_until(deadline.map_or(wake, |at| wake.min(at))).await;
+                backoff_ms = (backoff_ms.saturating_mul(2)).min(10_000);
+            }
+
+            tracing::warn!(provider = provider_name, "Switching to fallback provider");
+        }
+
+        self.maybe_flush();
+
+        let error = anyhow::Error::from(failures.into_error());
+        if emitted > 0 {
+            return Err(StreamInterrupted {
+                emitted,
+                source: error,
+            }
+            .into());
+        }
+        Err(error)
+    }
+}
+
+/// One streamed call, passing its chunks on to `out` and counting the
+/// characters the consumer now holds in `emitted`.
+async fn forward_stream(
+    provider: &dyn Provider,
+    system_prompt: Option<&str>,
+    message: &str,
+    model: &str,
+    temperature: f64,
+    out: &mpsc::Sender<StreamChunk>,
+    emitted: &mut usize,
+) -> anyhow::Result<()> {
+    let (tx, mut rx) = mpsc::channel(STREAM_BUFFER);
+    let call = provider.chat_stream(system_prompt, message, model, temperature, tx);
+    let forward = async {
+        while let Some(chunk) = rx.recv().await {
+            match &chunk {
+                StreamChunk::Text(text) => *emitted += text.chars().count(),
+                StreamChunk::Reset => *emitted = 0,
+            }
+            let _ = out.send(chunk).await;
+        }
+    };
+    let (result, ()) = tokio::join!(call, forward);
+    result
+}
+
+#[async_trait]
+impl Provider for ReliableProvider {
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.complete(system_prompt, message, model, temperature, true, None)
+            .await
+    }
+
+    async fn chat_with_system_uncached(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.complete(system_prompt, message, model, temperature, false, None)
+            .await
+    }
+
+    async fn chat_pinned(
+        &self,
+        pin: &ProviderPin,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.complete(system_prompt, message, model, temperature, false, Some(pin))
+            .await
+    }
+
+    async fn chat_stream(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        chunks: mpsc::Sender<StreamChunk>,
+    ) -> anyhow::Result<()> {
+        self.stream(system_prompt, message, model, temperature, &chunks)
+            .await
+    }
+
+    /// The smallest window in the chain, since any provider may end up serving.
+    fn context_window(&self, model: &str) -> Option<usize> {
+        self.providers
+            .iter()
+            .filter_map(|(name, provider)| {
+                provider.context_window(self.provider_model(name, model))
+            })
+            .min()
+    }
+
+    /// Every provider in the chain, since any of them may end up serving.
+    async fn preflight(&self) -> anyhow::Result<()> {
+        let mut failures = Vec::new();
+        for (name, provider) in &self.providers {
+            if let Err(e) = provider.preflight().await {
+                failures.push(format!("{name}: {e}"));
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!("{}", failures.join("; "))
+        }
+    }
+
+    /// The primary's format. Tool definitions are built once per request, so
+    /// a chain should not mix providers that want different shapes.
+    fn tool_format(&self) -> ToolFormat {
+        self.providers
+            .first()
+            .map_or(ToolFormat::OpenAi, |(_, provider)| provider.tool_format())
+    }
+
+    fn stats(&self) -> Option<ProviderStatsSnapshot> {
+        Some(ReliableProvider::stats(self))
+    }
+
+    fn recent_failures(&self) -> Option<Vec<ProviderFailure>> {
+        Some(ReliableProvider::recent_failures(self))
     }
 }
 
-/// Adds +/-25% jitter to a backoff value to prevent thundering herd.
-/// Uses UUID v4 (OS CSPRNG) for random bytes.
-fn apply_jitter(base_ms: u64) -> u64 {
-    let random_bytes = uuid::Uuid::new_v4();
-    let raw = u32::from_le_bytes([
-        random_bytes.as_bytes()[0],
-        random_bytes.as_bytes()[1],
-        random_bytes.as_bytes()[2],
-        random_bytes.as_bytes()[3],
-    ]);
-    // Map raw u32 to [0.75, 1.25] range
-    let factor = 0.75 + (f64::from(raw) / f64::from(u32::MAX)) * 0.5;
-    #[allow(
-        clippy::cast_possible_truncation,
-        clippy::cast_sign_loss,
-        clippy::cast_precision_loss
-    )]
-    let result = (base_ms as f64 * factor) as u64;
-    result.max(1)
+/// Read a stats snapshot; missing or corrupt files just mean a cold start.
+/// A corrupt file is moved aside so it is not silently overwritten.
+fn load_snapshot(path: &Path) -> Option<ReliabilitySnapshot> {
+    crate::util::read_json_or_recover(path, "provider stats").unwrap_or_else(|e| {
+        tracing::warn!(path = %path.display(), "Ignoring unreadable provider stats: {e}");
+        None
+    })
 }
 
 #[cfg(test)]
@@ -149,18 +951,61 @@ mod tests {
 
     #[test]
     fn cache_key_deterministic() {
-        let k1 = ReliableProvider::cache_key("hello", "gpt-4");
-        let k2 = ReliableProvider::cache_key("hello", "gpt-4");
+        let k1 = CacheRequest::new(None, "hello", "gpt-4", 0.7).key();
+        let k2 = CacheRequest::new(None, "hello", "gpt-4", 0.7).key();
         assert_eq!(k1, k2);
     }
 
     #[test]
     fn cache_key_varies_by_model() {
-        let k1 = ReliableProvider::cache_key("hello", "gpt-4");
-        let k2 = ReliableProvider::cache_key("hello", "gpt-3.5");
+        let k1 = CacheRequest::new(None, "hello", "gpt-4", 0.7).key();
+        let k2 = CacheRequest::new(None, "hello", "gpt-3.5", 0.7).key();
         assert_ne!(k1, k2);
     }
 
+    #[test]
+    fn cache_key_varies_by_system_prompt_and_temperature() {
+        let base = CacheRequest::new(None, "hello", "gpt-4", 0.7).key();
+        assert_ne!(
+            base,
+            CacheRequest::new(Some("be terse"), "hello", "gpt-4", 0.7).key()
+        );
+        assert_ne!(base, CacheRequest::new(None, "hello", "gpt-4", 0.2).key());
+    }
+
+    #[tokio::test]
+    async fn colliding_key_does_not_cross_serve() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![(
+                "primary".into(),
+                Box::new(MockProvider {
+                    calls: Arc::clone(&calls),
+                    fail_until_attempt: 0,
+                    response: "right answer",
+                    error: "boom",
+                }),
+            )],
+            0,
+            1,
+        );
+
+        // Plant another request's answer under this request's 64-bit key.
+        let key = CacheRequest::new(None, "hello", "test", 0.0).key();
+        provider.cache.insert(
+            key,
+            CachedResponse {
+                request: CacheRequest::new(None, "something else", "test", 0.0),
+                content: "wrong answer".into(),
+                created_at: Instant::now(),
+            },
+        );
+
+        let resp = provider.chat("hello", "test", 0.0).await.unwrap();
+        assert_eq!(resp, "right answer");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
     #[tokio::test]
     async fn cache_returns_same_response() {
         let calls = Arc::new(AtomicUsize::new(0));
@@ -186,27 +1031,6 @@ mod tests {
         assert_eq!(calls.load(Ordering::SeqCst), 1);
     }
 
-    #[test]
-    fn jitter_within_bounds() {
-        for _ in 0..100 {
-            let result = apply_jitter(1000);
-            assert!(result >= 750, "Jitter too low: {result}");
-            assert!(result <= 1250, "Jitter too high: {result}");
-        }
-    }
-
-    #[test]
-    fn jitter_not_deterministic() {
-        let results: std::collections::HashSet<u64> = (0..20).map(|_| apply_jitter(1000)).collect();
-        assert!(results.len() > 1, "Jitter should produce varying values");
-    }
-
-    #[test]
-    fn jitter_minimum_one() {
-        assert!(apply_jitter(0) >= 1);
-        assert!(apply_jitter(1) >= 1);
-    }
-
     struct MockProvider {
         calls: Arc<AtomicUsize>,
         fail_until_attempt: usize,
@@ -309,13 +1133,772 @@ mod tests {
         assert_eq!(result, "from fallback");
         assert_eq!(primary_calls.load(Ordering::SeqCst), 2);
         assert_eq!(fallback_calls.load(Ordering::SeqCst), 1);
+
+        let failures = provider.recent_failures();
+        assert_eq!(failures.len(), 2);
+        assert!(failures.iter().all(|f| f.provider == "primary"));
+        assert!(failures[0].retried);
+        assert!(!failures[1].retried);
+        assert!(failures[1].error.contains("primary down"));
     }
 
     #[tokio::test]
-    async fn returns_aggregated_error_when_all_providers_fail() {
+    async fn failure_history_is_capped_and_redacted() {
+        let calls = Arc::new(AtomicUsize::new(0));
         let provider = ReliableProvider::new(
-            vec![
-                (
+            vec![(
+                "primary".into(),
+                failing("bad key sk-abcdefghijklmnopqrstuvwxyz0123", &calls),
+            )],
+            0,
+            1,
+        );
+
+        for i in 0..FAILURE_HISTORY + 5 {
+            let _ = provider.chat(&format!("hello {i}"), "test", 0.0).await;
+        }
+        let failures = provider.recent_failures();
+        assert_eq!(failures.len(), FAILURE_HISTORY);
+        assert!(!failures[0]
+            .error
+            .contains("sk-abcdefghijklmnopqrstuvwxyz0123"));
+    }
+
+    struct SlowProvider {
+        in_flight: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Provider for SlowProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(message.to_string())
+        }
+    }
+
+    async fn run_concurrently(provider: ReliableProvider, calls: usize) {
+        let provider = Arc::new(provider);
+        let handles: Vec<_> = (0..calls)
+            .map(|i| {
+                let provider = Arc::clone(&provider);
+                // Distinct messages so the response cache never short-circuits.
+                tokio::spawn(async move { provider.chat(&format!("msg-{i}"), "test", 0.0).await })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrency_limit_caps_in_flight_calls() {
+        let peak = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![(
+                "primary".into(),
+                Box::new(SlowProvider {
+                    in_flight: Arc::new(AtomicUsize::new(0)),
+                    peak: Arc::clone(&peak),
+                }),
+            )],
+            0,
+            1,
+        )
+        .with_concurrency_limit(Arc::new(Semaphore::new(2)));
+
+        run_concurrently(provider, 8).await;
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn per_provider_limit_caps_in_flight_calls() {
+        let peak = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![(
+                "primary".into(),
+                Box::new(SlowProvider {
+                    in_flight: Arc::new(AtomicUsize::new(0)),
+                    peak: Arc::clone(&peak),
+                }),
+            )],
+            0,
+            1,
+        )
+        .with_provider_limiters(|_| Arc::new(Semaphore::new(1)));
+
+        run_concurrently(provider, 4).await;
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn shared_provider_limiter_caps_calls_across_chains() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let shared = Arc::new(Semaphore::new(1));
+        let chain = || {
+            let shared = Arc::clone(&shared);
+            ReliableProvider::new(
+                vec![(
+                    "primary".into(),
+                    Box::new(SlowProvider {
+                        in_flight: Arc::clone(&in_flight),
+                        peak: Arc::clone(&peak),
+                    }) as Box<dyn Provider>,
+                )],
+                0,
+                1,
+            )
+            .with_provider_limiters(move |_| Arc::clone(&shared))
+        };
+
+        tokio::join!(run_concurrently(chain(), 2), run_concurrently(chain(), 2));
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn empty_response_fails_over_and_is_not_cached() {
+        let primary_calls = Arc::new(AtomicUsize::new(0));
+        let fallback_calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![
+                (
+                    "primary".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::clone(&primary_calls),
+                        fail_until_attempt: 0,
+                        response: "   ",
+                        error: "unused",
+                    }),
+                ),
+                (
+                    "fallback".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::clone(&fallback_calls),
+                        fail_until_attempt: 0,
+                        response: "real answer",
+                        error: "unused",
+                    }),
+                ),
+            ],
+            1,
+            1,
+        );
+
+        let result = provider.chat("hello", "test", 0.0).await.unwrap();
+        assert_eq!(result, "real answer");
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 2);
+        assert_eq!(fallback_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn custom_validator_rejects_refusals() {
+        let provider = ReliableProvider::new(
+            vec![(
+                "primary".into(),
+                Box::new(MockProvider {
+                    calls: Arc::new(AtomicUsize::new(0)),
+                    fail_until_attempt: 0,
+                    response: r#"{"error":"overloaded"}"#,
+                    error: "unused",
+                }),
+            )],
+            0,
+            1,
+        )
+        .with_response_validator(Arc::new(|resp: &str| {
+            if resp.contains("\"error\"") {
+                Err("error payload".into())
+            } else {
+                Ok(())
+            }
+        }));
+
+        let err = provider.chat("hello", "test", 0.0).await.unwrap_err();
+        assert!(err.to_string().contains("invalid response: error payload"));
+    }
+
+    #[test]
+    fn default_validator_rejects_blank() {
+        assert!(reject_empty_response("").is_err());
+        assert!(reject_empty_response(" \n\t").is_err());
+        assert!(reject_empty_response("hi").is_ok());
+    }
+
+    fn counted(calls: &Arc<AtomicUsize>, fail_until_attempt: usize) -> Box<dyn Provider> {
+        Box::new(MockProvider {
+            calls: Arc::clone(calls),
+            fail_until_attempt,
+            response: "ok",
+            error: "down",
+        })
+    }
+
+    #[test]
+    fn context_window_is_smallest_in_chain() {
+        use crate::providers::testing::ScriptedProvider;
+        let provider = ReliableProvider::new(
+            vec![
+                (
+                    "big".into(),
+                    Box::new(ScriptedProvider::new("ok").with_context_window(200_000)),
+                ),
+                ("unknown".into(), Box::new(ScriptedProvider::new("ok"))),
+                (
+                    "small".into(),
+                    Box::new(ScriptedProvider::new("ok").with_context_window(8_192)),
+                ),
+            ],
+            0,
+            1,
+        );
+        assert_eq!(provider.context_window("m"), Some(8_192));
+        assert_eq!(
+            ReliableProvider::new(vec![], 0, 1).context_window("m"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn preflight_checks_every_provider_in_chain() {
+        use crate::providers::testing::ScriptedProvider;
+        let provider = ReliableProvider::new(
+            vec![
+                ("primary".into(), Box::new(ScriptedProvider::new("ok"))),
+                (
+                    "fallback".into(),
+                    Box::new(ScriptedProvider::new("ok").with_preflight_error("bad key")),
+                ),
+            ],
+            0,
+            1,
+        );
+        let err = provider.preflight().await.unwrap_err().to_string();
+        assert_eq!(err, "fallback: bad key");
+    }
+
+    #[test]
+    fn tool_format_follows_primary() {
+        let provider = ReliableProvider::new(
+            vec![
+                (
+                    "anthropic".into(),
+                    Box::new(crate::providers::anthropic::AnthropicProvider::new(Some(
+                        "k",
+                    ))),
+                ),
+                (
+                    "scripted".into(),
+                    Box::new(crate::providers::testing::ScriptedProvider::new("ok")),
+                ),
+            ],
+            0,
+            1,
+        );
+        assert_eq!(provider.tool_format(), ToolFormat::Anthropic);
+    }
+
+    struct RateLimitedProvider;
+
+    #[async_trait]
+    impl Provider for RateLimitedProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            Err(crate::providers::error::ProviderError {
+                provider: "Test".into(),
+                status: 429,
+                kind: crate::providers::error::ProviderErrorKind::RateLimited,
+                retry_after: Some(Duration::from_secs(7)),
+                body: "slow down".into(),
+            }
+            .into())
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limited_chain_surfaces_retry_after() {
+        let provider = ReliableProvider::new(
+            vec![
+                ("a".into(), Box::new(RateLimitedProvider)),
+                ("b".into(), Box::new(RateLimitedProvider)),
+            ],
+            0,
+            1,
+        );
+        let err = provider.chat("hello", "test", 0.0).await.unwrap_err();
+        let chain = err
+            .downcast_ref::<crate::providers::ProviderChainError>()
+            .unwrap();
+        assert!(chain.rate_limited);
+        assert_eq!(chain.retry_after, Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn fallback_strategy_keeps_configured_order() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![
+                ("a".into(), counted(&calls, 0)),
+                ("b".into(), counted(&calls, 0)),
+                ("c".into(), counted(&calls, 0)),
+            ],
+            0,
+            1,
+        );
+        for _ in 0..3 {
+            assert_eq!(provider.provider_order(None).unwrap(), vec![0, 1, 2]);
+        }
+    }
+
+    #[tokio::test]
+    async fn round_robin_spreads_first_attempts() {
+        let counts: Vec<_> = (0..3).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+        let provider = ReliableProvider::new(
+            vec![
+                ("key1".into(), counted(&counts[0], 0)),
+                ("key2".into(), counted(&counts[1], 0)),
+                ("key3".into(), counted(&counts[2], 0)),
+            ],
+            0,
+            1,
+        )
+        .with_strategy(SelectionStrategy::RoundRobin, HashMap::new());
+
+        for i in 0..6 {
+            provider
+                .chat(&format!("msg {i}"), "test", 0.0)
+                .await
+                .unwrap();
+        }
+        for count in &counts {
+            assert_eq!(count.load(Ordering::SeqCst), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn pin_sticks_to_the_provider_that_answered() {
+        let primary_calls = Arc::new(AtomicUsize::new(0));
+        let fallback_calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![
+                ("primary".into(), counted(&primary_calls, 1)),
+                ("fallback".into(), counted(&fallback_calls, 0)),
+            ],
+            0,
+            1,
+        );
+
+        let pin = ProviderPin::new();
+        provider
+            .chat_pinned(&pin, None, "first", "test", 0.0)
+            .await
+            .unwrap();
+        assert_eq!(pin.get().as_deref(), Some("fallback"));
+
+        // The primary has recovered, but the conversation stays put.
+        provider
+            .chat_pinned(&pin, None, "second", "test", 0.0)
+            .await
+            .unwrap();
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(fallback_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn pinned_call_does_not_fail_over() {
+        let primary_calls = Arc::new(AtomicUsize::new(0));
+        let fallback_calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![
+                ("primary".into(), failing("primary down", &primary_calls)),
+                ("fallback".into(), counted(&fallback_calls, 0)),
+            ],
+            1,
+            1,
+        );
+
+        let pin = ProviderPin::new();
+        pin.set_if_unset("primary");
+        let err = provider
+            .chat_pinned(&pin, None, "hello", "test", 0.0)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("primary down"));
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 2);
+        assert_eq!(fallback_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn pin_to_a_provider_gone_from_the_chain_is_ignored() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(vec![("primary".into(), counted(&calls, 0))], 0, 1);
+
+        let pin = ProviderPin::new();
+        pin.set_if_unset("removed-on-reload");
+        provider
+            .chat_pinned(&pin, None, "hello", "test", 0.0)
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(pin.get().as_deref(), Some("removed-on-reload"));
+    }
+
+    #[tokio::test]
+    async fn preferred_provider_goes_first_and_can_fall_back() {
+        let primary_calls = Arc::new(AtomicUsize::new(0));
+        let preferred_calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![
+                ("primary".into(), counted(&primary_calls, 0)),
+                ("anthropic".into(), counted(&preferred_calls, 1)),
+            ],
+            0,
+            1,
+        );
+
+        let pin = ProviderPin::prefer("anthropic", true);
+        provider
+            .chat_pinned(&pin, None, "first", "test", 0.0)
+            .await
+            .unwrap();
+        assert_eq!(preferred_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(pin.get().as_deref(), Some("primary"));
+
+        let pin = ProviderPin::prefer("anthropic", true);
+        provider
+            .chat_pinned(&pin, None, "second", "test", 0.0)
+            .await
+            .unwrap();
+        assert_eq!(preferred_calls.load(Ordering::SeqCst), 2);
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn strict_preference_never_falls_back() {
+        let primary_calls = Arc::new(AtomicUsize::new(0));
+        let preferred_calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![
+                ("primary".into(), counted(&primary_calls, 0)),
+                (
+                    "anthropic".into(),
+                    failing("anthropic down", &preferred_calls),
+                ),
+            ],
+            0,
+            1,
+        );
+
+        let err = provider
+            .chat_pinned(
+                &ProviderPin::prefer("anthropic", false),
+                None,
+                "hi",
+                "test",
+                0.0,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("anthropic down"));
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 0);
+
+        let err = provider
+            .chat_pinned(&ProviderPin::prefer("groq", true), None, "hi", "test", 0.0)
+            .await
+            .unwrap_err();
+        let unknown = err.downcast_ref::<UnknownProviderError>().unwrap();
+        assert_eq!(unknown.available, vec!["primary", "anthropic"]);
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn round_robin_rotation_is_shared_across_instances_of_a_chain() {
+        let counts: Vec<_> = (0..2).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+        let chain = || {
+            ReliableProvider::new(
+                vec![
+                    ("rr-shared-a".into(), counted(&counts[0], 0)),
+                    ("rr-shared-b".into(), counted(&counts[1], 0)),
+                ],
+                0,
+                1,
+            )
+            .with_strategy(SelectionStrategy::RoundRobin, HashMap::new())
+        };
+
+        // A fresh instance per request, as a per-call chain would be.
+        for i in 0..4 {
+            chain()
+                .chat(&format!("msg {i}"), "test", 0.0)
+                .await
+                .unwrap();
+        }
+        for count in &counts {
+            assert_eq!(count.load(Ordering::SeqCst), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn round_robin_still_falls_back_on_failure() {
+        let dead = Arc::new(AtomicUsize::new(0));
+        let healthy = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![
+                ("dead".into(), counted(&dead, usize::MAX)),
+                ("healthy".into(), counted(&healthy, 0)),
+            ],
+            0,
+            1,
+        )
+        .with_strategy(SelectionStrategy::RoundRobin, HashMap::new());
+
+        for i in 0..2 {
+            let reply = provider.chat(&format!("msg {i}"), "test", 0.0).await;
+            assert_eq!(reply.unwrap(), "ok");
+        }
+        assert_eq!(healthy.load(Ordering::SeqCst), 2);
+        assert_eq!(dead.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn weighted_zero_weight_is_fallback_only() {
+        let spare = Arc::new(AtomicUsize::new(0));
+        let main = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![
+                ("spare".into(), counted(&spare, 0)),
+                ("main".into(), counted(&main, 0)),
+            ],
+            0,
+            1,
+        )
+        .with_strategy(
+            SelectionStrategy::Weighted,
+            HashMap::from([("spare".to_string(), 0)]),
+        );
+
+        for i in 0..20 {
+            provider
+                .chat(&format!("msg {i}"), "test", 0.0)
+                .await
+                .unwrap();
+        }
+        assert_eq!(main.load(Ordering::SeqCst), 20);
+        assert_eq!(spare.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn weighted_picks_every_positive_weight() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![
+                ("a".into(), counted(&calls, 0)),
+                ("b".into(), counted(&calls, 0)),
+                ("c".into(), counted(&calls, 0)),
+            ],
+            0,
+            1,
+        )
+        .with_strategy(
+            SelectionStrategy::Weighted,
+            HashMap::from([("b".to_string(), 0)]),
+        );
+
+        let mut firsts = std::collections::HashSet::new();
+        for _ in 0..200 {
+            let order = provider.provider_order(None).unwrap();
+            assert_eq!(order.len(), 3);
+            firsts.insert(order[0]);
+        }
+        assert_eq!(firsts, std::collections::HashSet::from([0, 2]));
+    }
+
+    #[test]
+    fn strategy_deserializes_snake_case() {
+        let strategy: SelectionStrategy = serde_json::from_str("\"round_robin\"").unwrap();
+        assert_eq!(strategy, SelectionStrategy::RoundRobin);
+    }
+
+    #[tokio::test]
+    async fn breaker_opens_and_skips_dead_primary() {
+        let primary_calls = Arc::new(AtomicUsize::new(0));
+        let fallback_calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![
+                (
+                    "primary".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::clone(&primary_calls),
+                        fail_until_attempt: usize::MAX,
+                        response: "never",
+                        error: "down",
+                    }),
+                ),
+                (
+                    "fallback".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::clone(&fallback_calls),
+                        fail_until_attempt: 0,
+                        response: "ok",
+                        error: "unused",
+                    }),
+                ),
+            ],
+            BREAKER_THRESHOLD - 1,
+            1,
+        );
+
+        provider.chat("first", "test", 0.0).await.unwrap();
+        assert_eq!(
+            primary_calls.load(Ordering::SeqCst),
+            BREAKER_THRESHOLD as usize
+        );
+        assert!(provider.breaker_open("primary"));
+
+        provider.chat("second", "test", 0.0).await.unwrap();
+        // Primary skipped while its breaker is open
+        assert_eq!(
+            primary_calls.load(Ordering::SeqCst),
+            BREAKER_THRESHOLD as usize
+        );
+        assert_eq!(fallback_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn stats_track_cache_and_latency() {
+        let provider = ReliableProvider::new(
+            vec![(
+                "primary".into(),
+                Box::new(MockProvider {
+                    calls: Arc::new(AtomicUsize::new(0)),
+                    fail_until_attempt: 0,
+                    response: "ok",
+                    error: "unused",
+                }),
+            )],
+            0,
+            1,
+        );
+        provider.chat("hello", "test", 0.0).await.unwrap();
+        provider.chat("hello", "test", 0.0).await.unwrap();
+
+        let snap = provider.snapshot();
+        assert_eq!(snap.cache_hits, 1);
+        assert_eq!(snap.cache_misses, 1);
+        let stats = &snap.providers["primary"];
+        assert_eq!(stats.successes, 1);
+        assert!(stats.avg_latency_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn stats_count_since_start_only() {
+        let provider = ReliableProvider::new(
+            vec![(
+                "primary".into(),
+                Box::new(MockProvider {
+                    calls: Arc::new(AtomicUsize::new(0)),
+                    fail_until_attempt: 2,
+                    response: "ok",
+                    error: "boom",
+                }),
+            )],
+            2,
+            1,
+        );
+        // Persisted counters from an earlier run stay out of the live view.
+        provider.restore(ReliabilitySnapshot {
+            providers: BTreeMap::new(),
+            cache_hits: 40,
+            cache_misses: 2,
+        });
+
+        provider.chat("hello", "test", 0.0).await.unwrap();
+        provider.chat("hello", "test", 0.0).await.unwrap();
+
+        let stats = provider.stats();
+        assert_eq!(stats.cache_hits, 1);
+        assert_eq!(stats.cache_misses, 1);
+        let primary = &stats.providers["primary"];
+        assert_eq!(primary.successes, 1);
+        assert_eq!(primary.failures, 2);
+        assert_eq!(primary.retries, 2);
+        assert!(primary.avg_latency_ms.is_some());
+
+        let as_trait: &dyn Provider = &provider;
+        assert!(as_trait.stats().is_some());
+    }
+
+    #[test]
+    fn state_file_roundtrip_restores_open_breaker() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("provider_state.json");
+        let mut snapshot = ReliabilitySnapshot {
+            cache_hits: 7,
+            ..ReliabilitySnapshot::default()
+        };
+        snapshot.providers.insert(
+            "primary".into(),
+            ProviderStats {
+                consecutive_failures: BREAKER_THRESHOLD,
+                open_until: Some(chrono::Utc::now().timestamp() + 60),
+                ..ProviderStats::default()
+            },
+        );
+        std::fs::write(&path, serde_json::to_vec(&snapshot).unwrap()).unwrap();
+
+        let provider = ReliableProvider::new(
+            vec![(
+                "primary".into(),
+                Box::new(MockProvider {
+                    calls: Arc::new(AtomicUsize::new(0)),
+                    fail_until_attempt: 0,
+                    response: "ok",
+                    error: "unused",
+                }),
+            )],
+            0,
+            1,
+        )
+        .with_state_file(path);
+
+        assert!(provider.breaker_open("primary"));
+        assert_eq!(provider.snapshot().cache_hits, 7);
+    }
+
+    #[test]
+    fn corrupt_state_file_starts_cold() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("provider_state.json");
+        std::fs::write(&path, b"{not json").unwrap();
+
+        let provider = ReliableProvider::new(vec![], 0, 1).with_state_file(path.clone());
+        let snap = provider.snapshot();
+        assert!(snap.providers.is_empty());
+        assert_eq!(snap.cache_hits, 0);
+        // Moved aside rather than left to be overwritten.
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn returns_aggregated_error_when_all_providers_fail() {
+        let provider = ReliableProvider::new(
+            vec![
+                (
                     "p1".into(),
                     Box::new(MockProvider {
                         calls: Arc::new(AtomicUsize::new(0)),
@@ -347,4 +1930,341 @@ mod tests {
         assert!(msg.contains("p1 attempt 1/1"));
         assert!(msg.contains("p2 attempt 1/1"));
     }
+
+    struct EchoModelProvider;
+
+    #[async_trait]
+    impl Provider for EchoModelProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            Ok(model.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn model_map_translates_per_provider() {
+        let provider =
+            ReliableProvider::new(vec![("gateway".into(), Box::new(EchoModelProvider))], 0, 1)
+                .with_model_map(HashMap::from([(
+                    "gateway".to_string(),
+                    HashMap::from([("gpt-4o".to_string(), "openai/gpt-4o".to_string())]),
+                )]));
+
+        assert_eq!(
+            provider.chat("hello", "gpt-4o", 0.0).await.unwrap(),
+            "openai/gpt-4o"
+        );
+        assert_eq!(
+            provider.chat("hello", "claude", 0.0).await.unwrap(),
+            "claude"
+        );
+        // Cached under the caller's model name
+        assert!(provider
+            .cache
+            .contains_key(&CacheRequest::new(None, "hello", "gpt-4o", 0.0).key()));
+    }
+
+    fn failing(error: &'static str, calls: &Arc<AtomicUsize>) -> Box<dyn Provider> {
+        Box::new(MockProvider {
+            calls: Arc::clone(calls),
+            fail_until_attempt: usize::MAX,
+            response: "never",
+            error,
+        })
+    }
+
+    #[tokio::test]
+    async fn total_attempt_budget_spans_providers() {
+        let p1_calls = Arc::new(AtomicUsize::new(0));
+        let p2_calls = Arc::new(AtomicUsize::new(0));
+        let p3_calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![
+                ("p1".into(), failing("p1 error", &p1_calls)),
+                ("p2".into(), failing("p2 error", &p2_calls)),
+                ("p3".into(), failing("p3 error", &p3_calls)),
+            ],
+            2,
+            1,
+        )
+        .with_retry_budget(Some(4), None);
+
+        let err = provider.chat("hello", "test", 0.0).await.unwrap_err();
+        assert_eq!(p1_calls.load(Ordering::SeqCst), 3);
+        assert_eq!(p2_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(p3_calls.load(Ordering::SeqCst), 0);
+        let msg = err.to_string();
+        assert!(msg.contains("retry budget exhausted"));
+        assert!(msg.contains("p2 attempt 1/3"));
+    }
+
+    #[tokio::test]
+    async fn total_deadline_aborts_slow_provider() {
+        let provider = ReliableProvider::new(
+            vec![(
+                "slow".into(),
+                Box::new(SlowProvider {
+                    in_flight: Arc::new(AtomicUsize::new(0)),
+                    peak: Arc::new(AtomicUsize::new(0)),
+                }),
+            )],
+            5,
+            1,
+        )
+        .with_retry_budget(None, Some(Duration::from_millis(5)));
+
+        let started = Instant::now();
+        let err = provider.chat("hello", "test", 0.0).await.unwrap_err();
+        assert!(err.to_string().contains("total deadline reached"));
+        assert!(started.elapsed() < Duration::from_millis(500));
+    }
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl Observer for RecordingObserver {
+        fn record_event(&self, event: &ObserverEvent) {
+            self.events.lock().push(format!("{event:?}"));
+        }
+
+        fn record_metric(&self, _metric: &crate::observability::traits::ObserverMetric) {}
+
+        fn name(&self) -> &str {
+            "recording"
+        }
+    }
+
+    #[tokio::test]
+    async fn uncached_call_bypasses_cache_and_reports_hits() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let observer = Arc::new(RecordingObserver::default());
+        let provider = ReliableProvider::new(
+            vec![(
+                "primary".into(),
+                Box::new(MockProvider {
+                    calls: Arc::clone(&calls),
+                    fail_until_attempt: 0,
+                    response: "fresh",
+                    error: "boom",
+                }),
+            )],
+            0,
+            1,
+        )
+        .with_observer(observer.clone());
+
+        provider.chat("hello", "test", 0.0).await.unwrap();
+        provider
+            .chat_with_system_uncached(None, "hello", "test", 0.0)
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let cache_hits =
+            |events: &[String]| events.iter().filter(|e| e.starts_with("CacheHit")).count();
+        assert_eq!(cache_hits(&observer.events.lock()), 0);
+
+        provider.chat("hello", "test", 0.0).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(cache_hits(&observer.events.lock()), 1);
+    }
+
+    #[tokio::test]
+    async fn tool_call_replies_are_not_cached() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![(
+                "primary".into(),
+                Box::new(MockProvider {
+                    calls: Arc::clone(&calls),
+                    fail_until_attempt: 0,
+                    response: r#"<tool_call>{"name": "shell", "arguments": {"command": "ls"}}</tool_call>"#,
+                    error: "boom",
+                }),
+            )],
+            0,
+            1,
+        );
+
+        provider.chat("list files", "test", 0.0).await.unwrap();
+        provider.chat("list files", "test", 0.0).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(provider.stats().cache_hits, 0);
+    }
+
+    #[test]
+    fn default_cache_filter_spots_tool_calls() {
+        assert!(cacheable_response("The answer is 4."));
+        assert!(!cacheable_response(r#"{"tool_calls": [{"id": "1"}]}"#));
+        assert!(!cacheable_response(
+            "<function_calls><invoke name=\"shell\">"
+        ));
+    }
+
+    #[tokio::test]
+    async fn streaming_neither_reads_nor_fills_the_cache() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(vec![("primary".into(), counted(&calls, 0))], 0, 1);
+
+        let (result, _) = stream_chunks(&provider).await;
+        result.unwrap();
+        provider.chat("hi", "m", 0.0).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let (result, _) = stream_chunks(&provider).await;
+        result.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn provider_calls_are_reported_with_origin() {
+        use crate::providers::origin::{self, RequestOrigin};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let observer = Arc::new(RecordingObserver::default());
+        let provider = ReliableProvider::new(vec![("primary".into(), counted(&calls, 1))], 1, 1)
+            .with_observer(observer.clone());
+
+        origin::scope(
+            RequestOrigin::channel("telegram"),
+            provider.chat_with_system_uncached(None, "hello", "test", 0.0),
+        )
+        .await
+        .unwrap();
+
+        let events = observer.events.lock();
+        assert_eq!(events.len(), 2, "{events:?}");
+        assert!(events
+            .iter()
+            .all(|e| e.starts_with("ProviderCall") && e.contains("\"channel:telegram\"")));
+        assert!(events[0].contains("success: false"));
+        assert!(events[1].contains("success: true"));
+    }
+
+    async fn stream_chunks(provider: &ReliableProvider) -> (anyhow::Result<()>, Vec<StreamChunk>) {
+        let (tx, mut rx) = mpsc::channel(STREAM_BUFFER);
+        let result = provider.chat_stream(None, "hi", "m", 0.0, tx).await;
+        let mut chunks = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            chunks.push(chunk);
+        }
+        (result, chunks)
+    }
+
+    fn interrupt(partial: &str) -> crate::providers::testing::ScriptStep {
+        crate::providers::testing::ScriptStep::Interrupt {
+            partial: partial.into(),
+            error: "connection reset".into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn short_partial_stream_restarts_on_fallback() {
+        use crate::providers::testing::ScriptedProvider;
+        let provider = ReliableProvider::new(
+            vec![
+                (
+                    "primary".into(),
+                    Box::new(ScriptedProvider::new("unused").with_script([interrupt("Hel")])),
+                ),
+                ("fallback".into(), Box::new(ScriptedProvider::new("Hello!"))),
+            ],
+            0,
+            1,
+        );
+
+        let (result, chunks) = stream_chunks(&provider).await;
+        result.unwrap();
+        assert_eq!(
+            chunks,
+            [
+                StreamChunk::Text("Hel".into()),
+                StreamChunk::Reset,
+                StreamChunk::Text("Hello!".into()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn long_partial_stream_ends_truncated() {
+        use crate::providers::testing::ScriptedProvider;
+        let provider = ReliableProvider::new(
+            vec![
+                (
+                    "primary".into(),
+                    Box::new(
+                        ScriptedProvider::new("unused").with_script([interrupt("long answer")]),
+                    ),
+                ),
+                ("fallback".into(), Box::new(ScriptedProvider::new("never"))),
+            ],
+            2,
+            1,
+        )
+        .with_stream_restart_limit(5);
+
+        let (result, chunks) = stream_chunks(&provider).await;
+        assert_eq!(chunks, [StreamChunk::Text("long answer".into())]);
+        let err = result.unwrap_err();
+        let interrupted = err.downcast_ref::<StreamInterrupted>().unwrap();
+        assert_eq!(interrupted.emitted, 11);
+        assert!(interrupted.source.to_string().contains("connection reset"));
+    }
+
+    struct AuthRejectedProvider {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Provider for AuthRejectedProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err(crate::providers::error::ProviderError {
+                provider: "Test".into(),
+                status: 401,
+                kind: crate::providers::error::ProviderErrorKind::Auth,
+                retry_after: None,
+                body: "bad key".into(),
+            }
+            .into())
+        }
+    }
+
+    #[tokio::test]
+    async fn non_retryable_stream_error_skips_to_next_provider() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let rejected = ReliableProvider::new(
+            vec![
+                (
+                    "primary".into(),
+                    Box::new(AuthRejectedProvider {
+                        calls: Arc::clone(&calls),
+                    }),
+                ),
+                (
+                    "fallback".into(),
+                    Box::new(crate::providers::testing::ScriptedProvider::new("ok")),
+                ),
+            ],
+            3,
+            1,
+        );
+
+        let (result, chunks) = stream_chunks(&rejected).await;
+        result.unwrap();
+        assert_eq!(chunks, [StreamChunk::Text("ok".into())]);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
 }
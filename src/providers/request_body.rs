@@ -0,0 +1,243 @@
+//! Per-provider request overrides.
+//!
+//! Some OpenAI-compatible endpoints want vendor-specific fields (`OpenRouter`'s
+//! `provider` routing hints) or reject ones we always send, and gateways in
+//! front of them often want their own auth or org headers. Rather than fork a
+//! provider for one parameter, `[request_overrides.<provider>]` in config
+//! strips fields from and merges extra JSON into every outgoing request, and
+//! adds or replaces HTTP headers.
+
+use anyhow::Context;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RequestBody {
+    /// Merged into the request payload; nested tables merge key by key
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub extra_body: Map<String, Value>,
+    /// Top-level payload fields removed before `extra_body` is merged
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub strip_fields: Vec<String>,
+    /// Sent with every request, replacing built-in headers of the same name.
+    /// `${VAR}` in a value is replaced by that environment variable.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// Header that carries the API key as-is instead of the provider's own
+    /// scheme, e.g. `api-key` for Azure. Honoured by OpenAI-compatible
+    /// providers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_header: Option<String>,
+}
+
+impl RequestBody {
+    pub fn is_empty(&self) -> bool {
+        self.extra_body.is_empty()
+            && self.strip_fields.is_empty()
+            && self.headers.is_empty()
+            && self.auth_header.is_none()
+    }
+
+    /// Serialize `request` and apply the overrides to it.
+    pub fn apply<T: Serialize>(&self, request: &T) -> serde_json::Result<Value> {
+        let mut body = serde_json::to_value(request)?;
+        if let Value::Object(fields) = &mut body {
+            for name in &self.strip_fields {
+                fields.remove(name);
+            }
+            merge(fields, &self.extra_body);
+        }
+        Ok(body)
+    }
+
+    /// `headers` with environment variables filled in, ready for
+    /// `RequestBuilder::headers`. Fails on an unset variable or a name or
+    /// value HTTP does not allow.
+    pub fn header_map(&self) -> anyhow::Result<HeaderMap> {
+        let mut map = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let value = interpolate_env(value, |var| std::env::var(var).ok())
+                .with_context(|| format!("Header '{name}'"))?;
+            let header = HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("Invalid header name '{name}'"))?;
+            let mut value = HeaderValue::from_str(&value)
+                .with_context(|| format!("Invalid value for header '{name}'"))?;
+            // Keep interpolated secrets out of debug output.
+            value.set_sensitive(true);
+            map.insert(header, value);
+        }
+        Ok(map)
+    }
+
+    /// Header values as sent, plus every environment variable they pull in,
+    /// for scrubbing from logs. Values that fail to resolve are skipped.
+    pub fn secret_values(&self) -> Vec<String> {
+        self.secret_values_with(|var| std::env::var(var).ok())
+    }
+
+    fn secret_values_with(&self, lookup: impl Fn(&str) -> Option<String>) -> Vec<String> {
+        let mut secrets = Vec::new();
+        for value in self.headers.values() {
+            let Ok(resolved) = interpolate_env(value, |var| {
+                let found = lookup(var);
+                secrets.extend(found.clone());
+                found
+            }) else {
+                continue;
+            };
+            secrets.push(resolved);
+        }
+        secrets
+    }
+}
+
+/// Replace every `${VAR}` in `value` with `lookup(VAR)`.
+fn interpolate_env(
+    value: &str,
+    mut lookup: impl FnMut(&str) -> Option<String>,
+) -> anyhow::Result<String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| anyhow::anyhow!("unterminated '${{' in value"))?;
+        let var = &after[..end];
+        let resolved =
+            lookup(var).ok_or_else(|| anyhow::anyhow!("environment variable {var} is not set"))?;
+        out.push_str(&resolved);
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn merge(target: &mut Map<String, Value>, extra: &Map<String, Value>) {
+    for (key, value) in extra {
+        match (target.get_mut(key), value) {
+            (Some(Value::Object(existing)), Value::Object(nested)) => merge(existing, nested),
+            _ => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn body(extra: Value, strip: &[&str]) -> RequestBody {
+        let Value::Object(extra_body) = extra else {
+            panic!("extra_body must be an object");
+        };
+        RequestBody {
+            extra_body,
+            strip_fields: strip.iter().map(|s| (*s).to_string()).collect(),
+            ..RequestBody::default()
+        }
+    }
+
+    #[test]
+    fn empty_overrides_leave_request_unchanged() {
+        let request = json!({ "model": "m", "temperature": 0.7 });
+        assert!(RequestBody::default().is_empty());
+        assert_eq!(RequestBody::default().apply(&request).unwrap(), request);
+    }
+
+    #[test]
+    fn strips_then_merges() {
+        let request = json!({
+            "model": "m",
+            "temperature": 0.7,
+            "options": { "a": 1, "b": 2 },
+        });
+        let overrides = body(
+            json!({ "provider": { "order": ["anthropic"] }, "options": { "b": 3 } }),
+            &["temperature"],
+        );
+        assert_eq!(
+            overrides.apply(&request).unwrap(),
+            json!({
+                "model": "m",
+                "options": { "a": 1, "b": 3 },
+                "provider": { "order": ["anthropic"] },
+            })
+        );
+    }
+
+    #[test]
+    fn parses_from_toml() {
+        let parsed: RequestBody = toml::from_str(
+            r#"
+strip_fields = ["temperature"]
+[extra_body.provider]
+order = ["anthropic", "openai"]
+allow_fallbacks = false
+"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.strip_fields, vec!["temperature"]);
+        assert_eq!(
+            parsed.extra_body["provider"]["order"],
+            json!(["anthropic", "openai"])
+        );
+    }
+
+    #[test]
+    fn interpolates_environment_variables() {
+        let lookup = |var: &str| (var == "AZURE_KEY").then(|| "secret".to_string());
+        assert_eq!(interpolate_env("plain", lookup).unwrap(), "plain");
+        assert_eq!(
+            interpolate_env("Bearer ${AZURE_KEY}!", lookup).unwrap(),
+            "Bearer secret!"
+        );
+        let err = interpolate_env("${MISSING}", lookup).unwrap_err();
+        assert!(err.to_string().contains("MISSING"));
+        assert!(interpolate_env("${AZURE_KEY", lookup).is_err());
+    }
+
+    #[test]
+    fn secret_values_cover_headers_and_their_variables() {
+        let overrides = RequestBody {
+            headers: HashMap::from([
+                ("Authorization".into(), "Bearer ${FALLBACK_KEY}".into()),
+                ("X-Org".into(), "org-literal".into()),
+                ("X-Missing".into(), "${UNSET}".into()),
+            ]),
+            ..RequestBody::default()
+        };
+        let mut secrets = overrides
+            .secret_values_with(|var| (var == "FALLBACK_KEY").then(|| "sk-fallback".to_string()));
+        secrets.sort();
+        assert_eq!(
+            secrets,
+            ["Bearer sk-fallback", "org-literal", "sk-fallback"]
+        );
+    }
+
+    #[test]
+    fn header_map_validates_and_hides_values() {
+        let overrides: RequestBody = toml::from_str(
+            r#"
+auth_header = "api-key"
+[headers]
+"OpenAI-Organization" = "org-1"
+"#,
+        )
+        .unwrap();
+        assert_eq!(overrides.auth_header.as_deref(), Some("api-key"));
+        let map = overrides.header_map().unwrap();
+        assert_eq!(map["openai-organization"], "org-1");
+        assert!(map["openai-organization"].is_sensitive());
+
+        let mut bad = RequestBody::default();
+        bad.headers.insert("bad header".into(), "x".into());
+        assert!(bad.header_map().is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Streamed completions and how they end.
+//!
+//! [`Provider::chat_stream`] sends the reply as [`StreamChunk::Text`] pieces
+//! and returns once the stream ends: `Ok` when it finished, `Err` when it
+//! broke off. Text sent before an `Err` is the partial reply. A provider
+//! chain that restarts on another provider sends [`StreamChunk::Reset`]
+//! first, so consumers drop what they have and start over.
+
+use super::error::{ProviderError, ProviderErrorKind};
+use super::Provider;
+use tokio::sync::mpsc;
+
+/// Chunks a stream may get ahead of its consumer.
+pub const STREAM_BUFFER: usize = 32;
+
+/// One item of a streamed reply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamChunk {
+    /// Next piece of the reply.
+    Text(String),
+    /// Discard everything received so far; the reply starts over.
+    Reset,
+}
+
+/// A stream that broke off after sending part of the reply.
+#[derive(Debug, thiserror::Error)]
+#[error("stream interrupted after {emitted} characters: {source:#}")]
+pub struct StreamInterrupted {
+    /// Characters of reply text the consumer already has.
+    pub emitted: usize,
+    pub source: anyhow::Error,
+}
+
+/// A whole streamed reply, as collected by [`collect_stream`].
+#[derive(Debug)]
+pub enum StreamOutcome {
+    /// The provider finished the reply.
+    Complete(String),
+    /// The stream failed part-way; `partial` is everything received.
+    Truncated {
+        partial: String,
+        error: anyhow::Error,
+    },
+}
+
+impl StreamOutcome {
+    /// The reply text, complete or not.
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Complete(text) | Self::Truncated { partial: text, .. } => text,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Self::Complete(_))
+    }
+}
+
+/// Whether restarting the request could help after a stream failed. A
+/// dropped connection or a 5xx/429 may pass; a rejected key or request won't.
+pub fn is_retryable(error: &anyhow::Error) -> bool {
+    error.chain().all(|cause| {
+        cause
+            .downcast_ref::<ProviderError>()
+            .is_none_or(|e| !matches!(e.kind, ProviderErrorKind::Auth | ProviderErrorKind::Client))
+    })
+}
+
+/// Stream a reply from `provider` and gather it. Fails only if the stream
+/// broke off before sending any text.
+pub async fn collect_stream(
+    provider: &dyn Provider,
+    system_prompt: Option<&str>,
+    message: &str,
+    model: &str,
+    temperature: f64,
+) -> anyhow::Result<StreamOutcome> {
+    let (tx, mut rx) = mpsc::channel(STREAM_BUFFER);
+    let call = async move {
+        provider
+            .chat_stream(system_prompt, message, model, temperature, tx)
+            .await
+    };
+    let gather = async {
+        let mut text = String::new();
+        while let Some(chunk) = rx.recv().await {
+            match chunk {
+                StreamChunk::Text(piece) => text.push_str(&piece),
+                StreamChunk::Reset => text.clear(),
+            }
+        }
+        text
+    };
+    let (result, text) = tokio::join!(call, gather);
+    match result {
+        Ok(()) => Ok(StreamOutcome::Complete(text)),
+        Err(error) if text.is_empty() => Err(error),
+        Err(error) => Ok(StreamOutcome::Truncated {
+            partial: text,
+            error,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::testing::{ScriptStep, ScriptedProvider};
+
+    fn http_error(status: u16) -> anyhow::Error {
+        ProviderError {
+            provider: "Test".into(),
+            status,
+            kind: ProviderErrorKind::from_status(status),
+            retry_after: None,
+            body: String::new(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn retryable_errors() {
+        assert!(is_retryable(&anyhow::anyhow!("connection reset by peer")));
+        assert!(is_retryable(&http_error(503)));
+        assert!(is_retryable(&http_error(429)));
+        assert!(!is_retryable(&http_error(401)));
+        assert!(!is_retryable(&http_error(400).context("streaming")));
+    }
+
+    #[tokio::test]
+    async fn default_stream_is_one_complete_chunk() {
+        let provider = ScriptedProvider::new("hello there");
+        let outcome = collect_stream(&provider, None, "hi", "m", 0.0)
+            .await
+            .unwrap();
+        assert!(outcome.is_complete());
+        assert_eq!(outcome.text(), "hello there");
+    }
+
+    #[tokio::test]
+    async fn interrupted_stream_keeps_partial_text() {
+        let provider = ScriptedProvider::new("unused").with_script([ScriptStep::Interrupt {
+            partial: "The answer is".into(),
+            error: "connection reset".into(),
+        }]);
+        let outcome = collect_stream(&provider, None, "hi", "m", 0.0)
+            .await
+            .unwrap();
+        let StreamOutcome::Truncated { partial, error } = outcome else {
+            panic!("expected a truncated stream");
+        };
+        assert_eq!(partial, "The answer is");
+        assert!(error.to_string().contains("connection reset"));
+    }
+
+    #[tokio::test]
+    async fn failure_before_any_text_is_an_error() {
+        let provider = ScriptedProvider::new("unused").failing_first(1, "refused");
+        let err = collect_stream(&provider, None, "hi", "m", 0.0)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("refused"));
+    }
+}
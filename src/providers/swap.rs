@@ -0,0 +1,169 @@
+//! Hot-swappable provider handle for config and secret reloads.
+//!
+//! [`SwappableProvider`] is itself a [`Provider`], so it drops in wherever an
+//! `Arc<dyn Provider>` goes (e.g. [`super::ChatClient`]). Each call takes a
+//! snapshot of the current stack and releases the lock before awaiting:
+//! [`SwappableProvider::swap`] never waits on a request, requests already in
+//! flight finish on the stack they started with, and the next call uses the
+//! replacement. The old stack is dropped once its last call returns.
+//!
+//! `baihu daemon` holds one for its gateway and channels and swaps it on
+//! every reload, before the components restart.
+//!
+//! ```ignore
+//! let live = Arc::new(SwappableProvider::new(Arc::from(build_stack(&config)?)));
+//! let chat = ChatClient::from_config(live.clone(), &config);
+//!
+//! // On reload: build the new stack first, so a bad config keeps the old one.
+//! match build_stack(&reloaded) {
+//!     Ok(stack) => { live.swap(Arc::from(stack)); }
+//!     Err(e) => tracing::warn!("Keeping current provider: {e}"),
+//! }
+//! ```
+
+use super::stream::StreamChunk;
+use super::traits::{Provider, ProviderFailure, ProviderPin, ProviderStatsSnapshot, ToolFormat};
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+pub struct SwappableProvider {
+    current: RwLock<Arc<dyn Provider>>,
+}
+
+impl SwappableProvider {
+    pub fn new(provider: Arc<dyn Provider>) -> Self {
+        Self {
+            current: RwLock::new(provider),
+        }
+    }
+
+    /// The stack new calls go to right now.
+    pub fn current(&self) -> Arc<dyn Provider> {
+        Arc::clone(&self.current.read())
+    }
+
+    /// Route every later call to `provider`, returning the stack it replaces.
+    pub fn swap(&self, provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+        std::mem::replace(&mut *self.current.write(), provider)
+    }
+}
+
+#[async_trait]
+impl Provider for SwappableProvider {
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.current()
+            .chat_with_system(system_prompt, message, model, temperature)
+            .await
+    }
+
+    async fn chat_with_system_uncached(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.current()
+            .chat_with_system_uncached(system_prompt, message, model, temperature)
+            .await
+    }
+
+    async fn chat_stream(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        chunks: mpsc::Sender<StreamChunk>,
+    ) -> anyhow::Result<()> {
+        self.current()
+            .chat_stream(system_prompt, message, model, temperature, chunks)
+            .await
+    }
+
+    async fn chat_pinned(
+        &self,
+        pin: &ProviderPin,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.current()
+            .chat_pinned(pin, system_prompt, message, model, temperature)
+            .await
+    }
+
+    fn context_window(&self, model: &str) -> Option<usize> {
+        self.current().context_window(model)
+    }
+
+    fn tool_format(&self) -> ToolFormat {
+        self.current().tool_format()
+    }
+
+    async fn preflight(&self) -> anyhow::Result<()> {
+        self.current().preflight().await
+    }
+
+    fn stats(&self) -> Option<ProviderStatsSnapshot> {
+        self.current().stats()
+    }
+
+    fn recent_failures(&self) -> Option<Vec<ProviderFailure>> {
+        self.current().recent_failures()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::testing::ScriptedProvider;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn later_calls_use_the_replacement() {
+        let live = SwappableProvider::new(Arc::new(ScriptedProvider::new("old")));
+        assert_eq!(live.chat("hi", "m", 0.0).await.unwrap(), "old");
+
+        let previous = live.swap(Arc::new(ScriptedProvider::new("new")));
+        assert_eq!(previous.chat("hi", "m", 0.0).await.unwrap(), "old");
+        assert_eq!(live.chat("hi", "m", 0.0).await.unwrap(), "new");
+    }
+
+    #[tokio::test]
+    async fn in_flight_calls_finish_on_the_old_stack() {
+        let live = Arc::new(SwappableProvider::new(Arc::new(
+            ScriptedProvider::new("old").with_delay(Duration::from_millis(200)),
+        )));
+        let pending = {
+            let live = Arc::clone(&live);
+            tokio::spawn(async move { live.chat("hi", "m", 0.0).await })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // The slow call holds no lock, so this doesn't wait for it.
+        live.swap(Arc::new(ScriptedProvider::new("new")));
+
+        assert_eq!(pending.await.unwrap().unwrap(), "old");
+        assert_eq!(live.chat("hi", "m", 0.0).await.unwrap(), "new");
+    }
+
+    #[test]
+    fn delegates_metadata_to_current_stack() {
+        let live = SwappableProvider::new(Arc::new(ScriptedProvider::new("a")));
+        assert_eq!(live.context_window("m"), None);
+        live.swap(Arc::new(
+            ScriptedProvider::new("b").with_context_window(8_192),
+        ));
+        assert_eq!(live.context_window("m"), Some(8_192));
+    }
+}
@@ -0,0 +1,396 @@
+//! Opt-in request/response tap for prompt debugging.
+//!
+//! [`TapProvider`] wraps any provider and hands each exchange — the system
+//! prompt, the user message and the raw reply or error — to a [`ProviderTap`].
+//! Known secrets are redacted before the tap ever sees the text. Enable it with
+//! `[observability] provider_tap = true`; [`FileTap`] then appends one JSON
+//! object per call to `provider_tap.jsonl`.
+
+use super::stream::{StreamChunk, STREAM_BUFFER};
+use super::traits::{Provider, ToolFormat};
+use super::traits::{ProviderFailure, ProviderPin, ProviderStatsSnapshot};
+use crate::security::atomic_append::AppendLog;
+use crate::security::redact::redact;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// One completed provider call, already redacted.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ProviderExchange {
+    pub model: String,
+    pub temperature: f64,
+    pub system_prompt: Option<String>,
+    pub message: String,
+    /// The reply on success.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    /// The error chain on failure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    /// Who made the call, e.g. `channel:telegram`; see [`super::origin`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+}
+
+/// Receives every exchange seen by a [`TapProvider`].
+pub trait ProviderTap: Send + Sync {
+    fn record(&self, exchange: &ProviderExchange);
+}
+
+/// Appends exchanges to a JSON-lines file created with owner-only permissions.
+pub struct FileTap {
+    log: AppendLog,
+}
+
+impl FileTap {
+    pub fn open(path: &Path) -> Result<Self> {
+        let log = AppendLog::open(path)
+            .with_context(|| format!("Failed to open provider tap {}", path.display()))?;
+        Ok(Self { log })
+    }
+
+    /// Default location: `provider_tap.jsonl` next to `config.toml`.
+    pub fn default_path(config_path: &Path) -> PathBuf {
+        config_path
+            .parent()
+            .map_or_else(|| PathBuf::from("."), PathBuf::from)
+            .join("provider_tap.jsonl")
+    }
+}
+
+impl ProviderTap for FileTap {
+    fn record(&self, exchange: &ProviderExchange) {
+        if let Err(e) = self.log.append_json(exchange) {
+            tracing::warn!(path = %self.log.path().display(), "Provider tap write failed: {e:#}");
+        }
+    }
+}
+
+/// Provider wrapper that reports each call to a [`ProviderTap`].
+pub struct TapProvider {
+    inner: Box<dyn Provider>,
+    tap: Arc<dyn ProviderTap>,
+    secrets: Vec<String>,
+}
+
+impl TapProvider {
+    /// `secrets` are exact values (API keys, tokens) to scrub from the record.
+    pub fn new(inner: Box<dyn Provider>, tap: Arc<dyn ProviderTap>, secrets: Vec<String>) -> Self {
+        Self {
+            inner,
+            tap,
+            secrets,
+        }
+    }
+
+    fn record(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        result: &Result<String>,
+        elapsed: Duration,
+    ) {
+        let (response, error) = match result {
+            Ok(reply) => (Some(redact(reply, &self.secrets)), None),
+            Err(e) => (None, Some(redact(&format!("{e:#}"), &self.secrets))),
+        };
+        self.tap.record(&ProviderExchange {
+            model: model.to_string(),
+            temperature,
+            system_prompt: system_prompt.map(|s| redact(s, &self.secrets)),
+            message: redact(message, &self.secrets),
+            response,
+            error,
+            duration_ms: u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+            origin: super::origin::current_label(),
+        });
+    }
+}
+
+#[async_trait]
+impl Provider for TapProvider {
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        let started = Instant::now();
+        let result = self
+            .inner
+            .chat_with_system(system_prompt, message, model, temperature)
+            .await;
+        self.record(
+            system_prompt,
+            message,
+            model,
+            temperature,
+            &result,
+            started.elapsed(),
+        );
+        result
+    }
+
+    async fn chat_with_system_uncached(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        let started = Instant::now();
+        let result = self
+            .inner
+            .chat_with_system_uncached(system_prompt, message, model, temperature)
+            .await;
+        self.record(
+            system_prompt,
+            message,
+            model,
+            temperature,
+            &result,
+            started.elapsed(),
+        );
+        result
+    }
+
+    async fn chat_pinned(
+        &self,
+        pin: &ProviderPin,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        let started = Instant::now();
+        let result = self
+            .inner
+            .chat_pinned(pin, system_prompt, message, model, temperature)
+            .await;
+        self.record(
+            system_prompt,
+            message,
+            model,
+            temperature,
+            &result,
+            started.elapsed(),
+        );
+        result
+    }
+
+    /// Passes chunks straight through and records the reply once the stream
+    /// ends; a stream that broke off records its error.
+    async fn chat_stream(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        chunks: mpsc::Sender<StreamChunk>,
+    ) -> Result<()> {
+        let started = Instant::now();
+        let (tx, mut rx) = mpsc::channel(STREAM_BUFFER);
+        let call = self
+            .inner
+            .chat_stream(system_prompt, message, model, temperature, tx);
+        let forward = async {
+            let mut reply = String::new();
+            while let Some(chunk) = rx.recv().await {
+                match &chunk {
+                    StreamChunk::Text(text) => reply.push_str(text),
+                    StreamChunk::Reset => reply.clear(),
+                }
+                let _ = chunks.send(chunk).await;
+            }
+            reply
+        };
+        let (result, reply) = tokio::join!(call, forward);
+        let recorded = match &result {
+            Ok(()) => Ok(reply),
+            Err(e) => Err(anyhow::anyhow!("{e:#}")),
+        };
+        self.record(
+            system_prompt,
+            message,
+            model,
+            temperature,
+            &recorded,
+            started.elapsed(),
+        );
+        result
+    }
+
+    fn context_window(&self, model: &str) -> Option<usize> {
+        self.inner.context_window(model)
+    }
+
+    fn tool_format(&self) -> ToolFormat {
+        self.inner.tool_format()
+    }
+
+    async fn preflight(&self) -> anyhow::Result<()> {
+        self.inner.preflight().await
+    }
+
+    fn stats(&self) -> Option<ProviderStatsSnapshot> {
+        self.inner.stats()
+    }
+
+    fn recent_failures(&self) -> Option<Vec<ProviderFailure>> {
+        self.inner.recent_failures()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use tempfile::TempDir;
+
+    #[derive(Default)]
+    struct VecTap(Mutex<Vec<ProviderExchange>>);
+
+    impl ProviderTap for VecTap {
+        fn record(&self, exchange: &ProviderExchange) {
+            self.0.lock().push(exchange.clone());
+        }
+    }
+
+    struct Echo;
+
+    #[async_trait]
+    impl Provider for Echo {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> Result<String> {
+            if message == "fail" {
+                anyhow::bail!("upstream rejected key sk-live-0123456789abcdef");
+            }
+            Ok(format!("echo: {message}"))
+        }
+    }
+
+    #[tokio::test]
+    async fn records_success_and_failure_redacted() {
+        let tap = Arc::new(VecTap::default());
+        let provider = TapProvider::new(
+            Box::new(Echo),
+            Arc::clone(&tap) as Arc<dyn ProviderTap>,
+            vec!["my-api-key-value".into()],
+        );
+
+        let reply = provider
+            .chat_with_system(Some("system my-api-key-value"), "hello", "m", 0.3)
+            .await
+            .unwrap();
+        assert_eq!(reply, "echo: hello");
+        assert!(provider.chat("fail", "m", 0.3).await.is_err());
+
+        let exchanges = tap.0.lock();
+        assert_eq!(exchanges.len(), 2);
+        assert_eq!(
+            exchanges[0].system_prompt.as_deref(),
+            Some("system [REDACTED]")
+        );
+        assert_eq!(exchanges[0].response.as_deref(), Some("echo: hello"));
+        assert!(exchanges[0].error.is_none());
+        assert_eq!(
+            exchanges[1].error.as_deref(),
+            Some("upstream rejected key [REDACTED]")
+        );
+    }
+
+    #[tokio::test]
+    async fn streams_pass_through_and_are_recorded() {
+        use crate::providers::testing::{ScriptStep, ScriptedProvider};
+
+        let tap = Arc::new(VecTap::default());
+        let inner = ScriptedProvider::new("streamed my-api-key-value").with_script([
+            ScriptStep::Interrupt {
+                partial: "half".into(),
+                error: "reset by my-api-key-value".into(),
+            },
+        ]);
+        let provider = TapProvider::new(
+            Box::new(inner),
+            Arc::clone(&tap) as Arc<dyn ProviderTap>,
+            vec!["my-api-key-value".into()],
+        );
+
+        let (tx, mut rx) = mpsc::channel(STREAM_BUFFER);
+        assert!(provider
+            .chat_stream(None, "one", "m", 0.0, tx)
+            .await
+            .is_err());
+        assert_eq!(rx.recv().await, Some(StreamChunk::Text("half".into())));
+
+        let (tx, mut rx) = mpsc::channel(STREAM_BUFFER);
+        provider
+            .chat_stream(None, "two", "m", 0.0, tx)
+            .await
+            .unwrap();
+        assert_eq!(
+            rx.recv().await,
+            Some(StreamChunk::Text("streamed my-api-key-value".into()))
+        );
+
+        let exchanges = tap.0.lock();
+        assert_eq!(exchanges.len(), 2);
+        assert!(exchanges[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("[REDACTED]"));
+        assert_eq!(
+            exchanges[1].response.as_deref(),
+            Some("streamed [REDACTED]")
+        );
+    }
+
+    #[test]
+    fn file_tap_appends_json_lines() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("logs").join("provider_tap.jsonl");
+        let tap = FileTap::open(&path).unwrap();
+        for message in ["one", "two"] {
+            tap.record(&ProviderExchange {
+                model: "m".into(),
+                temperature: 0.0,
+                system_prompt: None,
+                message: message.into(),
+                response: Some("ok".into()),
+                error: None,
+                duration_ms: 1,
+                origin: None,
+            });
+        }
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["message"], "two");
+        assert!(lines[1].get("error").is_none());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! Test doubles for code that needs a [`Provider`] without real API calls.
+//!
+//! Enabled for this crate's own tests and, for downstream crates, behind the
+//! `testing` feature.
+
+use super::stream::StreamChunk;
+use super::Provider;
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// One scripted outcome for a provider call.
+#[derive(Debug, Clone)]
+pub enum ScriptStep {
+    Respond(String),
+    Fail(String),
+    /// Stream `partial`, then fail with `error`. Non-streaming calls just fail.
+    Interrupt {
+        partial: String,
+        error: String,
+    },
+}
+
+/// A call the provider received, for assertions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedCall {
+    pub system_prompt: Option<String>,
+    pub message: String,
+    pub model: String,
+    pub temperature: f64,
+}
+
+/// Provider that plays back a script of responses and failures, in order.
+/// Once the script runs out every call gets the fallback response.
+pub struct ScriptedProvider {
+    script: Mutex<VecDeque<ScriptStep>>,
+    fallback: String,
+    delay: Option<Duration>,
+    context_window: Option<usize>,
+    preflight_error: Option<String>,
+    calls: Mutex<Vec<RecordedCall>>,
+}
+
+impl ScriptedProvider {
+    /// Always answers `response`.
+    pub fn new(response: impl Into<String>) -> Self {
+        Self {
+            script: Mutex::new(VecDeque::new()),
+            fallback: response.into(),
+            delay: None,
+            context_window: None,
+            preflight_error: None,
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Queue steps to play before falling back to the default response.
+    pub fn with_script(self, steps: impl IntoIterator<Item = ScriptStep>) -> Self {
+        self.script.lock().extend(steps);
+        self
+    }
+
+    /// Fail the next `count` calls with `error`, then recover.
+    pub fn failing_first(self, count: usize, error: &str) -> Self {
+        self.with_script((0..count).map(|_| ScriptStep::Fail(error.to_string())))
+    }
+
+    /// Sleep this long before every answer (for timeout/cancellation tests).
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Advertise this context window for every model.
+    pub fn with_context_window(mut self, tokens: usize) -> Self {
+        self.context_window = Some(tokens);
+        self
+    }
+
+    /// Fail [`Provider::preflight`] with `error`; chat calls are unaffected.
+    pub fn with_preflight_error(mut self, error: &str) -> Self {
+        self.preflight_error = Some(error.to_string());
+        self
+    }
+
+    /// Every call received so far, in order.
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.calls.lock().clone()
+    }
+
+    pub fn call_count(&self) -> usize {
+        self.calls.lock().len()
+    }
+
+    /// Record the call, wait out any delay and take the next step.
+    async fn next_step(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> ScriptStep {
+        self.calls.lock().push(RecordedCall {
+            system_prompt: system_prompt.map(str::to_string),
+            message: message.to_string(),
+            model: model.to_string(),
+            temperature,
+        });
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+        let step = self.script.lock().pop_front();
+        step.unwrap_or_else(|| ScriptStep::Respond(self.fallback.clone()))
+    }
+}
+
+#[async_trait]
+impl Provider for ScriptedProvider {
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        match self
+            .next_step(system_prompt, message, model, temperature)
+            .await
+        {
+            ScriptStep::Respond(text) => Ok(text),
+            ScriptStep::Fail(error) | ScriptStep::Interrupt { error, .. } => anyhow::bail!(error),
+        }
+    }
+
+    async fn chat_stream(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        chunks: mpsc::Sender<StreamChunk>,
+    ) -> anyhow::Result<()> {
+        match self
+            .next_step(system_prompt, message, model, temperature)
+            .await
+        {
+            ScriptStep::Respond(text) => {
+                let _ = chunks.send(StreamChunk::Text(text)).await;
+                Ok(())
+            }
+            ScriptStep::Fail(error) => anyhow::bail!(error),
+            ScriptStep::Interrupt { partial, error } => {
+                let _ = chunks.send(StreamChunk::Text(partial)).await;
+                anyhow::bail!(error)
+            }
+        }
+    }
+
+    fn context_window(&self, _model: &str) -> Option<usize> {
+        self.context_window
+    }
+
+    async fn preflight(&self) -> anyhow::Result<()> {
+        match &self.preflight_error {
+            Some(error) => anyhow::bail!("{error}"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn plays_script_then_falls_back() {
+        let provider = ScriptedProvider::new("default").with_script([
+            ScriptStep::Respond("first".into()),
+            ScriptStep::Fail("rate limited".into()),
+        ]);
+
+        assert_eq!(provider.chat("a", "m", 0.0).await.unwrap(), "first");
+        let err = provider.chat("b", "m", 0.0).await.unwrap_err();
+        assert!(err.to_string().contains("rate limited"));
+        assert_eq!(provider.chat("c", "m", 0.0).await.unwrap(), "default");
+        assert_eq!(provider.call_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn records_call_arguments() {
+        let provider = ScriptedProvider::new("ok");
+        provider
+            .chat_with_system(Some("sys"), "hello", "model-x", 0.4)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            provider.calls(),
+            vec![RecordedCall {
+                system_prompt: Some("sys".into()),
+                message: "hello".into(),
+                model: "model-x".into(),
+                temperature: 0.4,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn recovers_inside_reliable_provider() {
+        let provider = super::super::reliable::ReliableProvider::new(
+            vec![(
+                "scripted".into(),
+                Box::new(ScriptedProvider::new("recovered").failing_first(1, "boom")),
+            )],
+            1,
+            1,
+        );
+        assert_eq!(provider.chat("hi", "m", 0.0).await.unwrap(), "recovered");
+    }
+}
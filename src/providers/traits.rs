@@ -1,4 +1,109 @@
+use super::stream::StreamChunk;
 use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Envelope a provider's API expects tool definitions in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToolFormat {
+    /// `{"type": "function", "function": {name, description, parameters}}`
+    #[default]
+    OpenAi,
+    /// `{name, description, input_schema}`
+    Anthropic,
+}
+
+/// Per-provider counters accumulated since this process started.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProviderCallStats {
+    pub successes: u64,
+    pub failures: u64,
+    /// Failed attempts scheduled for another try on the same provider.
+    pub retries: u64,
+    /// Mean latency of successful calls.
+    pub avg_latency_ms: Option<f64>,
+}
+
+/// One failed provider call, kept in memory for diagnostics.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderFailure {
+    pub provider: String,
+    /// RFC 3339 time the call failed.
+    pub at: String,
+    pub error: String,
+    /// Whether the chain went on to retry the same provider.
+    pub retried: bool,
+}
+
+/// Ties a conversation to the provider that served its first call.
+///
+/// Once a turn has sent tool calls in one provider's format, a fallback
+/// provider may not understand that history and reply with garbage. Calls
+/// made through [`Provider::chat_pinned`] with the same pin therefore fail
+/// over only until the first success; after that they retry the pinned
+/// provider and fail rather than switch. Clones share the pin.
+///
+/// If the pinned provider is no longer in the chain (a reload removed it),
+/// the pin is ignored with a warning and the call runs as if unpinned.
+///
+/// A pin made with [`ProviderPin::prefer`] also picks where the first call
+/// starts, for requests that need a specific provider: the preferred entry
+/// goes first and the rest follow in configured order, bypassing the
+/// chain's selection strategy.
+#[derive(Debug, Clone, Default)]
+pub struct ProviderPin {
+    chosen: Arc<Mutex<Option<String>>>,
+    /// Chain entry to try first; the rest follow in configured order.
+    pub(super) preferred: Option<String>,
+}
+
+impl ProviderPin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start on the chain entry named `provider`. With `fallback`, the rest
+    /// of the chain is tried if it fails; without, only `provider` is ever
+    /// called. Either way, a name missing from the chain is an
+    /// [`UnknownProviderError`](super::UnknownProviderError).
+    pub fn prefer(provider: impl Into<String>, fallback: bool) -> Self {
+        let provider = provider.into();
+        if fallback {
+            Self {
+                preferred: Some(provider),
+                ..Self::default()
+            }
+        } else {
+            Self {
+                chosen: Arc::new(Mutex::new(Some(provider.clone()))),
+                preferred: Some(provider),
+            }
+        }
+    }
+
+    /// Name of the provider this conversation is pinned to, once set.
+    pub fn get(&self) -> Option<String> {
+        self.chosen.lock().clone()
+    }
+
+    pub(super) fn set_if_unset(&self, provider: &str) {
+        self.chosen
+            .lock()
+            .get_or_insert_with(|| provider.to_string());
+    }
+}
+
+/// Cheap cumulative view for polling, returned by [`Provider::stats`].
+/// Unlike [`ReliabilitySnapshot`](super::reliable::ReliabilitySnapshot) it is never persisted, so it always starts at zero.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProviderStatsSnapshot {
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub providers: BTreeMap<String, ProviderCallStats>,
+}
 
 #[async_trait]
 pub trait Provider: Send + Sync {
@@ -14,4 +119,82 @@ pub trait Provider: Send + Sync {
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<String>;
+
+    /// Like [`Provider::chat_with_system`], but never answered from a response
+    /// cache. User-initiated turns use this so they always get a fresh completion.
+    async fn chat_with_system_uncached(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.chat_with_system(system_prompt, message, model, temperature)
+            .await
+    }
+
+    /// Stream the reply into `chunks`. `Ok` means the reply is complete; an
+    /// `Err` after text was sent means it was cut short (see
+    /// [`super::stream`]). Providers without streaming send the whole reply
+    /// as one chunk.
+    async fn chat_stream(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        chunks: mpsc::Sender<StreamChunk>,
+    ) -> anyhow::Result<()> {
+        let reply = self
+            .chat_with_system_uncached(system_prompt, message, model, temperature)
+            .await?;
+        // A consumer that hung up no longer wants the reply.
+        let _ = chunks.send(StreamChunk::Text(reply)).await;
+        Ok(())
+    }
+
+    /// Like [`Provider::chat_with_system_uncached`], but keeps every call
+    /// sharing `pin` on one backend; see [`ProviderPin`]. Single providers
+    /// have nothing to switch between, so the default ignores the pin.
+    async fn chat_pinned(
+        &self,
+        _pin: &ProviderPin,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.chat_with_system_uncached(system_prompt, message, model, temperature)
+            .await
+    }
+
+    /// Total tokens (prompt plus reply) `model` accepts, when known. Callers
+    /// use it to trim injected context before sending.
+    fn context_window(&self, _model: &str) -> Option<usize> {
+        None
+    }
+
+    /// Shape this provider wants tool definitions in. Most backends speak the
+    /// `OpenAI` function-calling format.
+    fn tool_format(&self) -> ToolFormat {
+        ToolFormat::OpenAi
+    }
+
+    /// Cheap authenticated call (usually a models list) confirming the
+    /// endpoint is reachable and accepts our key, without spending tokens.
+    /// Providers with no such endpoint report success.
+    async fn preflight(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Cumulative call counters, for wrappers that keep them. Plain
+    /// providers have none.
+    fn stats(&self) -> Option<ProviderStatsSnapshot> {
+        None
+    }
+
+    /// Recent failed calls with their errors, for wrappers that keep them.
+    fn recent_failures(&self) -> Option<Vec<ProviderFailure>> {
+        None
+    }
 }
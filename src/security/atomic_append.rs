@@ -0,0 +1,238 @@
+// Crash-safe appends for JSON-lines logs.
+//
+// Each record is built in memory with its trailing newline and written with a
+// single `write_all` while holding both an in-process mutex and an advisory
+// file lock, so concurrent writers (threads or processes) never interleave.
+// A crash can still cut the final `write_all` short; `open` trims such a torn
+// tail back to the last complete line so readers only ever see whole records.
+// Data is fsynced every `sync_every` records and on `sync`/drop.
+
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use parking_lot::Mutex;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Records between fsyncs unless overridden with [`AppendLog::with_sync_every`].
+pub const DEFAULT_SYNC_EVERY: usize = 32;
+
+/// Append-only log of newline-terminated records.
+pub struct AppendLog {
+    path: PathBuf,
+    state: Mutex<AppendState>,
+    sync_every: usize,
+}
+
+struct AppendState {
+    file: File,
+    unsynced: usize,
+}
+
+impl AppendLog {
+    /// Open (or create, owner-only on unix) the log at `path`, repairing a
+    /// torn final line left by a crash.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.create(true).read(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+
+        file.lock_exclusive()?;
+        let repaired = trim_torn_tail(&file);
+        FileExt::unlock(&file)?;
+        if let Some(dropped) =
+            repaired.with_context(|| format!("Failed to check {}", path.display()))?
+        {
+            tracing::warn!(
+                path = %path.display(),
+                bytes = dropped,
+                "Dropped a partial record left by an interrupted write"
+            );
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            state: Mutex::new(AppendState { file, unsynced: 0 }),
+            sync_every: DEFAULT_SYNC_EVERY,
+        })
+    }
+
+    /// Fsync after every `n` records (1 = every record).
+    #[must_use]
+    pub fn with_sync_every(mut self, n: usize) -> Self {
+        self.sync_every = n.max(1);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append one record. `record` must not contain a newline.
+    pub fn append(&self, record: &[u8]) -> Result<()> {
+        anyhow::ensure!(
+            !record.contains(&b'\n'),
+            "Append log records must be a single line"
+        );
+        let mut line = Vec::with_capacity(record.len() + 1);
+        line.extend_from_slice(record);
+        line.push(b'\n');
+
+        let mut state = self.state.lock();
+        state.file.lock_exclusive()?;
+        let written = state.file.write_all(&line);
+        let _ = FileExt::unlock(&state.file);
+        written.with_context(|| format!("Failed to append to {}", self.path.display()))?;
+
+        state.unsynced += 1;
+        if state.unsynced >= self.sync_every {
+            state.file.sync_data()?;
+            state.unsynced = 0;
+        }
+        Ok(())
+    }
+
+    /// Serialize `record` as one JSON line and append it.
+    pub fn append_json<T: serde::Serialize>(&self, record: &T) -> Result<()> {
+        self.append(&serde_json::to_vec(record)?)
+    }
+
+    /// Flush everything appended so far to disk.
+    pub fn sync(&self) -> Result<()> {
+        let mut state = self.state.lock();
+        state.file.sync_data()?;
+        state.unsynced = 0;
+        Ok(())
+    }
+}
+
+impl Drop for AppendLog {
+    fn drop(&mut self) {
+        let state = self.state.get_mut();
+        if state.unsynced > 0 {
+            let _ = state.file.sync_data();
+        }
+    }
+}
+
+/// Cut `file` back to its last newline. Returns the bytes dropped, if any.
+fn trim_torn_tail(mut file: &File) -> std::io::Result<Option<u64>> {
+    const CHUNK: u64 = 4096;
+    let len = file.metadata()?.len();
+    if len == 0 {
+        return Ok(None);
+    }
+    let mut last = [0_u8; 1];
+    file.seek(SeekFrom::Start(len - 1))?;
+    file.read_exact(&mut last)?;
+    if last[0] == b'\n' {
+        return Ok(None);
+    }
+
+    // Scan backwards for the end of the last complete line.
+    let mut end = len;
+    let mut keep = 0;
+    let mut buf = vec![0_u8; usize::try_from(CHUNK).unwrap_or(4096)];
+    while end > 0 {
+        let start = end.saturating_sub(CHUNK);
+        let chunk = &mut buf[..usize::try_from(end - start).unwrap_or(0)];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+        if let Some(pos) = chunk.iter().rposition(|&b| b == b'\n') {
+            keep = start + pos as u64 + 1;
+            break;
+        }
+        end = start;
+    }
+    file.set_len(keep)?;
+    file.sync_data()?;
+    Ok(Some(len - keep))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn records_are_newline_terminated() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("logs").join("audit.jsonl");
+        let log = AppendLog::open(&path).unwrap();
+        log.append(b"one").unwrap();
+        log.append_json(&serde_json::json!({"n": 2})).unwrap();
+        drop(log);
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\n{\"n\":2}\n");
+    }
+
+    #[test]
+    fn multi_line_record_is_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let log = AppendLog::open(&tmp.path().join("a.jsonl")).unwrap();
+        assert!(log.append(b"one\ntwo").is_err());
+    }
+
+    #[test]
+    fn concurrent_writers_never_interleave() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("a.jsonl");
+        let log = Arc::new(AppendLog::open(&path).unwrap().with_sync_every(1_000));
+        let writers: Vec<_> = (0..8)
+            .map(|id| {
+                let log = Arc::clone(&log);
+                std::thread::spawn(move || {
+                    let record = format!("{id}:{}", "x".repeat(10_000));
+                    for _ in 0..20 {
+                        log.append(record.as_bytes()).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        log.sync().unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 160);
+        for line in lines {
+            let (_, body) = line.split_once(':').unwrap();
+            assert_eq!(body.len(), 10_000);
+        }
+    }
+
+    #[test]
+    fn open_trims_a_torn_tail() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("a.jsonl");
+        let mut torn = b"{\"ok\":1}\n".to_vec();
+        torn.extend_from_slice("y".repeat(5_000).as_bytes());
+        std::fs::write(&path, &torn).unwrap();
+
+        let log = AppendLog::open(&path).unwrap();
+        log.append(b"{\"ok\":2}").unwrap();
+        drop(log);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\"ok\":1}\n{\"ok\":2}\n"
+        );
+
+        // No complete line at all: start over.
+        std::fs::write(&path, "partial").unwrap();
+        drop(AppendLog::open(&path).unwrap());
+        assert_eq!(std::fs::read(&path).unwrap().len(), 0);
+    }
+}
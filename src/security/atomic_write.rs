@@ -2,20 +2,93 @@
 //
 // Prevents corruption from crashes or power loss during writes.
 // The rename is atomic on both POSIX (rename(2)) and Windows (MoveFileEx).
+//
+// On Windows, antivirus scanners and the search indexer briefly hold freshly
+// written files open, so the rename can fail with access denied or a sharing
+// violation even though the write is fine. The rename is retried with a short
+// backoff there before giving up.
 
 use anyhow::{Context, Result};
 use std::fs;
-use std::io::Write;
+use std::io::{self, Write};
 use std::path::Path;
+use std::time::Duration;
+
+/// How often to retry a rename that failed on a transient lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenameRetry {
+    /// Total rename attempts, including the first (1 = no retry)
+    pub attempts: u32,
+    /// Delay before the first retry; doubles after each one
+    pub backoff: Duration,
+}
+
+impl RenameRetry {
+    /// Never retry.
+    pub const NONE: Self = Self {
+        attempts: 1,
+        backoff: Duration::ZERO,
+    };
+
+    /// Retries on Windows, where transient locks are common; none elsewhere.
+    pub fn platform_default() -> Self {
+        if cfg!(windows) {
+            Self {
+                attempts: 5,
+                backoff: Duration::from_millis(20),
+            }
+        } else {
+            Self::NONE
+        }
+    }
+}
+
+impl Default for RenameRetry {
+    fn default() -> Self {
+        Self::platform_default()
+    }
+}
 
 /// Atomically writes `data` to `path` via a temp file + rename.
 /// Ensures data is fsynced to disk before the rename occurs.
 pub fn atomic_write(path: &Path, data: &[u8]) -> Result<()> {
+    atomic_write_with(path, data, RenameRetry::platform_default())
+}
+
+/// [`atomic_write`] with an explicit rename retry policy.
+pub fn atomic_write_with(path: &Path, data: &[u8], retry: RenameRetry) -> Result<()> {
+    write_and_rename(path, data, retry, false)
+}
+
+/// [`atomic_write`] for secrets: the temp file is created owner-only (0600 on
+/// unix) before anything is written, so the data is never readable by others,
+/// not even between the write and a later chmod.
+pub fn atomic_write_private(path: &Path, data: &[u8]) -> Result<()> {
+    write_and_rename(path, data, RenameRetry::platform_default(), true)
+}
+
+fn create_tmp(tmp_path: &Path, private: bool) -> io::Result<fs::File> {
+    if !private {
+        return fs::File::create(tmp_path);
+    }
+    // A leftover temp file would keep its old mode; start from a new one.
+    let _ = fs::remove_file(tmp_path);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(tmp_path)
+}
+
+fn write_and_rename(path: &Path, data: &[u8], retry: RenameRetry, private: bool) -> Result<()> {
     let tmp_path = path.with_extension("tmp");
 
     // Write to temp file with explicit fsync
     let result = (|| -> Result<()> {
-        let mut file = fs::File::create(&tmp_path)
+        let mut file = create_tmp(&tmp_path, private)
             .with_context(|| format!("Failed to create temp file: {}", tmp_path.display()))?;
         file.write_all(data)
             .context("Failed to write data to temp file")?;
@@ -30,7 +103,7 @@ pub fn atomic_write(path: &Path, data: &[u8]) -> Result<()> {
     }
 
     // Atomic rename
-    fs::rename(&tmp_path, path).with_context(|| {
+    rename_with_retry(&tmp_path, path, retry, |from, to| fs::rename(from, to)).with_context(|| {
         // Cleanup on rename failure too
         let _ = fs::remove_file(&tmp_path);
         format!(
@@ -41,6 +114,38 @@ pub fn atomic_write(path: &Path, data: &[u8]) -> Result<()> {
     })
 }
 
+fn rename_with_retry(
+    from: &Path,
+    to: &Path,
+    retry: RenameRetry,
+    mut rename: impl FnMut(&Path, &Path) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut delay = retry.backoff;
+    let mut attempt = 1;
+    loop {
+        match rename(from, to) {
+            Err(e) if attempt < retry.attempts && is_transient_lock(&e) => {
+                tracing::debug!(
+                    path = %to.display(),
+                    attempt,
+                    "Rename blocked by a transient lock, retrying: {e}"
+                );
+                std::thread::sleep(delay);
+                delay = delay.saturating_mul(2);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Access denied, or on Windows a sharing/lock violation
+/// (`ERROR_SHARING_VIOLATION` = 32, `ERROR_LOCK_VIOLATION` = 33).
+fn is_transient_lock(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::PermissionDenied
+        || (cfg!(windows) && matches!(e.raw_os_error(), Some(32 | 33)))
+}
+
 /// Async version for tokio contexts. Delegates to blocking threadpool
 /// since fsync and rename must happen synchronously.
 pub async fn atomic_write_async(path: &Path, data: Vec<u8>) -> Result<()> {
@@ -98,6 +203,26 @@ mod tests {
         assert!(atomic_write(&path, b"data").is_err());
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn private_write_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("secret.json");
+        // A stale, world-readable temp file must not leak its mode.
+        fs::write(path.with_extension("tmp"), b"old").unwrap();
+        fs::set_permissions(
+            path.with_extension("tmp"),
+            fs::Permissions::from_mode(0o644),
+        )
+        .unwrap();
+
+        atomic_write_private(&path, b"secret").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "secret");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
     #[tokio::test]
     async fn async_creates_file() {
         let tmp = TempDir::new().unwrap();
@@ -107,4 +232,76 @@ mod tests {
             .unwrap();
         assert_eq!(fs::read_to_string(&path).unwrap(), "async data");
     }
+
+    fn denied() -> io::Error {
+        io::Error::from(io::ErrorKind::PermissionDenied)
+    }
+
+    #[test]
+    fn transient_rename_failures_are_retried() {
+        let retry = RenameRetry {
+            attempts: 3,
+            backoff: Duration::from_millis(1),
+        };
+        let mut calls = 0;
+        let result = rename_with_retry(Path::new("a"), Path::new("b"), retry, |_, _| {
+            calls += 1;
+            if calls < 3 {
+                Err(denied())
+            } else {
+                Ok(())
+            }
+        });
+        assert!(result.is_ok());
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn retries_stop_after_budget_or_on_other_errors() {
+        let retry = RenameRetry {
+            attempts: 2,
+            backoff: Duration::ZERO,
+        };
+        let mut calls = 0;
+        let result = rename_with_retry(Path::new("a"), Path::new("b"), retry, |_, _| {
+            calls += 1;
+            Err(denied())
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 2);
+
+        let mut calls = 0;
+        let result = rename_with_retry(Path::new("a"), Path::new("b"), retry, |_, _| {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn failed_rename_removes_temp_file() {
+        let tmp = TempDir::new().unwrap();
+        // A non-empty directory can't be replaced by a file on any platform.
+        let path = tmp.path().join("target");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("inside"), "x").unwrap();
+
+        let retry = RenameRetry {
+            attempts: 2,
+            backoff: Duration::ZERO,
+        };
+        assert!(atomic_write_with(&path, b"data", retry).is_err());
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn retry_defaults_follow_platform() {
+        let default = RenameRetry::default();
+        if cfg!(windows) {
+            assert!(default.attempts > 1);
+        } else {
+            assert_eq!(default, RenameRetry::NONE);
+        }
+    }
 }
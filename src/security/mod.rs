@@ -1,10 +1,10 @@
+pub mod atomic_append;
 pub mod atomic_write;
 pub mod pairing;
 pub mod policy;
+pub mod redact;
 pub mod secrets;
+pub mod token_store;
 
-#[allow(unused_imports)]
-pub use pairing::PairingGuard;
 pub use policy::{AutonomyLevel, SecurityPolicy};
-#[allow(unused_imports)]
 pub use secrets::SecretStore;
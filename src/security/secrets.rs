@@ -20,6 +20,7 @@
 // using the old algorithm for backward compatibility. New encryptions always
 // produce `enc2:` (ChaCha20-Poly1305).
 
+use crate::util::{hex_decode, hex_encode};
 use anyhow::{Context, Result};
 use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
 use chacha20poly1305::{AeadCore, ChaCha20Poly1305, Key, Nonce};
@@ -255,29 +256,6 @@ fn generate_random_key() -> Vec<u8> {
     ChaCha20Poly1305::generate_key(&mut OsRng).to_vec()
 }
 
-fn hex_encode(data: &[u8]) -> String {
-    let mut s = String::with_capacity(data.len() * 2);
-    for b in data {
-        use std::fmt::Write;
-        let _ = write!(s, "{b:02x}");
-    }
-    s
-}
-
-fn hex_decode(hex: &str) -> Result<Vec<u8>> {
-    #[allow(clippy::manual_is_multiple_of)]
-    if hex.len() % 2 != 0 {
-        anyhow::bail!("Hex string has odd length");
-    }
-    (0..hex.len())
-        .step_by(2)
-        .map(|i| {
-            u8::from_str_radix(&hex[i..i + 2], 16)
-                .map_err(|e| anyhow::anyhow!("Invalid hex at position {i}: {e}"))
-        })
-        .collect()
-}
-
 // DPAPI envelope encryption for Windows.
 // Wraps/unwraps secret key material using the current user's DPAPI master key.
 #[cfg(windows)]
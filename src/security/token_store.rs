@@ -0,0 +1,243 @@
+// Pairing token persistence — where the gateway remembers paired clients.
+//
+// `PairingGuard` loads tokens from a `TokenStore` at startup and saves the
+// full set after each successful pairing. Keeping them out of config.toml
+// means a read-only, baked-in config still remembers clients across restarts.
+//
+// The default `FileTokenStore` writes `paired_tokens.json` atomically with
+// mode 0600, and encrypts each token through `SecretStore` when secrets
+// encryption is enabled. A file it can't use (bad JSON, tokens it can't
+// decrypt) is moved aside rather than overwritten by the next pairing.
+
+use super::atomic_write::atomic_write_private;
+use super::secrets::SecretStore;
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+
+/// Backend that persists the set of paired bearer tokens.
+pub trait TokenStore: Send + Sync + Debug {
+    /// Every stored token, in plaintext. A store that was never written is empty.
+    fn load(&self) -> Result<Vec<String>>;
+
+    /// Replace the stored set with `tokens`.
+    fn save(&self, tokens: &[String]) -> Result<()>;
+
+    /// Add `tokens` to the stored set, writing only if something is new.
+    fn import(&self, tokens: &[String]) -> Result<()> {
+        let mut stored = self.load()?;
+        let before = stored.len();
+        for token in tokens {
+            if !stored.contains(token) {
+                stored.push(token.clone());
+            }
+        }
+        if stored.len() > before {
+            self.save(&stored)?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps tokens for the life of the process only.
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    tokens: Mutex<Vec<String>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new(tokens: Vec<String>) -> Self {
+        Self {
+            tokens: Mutex::new(tokens),
+        }
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self) -> Result<Vec<String>> {
+        Ok(self.tokens.lock().clone())
+    }
+
+    fn save(&self, tokens: &[String]) -> Result<()> {
+        *self.tokens.lock() = tokens.to_vec();
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TokenFile {
+    tokens: Vec<String>,
+}
+
+/// JSON file of tokens, written atomically with owner-only permissions.
+#[derive(Debug, Clone)]
+pub struct FileTokenStore {
+    path: PathBuf,
+    secrets: Option<SecretStore>,
+}
+
+impl FileTokenStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            secrets: None,
+        }
+    }
+
+    /// Encrypt tokens at rest with `secrets` (a no-op if it is disabled).
+    pub fn with_secret_store(mut self, secrets: SecretStore) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Default location: `paired_tokens.json` next to `config.toml`.
+    pub fn default_path(config_path: &Path) -> PathBuf {
+        config_path
+            .parent()
+            .map_or_else(|| PathBuf::from("."), PathBuf::from)
+            .join("paired_tokens.json")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> Result<Vec<String>> {
+        let raw = match std::fs::read(&self.path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.path.display()));
+            }
+        };
+        let tokens = serde_json::from_slice::<TokenFile>(&raw)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| {
+                file.tokens
+                    .iter()
+                    .map(|token| match &self.secrets {
+                        Some(secrets) => secrets.decrypt(token),
+                        None => Ok(token.clone()),
+                    })
+                    .collect()
+            });
+        // Starting empty would let the next pairing overwrite the file, so
+        // keep a copy of it first.
+        Ok(tokens.unwrap_or_else(|e| {
+            crate::util::recover_corrupt(&self.path, "paired token store", &format!("{e:#}"));
+            Vec::new()
+        }))
+    }
+
+    fn save(&self, tokens: &[String]) -> Result<()> {
+        let tokens = tokens
+            .iter()
+            .map(|token| match &self.secrets {
+                Some(secrets) => secrets.encrypt(token),
+                None => Ok(token.clone()),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let data = serde_json::to_vec_pretty(&TokenFile { tokens })?;
+        atomic_write_private(&self.path, &data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn missing_file_is_empty() {
+        let tmp = TempDir::new().unwrap();
+        let store = FileTokenStore::new(tmp.path().join("paired_tokens.json"));
+        assert!(store.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn file_store_roundtrip() {
+        let tmp = TempDir::new().unwrap();
+        let store = FileTokenStore::new(tmp.path().join("nested").join("paired_tokens.json"));
+        store.save(&["bh_a".into(), "bh_b".into()]).unwrap();
+        assert_eq!(store.load().unwrap(), vec!["bh_a", "bh_b"]);
+    }
+
+    #[test]
+    fn file_store_encrypts_at_rest() {
+        let tmp = TempDir::new().unwrap();
+        let store = FileTokenStore::new(tmp.path().join("paired_tokens.json"))
+            .with_secret_store(SecretStore::new(tmp.path(), true));
+        store.save(&["bh_secret".into()]).unwrap();
+
+        let on_disk = std::fs::read_to_string(store.path()).unwrap();
+        assert!(!on_disk.contains("bh_secret"));
+        assert!(on_disk.contains("enc2:"));
+        assert_eq!(store.load().unwrap(), vec!["bh_secret"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_store_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = TempDir::new().unwrap();
+        let store = FileTokenStore::new(tmp.path().join("paired_tokens.json"));
+        store.save(&["bh_a".into()]).unwrap();
+        let mode = std::fs::metadata(store.path())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn corrupt_file_is_moved_aside() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("paired_tokens.json");
+        std::fs::write(&path, "not json").unwrap();
+        let store = FileTokenStore::new(path.clone());
+        assert!(store.load().unwrap().is_empty());
+        assert!(!path.exists());
+
+        store.save(&["bh_new".into()]).unwrap();
+        let kept: Vec<_> = std::fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with("paired_tokens.json.corrupt-"))
+            .collect();
+        assert_eq!(kept.len(), 1, "{kept:?}");
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join(&kept[0])).unwrap(),
+            "not json"
+        );
+    }
+
+    #[test]
+    fn undecryptable_file_is_moved_aside() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("paired_tokens.json");
+        FileTokenStore::new(path.clone())
+            .with_secret_store(SecretStore::new(tmp.path(), true))
+            .save(&["bh_secret".into()])
+            .unwrap();
+        // The key is gone, e.g. the config directory was copied without it.
+        let other = TempDir::new().unwrap();
+        let store = FileTokenStore::new(path.clone())
+            .with_secret_store(SecretStore::new(other.path(), true));
+        assert!(store.load().unwrap().is_empty());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn import_merges_without_duplicates() {
+        let store = MemoryTokenStore::new(vec!["bh_a".into()]);
+        store.import(&["bh_a".into(), "bh_b".into()]).unwrap();
+        assert_eq!(store.load().unwrap(), vec!["bh_a", "bh_b"]);
+    }
+}
@@ -695,4 +695,5 @@ description = "Bare minimum"
 }
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod symlink_tests;
@@ -4,7 +4,7 @@
 //! It supports semantic element selection, accessibility snapshots, and JSON output
 //! for efficient LLM integration.
 
-use super::traits::{Tool, ToolResult};
+use super::traits::{Tool, ToolErrorKind, ToolResult};
 use crate::security::SecurityPolicy;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -117,8 +117,7 @@ impl BrowserTool {
             .stderr(Stdio::null())
             .status()
             .await
-            .map(|s| s.success())
-            .unwrap_or(false)
+            .is_ok_and(|s| s.success())
     }
 
     /// Validate URL against allowlist
@@ -354,12 +353,14 @@ impl BrowserTool {
                 success: true,
                 output,
                 error: None,
+                error_kind: None,
             })
         } else {
             Ok(ToolResult {
                 success: false,
                 output: String::new(),
                 error: resp.error,
+                error_kind: Some(ToolErrorKind::ExecFailed),
             })
         }
     }
@@ -469,6 +470,7 @@ impl Tool for BrowserTool {
                 success: false,
                 output: String::new(),
                 error: Some("Action blocked: autonomy is read-only".into()),
+                error_kind: Some(ToolErrorKind::PolicyDenied),
             });
         }
 
@@ -477,6 +479,7 @@ impl Tool for BrowserTool {
                 success: false,
                 output: String::new(),
                 error: Some("Action blocked: rate limit exceeded".into()),
+                error_kind: Some(ToolErrorKind::PolicyDenied),
             });
         }
 
@@ -489,6 +492,7 @@ impl Tool for BrowserTool {
                     "agent-browser CLI not found. Install with: npm install -g agent-browser"
                         .into(),
                 ),
+                error_kind: Some(ToolErrorKind::ExecFailed),
             });
         }
 
@@ -650,6 +654,7 @@ impl Tool for BrowserTool {
                     success: false,
                     output: String::new(),
                     error: Some(format!("Unknown action: {action_str}")),
+                    error_kind: Some(ToolErrorKind::InvalidArgs),
                 });
             }
         };
@@ -750,7 +755,7 @@ mod tests {
         let domains = vec![
             "  Example.COM  ".into(),
             "docs.example.com".into(),
-            "".into(),
+            String::new(),
         ];
         let normalized = normalize_domains(domains);
         assert_eq!(normalized, vec!["example.com", "docs.example.com"]);
@@ -6,7 +6,7 @@
 // This is opt-in. Users who prefer sovereign/local-only mode skip this entirely.
 // The Composio API key is stored in the encrypted secret store.
 
-use super::traits::{Tool, ToolResult};
+use super::traits::{Tool, ToolErrorKind, ToolResult};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -168,6 +168,7 @@ impl Tool for ComposioTool {
         })
     }
 
+    #[allow(clippy::too_many_lines)]
     async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
         let action = args
             .get("action")
@@ -210,12 +211,14 @@ impl Tool for ComposioTool {
                             success: true,
                             output,
                             error: None,
+                            error_kind: None,
                         })
                     }
                     Err(e) => Ok(ToolResult {
                         success: false,
                         output: String::new(),
                         error: Some(format!("Failed to list actions: {e}")),
+                        error_kind: Some(ToolErrorKind::ExecFailed),
                     }),
                 }
             }
@@ -239,12 +242,14 @@ impl Tool for ComposioTool {
                             success: true,
                             output,
                             error: None,
+                            error_kind: None,
                         })
                     }
                     Err(e) => Ok(ToolResult {
                         success: false,
                         output: String::new(),
                         error: Some(format!("Action execution failed: {e}")),
+                        error_kind: Some(ToolErrorKind::ExecFailed),
                     }),
                 }
             }
@@ -260,11 +265,13 @@ impl Tool for ComposioTool {
                         success: true,
                         output: format!("Open this URL to connect {app}:\n{url}"),
                         error: None,
+                        error_kind: None,
                     }),
                     Err(e) => Ok(ToolResult {
                         success: false,
                         output: String::new(),
                         error: Some(format!("Failed to get connection URL: {e}")),
+                        error_kind: Some(ToolErrorKind::ExecFailed),
                     }),
                 }
             }
@@ -275,6 +282,7 @@ impl Tool for ComposioTool {
                 error: Some(format!(
                     "Unknown action '{action}'. Use 'list', 'execute', or 'connect'."
                 )),
+                error_kind: Some(ToolErrorKind::InvalidArgs),
             }),
         }
     }
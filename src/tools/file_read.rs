@@ -1,4 +1,4 @@
-use super::traits::{Tool, ToolResult};
+use super::traits::{Tool, ToolErrorKind, ToolResult};
 use crate::security::SecurityPolicy;
 use async_trait::async_trait;
 use serde_json::json;
@@ -50,10 +50,11 @@ impl Tool for FileReadTool {
                 success: false,
                 output: String::new(),
                 error: Some(format!("Path not allowed by security policy: {path}")),
+                error_kind: Some(ToolErrorKind::PolicyDenied),
             });
         }
 
-        let full_path = self.security.workspace_dir.join(path);
+        let full_path = self.security.canonical_workspace().join(path);
 
         // Resolve path before reading to block symlink escapes.
         let resolved_path = match tokio::fs::canonicalize(&full_path).await {
@@ -63,6 +64,7 @@ impl Tool for FileReadTool {
                     success: false,
                     output: String::new(),
                     error: Some(format!("Failed to resolve file path: {e}")),
+                    error_kind: Some(ToolErrorKind::ExecFailed),
                 });
             }
         };
@@ -75,6 +77,7 @@ impl Tool for FileReadTool {
                     "Resolved path escapes workspace: {}",
                     resolved_path.display()
                 )),
+                error_kind: Some(ToolErrorKind::PolicyDenied),
             });
         }
 
@@ -83,11 +86,13 @@ impl Tool for FileReadTool {
                 success: true,
                 output: contents,
                 error: None,
+                error_kind: None,
             }),
             Err(e) => Ok(ToolResult {
                 success: false,
                 output: String::new(),
                 error: Some(format!("Failed to read file: {e}")),
+                error_kind: Some(ToolErrorKind::ExecFailed),
             }),
         }
     }
@@ -1,4 +1,4 @@
-use super::traits::{Tool, ToolResult};
+use super::traits::{Tool, ToolErrorKind, ToolResult};
 use crate::security::SecurityPolicy;
 use async_trait::async_trait;
 use serde_json::json;
@@ -59,10 +59,11 @@ impl Tool for FileWriteTool {
                 success: false,
                 output: String::new(),
                 error: Some(format!("Path not allowed by security policy: {path}")),
+                error_kind: Some(ToolErrorKind::PolicyDenied),
             });
         }
 
-        let full_path = self.security.workspace_dir.join(path);
+        let full_path = self.security.canonical_workspace().join(path);
 
         // Ensure parent directory exists
         if let Some(parent) = full_path.parent() {
@@ -74,6 +75,7 @@ impl Tool for FileWriteTool {
                 success: false,
                 output: String::new(),
                 error: Some("Invalid path: missing parent directory".into()),
+                error_kind: Some(ToolErrorKind::InvalidArgs),
             });
         };
 
@@ -85,6 +87,7 @@ impl Tool for FileWriteTool {
                     success: false,
                     output: String::new(),
                     error: Some(format!("Failed to resolve file path: {e}")),
+                    error_kind: Some(ToolErrorKind::ExecFailed),
                 });
             }
         };
@@ -97,6 +100,7 @@ impl Tool for FileWriteTool {
                     "Resolved path escapes workspace: {}",
                     resolved_parent.display()
                 )),
+                error_kind: Some(ToolErrorKind::PolicyDenied),
             });
         }
 
@@ -105,6 +109,7 @@ impl Tool for FileWriteTool {
                 success: false,
                 output: String::new(),
                 error: Some("Invalid path: missing file name".into()),
+                error_kind: Some(ToolErrorKind::InvalidArgs),
             });
         };
 
@@ -115,11 +120,13 @@ impl Tool for FileWriteTool {
                 success: true,
                 output: format!("Written {} bytes to {path}", content.len()),
                 error: None,
+                error_kind: None,
             }),
             Err(e) => Ok(ToolResult {
                 success: false,
                 output: String::new(),
                 error: Some(format!("Failed to write file: {e}")),
+                error_kind: Some(ToolErrorKind::ExecFailed),
             }),
         }
     }
@@ -0,0 +1,279 @@
+use super::traits::{Tool, ToolErrorKind, ToolResult};
+use crate::memory::{Memory, MemoryCategory, MemoryEntry};
+use async_trait::async_trait;
+use serde_json::json;
+use std::fmt::Write;
+use std::sync::Arc;
+
+/// Max entries `list` returns so a large store doesn't flood the context.
+const MAX_LIST_ENTRIES: usize = 50;
+/// Characters of each entry `list` shows; `get` returns the whole entry.
+const LIST_PREVIEW_CHARS: usize = 200;
+/// Cap on the whole output, whatever the action.
+const MAX_OUTPUT_BYTES: usize = 65_536;
+
+/// Read-only view of the memory store: fetch one entry or list by category
+pub struct MemoryInspectTool {
+    memory: Arc<dyn Memory>,
+}
+
+impl MemoryInspectTool {
+    pub fn new(memory: Arc<dyn Memory>) -> Self {
+        Self { memory }
+    }
+}
+
+fn parse_category(raw: &str) -> MemoryCategory {
+    let Ok(category) = raw.parse();
+    category
+}
+
+fn format_entry(output: &mut String, entry: &MemoryEntry, max_chars: Option<usize>) {
+    let content = match max_chars {
+        Some(max) if entry.content.chars().nth(max).is_some() => {
+            let head: String = entry.content.chars().take(max).collect();
+            format!("{head}…")
+        }
+        _ => entry.content.clone(),
+    };
+    let _ = writeln!(
+        output,
+        "- [{}] {} ({}): {}",
+        entry.category, entry.key, entry.timestamp, content
+    );
+}
+
+fn truncate_output(mut output: String) -> String {
+    if output.len() > MAX_OUTPUT_BYTES {
+        let mut end = MAX_OUTPUT_BYTES;
+        while !output.is_char_boundary(end) {
+            end -= 1;
+        }
+        output.truncate(end);
+        output.push_str("\n... [output truncated at 64KB]");
+    }
+    output
+}
+
+#[async_trait]
+impl Tool for MemoryInspectTool {
+    fn name(&self) -> &str {
+        "memory_inspect"
+    }
+
+    fn description(&self) -> &str {
+        "Look up what is stored in memory without changing it. Use action 'get' with a key for one entry, or 'list' (optionally with a category) to browse stored keys."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["get", "list"],
+                    "description": "'get' fetches one entry by key, 'list' browses entries"
+                },
+                "key": {
+                    "type": "string",
+                    "description": "Memory key (required for 'get')"
+                },
+                "category": {
+                    "type": "string",
+                    "description": "Filter for 'list': core, daily, conversation, or a custom name"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let action = args
+            .get("action")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
+
+        match action {
+            "get" => {
+                let key = args
+                    .get("key")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'key' parameter for get"))?;
+
+                match self.memory.get(key).await {
+                    Ok(Some(entry)) => {
+                        let mut output = String::new();
+                        format_entry(&mut output, &entry, None);
+                        Ok(ToolResult {
+                            success: true,
+                            output: truncate_output(output),
+                            error: None,
+                            error_kind: None,
+                        })
+                    }
+                    Ok(None) => Ok(ToolResult {
+                        success: true,
+                        output: format!("No memory found with key: {key}"),
+                        error: None,
+                        error_kind: None,
+                    }),
+                    Err(e) => Ok(ToolResult {
+                        success: false,
+                        output: String::new(),
+                        error: Some(format!("Memory get failed: {e}")),
+                        error_kind: Some(ToolErrorKind::ExecFailed),
+                    }),
+                }
+            }
+            "list" => {
+                let category = args
+                    .get("category")
+                    .and_then(|v| v.as_str())
+                    .map(parse_category);
+
+                match self.memory.list(category.as_ref()).await {
+                    Ok(entries) if entries.is_empty() => Ok(ToolResult {
+                        success: true,
+                        output: "Memory is empty.".into(),
+                        error: None,
+                        error_kind: None,
+                    }),
+                    Ok(entries) => {
+                        let mut output = format!("{} memories", entries.len());
+                        if entries.len() > MAX_LIST_ENTRIES {
+                            let _ = write!(output, " (showing first {MAX_LIST_ENTRIES})");
+                        }
+                        output.push_str(":\n");
+                        for entry in entries.iter().take(MAX_LIST_ENTRIES) {
+                            format_entry(&mut output, entry, Some(LIST_PREVIEW_CHARS));
+                        }
+                        Ok(ToolResult {
+                            success: true,
+                            output: truncate_output(output),
+                            error: None,
+                            error_kind: None,
+                        })
+                    }
+                    Err(e) => Ok(ToolResult {
+                        success: false,
+                        output: String::new(),
+                        error: Some(format!("Memory list failed: {e}")),
+                        error_kind: Some(ToolErrorKind::ExecFailed),
+                    }),
+                }
+            }
+            other => Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(format!("Unknown action '{other}'. Use 'get' or 'list'.")),
+                error_kind: Some(ToolErrorKind::InvalidArgs),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::SqliteMemory;
+    use tempfile::TempDir;
+
+    fn test_mem() -> (TempDir, Arc<dyn Memory>) {
+        let tmp = TempDir::new().unwrap();
+        let mem = SqliteMemory::new(tmp.path()).unwrap();
+        (tmp, Arc::new(mem))
+    }
+
+    #[tokio::test]
+    async fn get_existing_key() {
+        let (_tmp, mem) = test_mem();
+        mem.store("lang", "User prefers Rust", MemoryCategory::Core)
+            .await
+            .unwrap();
+
+        let tool = MemoryInspectTool::new(mem);
+        let result = tool
+            .execute(json!({"action": "get", "key": "lang"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(result.output.contains("User prefers Rust"));
+    }
+
+    #[tokio::test]
+    async fn get_missing_key() {
+        let (_tmp, mem) = test_mem();
+        let tool = MemoryInspectTool::new(mem);
+        let result = tool
+            .execute(json!({"action": "get", "key": "nope"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(result.output.contains("No memory found"));
+    }
+
+    #[tokio::test]
+    async fn list_filters_by_category() {
+        let (_tmp, mem) = test_mem();
+        mem.store("a", "core fact", MemoryCategory::Core)
+            .await
+            .unwrap();
+        mem.store("b", "daily note", MemoryCategory::Daily)
+            .await
+            .unwrap();
+
+        let tool = MemoryInspectTool::new(mem);
+        let result = tool
+            .execute(json!({"action": "list", "category": "daily"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(result.output.contains("daily note"));
+        assert!(!result.output.contains("core fact"));
+    }
+
+    #[tokio::test]
+    async fn list_empty() {
+        let (_tmp, mem) = test_mem();
+        let tool = MemoryInspectTool::new(mem);
+        let result = tool.execute(json!({"action": "list"})).await.unwrap();
+        assert!(result.success);
+        assert!(result.output.contains("empty"));
+    }
+
+    #[tokio::test]
+    async fn unknown_action_rejected() {
+        let (_tmp, mem) = test_mem();
+        let tool = MemoryInspectTool::new(mem);
+        let result = tool.execute(json!({"action": "delete"})).await.unwrap();
+        assert!(!result.success);
+    }
+
+    #[tokio::test]
+    async fn get_requires_key() {
+        let (_tmp, mem) = test_mem();
+        let tool = MemoryInspectTool::new(mem);
+        assert!(tool.execute(json!({"action": "get"})).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn list_and_get_bound_large_entries() {
+        let (_tmp, mem) = test_mem();
+        let big = "é".repeat(100_000);
+        mem.store("big", &big, MemoryCategory::Core).await.unwrap();
+        let tool = MemoryInspectTool::new(mem);
+
+        let listed = tool.execute(json!({"action": "list"})).await.unwrap();
+        assert!(listed
+            .output
+            .contains(&format!("{}…", "é".repeat(LIST_PREVIEW_CHARS))));
+        assert!(listed.output.len() < 1_000);
+
+        let got = tool
+            .execute(json!({"action": "get", "key": "big"}))
+            .await
+            .unwrap();
+        assert!(got.output.ends_with("[output truncated at 64KB]"));
+        assert!(got.output.len() <= MAX_OUTPUT_BYTES + 40);
+    }
+}
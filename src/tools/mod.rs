@@ -4,8 +4,10 @@ pub mod composio;
 pub mod file_read;
 pub mod file_write;
 pub mod memory_forget;
+pub mod memory_inspect;
 pub mod memory_recall;
 pub mod memory_store;
+pub mod registry;
 pub mod shell;
 pub mod traits;
 
@@ -15,16 +17,19 @@ pub use composio::ComposioTool;
 pub use file_read::FileReadTool;
 pub use file_write::FileWriteTool;
 pub use memory_forget::MemoryForgetTool;
+pub use memory_inspect::MemoryInspectTool;
 pub use memory_recall::MemoryRecallTool;
 pub use memory_store::MemoryStoreTool;
+pub use registry::ToolRegistry;
 pub use shell::ShellTool;
-pub use traits::Tool;
-#[allow(unused_imports)]
-pub use traits::{ToolResult, ToolSpec};
+pub use traits::{Tool, ToolErrorKind, ToolResult};
 
+use crate::agent::CancelToken;
 use crate::memory::Memory;
 use crate::security::SecurityPolicy;
 use std::sync::Arc;
+use std::time::Instant;
+use tracing::Instrument;
 
 /// Create the default tool registry
 pub fn default_tools(security: Arc<SecurityPolicy>) -> Vec<Box<dyn Tool>> {
@@ -35,7 +40,8 @@ pub fn default_tools(security: Arc<SecurityPolicy>) -> Vec<Box<dyn Tool>> {
     ]
 }
 
-/// Create full tool registry including memory tools and optional Composio
+/// Create full tool registry including memory tools and optional Composio.
+/// Memory-mutating tools are left out under read-only autonomy.
 pub fn all_tools(
     security: &Arc<SecurityPolicy>,
     memory: Arc<dyn Memory>,
@@ -46,11 +52,15 @@ pub fn all_tools(
         Box::new(ShellTool::new(security.clone())),
         Box::new(FileReadTool::new(security.clone())),
         Box::new(FileWriteTool::new(security.clone())),
-        Box::new(MemoryStoreTool::new(memory.clone())),
         Box::new(MemoryRecallTool::new(memory.clone())),
-        Box::new(MemoryForgetTool::new(memory)),
+        Box::new(MemoryInspectTool::new(memory.clone())),
     ];
 
+    if security.can_act() {
+        tools.push(Box::new(MemoryStoreTool::new(memory.clone())));
+        tools.push(Box::new(MemoryForgetTool::new(memory)));
+    }
+
     if browser_config.enabled {
         // Add legacy browser_open tool for simple URL opening
         tools.push(Box::new(BrowserOpenTool::new(
@@ -74,8 +84,88 @@ pub fn all_tools(
     tools
 }
 
+/// Run `tool` bounded by its declared timeout. An overrun is reported as a
+/// failed `ToolResult` so the agent loop can carry on instead of hanging.
+///
+/// Each call runs inside a `tool_call` tracing span (tool name, argument names,
+/// duration, success), nested under whatever span the caller is in.
+pub async fn execute_with_timeout(
+    tool: &dyn Tool,
+    args: serde_json::Value,
+) -> anyhow::Result<ToolResult> {
+    let span = tracing::info_span!(
+        "tool_call",
+        tool = tool.name(),
+        arg_keys = %arg_keys(&args),
+        duration_ms = tracing::field::Empty,
+        success = tracing::field::Empty,
+    );
+    let started = Instant::now();
+    let result = run_bounded(tool, args).instrument(span.clone()).await;
+    span.record(
+        "duration_ms",
+        u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+    );
+    span.record("success", result.as_ref().is_ok_and(|r| r.success));
+    result
+}
+
+/// Like [`execute_with_timeout`], but gives up as soon as `cancel` fires or its
+/// deadline passes, so an abandoned request stops running tools.
+pub async fn execute_with_cancel(
+    tool: &dyn Tool,
+    args: serde_json::Value,
+    cancel: &CancelToken,
+) -> anyhow::Result<ToolResult> {
+    tokio::select! {
+        result = execute_with_timeout(tool, args) => result,
+        () = cancel.cancelled() => Ok(ToolResult {
+            success: false,
+            output: String::new(),
+            error: Some(if cancel.is_expired() {
+                format!("Tool '{}' stopped: request deadline exceeded", tool.name())
+            } else {
+                format!("Tool '{}' cancelled", tool.name())
+            }),
+            error_kind: Some(ToolErrorKind::Cancelled),
+        }),
+    }
+}
+
+async fn run_bounded(tool: &dyn Tool, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+    let Some(limit) = tool.timeout() else {
+        return tool.execute(args).await;
+    };
+    let Ok(result) = tokio::time::timeout(limit, tool.execute(args)).await else {
+        tracing::warn!(tool = tool.name(), "Tool execution timed out");
+        return Ok(ToolResult {
+            success: false,
+            output: String::new(),
+            error: Some(format!(
+                "Tool '{}' timed out after {}s",
+                tool.name(),
+                limit.as_secs_f64()
+            )),
+            error_kind: Some(ToolErrorKind::Timeout),
+        });
+    };
+    result
+}
+
+/// Names of the arguments, for spans. Values stay out of traces: they can
+/// carry file contents, commands with tokens, or memory text.
+fn arg_keys(args: &serde_json::Value) -> String {
+    match args {
+        serde_json::Value::Object(map) => {
+            map.keys().map(String::as_str).collect::<Vec<_>>().join(",")
+        }
+        _ => String::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::traits::ToolSpec;
     use super::*;
     use crate::config::{BrowserConfig, MemoryConfig};
     use tempfile::TempDir;
@@ -131,6 +221,28 @@ mod tests {
         assert!(names.contains(&"browser_open"));
     }
 
+    #[test]
+    fn all_tools_read_only_excludes_memory_writes() {
+        let tmp = TempDir::new().unwrap();
+        let security = Arc::new(SecurityPolicy {
+            autonomy: crate::security::AutonomyLevel::ReadOnly,
+            ..SecurityPolicy::default()
+        });
+        let mem_cfg = MemoryConfig {
+            backend: "markdown".into(),
+            ..MemoryConfig::default()
+        };
+        let mem: Arc<dyn Memory> =
+            Arc::from(crate::memory::create_memory(&mem_cfg, tmp.path(), None).unwrap());
+
+        let tools = all_tools(&security, mem, None, &BrowserConfig::default());
+        let names: Vec<&str> = tools.iter().map(|t| t.name()).collect();
+        assert!(names.contains(&"memory_recall"));
+        assert!(names.contains(&"memory_inspect"));
+        assert!(!names.contains(&"memory_store"));
+        assert!(!names.contains(&"memory_forget"));
+    }
+
     #[test]
     fn default_tools_names() {
         let security = Arc::new(SecurityPolicy::default());
@@ -191,6 +303,7 @@ mod tests {
             success: true,
             output: "hello".into(),
             error: None,
+            error_kind: None,
         };
         let json = serde_json::to_string(&result).unwrap();
         let parsed: ToolResult = serde_json::from_str(&json).unwrap();
@@ -205,11 +318,28 @@ mod tests {
             success: false,
             output: String::new(),
             error: Some("boom".into()),
+            error_kind: Some(ToolErrorKind::ExecFailed),
         };
         let json = serde_json::to_string(&result).unwrap();
         let parsed: ToolResult = serde_json::from_str(&json).unwrap();
         assert!(!parsed.success);
         assert_eq!(parsed.error.as_deref(), Some("boom"));
+        assert_eq!(parsed.error_kind, Some(ToolErrorKind::ExecFailed));
+        assert!(json.contains(r#""error_kind":"exec_failed""#));
+
+        // Results recorded before `error_kind` existed still parse.
+        let legacy: ToolResult =
+            serde_json::from_str(r#"{"success":false,"output":"","error":"boom"}"#).unwrap();
+        assert!(legacy.error_kind.is_none());
+    }
+
+    #[test]
+    fn only_transient_failures_are_retryable() {
+        assert!(ToolErrorKind::Timeout.is_retryable());
+        assert!(ToolErrorKind::ExecFailed.is_retryable());
+        assert!(!ToolErrorKind::PolicyDenied.is_retryable());
+        assert!(!ToolErrorKind::InvalidArgs.is_retryable());
+        assert!(!ToolErrorKind::Cancelled.is_retryable());
     }
 
     #[test]
@@ -224,4 +354,112 @@ mod tests {
         assert_eq!(parsed.name, "test");
         assert_eq!(parsed.description, "A test tool");
     }
+
+    #[test]
+    fn tool_spec_vendor_envelopes() {
+        let spec = ToolSpec {
+            name: "shell".into(),
+            description: "Run a command".into(),
+            parameters: serde_json::json!({"type": "object", "properties": {}}),
+        };
+
+        let openai = spec.to_format(crate::providers::ToolFormat::OpenAi);
+        assert_eq!(openai["type"], "function");
+        assert_eq!(openai["function"]["name"], "shell");
+        assert_eq!(openai["function"]["parameters"], spec.parameters);
+
+        let anthropic = spec.to_format(crate::providers::ToolFormat::Anthropic);
+        assert_eq!(anthropic["name"], "shell");
+        assert_eq!(anthropic["description"], "Run a command");
+        assert_eq!(anthropic["input_schema"], spec.parameters);
+        assert!(anthropic.get("type").is_none());
+    }
+
+    struct StallTool {
+        limit: Option<std::time::Duration>,
+    }
+
+    #[async_trait::async_trait]
+    impl Tool for StallTool {
+        fn name(&self) -> &str {
+            "stall"
+        }
+
+        fn description(&self) -> &str {
+            "Sleeps briefly"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        async fn execute(&self, _args: serde_json::Value) -> anyhow::Result<ToolResult> {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            Ok(ToolResult {
+                success: true,
+                output: "done".into(),
+                error: None,
+                error_kind: None,
+            })
+        }
+
+        fn timeout(&self) -> Option<std::time::Duration> {
+            self.limit
+        }
+    }
+
+    #[test]
+    fn arg_keys_leave_out_values() {
+        let args =
+            serde_json::json!({"command": "curl -H 'Authorization: Bearer sk-123'", "cwd": "x"});
+        let keys = arg_keys(&args);
+        assert_eq!(keys, "command,cwd");
+        assert!(!keys.contains("sk-123"));
+        assert_eq!(arg_keys(&serde_json::json!("raw")), "");
+    }
+
+    #[tokio::test]
+    async fn execute_with_timeout_reports_overrun() {
+        let tool = StallTool {
+            limit: Some(std::time::Duration::from_millis(20)),
+        };
+        let result = execute_with_timeout(&tool, serde_json::json!({}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error_kind, Some(ToolErrorKind::Timeout));
+        assert!(result.error.unwrap().contains("timed out"));
+    }
+
+    #[tokio::test]
+    async fn execute_with_cancel_stops_at_deadline() {
+        let tool = StallTool { limit: None };
+        let cancel = CancelToken::with_deadline(
+            tokio::time::Instant::now() + std::time::Duration::from_millis(20),
+        );
+        let result = execute_with_cancel(&tool, serde_json::json!({}), &cancel)
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error_kind, Some(ToolErrorKind::Cancelled));
+        assert!(result.error.unwrap().contains("deadline exceeded"));
+    }
+
+    #[tokio::test]
+    async fn execute_with_timeout_passes_through_when_unbounded() {
+        let tool = StallTool { limit: None };
+        let result = execute_with_timeout(&tool, serde_json::json!({}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, "done");
+    }
+
+    #[test]
+    fn builtin_tools_declare_a_timeout() {
+        let security = Arc::new(SecurityPolicy::default());
+        for tool in default_tools(security) {
+            assert!(tool.timeout().is_some(), "{} has no timeout", tool.name());
+        }
+    }
 }
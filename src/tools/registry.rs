@@ -0,0 +1,169 @@
+use super::traits::{Tool, ToolSpec};
+use crate::config::Config;
+use crate::memory::Memory;
+use crate::security::SecurityPolicy;
+use std::sync::Arc;
+
+/// When-to-use guidance for the system prompt, richer than the tool's own
+/// one-line description. Tools not listed fall back to that description.
+const PROMPT_GUIDANCE: &[(&str, &str)] = &[
+    (
+        "shell",
+        "Execute terminal commands. Use when: running local checks, build/test commands, diagnostics. Don't use when: a safer dedicated tool exists, or command is destructive without approval.",
+    ),
+    (
+        "file_read",
+        "Read file contents. Use when: inspecting project files, configs, logs. Don't use when: a targeted search is enough.",
+    ),
+    (
+        "file_write",
+        "Write file contents. Use when: applying focused edits, scaffolding files, updating docs/code. Don't use when: side effects are unclear or file ownership is uncertain.",
+    ),
+    (
+        "memory_store",
+        "Save to memory. Use when: preserving durable preferences, decisions, key context. Don't use when: information is transient/noisy/sensitive without need.",
+    ),
+    (
+        "memory_recall",
+        "Search memory. Use when: retrieving prior decisions, user preferences, historical context. Don't use when: answer is already in current context.",
+    ),
+    (
+        "memory_inspect",
+        "Read memory without changing it. Use when: checking what was remembered under a key, or browsing stored entries by category. Don't use when: a keyword search (memory_recall) is what you need.",
+    ),
+    (
+        "memory_forget",
+        "Delete a memory entry. Use when: memory is incorrect/stale or explicitly requested for removal. Don't use when: impact is uncertain.",
+    ),
+    (
+        "browser_open",
+        "Open approved HTTPS URLs in Brave Browser (allowlist-only, no scraping)",
+    ),
+];
+
+/// The tools available under a given config and security policy.
+pub struct ToolRegistry {
+    tools: Vec<Box<dyn Tool>>,
+}
+
+impl ToolRegistry {
+    pub fn new(tools: Vec<Box<dyn Tool>>) -> Self {
+        Self { tools }
+    }
+
+    /// Every tool [`super::all_tools`] enables for this config, with the
+    /// Composio key passed only when the integration is turned on.
+    pub fn from_config(
+        config: &Config,
+        security: &Arc<SecurityPolicy>,
+        memory: Arc<dyn Memory>,
+    ) -> Self {
+        let composio_key = if config.composio.enabled {
+            config.composio.api_key.as_deref()
+        } else {
+            None
+        };
+        Self::new(super::all_tools(
+            security,
+            memory,
+            composio_key,
+            &config.browser,
+        ))
+    }
+
+    /// The registered tool called `name`, if this config and policy enable it.
+    pub fn get(&self, name: &str) -> Option<&dyn Tool> {
+        self.tools
+            .iter()
+            .find(|tool| tool.name() == name)
+            .map(AsRef::as_ref)
+    }
+
+    /// `(name, guidance)` for the system prompt's tool section: only the
+    /// tools this registry actually offers, so the prompt never advertises
+    /// one the policy left out.
+    pub fn prompt_descriptions(&self) -> Vec<(&str, &str)> {
+        self.tools
+            .iter()
+            .map(|tool| {
+                let name = tool.name();
+                let guidance = PROMPT_GUIDANCE
+                    .iter()
+                    .find(|(listed, _)| *listed == name)
+                    .map_or_else(|| tool.description(), |(_, guidance)| guidance);
+                (name, guidance)
+            })
+            .collect()
+    }
+
+    /// Name, description and parameter schema of every registered tool, in
+    /// registration order.
+    pub fn specs(&self) -> Vec<ToolSpec> {
+        self.tools.iter().map(|tool| tool.spec()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MemoryConfig;
+    use crate::security::AutonomyLevel;
+    use tempfile::TempDir;
+
+    fn registry(autonomy: AutonomyLevel) -> (TempDir, ToolRegistry) {
+        let tmp = TempDir::new().unwrap();
+        let mem_cfg = MemoryConfig {
+            backend: "markdown".into(),
+            ..MemoryConfig::default()
+        };
+        let mem: Arc<dyn Memory> =
+            Arc::from(crate::memory::create_memory(&mem_cfg, tmp.path(), None).unwrap());
+        let security = Arc::new(SecurityPolicy {
+            autonomy,
+            ..SecurityPolicy::default()
+        });
+        let registry = ToolRegistry::from_config(&Config::default(), &security, mem);
+        (tmp, registry)
+    }
+
+    #[test]
+    fn specs_follow_the_autonomy_level() {
+        let (_tmp, supervised) = registry(AutonomyLevel::Supervised);
+        let names: Vec<String> = supervised.specs().into_iter().map(|s| s.name).collect();
+        assert_eq!(names[..3], ["shell", "file_read", "file_write"]);
+        assert!(names.contains(&"memory_store".to_string()));
+
+        let (_tmp, read_only) = registry(AutonomyLevel::ReadOnly);
+        let names: Vec<String> = read_only.specs().into_iter().map(|s| s.name).collect();
+        assert!(names.contains(&"memory_recall".to_string()));
+        assert!(!names.contains(&"memory_store".to_string()));
+    }
+
+    #[test]
+    fn read_only_prompt_omits_memory_writes() {
+        let (_tmp, read_only) = registry(AutonomyLevel::ReadOnly);
+        let names: Vec<&str> = read_only
+            .prompt_descriptions()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert!(names.contains(&"memory_inspect"));
+        assert!(!names.contains(&"memory_store"));
+        assert!(!names.contains(&"memory_forget"));
+
+        let (_tmp, supervised) = registry(AutonomyLevel::Supervised);
+        let descs = supervised.prompt_descriptions();
+        let (_, shell) = descs.iter().find(|(name, _)| *name == "shell").unwrap();
+        assert!(shell.contains("Use when"));
+    }
+
+    #[test]
+    fn specs_serialize_with_schemas() {
+        let (_tmp, registry) = registry(AutonomyLevel::Supervised);
+        let json = serde_json::to_value(registry.specs()).unwrap();
+        let first = &json[0];
+        assert_eq!(first["name"], "shell");
+        assert!(first["description"].as_str().is_some_and(|d| !d.is_empty()));
+        assert!(first["parameters"]["properties"].is_object());
+    }
+}
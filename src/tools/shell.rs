@@ -1,7 +1,8 @@
-use super::traits::{Tool, ToolResult};
+use super::traits::{Tool, ToolErrorKind, ToolResult};
 use crate::security::SecurityPolicy;
 use async_trait::async_trait;
 use serde_json::json;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -19,6 +20,46 @@ impl ShellTool {
     pub fn new(security: Arc<SecurityPolicy>) -> Self {
         Self { security }
     }
+
+    /// Resolve an optional workspace-relative `cwd` to a directory that is
+    /// still inside the canonical workspace once symlinks are followed.
+    async fn resolve_cwd(&self, cwd: Option<&str>) -> Result<PathBuf, (ToolErrorKind, String)> {
+        let workspace = self.security.canonical_workspace();
+        let Some(cwd) = cwd.map(str::trim).filter(|c| !c.is_empty() && *c != ".") else {
+            return Ok(workspace);
+        };
+
+        let outside = || {
+            (
+                ToolErrorKind::PolicyDenied,
+                format!(
+                    "cwd must be a directory inside the workspace ({}): {cwd}",
+                    workspace.display()
+                ),
+            )
+        };
+        if !self.security.is_path_allowed(cwd) {
+            return Err(outside());
+        }
+        let resolved = tokio::fs::canonicalize(workspace.join(cwd))
+            .await
+            .map_err(|e| {
+                (
+                    ToolErrorKind::InvalidArgs,
+                    format!("Failed to resolve cwd {cwd}: {e}"),
+                )
+            })?;
+        if !self.security.is_resolved_path_allowed(&resolved) {
+            return Err(outside());
+        }
+        if !resolved.is_dir() {
+            return Err((
+                ToolErrorKind::InvalidArgs,
+                format!("cwd is not a directory: {cwd}"),
+            ));
+        }
+        Ok(resolved)
+    }
 }
 
 #[async_trait]
@@ -28,7 +69,7 @@ impl Tool for ShellTool {
     }
 
     fn description(&self) -> &str {
-        "Execute a shell command in the workspace directory"
+        "Execute a shell command in the workspace directory, or a subdirectory of it via `cwd`"
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -38,37 +79,73 @@ impl Tool for ShellTool {
                 "command": {
                     "type": "string",
                     "description": "The shell command to execute"
+                },
+                "cwd": {
+                    "type": "string",
+                    "description": "Workspace-relative directory to run in (default: workspace root)"
                 }
             },
             "required": ["command"]
         })
     }
 
+    /// Slightly past the command timeout so the child is killed and reported first.
+    fn timeout(&self) -> Option<Duration> {
+        Some(Duration::from_secs(SHELL_TIMEOUT_SECS + 5))
+    }
+
     async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
         let command = args
             .get("command")
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Missing 'command' parameter"))?;
 
-        // Security check: validate command against allowlist
-        if !self.security.is_command_allowed(command) {
+        if let Err(e) = self.security.validate_workspace() {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(e.to_string()),
+                error_kind: Some(ToolErrorKind::PolicyDenied),
+            });
+        }
+
+        let cwd = match self
+            .resolve_cwd(args.get("cwd").and_then(|v| v.as_str()))
+            .await
+        {
+            Ok(dir) => dir,
+            Err((kind, e)) => {
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(e),
+                    error_kind: Some(kind),
+                });
+            }
+        };
+
+        // Security check: validate command against allowlist, resolving
+        // relative programs where the shell will actually start
+        if !self.security.is_command_allowed_at(command, &cwd) {
             return Ok(ToolResult {
                 success: false,
                 output: String::new(),
                 error: Some(format!("Command not allowed by security policy: {command}")),
+                error_kind: Some(ToolErrorKind::PolicyDenied),
             });
         }
 
         // Execute with timeout and OS-level sandboxing
-        let workspace = self.security.workspace_dir.clone();
         let cmd = command.to_string();
         let result = tokio::time::timeout(Duration::from_secs(SHELL_TIMEOUT_SECS), async {
             let child = tokio::process::Command::new("sh")
                 .arg("-c")
                 .arg(&cmd)
-                .current_dir(&workspace)
+                .current_dir(&cwd)
                 .stdout(std::process::Stdio::piped())
                 .stderr(std::process::Stdio::piped())
+                // Timeouts and cancelled turns drop this future; take the child with it.
+                .kill_on_drop(true)
                 .spawn()?;
 
             // Apply OS-level sandbox to the spawned process
@@ -109,12 +186,14 @@ impl Tool for ShellTool {
                     } else {
                         Some(stderr)
                     },
+                    error_kind: (!output.status.success()).then_some(ToolErrorKind::ExecFailed),
                 })
             }
             Ok(Err(e)) => Ok(ToolResult {
                 success: false,
                 output: String::new(),
                 error: Some(format!("Failed to execute command: {e}")),
+                error_kind: Some(ToolErrorKind::ExecFailed),
             }),
             Err(_) => Ok(ToolResult {
                 success: false,
@@ -122,6 +201,7 @@ impl Tool for ShellTool {
                 error: Some(format!(
                     "Command timed out after {SHELL_TIMEOUT_SECS}s and was killed"
                 )),
+                error_kind: Some(ToolErrorKind::Timeout),
             }),
         }
     }
@@ -277,6 +357,7 @@ mod tests {
         let tool = ShellTool::new(test_security(AutonomyLevel::Supervised));
         let result = tool.execute(json!({"command": "rm -rf /"})).await.unwrap();
         assert!(!result.success);
+        assert_eq!(result.error_kind, Some(ToolErrorKind::PolicyDenied));
         assert!(result.error.as_ref().unwrap().contains("not allowed"));
     }
 
@@ -312,4 +393,79 @@ mod tests {
             .unwrap();
         assert!(!result.success);
     }
+
+    fn workspace_security(workspace: &std::path::Path) -> Arc<SecurityPolicy> {
+        Arc::new(SecurityPolicy {
+            autonomy: AutonomyLevel::Supervised,
+            workspace_dir: workspace.to_path_buf(),
+            ..SecurityPolicy::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn shell_runs_in_requested_subdirectory() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("sub/project")).unwrap();
+        let tool = ShellTool::new(workspace_security(tmp.path()));
+
+        let result = tool
+            .execute(json!({"command": "pwd", "cwd": "sub/project"}))
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        let expected = tmp.path().join("sub/project").canonicalize().unwrap();
+        assert_eq!(result.output.trim(), expected.to_str().unwrap());
+    }
+
+    #[tokio::test]
+    async fn shell_rejects_cwd_outside_workspace() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let tool = ShellTool::new(workspace_security(tmp.path()));
+
+        for cwd in ["../", "/tmp", "sub/../.."] {
+            let result = tool
+                .execute(json!({"command": "pwd", "cwd": cwd}))
+                .await
+                .unwrap();
+            assert!(!result.success, "{cwd} should be rejected");
+            assert!(result.error.unwrap().contains("inside the workspace"));
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn shell_rejects_cwd_symlink_escape() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let outside = tempfile::TempDir::new().unwrap();
+        std::os::unix::fs::symlink(outside.path(), tmp.path().join("escape")).unwrap();
+        let tool = ShellTool::new(workspace_security(tmp.path()));
+
+        let result = tool
+            .execute(json!({"command": "pwd", "cwd": "escape"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error_kind, Some(ToolErrorKind::PolicyDenied));
+        assert!(result.error.unwrap().contains("inside the workspace"));
+    }
+
+    #[tokio::test]
+    async fn shell_rejects_missing_or_file_cwd() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("notes.txt"), "x").unwrap();
+        let tool = ShellTool::new(workspace_security(tmp.path()));
+
+        let missing = tool
+            .execute(json!({"command": "pwd", "cwd": "nope"}))
+            .await
+            .unwrap();
+        assert!(missing.error.unwrap().contains("Failed to resolve cwd"));
+
+        let file = tool
+            .execute(json!({"command": "pwd", "cwd": "notes.txt"}))
+            .await
+            .unwrap();
+        assert_eq!(file.error_kind, Some(ToolErrorKind::InvalidArgs));
+        assert!(file.error.unwrap().contains("not a directory"));
+    }
 }
@@ -1,5 +1,10 @@
+use crate::providers::ToolFormat;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Execution limit for tools that don't declare their own.
+pub const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(120);
 
 /// Result of a tool execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -7,6 +12,36 @@ pub struct ToolResult {
     pub success: bool,
     pub output: String,
     pub error: Option<String>,
+    /// Why the call failed, for callers that react to failures; `error`
+    /// carries the human-readable detail
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<ToolErrorKind>,
+}
+
+/// Machine-readable cause of a failed tool call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolErrorKind {
+    /// The security policy refused the call (allowlist, path, autonomy,
+    /// rate limit).
+    PolicyDenied,
+    /// The call ran past its time limit.
+    Timeout,
+    /// The call was cancelled or its request deadline passed.
+    Cancelled,
+    /// The arguments were unusable (unknown action, malformed path).
+    InvalidArgs,
+    /// The tool ran but the operation failed.
+    ExecFailed,
+}
+
+impl ToolErrorKind {
+    /// Whether the same call could succeed if made again. A timeout or a
+    /// failed operation may be transient; a policy denial or bad arguments
+    /// will fail the same way every time.
+    pub fn is_retryable(self) -> bool {
+        matches!(self, Self::Timeout | Self::ExecFailed)
+    }
 }
 
 /// Description of a tool for the LLM
@@ -17,6 +52,39 @@ pub struct ToolSpec {
     pub parameters: serde_json::Value,
 }
 
+impl ToolSpec {
+    /// `OpenAI` function-calling definition, also accepted by `OpenRouter`,
+    /// Ollama and the OpenAI-compatible backends.
+    pub fn to_openai_function(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": self.name,
+                "description": self.description,
+                "parameters": self.parameters,
+            },
+        })
+    }
+
+    /// Anthropic Messages API tool definition.
+    pub fn to_anthropic_tool(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "description": self.description,
+            "input_schema": self.parameters,
+        })
+    }
+
+    /// The definition in whichever shape `format` names; pass
+    /// [`Provider::tool_format`](crate::providers::Provider::tool_format).
+    pub fn to_format(&self, format: ToolFormat) -> serde_json::Value {
+        match format {
+            ToolFormat::OpenAi => self.to_openai_function(),
+            ToolFormat::Anthropic => self.to_anthropic_tool(),
+        }
+    }
+}
+
 /// Core tool trait — implement for any capability
 #[async_trait]
 pub trait Tool: Send + Sync {
@@ -32,6 +100,11 @@ pub trait Tool: Send + Sync {
     /// Execute the tool with given arguments
     async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult>;
 
+    /// Upper bound on a single `execute` call; `None` disables the limit
+    fn timeout(&self) -> Option<Duration> {
+        Some(DEFAULT_TOOL_TIMEOUT)
+    }
+
     /// Get the full spec for LLM registration
     fn spec(&self) -> ToolSpec {
         ToolSpec {
@@ -7,8 +7,6 @@ mod tailscale;
 pub use cloudflare::CloudflareTunnel;
 pub use custom::CustomTunnel;
 pub use ngrok::NgrokTunnel;
-#[allow(unused_imports)]
-pub use none::NoneTunnel;
 pub use tailscale::TailscaleTunnel;
 
 use crate::config::schema::{TailscaleTunnelConfig, TunnelConfig};
@@ -124,6 +122,7 @@ pub fn create_tunnel(config: &TunnelConfig) -> Result<Option<Box<dyn Tunnel>>> {
 
 #[cfg(test)]
 mod tests {
+    use super::none::NoneTunnel;
     use super::*;
     use crate::config::schema::{
         CloudflareTunnelConfig, CustomTunnelConfig, NgrokTunnelConfig, TunnelConfig,
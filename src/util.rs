@@ -0,0 +1,244 @@
+//! Small helpers shared across subsystems.
+
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+
+/// Jitter applied to retry and restart backoffs unless a caller picks its own.
+pub const DEFAULT_JITTER_FRACTION: f64 = 0.25;
+
+/// Uniformly random `u32` from UUID v4, i.e. the OS CSPRNG.
+pub fn random_u32() -> u32 {
+    let random_bytes = uuid::Uuid::new_v4();
+    let bytes = random_bytes.as_bytes();
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Scales `base` by a random factor in `[1 - fraction, 1 + fraction]` so many
+/// clients retrying at once spread out instead of stampeding. `fraction` is
+/// clamped to `[0, 1]`; the result is never below 1 (in whatever unit `base`
+/// uses).
+pub fn jittered_backoff(base: u64, fraction: f64) -> u64 {
+    let fraction = if fraction.is_finite() {
+        fraction.clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let raw = random_u32();
+    // Map raw u32 to [1 - fraction, 1 + fraction]
+    let factor = 1.0 - fraction + (f64::from(raw) / f64::from(u32::MAX)) * 2.0 * fraction;
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    let result = (base as f64 * factor) as u64;
+    result.max(1)
+}
+
+/// Structured error message: what happened, why, and how to fix it.
+/// Produces consistent "what/why/fix" format across the codebase.
+pub fn structured_error(what: &str, why: &str, fix: &str) -> String {
+    format!("{what}\n  Cause: {why}\n  Fix: {fix}")
+}
+
+/// Move a corrupt file aside as `<name>.corrupt-<timestamp>` so the caller
+/// can start fresh without losing it. Returns where it went.
+pub fn quarantine_corrupt(path: &Path) -> std::io::Result<PathBuf> {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(
+        ".corrupt-{}",
+        chrono::Utc::now().format("%Y%m%dT%H%M%S")
+    ));
+    let backup = PathBuf::from(name);
+    std::fs::rename(path, &backup)?;
+    Ok(backup)
+}
+
+/// Read JSON state from `path`. A missing file is `None`; a file that doesn't
+/// parse (truncated by a crash, edited by hand) is logged, quarantined and
+/// also treated as `None`, so startup degrades to a fresh state instead of
+/// failing. Only I/O errors other than "not found" are returned.
+pub fn read_json_or_recover<T: DeserializeOwned>(
+    path: &Path,
+    what: &str,
+) -> std::io::Result<Option<T>> {
+    let raw = match std::fs::read(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    match serde_json::from_slice(&raw) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => {
+            recover_corrupt(path, what, &e.to_string());
+            Ok(None)
+        }
+    }
+}
+
+/// Log a structured error for a corrupt `path` and move it aside.
+pub fn recover_corrupt(path: &Path, what: &str, why: &str) {
+    let fix = match quarantine_corrupt(path) {
+        Ok(backup) => format!(
+            "starting with a fresh {what}; the damaged file was saved as {}",
+            backup.display()
+        ),
+        Err(e) => format!(
+            "could not move it aside ({e}); delete {} to start fresh",
+            path.display()
+        ),
+    };
+    tracing::error!(
+        "{}",
+        structured_error(
+            &format!("{what} at {} is corrupt", path.display()),
+            why,
+            &fix
+        )
+    );
+}
+
+/// Lowercase hex, two digits per byte.
+pub fn hex_encode(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut s, b| {
+            let _ = write!(s, "{b:02x}");
+            s
+        })
+}
+
+/// Value of one ASCII hex digit, either case.
+pub fn hex_nibble(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Inverse of [`hex_encode`]. Works on bytes, so non-ASCII input is an
+/// error rather than a slice panic, and a sign is never taken as a digit.
+pub fn hex_decode(hex: &str) -> anyhow::Result<Vec<u8>> {
+    let bytes = hex.as_bytes();
+    #[allow(clippy::manual_is_multiple_of)]
+    if bytes.len() % 2 != 0 {
+        anyhow::bail!("Hex string has odd length");
+    }
+    bytes
+        .chunks_exact(2)
+        .enumerate()
+        .map(
+            |(i, pair)| match (hex_nibble(pair[0]), hex_nibble(pair[1])) {
+                (Some(hi), Some(lo)) => Ok(hi << 4 | lo),
+                _ => Err(anyhow::anyhow!("Invalid hex at position {}", i * 2)),
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jitter_within_bounds() {
+        for _ in 0..100 {
+            let result = jittered_backoff(1000, DEFAULT_JITTER_FRACTION);
+            assert!(result >= 750, "Jitter too low: {result}");
+            assert!(result <= 1250, "Jitter too high: {result}");
+        }
+    }
+
+    #[test]
+    fn jitter_respects_custom_fraction() {
+        for _ in 0..100 {
+            let result = jittered_backoff(1000, 0.1);
+            assert!((900..=1100).contains(&result), "Out of bounds: {result}");
+        }
+    }
+
+    #[test]
+    fn jitter_not_deterministic() {
+        let results: std::collections::HashSet<u64> = (0..20)
+            .map(|_| jittered_backoff(1000, DEFAULT_JITTER_FRACTION))
+            .collect();
+        assert!(results.len() > 1, "Jitter should produce varying values");
+    }
+
+    #[test]
+    fn jitter_minimum_one() {
+        assert_eq!(jittered_backoff(0, DEFAULT_JITTER_FRACTION), 1);
+        assert!(jittered_backoff(1, DEFAULT_JITTER_FRACTION) >= 1);
+        assert!(jittered_backoff(1, 1.0) >= 1);
+    }
+
+    #[test]
+    fn zero_or_invalid_fraction_means_no_jitter() {
+        assert_eq!(jittered_backoff(1000, 0.0), 1000);
+        assert_eq!(jittered_backoff(1000, -0.5), 1000);
+        assert_eq!(jittered_backoff(1000, f64::NAN), 1000);
+    }
+
+    #[test]
+    fn fraction_above_one_is_clamped() {
+        for _ in 0..100 {
+            assert!(jittered_backoff(1000, 5.0) <= 2000);
+        }
+    }
+
+    #[test]
+    fn missing_state_is_none() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let loaded: Option<serde_json::Value> =
+            read_json_or_recover(&tmp.path().join("state.json"), "state").unwrap();
+        assert!(loaded.is_none());
+    }
+
+    #[test]
+    fn truncated_state_is_quarantined() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("state.json");
+        std::fs::write(&path, "{\"components\": {\"gate").unwrap();
+
+        let loaded: Option<serde_json::Value> = read_json_or_recover(&path, "state").unwrap();
+        assert!(loaded.is_none());
+        assert!(!path.exists());
+        let backups: Vec<_> = std::fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert!(backups[0].starts_with("state.json.corrupt-"));
+    }
+
+    #[test]
+    fn valid_state_loads() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("state.json");
+        std::fs::write(&path, r#"{"pid": 7}"#).unwrap();
+        let loaded: serde_json::Value = read_json_or_recover(&path, "state").unwrap().unwrap();
+        assert_eq!(loaded["pid"], 7);
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        let data = [0x00, 0x7f, 0x80, 0xff, 0x12];
+        assert_eq!(hex_encode(&data), "007f80ff12");
+        assert_eq!(hex_decode("007F80ff12").unwrap(), data);
+        assert!(hex_decode("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn hex_decode_rejects_adversarial_input() {
+        assert!(hex_decode("abc").is_err());
+        assert!(hex_decode("zz").is_err());
+        // from_str_radix would accept a leading sign
+        assert!(hex_decode("+f").is_err());
+        // A pair boundary inside a multibyte char must not panic
+        assert!(hex_decode("aéb").is_err());
+        assert!(hex_decode("ééé").is_err());
+    }
+}